@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// Flashing the screen or a window, when the bell rings or a window requests attention
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct VisualBell {
+    pub enabled: bool,
+    pub scope: BellScope,
+    /// Opacity of the flash from 0.0 to 1.0
+    pub intensity: f32,
+}
+
+impl Default for VisualBell {
+    fn default() -> Self {
+        VisualBell {
+            enabled: false,
+            scope: BellScope::default(),
+            intensity: 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BellScope {
+    /// Flash the window ringing the bell, if it is visible, the focused output otherwise
+    #[default]
+    Window,
+    /// Flash the whole output
+    Output,
+}
+
+/// What happens besides the visual bell, when an application rings the system bell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SystemBell {
+    /// Play the bell sound of the sound theme
+    pub sound: bool,
+    /// Mark the window ringing the bell as requesting attention, unless it is focused
+    pub mark_urgent: bool,
+}
+
+impl Default for SystemBell {
+    fn default() -> Self {
+        SystemBell {
+            sound: true,
+            mark_urgent: true,
+        }
+    }
+}
+
+/// Outline around the widget or text caret focused according to the screen reader
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct FocusHighlight {
+    pub enabled: bool,
+    pub color: [f32; 3],
+    pub thickness: u8,
+}
+
+impl Default for FocusHighlight {
+    fn default() -> Self {
+        FocusHighlight {
+            enabled: false,
+            color: [1.0, 0.8, 0.0],
+            thickness: 3,
+        }
+    }
+}
+
+/// Lines across the whole output crossing at the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Crosshair {
+    pub enabled: bool,
+    pub color: [f32; 3],
+    pub thickness: u8,
+    /// Opacity of the lines from 0.0 to 1.0
+    pub opacity: f32,
+}
+
+impl Default for Crosshair {
+    fn default() -> Self {
+        Crosshair {
+            enabled: false,
+            color: [1.0, 0.0, 0.0],
+            thickness: 2,
+            opacity: 0.8,
+        }
+    }
+}
+
+/// Clicking by resting the pointer, for users who can't press a button
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct DwellClick {
+    pub enabled: bool,
+    /// Milliseconds the pointer has to rest before clicking
+    pub delay: u32,
+    /// Distance in logical pixels the pointer may move while resting
+    pub threshold: u32,
+    pub click_type: DwellClickType,
+}
+
+impl Default for DwellClick {
+    fn default() -> Self {
+        DwellClick {
+            enabled: false,
+            delay: 1200,
+            threshold: 3,
+            click_type: DwellClickType::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DwellClickType {
+    #[default]
+    Single,
+    Double,
+    /// Press the button on one dwell and release it on the next
+    Drag,
+}
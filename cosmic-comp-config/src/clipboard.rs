@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// Keeping the clipboard alive after the client offering it exits.
+///
+/// Only plain text and a single image format are kept, disabled by default.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClipboardPersistence {
+    pub enabled: bool,
+    /// Maximum size in bytes of all formats of a selection, larger selections aren't kept
+    pub max_size: usize,
+    /// Selections offering any of these mime types are never copied, e.g. passwords
+    pub excluded_mime_types: Vec<String>,
+}
+
+impl Default for ClipboardPersistence {
+    fn default() -> Self {
+        ClipboardPersistence {
+            enabled: false,
+            max_size: 16 * 1024 * 1024,
+            excluded_mime_types: vec![
+                "x-kde-passwordManagerHint".into(),
+                "application/x-nspasteboard-concealed-type".into(),
+            ],
+        }
+    }
+}
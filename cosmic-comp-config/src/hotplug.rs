@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::OutputMatch;
+
+/// Commands run whenever a specific output is connected or disconnected,
+/// e.g. to configure a docking station.
+///
+/// Outputs are matched by their EDID, if both have one, otherwise by connector name.
+/// The arrangement of outputs and pinned workspaces is already restored per set of
+/// connected outputs, so hooks are only needed for anything beyond that.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OutputHook {
+    pub output: OutputMatch,
+    /// Shell command run once the output is connected and enabled
+    #[serde(default)]
+    pub connected: Option<String>,
+    /// Shell command run once the output was disconnected or disabled
+    #[serde(default)]
+    pub disconnected: Option<String>,
+}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// Compositor-managed idle sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdleConfig {
+    /// Run the idle sequence in the compositor, instead of relying on an external idle daemon
+    pub enabled: bool,
+    /// Timeouts while on AC power
+    pub ac: IdleTimeouts,
+    /// Timeouts while on battery
+    pub battery: IdleTimeouts,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        IdleConfig {
+            enabled: false,
+            ac: IdleTimeouts {
+                dim: Some(840),
+                lock: Some(900),
+                screen_off: Some(900),
+                suspend: None,
+            },
+            battery: IdleTimeouts {
+                dim: Some(240),
+                lock: Some(300),
+                screen_off: Some(300),
+                suspend: Some(900),
+            },
+        }
+    }
+}
+
+/// Seconds of inactivity before each step of the idle sequence, `None` disables the step
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdleTimeouts {
+    pub dim: Option<u32>,
+    pub lock: Option<u32>,
+    pub screen_off: Option<u32>,
+    pub suspend: Option<u32>,
+}
@@ -31,6 +31,9 @@ pub struct InputConfig {
     pub tap_config: Option<TapConfig>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub map_to_output: Option<String>,
+    /// Show an osd, when caps lock or num lock is toggled on this keyboard
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub lock_keys_osd: Option<bool>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
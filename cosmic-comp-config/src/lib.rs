@@ -6,9 +6,15 @@ use std::collections::HashMap;
 
 use crate::input::TouchpadOverride;
 
+pub mod a11y;
+pub mod clipboard;
+pub mod hotplug;
+pub mod idle;
 pub mod input;
 #[cfg(feature = "output")]
 pub mod output;
+pub mod policy;
+pub mod rules;
 pub mod workspace;
 
 #[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -60,8 +66,12 @@ pub struct CosmicCompConfig {
     pub input_devices: HashMap<String, input::InputConfig>,
     pub xkb_config: XkbConfig,
     pub keyboard_config: KeyboardConfig,
+    /// Show the name of the new keyboard layout on the focused output, whenever it changes
+    pub keyboard_layout_osd: bool,
     /// Autotiling enabled
     pub autotile: bool,
+    /// How windows are arranged on tiled workspaces
+    pub tiling_algorithm: workspace::TilingAlgorithm,
     /// Determines the behavior of the autotile variable
     /// If set to Global, autotile applies to all windows in all workspaces
     /// If set to PerWorkspace, autotile only applies to new windows, and new workspaces
@@ -78,9 +88,54 @@ pub struct CosmicCompConfig {
     pub descale_xwayland: XwaylandDescaling,
     /// Let X11 applications snoop on certain key-presses to allow for global shortcuts
     pub xwayland_eavesdropping: XwaylandEavesdropping,
+    /// App ids of X11 applications rendered at scale 1 and upscaled, if descaling is enabled
+    pub xwayland_upscale: Vec<String>,
+    /// DPI X11 applications render at regardless of the advertised DPI by app id,
+    /// their windows are scaled to match the descaled Xwayland DPI
+    pub xwayland_dpi: HashMap<String, u32>,
+    /// App ids of X11 applications allowed to grab the keyboard, all applications if unset
+    pub xwayland_keyboard_grab: Option<Vec<String>>,
+    /// How X11 override-redirect windows interact with other windows and outputs
+    pub xwayland_override_redirect: XwaylandOverrideRedirect,
+    /// Decorations forced on the windows of Wayland applications by app id,
+    /// regardless of the mode they request
+    pub decoration_overrides: HashMap<String, DecorationMode>,
     /// The threshold before windows snap themselves to output edges
     pub edge_snap_threshold: u32,
     pub accessibility_zoom: ZoomConfig,
+    /// Draw the compositor's own ui in high contrast, independent of the application theme
+    pub high_contrast: bool,
+    /// Flash the screen or windows instead of relying on the audible bell
+    pub visual_bell: a11y::VisualBell,
+    /// Sound and urgency of the bell rung by applications
+    pub system_bell: a11y::SystemBell,
+    /// Highlight the widget focused according to the screen reader
+    pub focus_highlight: a11y::FocusHighlight,
+    /// Draw a crosshair tracking the pointer above all windows
+    pub crosshair: a11y::Crosshair,
+    /// Click when the pointer rests for a while
+    pub dwell_click: a11y::DwellClick,
+    /// Dim, lock, blank and suspend after periods of inactivity
+    pub idle: idle::IdleConfig,
+    /// Clients allowed to bind privileged protocols
+    pub privileged_protocols: policy::ProtocolPolicy,
+    /// Applications allowed to inhibit the compositor's shortcuts
+    pub keyboard_shortcuts_inhibit: policy::ShortcutsInhibitPolicy,
+    /// Keep the clipboard after the client offering it exits
+    pub clipboard_persistence: clipboard::ClipboardPersistence,
+    /// Speed factor of all compositor animations, `2.0` plays them twice as fast
+    pub animation_speed: f32,
+    /// Slow down animations to a tenth of their speed while shift is held, for debugging them
+    pub debug_slow_motion: bool,
+    /// Commands run when specific outputs are connected or disconnected
+    pub output_hooks: Vec<hotplug::OutputHook>,
+    /// Color shown behind all surfaces, e.g. when no background is running
+    pub clear_color: ClearColor,
+    /// Let fullscreen surfaces asking for it through tearing-control present without waiting
+    /// for vblank, trading tearing for lower latency
+    pub allow_tearing: bool,
+    /// Rules applied to new toplevels, e.g. to float them or map them on a specific workspace
+    pub window_rules: Vec<rules::WindowRule>,
 }
 
 impl Default for CosmicCompConfig {
@@ -106,7 +161,9 @@ impl Default for CosmicCompConfig {
             input_devices: Default::default(),
             xkb_config: Default::default(),
             keyboard_config: Default::default(),
+            keyboard_layout_osd: true,
             autotile: Default::default(),
+            tiling_algorithm: Default::default(),
             autotile_behavior: Default::default(),
             active_hint: true,
             focus_follows_cursor: false,
@@ -114,8 +171,29 @@ impl Default for CosmicCompConfig {
             focus_follows_cursor_delay: 250,
             descale_xwayland: XwaylandDescaling::Fractional,
             xwayland_eavesdropping: XwaylandEavesdropping::default(),
+            xwayland_upscale: Vec::new(),
+            xwayland_dpi: HashMap::new(),
+            xwayland_keyboard_grab: None,
+            xwayland_override_redirect: XwaylandOverrideRedirect::default(),
+            decoration_overrides: HashMap::new(),
             edge_snap_threshold: 0,
             accessibility_zoom: ZoomConfig::default(),
+            high_contrast: false,
+            visual_bell: a11y::VisualBell::default(),
+            system_bell: a11y::SystemBell::default(),
+            focus_highlight: a11y::FocusHighlight::default(),
+            crosshair: a11y::Crosshair::default(),
+            dwell_click: a11y::DwellClick::default(),
+            idle: idle::IdleConfig::default(),
+            privileged_protocols: policy::ProtocolPolicy::new(),
+            keyboard_shortcuts_inhibit: policy::ShortcutsInhibitPolicy::default(),
+            clipboard_persistence: clipboard::ClipboardPersistence::default(),
+            animation_speed: 1.0,
+            debug_slow_motion: false,
+            output_hooks: Vec::new(),
+            clear_color: ClearColor::default(),
+            allow_tearing: true,
+            window_rules: Vec::new(),
         }
     }
 }
@@ -162,6 +240,35 @@ fn default_repeat_delay() -> u32 {
     600
 }
 
+/// Color rendered below all surfaces.
+///
+/// Visible behind transparent clients, during workspace transitions and whenever cosmic-bg
+/// isn't running. Colors are linear RGB.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ClearColor {
+    pub color: [f32; 3],
+    /// Colors by output connector name, overriding `color`
+    #[serde(default)]
+    pub outputs: HashMap<String, [f32; 3]>,
+    /// Colors by id of pinned workspaces, overriding the color of their output
+    #[serde(default)]
+    pub workspaces: HashMap<String, [f32; 3]>,
+    /// Darken the color while the session is locked or the outputs are dimmed for inactivity
+    #[serde(default)]
+    pub dim_inactive: bool,
+}
+
+impl Default for ClearColor {
+    fn default() -> Self {
+        ClearColor {
+            color: [0.153, 0.161, 0.165],
+            outputs: HashMap::new(),
+            workspaces: HashMap::new(),
+            dim_inactive: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ZoomConfig {
     pub start_on_login: bool,
@@ -196,6 +303,17 @@ pub struct XwaylandEavesdropping {
     pub pointer: bool,
 }
 
+/// Handling of X11 override-redirect windows, like menus and tooltips
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct XwaylandOverrideRedirect {
+    /// Only show them, while a window of the same application is visible on the output
+    pub follow_app: bool,
+    /// Only show them on the output containing their top-left corner
+    pub confine_to_output: bool,
+    /// Hide them, while no X11 window has keyboard focus
+    pub hide_without_focus: bool,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum EavesdroppingKeyboardMode {
     #[default]
@@ -215,3 +333,12 @@ pub enum XwaylandDescaling {
     #[default]
     Fractional,
 }
+
+/// Who draws the decorations of a window
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationMode {
+    /// The compositor draws a header bar
+    ServerSide,
+    /// The application draws its own decorations
+    ClientSide,
+}
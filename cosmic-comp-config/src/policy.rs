@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Globals giving clients access beyond their own surfaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum PrivilegedProtocol {
+    /// Capturing outputs, workspaces and toplevels
+    Screencopy,
+    /// Listing and controlling toplevels of other clients
+    ToplevelManagement,
+    /// Listing and controlling workspaces
+    WorkspaceManagement,
+    /// Configuring and powering outputs
+    OutputManagement,
+    /// Emulating input devices
+    VirtualInput,
+    /// Reading and setting the clipboard without focus
+    DataControl,
+}
+
+/// Identifies a client in a [`ProtocolPolicy`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ClientMatcher {
+    /// Clients connected through a security context of `sandbox_engine`,
+    /// optionally restricted to a single `app_id`
+    SecurityContext {
+        sandbox_engine: String,
+        #[serde(default)]
+        app_id: Option<String>,
+    },
+    /// Clients, whose process runs the given executable
+    Executable(PathBuf),
+    /// Clients, whose process owns the given well-known name on the session bus
+    DBusName(String),
+}
+
+/// Allow-lists for privileged globals.
+///
+/// Protocols without an entry are available to all clients, that aren't sandboxed.
+/// Protocols with an entry are only advertised to clients matching one of its matchers.
+pub type ProtocolPolicy = HashMap<PrivilegedProtocol, Vec<ClientMatcher>>;
+
+/// Which applications may inhibit the compositor's shortcuts while focused,
+/// e.g. remote desktop clients or virtual machines.
+///
+/// Super+Escape always reaches the compositor and lifts the inhibitor of the focused window.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShortcutsInhibitPolicy {
+    /// Whether applications without a rule may inhibit shortcuts
+    pub allow_by_default: bool,
+    /// Allow (`true`) or deny (`false`) inhibiting shortcuts by app id
+    pub apps: HashMap<String, bool>,
+}
+
+impl Default for ShortcutsInhibitPolicy {
+    fn default() -> Self {
+        Self {
+            allow_by_default: true,
+            apps: HashMap::new(),
+        }
+    }
+}
+
+impl ShortcutsInhibitPolicy {
+    /// Whether the application with `app_id` may inhibit shortcuts
+    pub fn allows(&self, app_id: Option<&str>) -> bool {
+        app_id
+            .and_then(|app_id| self.apps.get(app_id))
+            .copied()
+            .unwrap_or(self.allow_by_default)
+    }
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+use crate::DecorationMode;
+
+/// Actions applied to new toplevels matching all patterns of the rule.
+///
+/// Patterns are regular expressions, unset patterns match any window.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WindowRule {
+    /// Pattern for the app id
+    pub app_id: Option<String>,
+    /// Pattern for the title at the time the window is mapped
+    pub title: Option<String>,
+    /// Pattern for the class of X11 windows, never matches Wayland windows
+    pub class: Option<String>,
+    pub actions: WindowRuleActions,
+}
+
+/// What to do with a window matching a [`WindowRule`].
+///
+/// If multiple rules match, unset actions of later rules don't override earlier ones.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WindowRuleActions {
+    /// Map the window floating or tiled, regardless of the tiling state of the workspace
+    pub floating: Option<bool>,
+    /// Name of the output to map the window on, e.g. `DP-1`
+    pub output: Option<String>,
+    /// Index of the workspace to map the window on, starting at 1
+    pub workspace: Option<u32>,
+    /// Initial size in logical pixels
+    pub size: Option<(i32, i32)>,
+    /// Initial position of floating windows in logical pixels relative to the output
+    pub position: Option<(i32, i32)>,
+    /// Opacity of the window from `0.0` to `1.0`
+    pub opacity: Option<f32>,
+    /// Force decorations, regardless of the mode the window requests
+    pub decorations: Option<DecorationMode>,
+    /// Don't focus the window when it is mapped
+    pub no_focus_steal: Option<bool>,
+}
+
+impl WindowRuleActions {
+    /// Overrides the actions set in `self` with the ones set in `other`
+    pub fn merge(&mut self, other: &WindowRuleActions) {
+        let WindowRuleActions {
+            floating,
+            output,
+            workspace,
+            size,
+            position,
+            opacity,
+            decorations,
+            no_focus_steal,
+        } = other.clone();
+        self.floating = floating.or(self.floating);
+        self.output = output.or(self.output.take());
+        self.workspace = workspace.or(self.workspace);
+        self.size = size.or(self.size);
+        self.position = position.or(self.position);
+        self.opacity = opacity.or(self.opacity);
+        self.decorations = decorations.or(self.decorations);
+        self.no_focus_steal = no_focus_steal.or(self.no_focus_steal);
+    }
+}
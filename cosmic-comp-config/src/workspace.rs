@@ -33,6 +33,28 @@ pub enum WorkspaceLayout {
     Horizontal,
 }
 
+/// How windows are arranged on tiled workspaces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TilingAlgorithm {
+    /// Windows are split freely, new windows split the focused one
+    #[default]
+    Tree,
+    /// The first `masters` windows share a column taking `ratio` per mille of the output's
+    /// width, the remaining windows are stacked in a column next to it
+    MasterStack { masters: u32, ratio: u32 },
+    /// New windows split the most recently focused one, alternating between splitting
+    /// side by side and on top of each other, so windows spiral into a corner
+    Dwindle,
+}
+
+impl TilingAlgorithm {
+    /// Master-stack arrangement used when the configured algorithm doesn't specify one
+    pub const DEFAULT_MASTER_STACK: TilingAlgorithm = TilingAlgorithm::MasterStack {
+        masters: 1,
+        ratio: 500,
+    };
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OutputMatch {
     pub name: String,
@@ -43,6 +65,9 @@ pub struct OutputMatch {
 pub struct PinnedWorkspace {
     pub output: OutputMatch,
     pub tiling_enabled: bool,
+    /// Algorithm chosen for this workspace, instead of the configured `tiling_algorithm`
+    #[serde(default)]
+    pub tiling_algorithm: Option<TilingAlgorithm>,
     pub id: Option<String>,
     // TODO: name
 }
@@ -6,6 +6,16 @@ grow-window = Grow
 shrink-window = Shrink
 swap-windows = Swap Windows
 stack-windows = Stack Windows
+resize-mode = Resize Mode
+caps-lock-on = Caps Lock On
+caps-lock-off = Caps Lock Off
+num-lock-on = Num Lock On
+num-lock-off = Num Lock Off
+keyboard-brightness = Keyboard Brightness {$percent}%
+keybindings-windows = Windows
+keybindings-workspaces = Workspaces
+keybindings-outputs = Displays
+keybindings-system = System
 unknown-keybinding = <unset>
 window-menu-minimize = Minimize
 window-menu-maximize = Maximize
@@ -20,9 +30,25 @@ window-menu-stack = Create window stack
 window-menu-unstack-all = Unstack windows
 window-menu-unstack = Unstack window
 window-menu-sticky = Sticky window
+window-menu-xwayland-upscale = Render at low resolution
 window-menu-close = Close
 window-menu-close-all = Close all windows
 window-menu-resize-edge-top = Top
 window-menu-resize-edge-left = Left
 window-menu-resize-edge-right = Right
-window-menu-resize-edge-bottom = Bottom
\ No newline at end of file
+window-menu-resize-edge-bottom = Bottom
+unresponsive-window = “{ $app }” is not responding
+unresponsive-window-force-quit = Force Quit
+unresponsive-window-wait = Wait
+consent-screencopy = “{ $app }” wants to record your screen
+consent-clipboard = “{ $app }” wants to access your clipboard
+consent-virtual-input = “{ $app }” wants to control your keyboard and pointer
+consent-allow-once = Allow Once
+consent-allow-until-logout = Allow Until Logout
+consent-always-allow = Always Allow
+consent-deny = Deny
+consent-keyboard-hint = Choose with the arrow keys and confirm with Enter
+lock-fallback-title = The screen locker stopped working
+lock-fallback-hint = Your session is still locked. Switch to another virtual terminal with Ctrl+Alt+F3, log in and run “loginctl unlock-session” to unlock it.
+shortcuts-inhibited = Shortcuts are inhibited, press Super+Escape to restore them
+screenshot-selection-hint = Drag to select an area or click a window. Press Enter for the whole screen or Escape to cancel.
@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Execution of XDG autostart entries.
+//!
+//! When cosmic-comp is started without cosmic-session, nothing else runs the applications
+//! from the `autostart` directories, so the compositor launches them once it is ready.
+//! See <https://specifications.freedesktop.org/autostart-spec/latest/>.
+
+use std::{collections::HashMap, fs, path::Path};
+use tracing::{debug, info, warn};
+
+use crate::{state::State, utils::env::bool_var};
+
+const DESKTOP_NAME: &str = "COSMIC";
+
+/// The relevant keys of a `[Desktop Entry]` group
+#[derive(Debug, Default)]
+struct DesktopEntry {
+    keys: HashMap<String, String>,
+}
+
+impl DesktopEntry {
+    fn parse(content: &str) -> DesktopEntry {
+        let mut keys = HashMap::new();
+        let mut in_entry = false;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_entry {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                // localized keys (`Name[de]`) are of no interest here
+                let key = key.trim();
+                if !key.contains('[') {
+                    keys.insert(key.to_string(), value.trim().to_string());
+                }
+            }
+        }
+        DesktopEntry { keys }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.keys.get(key).map(String::as_str)
+    }
+
+    fn get_bool(&self, key: &str) -> bool {
+        self.get(key) == Some("true")
+    }
+
+    fn get_list(&self, key: &str) -> impl Iterator<Item = &str> {
+        self.get(key)
+            .unwrap_or_default()
+            .split(';')
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Whether this entry should be started in a COSMIC session
+    fn should_autostart(&self) -> bool {
+        if self.get("Type").is_some_and(|t| t != "Application")
+            || self.get_bool("Hidden")
+            || self.get("Exec").is_none_or(str::is_empty)
+        {
+            return false;
+        }
+
+        if self.keys.contains_key("OnlyShowIn")
+            && !self.get_list("OnlyShowIn").any(|d| d == DESKTOP_NAME)
+        {
+            return false;
+        }
+        if self.get_list("NotShowIn").any(|d| d == DESKTOP_NAME) {
+            return false;
+        }
+
+        // `X-GNOME-Autostart-enabled`, `X-KDE-autostart-enabled`, ...
+        if self.keys.iter().any(|(key, value)| {
+            let key = key.to_lowercase();
+            key.starts_with("x-") && key.ends_with("-autostart-enabled") && value == "false"
+        }) {
+            return false;
+        }
+        for (key, condition) in self.keys.iter() {
+            let lower = key.to_lowercase();
+            if lower.starts_with("x-")
+                && lower.ends_with("-autostartcondition")
+                && !condition_holds(condition)
+            {
+                return false;
+            }
+        }
+
+        if let Some(try_exec) = self.get("TryExec") {
+            if !executable_exists(try_exec) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// The command line to pass to `sh -c`
+    fn command(&self) -> Option<String> {
+        let exec = strip_field_codes(self.get("Exec")?);
+        Some(match self.get("Path").filter(|p| !p.is_empty()) {
+            Some(path) => format!("cd '{}' && exec {}", path.replace('\'', "'\\''"), exec),
+            None => exec,
+        })
+    }
+}
+
+/// Evaluates the conditions commonly used by `X-GNOME-AutostartCondition`.
+///
+/// Conditions depending on the settings of other desktops can't be evaluated
+/// and are treated as not holding.
+fn condition_holds(condition: &str) -> bool {
+    let (kind, arg) = condition
+        .trim()
+        .split_once(char::is_whitespace)
+        .map(|(kind, arg)| (kind, arg.trim()))
+        .unwrap_or((condition.trim(), ""));
+    let config_file_exists = || {
+        let path = Path::new(arg);
+        if path.is_absolute() {
+            path.exists()
+        } else {
+            xdg::BaseDirectories::new()
+                .get_config_home()
+                .is_some_and(|config| config.join(path).exists())
+        }
+    };
+
+    match kind {
+        "if-exists" => config_file_exists(),
+        "unless-exists" => !config_file_exists(),
+        "if-session" => arg.eq_ignore_ascii_case(DESKTOP_NAME),
+        "unless-session" => !arg.eq_ignore_ascii_case(DESKTOP_NAME),
+        _ => false,
+    }
+}
+
+fn executable_exists(exec: &str) -> bool {
+    let path = Path::new(exec);
+    if path.is_absolute() {
+        return path.exists();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(exec).exists()))
+}
+
+/// Removes the `%f`, `%U`, ... field codes of an `Exec` key, as there are no files to pass.
+///
+/// Arguments only consisting of a field code are removed with their separating whitespace,
+/// everything else, including quoted arguments, is left as is.
+fn strip_field_codes(exec: &str) -> String {
+    let mut result = String::with_capacity(exec.len());
+    let mut chars = exec.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                result.push(c);
+                result.extend(chars.next());
+            }
+            '"' => {
+                quoted = !quoted;
+                result.push(c);
+            }
+            '%' => match chars.next() {
+                Some('%') => result.push('%'),
+                // field codes aren't allowed in quoted arguments, keep them
+                Some(code) if quoted => {
+                    result.push('%');
+                    result.push(code);
+                }
+                // deprecated or unsupported field codes are dropped as well
+                Some(_) => {
+                    let argument = result.chars().last().is_none_or(char::is_whitespace)
+                        && chars.peek().is_none_or(|c| c.is_whitespace());
+                    if argument {
+                        result.truncate(result.trim_end().len());
+                        if result.is_empty() {
+                            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                        }
+                    }
+                }
+                None => {}
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Whether autostart entries should be run by the compositor.
+///
+/// cosmic-session already takes care of autostart and nested sessions shouldn't start
+/// the user's apps again, so this defaults to sessions on the kms backend started
+/// without cosmic-session. `COSMIC_AUTOSTART` may be used to override the default.
+pub fn enabled(kms: bool) -> bool {
+    bool_var("COSMIC_AUTOSTART")
+        .unwrap_or_else(|| kms && std::env::var_os("COSMIC_SESSION_SOCK").is_none())
+}
+
+impl State {
+    /// Launches all applicable XDG autostart entries
+    pub fn run_autostart(&mut self) {
+        let xdg = xdg::BaseDirectories::new();
+        // user entries shadow system entries of the same name
+        let mut files = xdg.list_config_files_once("autostart");
+        files.retain(|path| path.extension().is_some_and(|ext| ext == "desktop"));
+        files.sort();
+
+        for path in files {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(err) => {
+                    warn!(?err, "Failed to read autostart entry {:?}", path);
+                    continue;
+                }
+            };
+            let entry = DesktopEntry::parse(&content);
+            if !entry.should_autostart() {
+                debug!("Skipping autostart entry {:?}", path);
+                continue;
+            }
+            let Some(command) = entry.command() else {
+                continue;
+            };
+
+            info!("Autostarting {:?}", path);
+            self.spawn_command(command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::strip_field_codes;
+
+    #[test]
+    fn test_strip_field_codes() {
+        assert_eq!(strip_field_codes("app"), "app");
+        assert_eq!(strip_field_codes("app %U"), "app");
+        assert_eq!(strip_field_codes("app %f --flag"), "app --flag");
+        assert_eq!(strip_field_codes("app  --a   %u  --b"), "app  --a  --b");
+        assert_eq!(strip_field_codes("%k app"), "app");
+        assert_eq!(strip_field_codes("app --file=%f"), "app --file=");
+        assert_eq!(strip_field_codes("app 100%%"), "app 100%");
+        assert_eq!(
+            strip_field_codes(r#"sh -c "echo  'a   b' %u" %U"#),
+            r#"sh -c "echo  'a   b' %u""#
+        );
+        assert_eq!(
+            strip_field_codes(r#"app "quoted \"  %F" %F"#),
+            r#"app "quoted \"  %F""#
+        );
+    }
+}
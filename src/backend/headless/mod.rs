@@ -0,0 +1,830 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Backend without any physical outputs or input devices.
+//!
+//! Outputs are rendered offscreen with the software renderer and input is injected through
+//! [`HeadlessCommand`]s, which makes this backend suitable for test harnesses (like wlcs)
+//! and automated rendering.
+
+use crate::{backend::render, config::ScreenFilter, state::BackendData, utils::prelude::*};
+use anyhow::{Context, Result};
+use cosmic_comp_config::output::comp::OutputConfig;
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        input::{
+            AbsolutePositionEvent, ButtonState, Device, DeviceCapability, Event, InputBackend,
+            InputEvent, KeyState, KeyboardKeyEvent, Keycode, PointerButtonEvent,
+            PointerMotionAbsoluteEvent, PointerMotionEvent, TouchDownEvent, TouchEvent,
+            TouchFrameEvent, TouchMotionEvent, TouchSlot, TouchUpEvent, UnusedEvent,
+        },
+        renderer::{
+            Bind, Offscreen,
+            damage::{OutputDamageTracker, RenderOutputResult},
+            gles::GlesRenderbuffer,
+            glow::GlowRenderer,
+        },
+    },
+    output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
+    reexports::{
+        calloop::{
+            EventLoop, LoopHandle, channel,
+            timer::{TimeoutAction, Timer},
+        },
+        wayland_protocols::wp::presentation_time::server::wp_presentation_feedback,
+        wayland_server::{Client, DisplayHandle, Resource},
+    },
+    utils::{Buffer as BufferCoords, Point, Size, Transform},
+    wayland::{presentation::Refresh, seat::WaylandFocus},
+};
+use std::{
+    borrow::BorrowMut, cell::RefCell, collections::HashMap, os::unix::net::UnixStream,
+    path::PathBuf, sync::Arc, time::Duration,
+};
+use tracing::{error, info, warn};
+
+use super::render::{CursorMode, ScreenFilterStorage, init_shaders};
+
+pub mod scene;
+
+const REFRESH_RATE: i32 = 60_000;
+
+/// How a headless compositor, that isn't started via `COSMIC_BACKEND`, is driven
+#[derive(Debug)]
+pub enum HeadlessMode {
+    /// Controlled by a test harness
+    Commands(channel::Channel<HeadlessCommand>),
+    /// Render a scripted scene to a PNG file and exit (see [`scene`])
+    Screenshot { scene: PathBuf, output: PathBuf },
+}
+
+/// Commands to drive the headless backend from a test harness
+#[derive(Debug)]
+pub enum HeadlessCommand {
+    /// Add a new client connected through the given socket
+    NewClient {
+        stream: UnixStream,
+        /// Identifier chosen by the caller to refer to this client
+        id: i32,
+    },
+    /// Move the toplevel of the surface with the given protocol id of a client,
+    /// previously added by [`HeadlessCommand::NewClient`], to a global position.
+    PositionWindow {
+        client: i32,
+        surface: u32,
+        position: Point<i32, Global>,
+    },
+    PointerMotionAbsolute(Point<f64, Global>),
+    PointerMotionRelative(Point<f64, Global>),
+    PointerButton {
+        button: u32,
+        pressed: bool,
+    },
+    Key {
+        /// evdev keycode
+        keycode: u32,
+        pressed: bool,
+    },
+    TouchDown {
+        id: u32,
+        position: Point<f64, Global>,
+    },
+    TouchMotion {
+        id: u32,
+        position: Point<f64, Global>,
+    },
+    TouchUp {
+        id: u32,
+    },
+    /// Add a virtual output with the given name, the names of existing outputs can't be reused
+    AddOutput {
+        name: String,
+        size: Size<i32, Physical>,
+    },
+    RemoveOutput {
+        name: String,
+    },
+    /// Render all outputs with pending damage (required in manual frame mode)
+    Step,
+    Stop,
+}
+
+#[derive(Debug)]
+pub struct HeadlessState {
+    pub renderer: GlowRenderer,
+    surfaces: Vec<Surface>,
+    clients: HashMap<i32, Client>,
+    device: HeadlessDevice,
+    loop_handle: LoopHandle<'static, State>,
+    /// Only render on [`HeadlessCommand::Step`] instead of a simulated refresh cycle
+    manual_frames: bool,
+    frame_pending: bool,
+    /// Error a scene failed with, returned once the event loop stopped
+    pub failure: Option<anyhow::Error>,
+}
+
+#[derive(Debug)]
+struct Surface {
+    output: Output,
+    buffer: GlesRenderbuffer,
+    size: Size<i32, Physical>,
+    damage_tracker: OutputDamageTracker,
+    screen_filter_state: ScreenFilterStorage,
+    rendered: bool,
+    dirty: bool,
+}
+
+impl HeadlessState {
+    pub fn add_output(&mut self, name: &str, size: Size<i32, Physical>) -> Result<Output> {
+        let props = PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: "COSMIC".to_string(),
+            model: name.to_string(),
+            serial_number: "Unknown".to_string(),
+        };
+        let mode = Mode {
+            size,
+            refresh: REFRESH_RATE,
+        };
+        // place new outputs next to each other
+        let x = self.surfaces.iter().map(|s| s.size.w).sum::<i32>();
+        let output = Output::new(name.to_string(), props);
+        output.add_mode(mode);
+        output.set_preferred(mode);
+        output.change_current_state(
+            Some(mode),
+            Some(Transform::Normal),
+            Some(Scale::Integer(1)),
+            Some((x, 0).into()),
+        );
+        output.user_data().insert_if_missing(|| {
+            RefCell::new(OutputConfig {
+                mode: ((size.w, size.h), Some(REFRESH_RATE as u32)),
+                position: (x as u32, 0),
+                ..Default::default()
+            })
+        });
+
+        let buffer = Offscreen::<GlesRenderbuffer>::create_buffer(
+            &mut self.renderer,
+            Fourcc::Abgr8888,
+            (size.w, size.h).into(),
+        )
+        .with_context(|| "Failed to create offscreen buffer")?;
+
+        self.surfaces.push(Surface {
+            damage_tracker: OutputDamageTracker::from_output(&output),
+            output: output.clone(),
+            buffer,
+            size,
+            screen_filter_state: ScreenFilterStorage::default(),
+            rendered: false,
+            dirty: true,
+        });
+
+        Ok(output)
+    }
+
+    pub fn remove_output(&mut self, name: &str) -> Option<Output> {
+        let index = self.surfaces.iter().position(|s| s.output.name() == name)?;
+        Some(self.surfaces.remove(index).output)
+    }
+
+    pub fn schedule_render(&mut self, output: &Output) {
+        if let Some(surface) = self.surfaces.iter_mut().find(|s| s.output == *output) {
+            surface.dirty = true;
+        }
+        if self.manual_frames || self.frame_pending {
+            return;
+        }
+
+        self.frame_pending = true;
+        if let Err(err) = self.loop_handle.insert_source(
+            Timer::from_duration(Duration::from_millis(1_000_000 / REFRESH_RATE as u64)),
+            |_, _, state| {
+                let headless = state.backend.headless();
+                headless.frame_pending = false;
+                headless.render_dirty(&mut state.common);
+                TimeoutAction::Drop
+            },
+        ) {
+            self.frame_pending = false;
+            error!(?err, "Failed to schedule frame");
+        }
+    }
+
+    /// Render all outputs, that have been scheduled since the last frame.
+    pub fn render_dirty(&mut self, common: &mut Common) {
+        for i in 0..self.surfaces.len() {
+            if !self.surfaces[i].dirty {
+                continue;
+            }
+            self.surfaces[i].dirty = false;
+            if let Err(err) = self.render_surface(i, common) {
+                error!(?err, "Error rendering.");
+            }
+        }
+    }
+
+    fn render_surface(&mut self, index: usize, common: &mut Common) -> Result<()> {
+        let surface = &mut self.surfaces[index];
+        let age = if surface.rendered { 1 } else { 0 };
+        let mut fb = self
+            .renderer
+            .bind(&mut surface.buffer)
+            .with_context(|| "Failed to bind offscreen buffer")?;
+        match render::render_output(
+            None,
+            &mut self.renderer,
+            &mut fb,
+            &mut surface.damage_tracker,
+            age,
+            &common.shell,
+            common.clock.now(),
+            &surface.output,
+            CursorMode::NotDefault,
+            &mut surface.screen_filter_state,
+            &common.event_loop_handle,
+        ) {
+            Ok(RenderOutputResult { damage, states, .. }) => {
+                surface.rendered = true;
+                common.send_frames(&surface.output, None);
+                common.update_primary_output(&surface.output, &states);
+                common.send_dmabuf_feedback(&surface.output, &states, |_| None);
+                if damage.is_some() {
+                    let mut output_presentation_feedback = common
+                        .shell
+                        .read()
+                        .take_presentation_feedback(&surface.output, &states);
+                    output_presentation_feedback.presented(
+                        common.clock.now(),
+                        Refresh::Fixed(Duration::from_secs_f64(1_000.0 / REFRESH_RATE as f64)),
+                        0,
+                        wp_presentation_feedback::Kind::empty(),
+                    );
+                }
+                Ok(())
+            }
+            Err(err) => {
+                surface.rendered = false;
+                anyhow::bail!("Rendering failed: {}", err)
+            }
+        }
+    }
+
+    /// Read back the last rendered frame of `output` as 8-bit RGBA.
+    pub fn read_pixels(&mut self, output: &Output) -> Result<(Size<i32, BufferCoords>, Vec<u8>)> {
+        use smithay::backend::renderer::ExportMem;
+        use smithay::utils::Rectangle;
+
+        let surface = self
+            .surfaces
+            .iter_mut()
+            .find(|s| s.output == *output)
+            .with_context(|| "Unknown output")?;
+        let size = Size::<i32, BufferCoords>::from((surface.size.w, surface.size.h));
+        let fb = self
+            .renderer
+            .bind(&mut surface.buffer)
+            .with_context(|| "Failed to bind offscreen buffer")?;
+        let mapping = self
+            .renderer
+            .copy_framebuffer(&fb, Rectangle::from_size(size), Fourcc::Abgr8888)
+            .with_context(|| "Failed to copy framebuffer")?;
+        std::mem::drop(fb);
+        let data = self
+            .renderer
+            .map_texture(&mapping)
+            .with_context(|| "Failed to map framebuffer")?;
+        Ok((size, data.to_vec()))
+    }
+
+    pub fn all_outputs(&self) -> Vec<Output> {
+        self.surfaces.iter().map(|s| s.output.clone()).collect()
+    }
+
+    pub fn apply_config_for_outputs(&mut self, test_only: bool) -> Result<(), anyhow::Error> {
+        // virtual outputs support any mode, we just need to resize our buffers
+        for surface in &mut self.surfaces {
+            let size: Size<i32, Physical> = surface
+                .output
+                .user_data()
+                .get::<RefCell<OutputConfig>>()
+                .unwrap()
+                .borrow()
+                .mode
+                .0
+                .into();
+            if test_only || surface.size == size {
+                continue;
+            }
+
+            surface.buffer = Offscreen::<GlesRenderbuffer>::create_buffer(
+                &mut self.renderer,
+                Fourcc::Abgr8888,
+                (size.w, size.h).into(),
+            )
+            .with_context(|| "Failed to create offscreen buffer")?;
+            surface.size = size;
+            surface.damage_tracker = OutputDamageTracker::from_output(&surface.output);
+            surface.rendered = false;
+            surface.dirty = true;
+        }
+        Ok(())
+    }
+
+    pub fn update_screen_filter(&mut self, screen_filter: &ScreenFilter) -> Result<()> {
+        for surface in &mut self.surfaces {
+            surface.screen_filter_state.filter = screen_filter.clone();
+        }
+        Ok(())
+    }
+}
+
+/// Parse `COSMIC_HEADLESS_OUTPUTS`, a comma-separated list of `<width>x<height>` modes.
+fn output_sizes() -> Vec<Size<i32, Physical>> {
+    let sizes = std::env::var("COSMIC_HEADLESS_OUTPUTS")
+        .ok()
+        .map(|value| {
+            value
+                .split(',')
+                .filter_map(|mode| {
+                    let (w, h) = mode.trim().split_once('x')?;
+                    Some(Size::from((w.parse().ok()?, h.parse().ok()?)))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if sizes.is_empty() {
+        vec![Size::from((1920, 1080))]
+    } else {
+        sizes
+    }
+}
+
+pub fn init_backend(
+    _dh: &DisplayHandle,
+    event_loop: &mut EventLoop<State>,
+    state: &mut State,
+    mode: Option<HeadlessMode>,
+) -> Result<()> {
+    let mut renderer =
+        super::kms::software_renderer().context("Failed to initialize software renderer")?;
+    init_shaders(renderer.borrow_mut()).context("Failed to initialize renderer")?;
+
+    state.backend = BackendData::Headless(HeadlessState {
+        renderer,
+        surfaces: Vec::new(),
+        clients: HashMap::new(),
+        device: HeadlessDevice,
+        loop_handle: event_loop.handle(),
+        manual_frames: crate::utils::env::bool_var("COSMIC_HEADLESS_MANUAL_FRAMES")
+            .unwrap_or(false),
+        frame_pending: false,
+        failure: None,
+    });
+
+    let mut outputs = Vec::new();
+    for (i, size) in output_sizes().into_iter().enumerate() {
+        let output = state
+            .backend
+            .headless()
+            .add_output(&format!("HEADLESS-{}", i), size)
+            .with_context(|| "Failed to create wl_output")?;
+        outputs.push(output);
+    }
+    state
+        .common
+        .output_configuration_state
+        .add_heads(outputs.iter());
+    for output in &outputs {
+        state.common.add_output(output);
+    }
+    if let Err(err) = state.common.config.read_outputs(
+        &mut state.common.output_configuration_state,
+        &mut state.backend,
+        &state.common.shell,
+        &state.common.event_loop_handle,
+        &mut state.common.workspace_state.update(),
+        &state.common.xdg_activation_state,
+        state.common.startup_done.clone(),
+        &state.common.clock,
+    ) {
+        error!("Unrecoverable output configuration error: {}", err);
+    }
+    state.common.refresh();
+
+    // The seat is only created after the backend is initialized.
+    event_loop.handle().insert_idle(|state| {
+        let device = state.backend.headless().device.clone();
+        state.process_input_event(InputEvent::<HeadlessInput>::DeviceAdded { device });
+    });
+
+    // unless started as a regular session, don't start Xwayland or signal readiness
+    match mode {
+        Some(HeadlessMode::Commands(commands)) => {
+            event_loop
+                .handle()
+                .insert_source(commands, |event, _, state| match event {
+                    channel::Event::Msg(command) => state.process_headless_command(command),
+                    channel::Event::Closed => state.common.should_stop = true,
+                })
+                .map_err(|_| anyhow::anyhow!("Failed to insert headless command channel"))?;
+        }
+        Some(HeadlessMode::Screenshot { scene, output }) => {
+            scene::run(state, &scene, output)?;
+        }
+        None => {
+            // a regular session without seat, that remote desktop daemons drive over D-Bus
+            let (commands, channel) = channel::channel();
+            event_loop
+                .handle()
+                .insert_source(channel, |event, _, state| {
+                    if let channel::Event::Msg(command) = event {
+                        state.process_headless_command(command);
+                    }
+                })
+                .map_err(|_| anyhow::anyhow!("Failed to insert remote session channel"))?;
+            crate::dbus::remote_session::serve(
+                commands,
+                state.common.policy_state.clone(),
+                &state.common.async_executor,
+            );
+            state.launch_xwayland(None);
+        }
+    }
+
+    info!(
+        "Headless backend initialized with {} outputs",
+        outputs.len()
+    );
+    Ok(())
+}
+
+impl State {
+    pub fn process_headless_command(&mut self, command: HeadlessCommand) {
+        let time = Duration::from(self.common.clock.now()).as_micros() as u64;
+        let event = |position: Option<Point<f64, Global>>| HeadlessInputEvent {
+            time,
+            position: position.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        match command {
+            HeadlessCommand::NewClient { stream, id } => {
+                let client_state = self.new_client_state(&stream);
+                match self
+                    .common
+                    .display_handle
+                    .insert_client(stream, Arc::new(client_state))
+                {
+                    Ok(client) => {
+                        self.backend.headless().clients.insert(id, client);
+                    }
+                    Err(err) => warn!(?err, "Error adding wayland client"),
+                }
+            }
+            HeadlessCommand::PositionWindow {
+                client,
+                surface,
+                position,
+            } => {
+                let Some(client) = self.backend.headless().clients.get(&client).cloned() else {
+                    warn!(client, "Unknown client");
+                    return;
+                };
+                let mut shell = self.common.shell.write();
+                let Some(mapped) = shell
+                    .workspaces
+                    .spaces()
+                    .flat_map(|w| w.mapped())
+                    .find(|m| {
+                        m.windows().any(|(w, _)| {
+                            w.wl_surface().is_some_and(|s| {
+                                s.id().protocol_id() == surface
+                                    && s.client().as_ref() == Some(&client)
+                            })
+                        })
+                    })
+                    .cloned()
+                else {
+                    warn!(surface, "Unknown surface");
+                    return;
+                };
+                if let Some(workspace) = shell.space_for_mut(&mapped) {
+                    if workspace.is_floating(&mapped.active_window()) {
+                        let position = position.to_local(workspace.output());
+                        workspace.floating_layer.map(mapped, position);
+                    }
+                }
+            }
+            HeadlessCommand::PointerMotionAbsolute(position) => {
+                let Some(output) = self.output_at(position) else {
+                    return;
+                };
+                let seat = self.common.shell.read().seats.last_active().clone();
+                seat.set_active_output(&output);
+                let geometry = output.geometry().to_f64();
+                let relative = Point::<f64, Global>::from((
+                    (position.x - geometry.loc.x) / geometry.size.w,
+                    (position.y - geometry.loc.y) / geometry.size.h,
+                ));
+                self.process_input_event(InputEvent::<HeadlessInput>::PointerMotionAbsolute {
+                    event: event(Some(relative)),
+                });
+            }
+            HeadlessCommand::PointerMotionRelative(delta) => {
+                self.process_input_event(InputEvent::<HeadlessInput>::PointerMotion {
+                    event: HeadlessInputEvent {
+                        delta,
+                        ..event(None)
+                    },
+                });
+            }
+            HeadlessCommand::PointerButton { button, pressed } => {
+                self.process_input_event(InputEvent::<HeadlessInput>::PointerButton {
+                    event: HeadlessInputEvent {
+                        code: button,
+                        pressed,
+                        ..event(None)
+                    },
+                });
+            }
+            HeadlessCommand::Key { keycode, pressed } => {
+                self.process_input_event(InputEvent::<HeadlessInput>::Keyboard {
+                    event: HeadlessInputEvent {
+                        code: keycode,
+                        pressed,
+                        ..event(None)
+                    },
+                });
+            }
+            HeadlessCommand::TouchDown { id, position } => {
+                let position = self.normalized_position(position);
+                self.process_input_event(InputEvent::<HeadlessInput>::TouchDown {
+                    event: HeadlessInputEvent {
+                        code: id,
+                        ..event(position)
+                    },
+                });
+                self.process_input_event(InputEvent::<HeadlessInput>::TouchFrame {
+                    event: event(None),
+                });
+            }
+            HeadlessCommand::TouchMotion { id, position } => {
+                let position = self.normalized_position(position);
+                self.process_input_event(InputEvent::<HeadlessInput>::TouchMotion {
+                    event: HeadlessInputEvent {
+                        code: id,
+                        ..event(position)
+                    },
+                });
+                self.process_input_event(InputEvent::<HeadlessInput>::TouchFrame {
+                    event: event(None),
+                });
+            }
+            HeadlessCommand::TouchUp { id } => {
+                self.process_input_event(InputEvent::<HeadlessInput>::TouchUp {
+                    event: HeadlessInputEvent {
+                        code: id,
+                        ..event(None)
+                    },
+                });
+                self.process_input_event(InputEvent::<HeadlessInput>::TouchFrame {
+                    event: event(None),
+                });
+            }
+            HeadlessCommand::AddOutput { name, size } => {
+                if self.common.shell.read().outputs().any(|o| o.name() == name) {
+                    warn!(name, "Output already exists");
+                    return;
+                }
+                let output = match self.backend.headless().add_output(&name, size) {
+                    Ok(output) => output,
+                    Err(err) => {
+                        warn!(?err, "Failed to add virtual output");
+                        return;
+                    }
+                };
+                self.common
+                    .output_configuration_state
+                    .add_heads(std::iter::once(&output));
+                self.common.add_output(&output);
+                if let Err(err) = self.refresh_output_config() {
+                    error!(?err, "Failed to apply output configuration");
+                }
+            }
+            HeadlessCommand::RemoveOutput { name } => {
+                // seats always need an active output
+                if self.backend.headless().surfaces.len() <= 1 {
+                    warn!(name, "Refusing to remove the last output");
+                    return;
+                }
+                let Some(output) = self.backend.headless().remove_output(&name) else {
+                    warn!(name, "Unknown output");
+                    return;
+                };
+                self.common
+                    .output_configuration_state
+                    .remove_heads(std::iter::once(&output));
+                self.common.remove_output(&output);
+                if let Err(err) = self.refresh_output_config() {
+                    error!(?err, "Failed to apply output configuration");
+                }
+            }
+            HeadlessCommand::Step => {
+                self.backend.headless().render_dirty(&mut self.common);
+            }
+            HeadlessCommand::Stop => {
+                self.common.should_stop = true;
+            }
+        }
+    }
+
+    fn output_at(&self, position: Point<f64, Global>) -> Option<Output> {
+        self.common
+            .shell
+            .read()
+            .outputs()
+            .find(|output| output.geometry().to_f64().contains(position))
+            .cloned()
+    }
+
+    /// Touch events are mapped to the first output (see `mapped_output_for_device`).
+    fn normalized_position(&self, position: Point<f64, Global>) -> Option<Point<f64, Global>> {
+        let shell = self.common.shell.read();
+        let geometry = shell.outputs().next()?.geometry().to_f64();
+        Some(Point::from((
+            (position.x - geometry.loc.x) / geometry.size.w,
+            (position.y - geometry.loc.y) / geometry.size.h,
+        )))
+    }
+}
+
+/// Input backend for events injected through [`HeadlessCommand`]s
+#[derive(Debug)]
+pub struct HeadlessInput;
+
+/// The single virtual device of the headless backend
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HeadlessDevice;
+
+impl Device for HeadlessDevice {
+    fn id(&self) -> String {
+        "headless".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Headless virtual device".to_string()
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        matches!(
+            capability,
+            DeviceCapability::Keyboard | DeviceCapability::Pointer | DeviceCapability::Touch
+        )
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Event type shared by all injected events.
+///
+/// `position` is normalized to the output for absolute events, `code` is either a keycode,
+/// a button code or a touch slot depending on the event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeadlessInputEvent {
+    time: u64,
+    position: Point<f64, Global>,
+    delta: Point<f64, Global>,
+    code: u32,
+    pressed: bool,
+}
+
+impl InputBackend for HeadlessInput {
+    type Device = HeadlessDevice;
+    type KeyboardKeyEvent = HeadlessInputEvent;
+    type PointerAxisEvent = UnusedEvent;
+    type PointerButtonEvent = HeadlessInputEvent;
+    type PointerMotionEvent = HeadlessInputEvent;
+    type PointerMotionAbsoluteEvent = HeadlessInputEvent;
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = HeadlessInputEvent;
+    type TouchUpEvent = HeadlessInputEvent;
+    type TouchMotionEvent = HeadlessInputEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = HeadlessInputEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+impl Event<HeadlessInput> for HeadlessInputEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> HeadlessDevice {
+        HeadlessDevice
+    }
+}
+
+impl KeyboardKeyEvent<HeadlessInput> for HeadlessInputEvent {
+    fn key_code(&self) -> Keycode {
+        // evdev to xkb keycode, like libinput
+        Keycode::new(self.code + 8)
+    }
+
+    fn state(&self) -> KeyState {
+        if self.pressed {
+            KeyState::Pressed
+        } else {
+            KeyState::Released
+        }
+    }
+
+    fn count(&self) -> u32 {
+        self.pressed as u32
+    }
+}
+
+impl PointerButtonEvent<HeadlessInput> for HeadlessInputEvent {
+    fn button_code(&self) -> u32 {
+        self.code
+    }
+
+    fn state(&self) -> ButtonState {
+        if self.pressed {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Released
+        }
+    }
+}
+
+impl PointerMotionEvent<HeadlessInput> for HeadlessInputEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.x
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.y
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.delta.x
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.delta.y
+    }
+}
+
+impl AbsolutePositionEvent<HeadlessInput> for HeadlessInputEvent {
+    fn x(&self) -> f64 {
+        self.position.x
+    }
+
+    fn y(&self) -> f64 {
+        self.position.y
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        self.position.x * width as f64
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        self.position.y * height as f64
+    }
+}
+
+impl PointerMotionAbsoluteEvent<HeadlessInput> for HeadlessInputEvent {}
+
+impl TouchEvent<HeadlessInput> for HeadlessInputEvent {
+    fn slot(&self) -> TouchSlot {
+        Some(self.code).into()
+    }
+}
+
+impl TouchDownEvent<HeadlessInput> for HeadlessInputEvent {}
+impl TouchUpEvent<HeadlessInput> for HeadlessInputEvent {}
+impl TouchMotionEvent<HeadlessInput> for HeadlessInputEvent {}
+impl TouchFrameEvent<HeadlessInput> for HeadlessInputEvent {}
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Scripted scenes for visual regression tests.
+//!
+//! A scene spawns a solid-color test client per window, waits for all of them to be mapped
+//! and placed, renders every output once and writes the result to a PNG file, before
+//! shutting down the compositor.
+//!
+//! Rendering depends on the usual configuration (gaps, corner radii, ...), so harnesses
+//! should point `XDG_CONFIG_HOME` to a fixed configuration.
+
+use crate::{session, utils::prelude::*};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use smithay::{
+    output::Output,
+    reexports::calloop::timer::{TimeoutAction, Timer},
+    utils::Point,
+};
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Deserialize)]
+pub struct Scene {
+    /// Test client to spawn for every window
+    #[serde(default = "default_client")]
+    pub client: String,
+    pub windows: Vec<SceneWindow>,
+    /// Time to wait after all windows are placed, so animations can finish
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u64,
+    /// Time to wait for all windows to be mapped
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SceneWindow {
+    pub app_id: String,
+    /// `RRGGBB` or `RRGGBBAA`
+    pub color: String,
+    pub size: (u32, u32),
+    /// Global position of a floating window, windows without a position are left
+    /// to the active layout.
+    #[serde(default)]
+    pub position: Option<(i32, i32)>,
+}
+
+fn default_client() -> String {
+    String::from("cosmic-comp-test-client")
+}
+
+fn default_settle_ms() -> u64 {
+    1000
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Scene> {
+        let file = File::open(path).with_context(|| format!("Failed to open scene {:?}", path))?;
+        ron::de::from_reader(file).with_context(|| format!("Failed to parse scene {:?}", path))
+    }
+}
+
+struct SceneRunner {
+    scene: Scene,
+    output_path: PathBuf,
+    children: Vec<Child>,
+    deadline: Instant,
+    placed_at: Option<Instant>,
+}
+
+/// Load the scene at `scene_path` and spawn its clients. The resulting frame is written
+/// to `output_path`, after which the compositor exits.
+pub fn run(state: &mut State, scene_path: &Path, output_path: PathBuf) -> Result<()> {
+    let scene = Scene::load(scene_path)?;
+    let env = session::get_env(&state.common)?;
+
+    let mut children = Vec::with_capacity(scene.windows.len());
+    for window in &scene.windows {
+        let child = Command::new(&scene.client)
+            .arg("--app-id")
+            .arg(&window.app_id)
+            .arg("--color")
+            .arg(&window.color)
+            .arg("--size")
+            .arg(format!("{}x{}", window.size.0, window.size.1))
+            .envs(&env)
+            .spawn()
+            .with_context(|| format!("Failed to spawn test client {:?}", scene.client))?;
+        children.push(child);
+    }
+
+    let mut runner = SceneRunner {
+        deadline: Instant::now() + Duration::from_millis(scene.timeout_ms),
+        scene,
+        output_path,
+        children,
+        placed_at: None,
+    };
+    state
+        .common
+        .event_loop_handle
+        .insert_source(
+            Timer::from_duration(POLL_INTERVAL),
+            move |_, _, state| match runner.poll(state) {
+                Ok(true) => TimeoutAction::ToDuration(POLL_INTERVAL),
+                Ok(false) => {
+                    runner.finish(state);
+                    TimeoutAction::Drop
+                }
+                Err(err) => {
+                    error!("Scene failed: {:#}", err);
+                    runner.finish(state);
+                    state.backend.headless().failure = Some(err);
+                    state.common.event_loop_signal.stop();
+                    state.common.event_loop_signal.wakeup();
+                    TimeoutAction::Drop
+                }
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("Failed to schedule scene"))?;
+
+    Ok(())
+}
+
+impl SceneRunner {
+    /// Advance the scene, returns `false` once the screenshot was taken.
+    fn poll(&mut self, state: &mut State) -> Result<bool> {
+        match self.placed_at {
+            None => {
+                if !self.all_mapped(state) {
+                    anyhow::ensure!(
+                        Instant::now() < self.deadline,
+                        "Timed out waiting for windows to be mapped"
+                    );
+                    return Ok(true);
+                }
+                self.place_windows(state);
+                self.placed_at = Some(Instant::now());
+                Ok(true)
+            }
+            Some(placed_at) => {
+                if placed_at.elapsed() < Duration::from_millis(self.scene.settle_ms) {
+                    return Ok(true);
+                }
+                self.screenshot(state)?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn all_mapped(&self, state: &State) -> bool {
+        let shell = state.common.shell.read();
+        self.scene.windows.iter().all(|window| {
+            shell
+                .workspaces
+                .spaces()
+                .flat_map(|w| w.mapped())
+                .any(|m| m.windows().any(|(s, _)| s.app_id() == window.app_id))
+        })
+    }
+
+    fn place_windows(&self, state: &mut State) {
+        let mut shell = state.common.shell.write();
+        let seat = shell.seats.last_active().clone();
+        for window in &self.scene.windows {
+            let Some(position) = window.position else {
+                continue;
+            };
+            let Some(mapped) = shell
+                .workspaces
+                .spaces()
+                .flat_map(|w| w.mapped())
+                .find(|m| m.windows().any(|(s, _)| s.app_id() == window.app_id))
+                .cloned()
+            else {
+                continue;
+            };
+            if let Some(workspace) = shell.space_for_mut(&mapped) {
+                if !workspace.is_floating(&mapped.active_window()) {
+                    workspace.toggle_floating_window(&seat, &mapped);
+                }
+                let position = Point::<i32, Global>::from(position).to_local(workspace.output());
+                workspace.floating_layer.map(mapped, position);
+            }
+        }
+    }
+
+    fn screenshot(&self, state: &mut State) -> Result<()> {
+        let outputs = state
+            .common
+            .shell
+            .read()
+            .outputs()
+            .cloned()
+            .collect::<Vec<_>>();
+        for output in &outputs {
+            state.backend.schedule_render(output);
+        }
+        let headless = state.backend.headless();
+        headless.render_dirty(&mut state.common);
+
+        for output in &outputs {
+            let path = if outputs.len() == 1 {
+                self.output_path.clone()
+            } else {
+                output_file_name(&self.output_path, output)
+            };
+            let (size, data) = headless.read_pixels(output)?;
+            let file =
+                File::create(&path).with_context(|| format!("Failed to create {:?}", path))?;
+            let mut encoder = png::Encoder::new(BufWriter::new(file), size.w as u32, size.h as u32);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder
+                .write_header()
+                .and_then(|mut writer| writer.write_image_data(&data))
+                .with_context(|| format!("Failed to write {:?}", path))?;
+            info!("Wrote {:?}", path);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, state: &mut State) {
+        for child in &mut self.children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        state.common.should_stop = true;
+    }
+}
+
+/// `out.png` -> `out-HEADLESS-1.png`
+fn output_file_name(path: &Path, output: &Output) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut name = format!("{}-{}", stem, output.name());
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
@@ -385,6 +385,10 @@ impl State {
         self.common
             .output_configuration_state
             .add_heads(wl_outputs.iter());
+        for output in &wl_outputs {
+            crate::dbus::colord::register(output, &self.common.async_executor);
+            crate::dbus::night_light::apply(self, output);
+        }
 
         self.backend.kms().refresh_used_devices()?;
         Ok(wl_outputs)
@@ -472,6 +476,14 @@ impl State {
         self.common
             .output_configuration_state
             .add_heads(outputs_added.iter());
+        for output in &outputs_removed {
+            self.common.gamma_control_state.output_removed(output);
+            crate::dbus::colord::unregister(output, &self.common.async_executor);
+        }
+        for output in &outputs_added {
+            crate::dbus::colord::register(output, &self.common.async_executor);
+            crate::dbus::night_light::apply(self, output);
+        }
 
         for output in outputs_removed {
             self.common.remove_output(&output);
@@ -525,6 +537,10 @@ impl State {
         self.common
             .output_configuration_state
             .remove_heads(outputs_removed.iter());
+        for output in &outputs_removed {
+            self.common.gamma_control_state.output_removed(output);
+            crate::dbus::colord::unregister(output, &self.common.async_executor);
+        }
 
         if backend.session.is_active() {
             for output in outputs_removed {
@@ -666,8 +682,11 @@ impl LockedDevice<'_> {
                     None => Vec::new(),
                 };
 
+                let clear_color = output_map
+                    .get(crtc)
+                    .map_or(CLEAR_COLOR, |output| shell.read().clear_color(output));
                 let mut compositor = compositor.lock().unwrap();
-                compositor.render_frame(renderer, &elements, CLEAR_COLOR, FrameFlags::empty())?;
+                compositor.render_frame(renderer, &elements, clear_color, FrameFlags::empty())?;
                 if let Err(err) = compositor.commit_frame() {
                     if !matches!(err, FrameError::EmptyFrame) {
                         return Err(err.into());
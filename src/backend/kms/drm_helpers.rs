@@ -15,6 +15,8 @@ use smithay::{
 };
 use std::{collections::HashMap, ops::Range};
 
+use crate::utils::gamma::GammaRamp;
+
 pub fn display_configuration(
     device: &mut impl ControlDevice,
     supports_atomic: bool,
@@ -299,6 +301,24 @@ pub fn set_max_bpc(dev: &impl ControlDevice, conn: connector::Handle, bpc: u32)
     })
 }
 
+/// Sets the gamma ramp of `crtc`, resetting it to linear if `None`
+pub fn set_gamma(
+    dev: &impl ControlDevice,
+    crtc: crtc::Handle,
+    ramp: Option<&GammaRamp>,
+) -> Result<()> {
+    let size = dev.get_crtc(crtc)?.gamma_length() as usize;
+    if size == 0 {
+        return Err(anyhow!("crtc does not support gamma ramps"));
+    }
+    let ramp = match ramp {
+        Some(ramp) => ramp.resample(size),
+        None => GammaRamp::linear(size),
+    };
+    dev.set_gamma(crtc, &ramp.red, &ramp.green, &ramp.blue)
+        .context("Failed to set gamma ramp")
+}
+
 pub fn panel_orientation(dev: &impl ControlDevice, conn: connector::Handle) -> Result<Transform> {
     let (val_type, val) = get_property_val(dev, conn, "panel orientation")?;
     match val_type.convert_value(val) {
@@ -4,7 +4,7 @@ use crate::{
     config::{CompOutputConfig, ScreenFilter},
     shell::Shell,
     state::BackendData,
-    utils::{env::dev_var, prelude::*},
+    utils::{env::dev_var, gamma::GammaRamp, prelude::*},
 };
 
 use anyhow::{Context, Result};
@@ -56,9 +56,9 @@ mod socket;
 mod surface;
 use device::*;
 pub(crate) use surface::Surface;
-pub use surface::Timings;
+pub use surface::{Timings, set_allow_tearing, set_power_saving};
 
-use super::render::{CLEAR_COLOR, CursorMode, output_elements};
+use super::render::{CursorMode, output_elements};
 
 #[derive(Debug)]
 pub struct KmsState {
@@ -73,6 +73,8 @@ pub struct KmsState {
     libinput: Libinput,
 
     pub syncobj_state: Option<DrmSyncobjState>,
+    /// Graphics mode reported by the power daemon, e.g. `hybrid` or `nvidia`
+    pub graphics_mode: Option<String>,
 }
 
 pub struct KmsGuard<'a> {
@@ -130,6 +132,7 @@ pub fn init_backend(
         libinput: libinput_context,
 
         syncobj_state: None,
+        graphics_mode: None,
     });
 
     // manually add already present gpus
@@ -225,6 +228,7 @@ fn determine_boot_gpu(seat: String) -> Option<DrmNode> {
 fn determine_primary_gpu(
     drm_devices: &IndexMap<DrmNode, Device>,
     seat: String,
+    prefer_discrete: bool,
 ) -> Result<Option<DrmNode>> {
     if let Some(device) = dev_var("COSMIC_RENDER_DEVICE") {
         if let Some(node) = drm_devices.values().find_map(|dev| {
@@ -236,6 +240,17 @@ fn determine_primary_gpu(
         }
     }
 
+    // in dedicated graphics mode, render on the gpu that isn't the boot gpu
+    if prefer_discrete {
+        let boot = determine_boot_gpu(seat.clone());
+        if let Some(dev) = drm_devices
+            .values()
+            .find(|dev| !dev.inner.is_software && Some(dev.inner.render_node) != boot)
+        {
+            return Ok(Some(dev.inner.render_node));
+        }
+    }
+
     // try to find builtin display
     for dev in drm_devices.values() {
         if dev.inner.surfaces.values().any(|s| {
@@ -270,7 +285,7 @@ fn determine_primary_gpu(
 }
 
 /// Create `GlowRenderer` for `EGL_MESA_device_software` device, if present
-fn software_renderer() -> anyhow::Result<GlowRenderer> {
+pub(crate) fn software_renderer() -> anyhow::Result<GlowRenderer> {
     let mut devices = EGLDevice::enumerate()?;
     let device = devices
         .find(|device| {
@@ -412,11 +427,116 @@ impl State {
                 }
             }
             state.common.refresh();
+
+            // outputs, that failed to come back, would stay black otherwise
+            let inactive = state
+                .backend
+                .kms()
+                .drm_devices
+                .values()
+                .flat_map(|device| device.inner.surfaces.values())
+                .filter(|surface| surface.output.is_enabled() && !surface.is_active())
+                .map(|surface| surface.output.name())
+                .collect::<Vec<_>>();
+            if !inactive.is_empty() {
+                warn!(
+                    ?inactive,
+                    "Outputs didn't resume, applying the output config again"
+                );
+                if let Err(err) = state.refresh_output_config() {
+                    error!(?err, "Failed to resume outputs");
+                }
+            }
+
+            // resumed surfaces only show their initial frame until something is damaged
+            let outputs = state
+                .common
+                .shell
+                .read()
+                .outputs()
+                .cloned()
+                .collect::<Vec<_>>();
+            for output in &outputs {
+                state.backend.schedule_render(output);
+            }
         });
         loop_signal.wakeup();
     }
 
+    /// Applies the graphics mode of the power daemon.
+    ///
+    /// Switching modes tears down and re-initializes all drm devices, moving outputs to the
+    /// gpu now driving their connectors and rendering to the new primary gpu.
+    pub fn set_graphics_mode(&mut self, mode: String) {
+        let BackendData::Kms(kms) = &mut self.backend else {
+            return;
+        };
+        let previous = kms.graphics_mode.replace(mode.clone());
+        if previous.as_ref() == Some(&mode) {
+            return;
+        }
+
+        let dh = self.common.display_handle.clone();
+        if previous.is_none() || !kms.session.is_active() {
+            // nothing to migrate, only the gpu preference changed
+            if let Err(err) = kms.select_primary_gpu(&dh) {
+                warn!("Failed to determine primary gpu: {}", err);
+            }
+            return;
+        }
+
+        info!(
+            ?previous,
+            ?mode,
+            "Graphics mode changed, re-initializing gpus."
+        );
+        let devices = kms
+            .drm_devices
+            .values()
+            .map(|device| {
+                (
+                    device.inner.dev_node.dev_id(),
+                    device.drm.device().dev_path(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut added = Vec::new();
+        for (dev, path) in devices {
+            if let Err(err) = self.device_removed(dev, &dh) {
+                error!(?err, "Failed to remove drm device {}.", dev);
+                continue;
+            }
+            let Some(path) = path else {
+                continue;
+            };
+            match self.device_added(dev, &path, &dh) {
+                Ok(outputs) => added.extend(outputs),
+                Err(err) => error!(?err, "Failed to add drm device {}.", path.display()),
+            }
+        }
+
+        if let Err(err) = self.backend.kms().select_primary_gpu(&dh) {
+            warn!("Failed to determine a new primary gpu: {}", err);
+        }
+
+        if let Err(err) = self.refresh_output_config() {
+            warn!("Unable to load output config: {}", err);
+            if !added.is_empty() {
+                for output in added {
+                    output.config_mut().enabled = OutputState::Disabled;
+                }
+                if let Err(err) = self.refresh_output_config() {
+                    error!("Unrecoverable config error: {}", err);
+                }
+            }
+        }
+        self.common.refresh();
+    }
+
     fn pause_session(&mut self) {
+        self.release_all_keys();
+
         let backend = self.backend.kms();
         backend.libinput.suspend();
         for device in backend.drm_devices.values_mut() {
@@ -438,7 +558,11 @@ impl KmsState {
 
         let mut primary_node = self.primary_node.write().unwrap();
         let _ = primary_node.take(); // if we error don't leave an old node in place
-        *primary_node = determine_primary_gpu(&self.drm_devices, self.session.seat())?;
+        *primary_node = determine_primary_gpu(
+            &self.drm_devices,
+            self.session.seat(),
+            self.prefers_discrete_gpu(),
+        )?;
 
         if let Some(node) = *primary_node {
             info!("Using {} as primary gpu for rendering.", node);
@@ -481,6 +605,11 @@ impl KmsState {
         Ok(())
     }
 
+    /// Whether the graphics mode routes rendering to the discrete gpu
+    fn prefers_discrete_gpu(&self) -> bool {
+        self.graphics_mode.as_deref() == Some("nvidia")
+    }
+
     pub fn switch_vt(&mut self, num: i32) -> Result<(), anyhow::Error> {
         self.session.change_vt(num).map_err(Into::into)
     }
@@ -580,6 +709,113 @@ impl KmsState {
             .copied()
     }
 
+    /// Applies `ramp` to the crtc driving `output`, resetting it to linear if `None`
+    pub fn set_gamma(&mut self, output: &Output, ramp: Option<GammaRamp>) -> Result<()> {
+        let device = self
+            .drm_devices
+            .values_mut()
+            .find(|dev| dev.inner.surfaces.values().any(|s| s.output == *output))
+            .ok_or_else(|| anyhow::anyhow!("Unknown output {}", output.name()))?;
+        let (crtc, surface) = device
+            .inner
+            .surfaces
+            .iter_mut()
+            .find(|(_, s)| s.output == *output)
+            .unwrap();
+
+        surface.gamma = ramp;
+        if surface.is_active() {
+            drm_helpers::set_gamma(
+                device.drm.device(),
+                *crtc,
+                surface.effective_gamma().as_ref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Entries per channel of the gamma ramp of the crtc driving `output`
+    pub fn gamma_size(&self, output: &Output) -> Option<u32> {
+        self.drm_devices.values().find_map(|device| {
+            let (crtc, _) = device
+                .inner
+                .surfaces
+                .iter()
+                .find(|(_, s)| s.output == *output)?;
+            let size = device.drm.device().get_crtc(*crtc).ok()?.gamma_length();
+            (size > 0).then_some(size)
+        })
+    }
+
+    /// Applies the gamma ramp of a gamma control client to `output`, below its calibration
+    pub fn set_client_gamma(&mut self, output: &Output, ramp: Option<GammaRamp>) -> Result<()> {
+        let device = self
+            .drm_devices
+            .values_mut()
+            .find(|dev| dev.inner.surfaces.values().any(|s| s.output == *output))
+            .ok_or_else(|| anyhow::anyhow!("Unknown output {}", output.name()))?;
+        let (crtc, surface) = device
+            .inner
+            .surfaces
+            .iter_mut()
+            .find(|(_, s)| s.output == *output)
+            .unwrap();
+
+        surface.client_gamma = ramp;
+        if surface.is_active() {
+            drm_helpers::set_gamma(
+                device.drm.device(),
+                *crtc,
+                surface.effective_gamma().as_ref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Scales the channels of the gamma ramp of `output` by `color`, on top of its calibration
+    pub fn set_gamma_color(&mut self, output: &Output, color: [f64; 3]) -> Result<()> {
+        let device = self
+            .drm_devices
+            .values_mut()
+            .find(|dev| dev.inner.surfaces.values().any(|s| s.output == *output))
+            .ok_or_else(|| anyhow::anyhow!("Unknown output {}", output.name()))?;
+        let (crtc, surface) = device
+            .inner
+            .surfaces
+            .iter_mut()
+            .find(|(_, s)| s.output == *output)
+            .unwrap();
+
+        surface.gamma_color = color;
+        if surface.is_active() {
+            drm_helpers::set_gamma(
+                device.drm.device(),
+                *crtc,
+                surface.effective_gamma().as_ref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Dims every output through its gamma ramp by `brightness`, on top of its calibration
+    pub fn set_gamma_brightness(&mut self, brightness: f64) {
+        for device in self.drm_devices.values_mut() {
+            for (crtc, surface) in device.inner.surfaces.iter_mut() {
+                surface.gamma_brightness = brightness;
+                if !surface.is_active() {
+                    continue;
+                }
+                if let Err(err) = drm_helpers::set_gamma(
+                    device.drm.device(),
+                    *crtc,
+                    surface.effective_gamma().as_ref(),
+                ) {
+                    warn!(?err, "Failed to dim {}", surface.output.name());
+                }
+            }
+        }
+    }
+
     pub fn update_screen_filter(&mut self, screen_filter: &ScreenFilter) -> Result<()> {
         for device in self.drm_devices.values_mut() {
             for surface in device.inner.surfaces.values_mut() {
@@ -922,7 +1158,11 @@ impl KmsGuard<'_> {
                                 )
                                 .with_context(|| "Failed to render outputs")?;
 
-                                elements.add_output(crtc, CLEAR_COLOR, output_elements);
+                                elements.add_output(
+                                    crtc,
+                                    shell.read().clear_color(output),
+                                    output_elements,
+                                );
                             }
 
                             let compositor = drm
@@ -953,6 +1193,18 @@ impl KmsGuard<'_> {
                             }
                         }
 
+                        if let Some(ramp) = surface.effective_gamma() {
+                            if let Err(err) =
+                                drm_helpers::set_gamma(drm.device(), *crtc, Some(&ramp))
+                            {
+                                warn!(
+                                    ?err,
+                                    "Failed to restore gamma ramp of {}",
+                                    surface.output.name()
+                                );
+                            }
+                        }
+
                         let vrr = output_config.0.vrr;
                         std::mem::drop(output_config);
 
@@ -1031,7 +1283,11 @@ impl KmsGuard<'_> {
                             )
                             .with_context(|| "Failed to render outputs")?;
 
-                            elements.add_output(crtc, CLEAR_COLOR, output_elements);
+                            elements.add_output(
+                                crtc,
+                                shell.read().clear_color(output),
+                                output_elements,
+                            );
                         }
 
                         drm.use_mode(&surface.crtc, *mode, &mut renderer, &elements)
@@ -1075,7 +1331,7 @@ impl KmsGuard<'_> {
                     )
                     .with_context(|| "Failed to render outputs")?;
 
-                    elements.add_output(crtc, CLEAR_COLOR, output_elements);
+                    elements.add_output(crtc, shell.read().clear_color(output), output_elements);
                 }
 
                 if let Err(err) = device
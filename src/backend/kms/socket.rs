@@ -86,7 +86,7 @@ impl State {
                     client_stream,
                     Arc::new(ClientState {
                         advertised_drm_node: Some(render_node),
-                        ..state.new_client_state()
+                        ..state.new_client_state(&client_stream)
                     }),
                 ) {
                     warn!(
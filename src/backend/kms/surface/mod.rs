@@ -2,19 +2,21 @@
 
 use crate::{
     backend::render::{
-        CLEAR_COLOR, CursorMode, GlMultiError, GlMultiRenderer, PostprocessOutputConfig,
-        PostprocessShader, PostprocessState,
+        CursorMode, GlMultiError, GlMultiRenderer, PostprocessOutputConfig, PostprocessShader,
+        PostprocessState,
         element::{CosmicElement, DamageElement},
         init_shaders, output_elements,
     },
     config::ScreenFilter,
     shell::Shell,
     state::SurfaceDmabufFeedback,
-    utils::prelude::*,
+    utils::{gamma::GammaRamp, prelude::*},
     wayland::{
         handlers::{
             compositor::recursive_frame_time_estimation,
+            content_type::{ContentType, content_type},
             screencopy::{FrameHolder, PendingImageCopyData, SessionData, submit_buffer},
+            tearing_control::wants_tearing,
         },
         protocols::screencopy::{
             FailureReason, Frame as ScreencopyFrame, SessionRef as ScreencopySessionRef,
@@ -63,6 +65,7 @@ use smithay::{
             sync::SyncPoint,
             utils::with_renderer_surface_state,
         },
+        session::Session,
     },
     desktop::utils::OutputPresentationFeedback,
     output::{Output, OutputNoMode},
@@ -110,6 +113,28 @@ use super::{drm_helpers, render::gles::GbmGlowBackend};
 #[cfg(feature = "debug")]
 use smithay_egui::EguiState;
 
+/// Shortest time between frames while saving power, capping the refresh rate at 60Hz
+const POWER_SAVING_FRAME_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Entries of the linear ramp dimmed, if no calibration is loaded, resampled for the crtc
+const GAMMA_SIZE: usize = 256;
+
+/// Whether all surfaces throttle rendering to save power
+static POWER_SAVING: AtomicBool = AtomicBool::new(false);
+
+/// Caps the refresh rate and disables forced adaptive sync of all surfaces, while `enabled`
+pub fn set_power_saving(enabled: bool) {
+    POWER_SAVING.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether fullscreen surfaces may present with tearing
+static ALLOW_TEARING: AtomicBool = AtomicBool::new(true);
+
+/// Allows or disallows async page flips for fullscreen surfaces asking for tearing
+pub fn set_allow_tearing(allowed: bool) {
+    ALLOW_TEARING.store(allowed, Ordering::Relaxed);
+}
+
 #[derive(Debug)]
 pub struct Surface {
     pub(crate) connector: connector::Handle,
@@ -128,6 +153,14 @@ pub struct Surface {
     thread: Option<JoinHandle<()>>,
 
     dpms: bool,
+    /// Gamma ramp of the crtc, reapplied on every resume
+    pub(super) gamma: Option<GammaRamp>,
+    /// Ramp set by a gamma control client, applied before `gamma`
+    pub(super) client_gamma: Option<GammaRamp>,
+    /// Factor `gamma` is scaled by, to dim the output without rendering
+    pub(super) gamma_brightness: f64,
+    /// Factors the channels of `gamma` are scaled by, to tint the output
+    pub(super) gamma_color: [f64; 3],
 }
 
 pub struct SurfaceThreadState {
@@ -139,6 +172,8 @@ pub struct SurfaceThreadState {
     vrr_mode: AdaptiveSync,
     frame_flags: FrameFlags,
     compositor: Option<GbmDrmOutput>,
+    /// Content type of the fullscreen surface of the last frame
+    fullscreen_content: Option<ContentType>,
 
     state: QueueState,
     timings: Timings,
@@ -282,10 +317,15 @@ impl Surface {
         let thread_token = evlh
             .insert_source(rx2, move |command, _, state| match command {
                 Event::Msg(SurfaceCommand::SendFrames(sequence)) => {
-                    if output_clone.mirroring().is_some() {
+                    // clients stay frozen until the session is active again
+                    if output_clone.mirroring().is_some()
+                        || !state.backend.kms().session.is_active()
+                    {
                         return;
                     }
                     state.common.send_frames(&output_clone, Some(sequence));
+                    #[cfg(feature = "systemd")]
+                    state.check_sleep_lock();
                 }
                 Event::Msg(SurfaceCommand::RenderStates(states)) => {
                     if output_clone.mirroring().is_some() {
@@ -349,6 +389,10 @@ impl Surface {
             thread_token,
             thread: Some(thread),
             dpms: true,
+            gamma: None,
+            client_gamma: None,
+            gamma_brightness: 1.,
+            gamma_color: [1.; 3],
         })
     }
 
@@ -356,6 +400,21 @@ impl Surface {
         &self.known_nodes
     }
 
+    /// Gamma ramp to apply to the crtc, combining `client_gamma`, `gamma`, `gamma_brightness`
+    /// and `gamma_color`
+    pub(super) fn effective_gamma(&self) -> Option<GammaRamp> {
+        let ramp = match (&self.client_gamma, &self.gamma) {
+            (Some(client), Some(calibration)) => Some(client.compose(calibration)),
+            (client, calibration) => client.clone().or_else(|| calibration.clone()),
+        };
+        let factors = self.gamma_color.map(|color| color * self.gamma_brightness);
+        if factors == [1.; 3] {
+            return ramp;
+        }
+        let ramp = ramp.unwrap_or_else(|| GammaRamp::linear(GAMMA_SIZE));
+        Some(ramp.scaled(factors))
+    }
+
     pub fn is_active(&self) -> bool {
         self.active.load(Ordering::SeqCst)
     }
@@ -534,6 +593,7 @@ fn surface_thread(
         compositor: None,
         frame_flags: FrameFlags::DEFAULT,
         vrr_mode: AdaptiveSync::Disabled,
+        fullscreen_content: None,
 
         state: QueueState::Idle,
         timings: Timings::new(None, None, false, target_node),
@@ -743,6 +803,8 @@ impl SurfaceThreadState {
 
     #[profiling::function]
     fn on_vblank(&mut self, metadata: Option<DrmEventMetadata>) {
+        let _span = crate::utils::profiler::span("page-flip");
+        crate::utils::profiler::frame_mark();
         let Some(compositor) = self.compositor.as_mut() else {
             return;
         };
@@ -858,6 +920,7 @@ impl SurfaceThreadState {
                 self.last_sequence = Some(sequence);
 
                 feedback.presented(clock, refresh, sequence as u64, flags);
+                crate::utils::latency::presented(&name, Duration::from(clock));
 
                 self.timings.presented(clock);
 
@@ -938,7 +1001,18 @@ impl SurfaceThreadState {
         }
 
         let estimated_presentation = self.timings.next_presentation_time(&self.clock);
-        let render_start = self.timings.next_render_time(&self.clock);
+        let mut render_start = self.timings.next_render_time(&self.clock);
+        // games and videos keep their latency and frame rate while saving power
+        let latency_sensitive = matches!(
+            self.fullscreen_content,
+            Some(ContentType::Game | ContentType::Video)
+        );
+        if POWER_SAVING.load(Ordering::Relaxed) && !latency_sensitive {
+            render_start = render_start.max(
+                self.timings
+                    .time_until_interval_elapsed(&self.clock, POWER_SAVING_FRAME_INTERVAL),
+            );
+        }
 
         let timer = if render_start.is_zero() {
             trace!("Running late for frame.");
@@ -990,6 +1064,7 @@ impl SurfaceThreadState {
 
     #[profiling::function]
     fn redraw(&mut self, estimated_presentation: Duration) -> Result<()> {
+        let _span = crate::utils::profiler::span("render");
         let Some(compositor) = self.compositor.as_mut() else {
             return Ok(());
         };
@@ -1004,6 +1079,10 @@ impl SurfaceThreadState {
             &self.target_node,
             &self.shell.read(),
         );
+        let clear_color = self
+            .shell
+            .read()
+            .clear_color(self.mirroring.as_ref().unwrap_or(&self.output));
 
         let mut renderer = if render_node != self.target_node {
             self.api
@@ -1018,38 +1097,71 @@ impl SurfaceThreadState {
         let mut additional_frame_flags = FrameFlags::empty();
         let mut remove_frame_flags = FrameFlags::empty();
 
-        let (has_active_fullscreen, fullscreen_drives_refresh_rate, animations_going) = {
+        let (
+            fullscreen_content,
+            fullscreen_drives_refresh_rate,
+            fullscreen_wants_tearing,
+            animations_going,
+        ) = {
             let shell = self.shell.read();
             let animations_going = shell.animations_going();
             let output = self.mirroring.as_ref().unwrap_or(&self.output);
             if let Some((_, workspace)) = shell.workspaces.active(output) {
                 if let Some(fullscreen_surface) = workspace.get_fullscreen() {
                     const _30_FPS: Duration = Duration::from_nanos(1_000_000_000 / 30);
+                    let surface = fullscreen_surface.wl_surface();
+                    let content = surface
+                        .as_ref()
+                        .map_or(ContentType::None, |surface| content_type(surface));
                     (
-                        true,
-                        fullscreen_surface.wl_surface().is_some_and(|surface| {
-                            recursive_frame_time_estimation(&self.clock, &surface)
-                                .is_some_and(|dur| dur <= _30_FPS)
-                        }),
+                        Some(content),
+                        // games and videos are expected to drive the refresh rate right away
+                        matches!(content, ContentType::Game | ContentType::Video)
+                            || surface.as_ref().is_some_and(|surface| {
+                                recursive_frame_time_estimation(&self.clock, surface)
+                                    .is_some_and(|dur| dur <= _30_FPS)
+                            }),
+                        surface
+                            .as_ref()
+                            .is_some_and(|surface| wants_tearing(surface)),
                         animations_going,
                     )
                 } else {
-                    (false, false, animations_going)
+                    (None, false, false, animations_going)
                 }
             } else {
-                (false, false, animations_going)
+                (None, false, false, animations_going)
             }
         };
+        let has_active_fullscreen = fullscreen_content.is_some();
+        self.fullscreen_content = fullscreen_content;
+
+        // tearing only affects the directly scanned out fullscreen surface, while nothing
+        // else on the output animates
+        if fullscreen_wants_tearing
+            && fullscreen_content != Some(ContentType::Photo)
+            && !animations_going
+            && self.mirroring.is_none()
+            && ALLOW_TEARING.load(Ordering::Relaxed)
+        {
+            additional_frame_flags |= FrameFlags::ALLOW_TEARING;
+        }
 
         if has_active_fullscreen || animations_going {
             // skip overlay plane assign if we have a fullscreen surface or dynamic contents to save on tests
             remove_frame_flags |= FrameFlags::ALLOW_OVERLAY_PLANE_SCANOUT;
         }
 
-        let mut vrr = matches!(self.vrr_mode, AdaptiveSync::Force);
+        // only use adaptive sync for fullscreen surfaces while saving power
+        let vrr_mode = match self.vrr_mode {
+            AdaptiveSync::Force if POWER_SAVING.load(Ordering::Relaxed) => AdaptiveSync::Enabled,
+            mode => mode,
+        };
+        let mut vrr = matches!(vrr_mode, AdaptiveSync::Force);
 
-        if self.vrr_mode == AdaptiveSync::Enabled {
-            vrr = has_active_fullscreen;
+        if vrr_mode == AdaptiveSync::Enabled {
+            // photos are static, varying the refresh rate only risks flicker
+            vrr = has_active_fullscreen && fullscreen_content != Some(ContentType::Photo);
         }
 
         let mut elements = output_elements(
@@ -1228,7 +1340,7 @@ impl SurfaceThreadState {
                         &mut fb,
                         1,
                         &elements,
-                        CLEAR_COLOR,
+                        clear_color,
                     ) {
                         Ok(res) => res,
                         Err(RenderError::Rendering(err)) => return Err(err),
@@ -1289,7 +1401,7 @@ impl SurfaceThreadState {
             compositor.render_frame(
                 &mut renderer,
                 &elements,
-                CLEAR_COLOR, // TODO use a theme neutral color
+                clear_color,
                 self.frame_flags
                     .union(additional_frame_flags)
                     .difference(remove_frame_flags),
@@ -1325,6 +1437,9 @@ impl SurfaceThreadState {
 
                         // Update `state` after `queue_frame`, before any early return from errors
                         if x.is_ok() {
+                            if self.mirroring.is_none() {
+                                crate::utils::latency::frame_queued(&self.output.name());
+                            }
                             let new_state = QueueState::WaitingForVBlank {
                                 redraw_needed: false,
                             };
@@ -325,6 +325,22 @@ impl Timings {
         }
     }
 
+    /// Time left until `interval` passed since the last presentation
+    pub fn time_until_interval_elapsed(
+        &self,
+        clock: &Clock<Monotonic>,
+        interval: Duration,
+    ) -> Duration {
+        let now: Duration = clock.now().into();
+        self.previous_frames
+            .back()
+            .map(|frame| {
+                let presented: Duration = frame.presentation_presented.into();
+                (presented + interval).saturating_sub(now)
+            })
+            .unwrap_or(Duration::ZERO)
+    }
+
     pub fn past_min_render_time(&self, clock: &Clock<Monotonic>) -> bool {
         let now: Duration = clock.now().into();
         let Some(min_refresh_interval_ns) = self.min_refresh_interval_ns else {
@@ -0,0 +1,398 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Blurred backdrops for layer surfaces requesting them through ext-background-effect.
+//!
+//! The backdrop is everything rendered below the surface, blurred with a dual kawase filter.
+//! Each surface renders what is below it offscreen through its own damage tracker and only
+//! blurs it again once that damage comes close to its blur region, so panels with a blurred
+//! backdrop don't cost a blur pass every frame.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            Bind, Color32F, Frame, Offscreen, Renderer, Texture,
+            damage::{Error as RenderError, OutputDamageTracker},
+            element::{Element, Id, Kind, RenderElement, texture::TextureRenderElement},
+            gles::{
+                GlesError, GlesTexProgram, GlesTexture, Uniform, element::TextureShaderElement,
+            },
+            glow::GlowRenderer,
+        },
+    },
+    output::{Output, OutputModeSource},
+    reexports::wayland_server::{Resource, backend::ObjectId, protocol::wl_surface::WlSurface},
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform},
+    wayland::compositor::{RectangleKind, RegionAttributes},
+};
+
+use super::element::{AsGlowRenderer, FromGlesError};
+
+pub static BLUR_DOWN_SHADER: &str = include_str!("./shaders/blur_down.frag");
+pub static BLUR_UP_SHADER: &str = include_str!("./shaders/blur_up.frag");
+
+/// Number of downsampling passes, each halving the resolution
+const BLUR_PASSES: u32 = 3;
+/// Distance of the samples of each pass in texels
+const BLUR_OFFSET: f32 = 2.5;
+/// Distance in pixels the passes spread content over
+const BLUR_SPREAD: i32 = (BLUR_OFFSET as i32 + 1) << (BLUR_PASSES + 1);
+
+pub struct BlurShaders {
+    pub down: GlesTexProgram,
+    pub up: GlesTexProgram,
+}
+
+/// Blurred content below a surface
+struct BlurCache {
+    damage_tracker: OutputDamageTracker,
+    background: GlesTexture,
+    age: usize,
+    /// Damage of the background not blurred yet
+    pending_damage: Option<Rectangle<i32, Physical>>,
+    /// Downsampled textures, each half the size of the previous one
+    levels: Vec<GlesTexture>,
+    result: GlesTexture,
+    /// Element ids of the backdrops, by rectangle of the blur region.
+    ///
+    /// Renewed every time the result changes, so the backdrops get damaged.
+    ids: HashMap<usize, Id>,
+}
+
+/// Blur caches of all surfaces rendered with a context, by output name and surface
+#[derive(Default)]
+struct BlurCaches(Rc<RefCell<HashMap<(String, ObjectId), BlurCache>>>);
+
+/// Blurred backdrop requested by a surface, waiting for the elements below it to be known
+pub struct PendingBackdrop {
+    surface: WlSurface,
+    region: RegionAttributes,
+    bounds: Rectangle<i32, Logical>,
+    location: Point<i32, Logical>,
+    /// Number of elements in front of the backdrop
+    index: usize,
+}
+
+impl PendingBackdrop {
+    /// Backdrop for the `region` of `surface` with the given `bounds`, both relative to
+    /// `location` on the output, going behind the first `index` elements.
+    pub fn new(
+        surface: WlSurface,
+        region: RegionAttributes,
+        bounds: Rectangle<i32, Logical>,
+        location: Point<i32, Logical>,
+        index: usize,
+    ) -> Self {
+        PendingBackdrop {
+            surface,
+            region,
+            bounds,
+            location,
+            index,
+        }
+    }
+}
+
+impl BlurCache {
+    fn new(
+        renderer: &mut GlowRenderer,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) -> Result<Self, GlesError> {
+        let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+        let background =
+            Offscreen::<GlesTexture>::create_buffer(renderer, Fourcc::Abgr8888, buffer_size)?;
+        let levels = (1..=BLUR_PASSES)
+            .map(|pass| {
+                let size = Size::from((
+                    (buffer_size.w >> pass).max(1),
+                    (buffer_size.h >> pass).max(1),
+                ));
+                Offscreen::<GlesTexture>::create_buffer(renderer, Fourcc::Abgr8888, size)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let result =
+            Offscreen::<GlesTexture>::create_buffer(renderer, Fourcc::Abgr8888, buffer_size)?;
+
+        Ok(BlurCache {
+            damage_tracker: OutputDamageTracker::new(size, scale, Transform::Normal),
+            background,
+            age: 0,
+            pending_damage: None,
+            levels,
+            result,
+            ids: HashMap::new(),
+        })
+    }
+
+    fn matches(&self, size: Size<i32, Physical>, scale: f64) -> bool {
+        self.damage_tracker.mode()
+            == &OutputModeSource::Static {
+                size,
+                scale: Scale::from(scale),
+                transform: Transform::Normal,
+            }
+    }
+
+    /// Renders the `elements` below the surface and blurs them again,
+    /// if they changed close enough to `bounds` to be visible in the backdrop
+    fn update<R, E>(
+        &mut self,
+        renderer: &mut R,
+        elements: &[E],
+        bounds: Rectangle<i32, Physical>,
+        clear_color: Color32F,
+    ) -> Result<(), RenderError<R::Error>>
+    where
+        R: Renderer + Bind<GlesTexture> + AsGlowRenderer,
+        R::TextureId: Clone + 'static,
+        R::Error: FromGlesError,
+        E: RenderElement<R>,
+    {
+        let mut fb = renderer
+            .bind(&mut self.background)
+            .map_err(RenderError::Rendering)?;
+        let res = self.damage_tracker.render_output(
+            renderer,
+            &mut fb,
+            self.age,
+            elements,
+            clear_color,
+        )?;
+        std::mem::drop(fb);
+        if self.age == 0 {
+            self.pending_damage = Some(bounds);
+        }
+        self.age = 1;
+        for rect in res.damage.into_iter().flatten() {
+            self.pending_damage = Some(
+                self.pending_damage
+                    .map_or(*rect, |damage| damage.merge(*rect)),
+            );
+        }
+
+        let reach = Rectangle::new(
+            bounds.loc - Point::from((BLUR_SPREAD, BLUR_SPREAD)),
+            bounds.size + Size::from((BLUR_SPREAD * 2, BLUR_SPREAD * 2)),
+        );
+        if !self
+            .pending_damage
+            .is_some_and(|damage| damage.overlaps(reach))
+        {
+            return Ok(());
+        }
+
+        self.blur(renderer.glow_renderer_mut())
+            .map_err(FromGlesError::from_gles_error)
+            .map_err(RenderError::Rendering)?;
+        self.pending_damage = None;
+        self.ids.clear();
+
+        Ok(())
+    }
+
+    /// Blurs the background into the result
+    fn blur(&mut self, renderer: &mut GlowRenderer) -> Result<(), GlesError> {
+        let shaders = renderer
+            .egl_context()
+            .user_data()
+            .get::<BlurShaders>()
+            .expect("BlurShaders should be available through `init_shaders`");
+        let (down, up) = (shaders.down.clone(), shaders.up.clone());
+
+        let mut source = self.background.clone();
+        for level in self.levels.iter_mut() {
+            blur_pass(renderer, &down, &source, level)?;
+            source = level.clone();
+        }
+        for level in self.levels.iter_mut().rev().skip(1) {
+            blur_pass(renderer, &up, &source, level)?;
+            source = level.clone();
+        }
+        blur_pass(renderer, &up, &source, &mut self.result)
+    }
+}
+
+/// Draws `source` scaled to `target` with a kawase `program`
+fn blur_pass(
+    renderer: &mut GlowRenderer,
+    program: &GlesTexProgram,
+    source: &GlesTexture,
+    target: &mut GlesTexture,
+) -> Result<(), GlesError> {
+    let size = target.size().to_logical(1, Transform::Normal);
+    let element = TextureShaderElement::new(
+        TextureRenderElement::from_static_texture(
+            Id::new(),
+            renderer.context_id(),
+            (0., 0.),
+            source.clone(),
+            1,
+            Transform::Normal,
+            None,
+            None,
+            Some(size),
+            None,
+            Kind::Unspecified,
+        ),
+        program.clone(),
+        vec![
+            Uniform::new("half_pixel", [0.5 / size.w as f32, 0.5 / size.h as f32]),
+            Uniform::new("offset", BLUR_OFFSET),
+        ],
+    );
+
+    let output_size = size.to_physical(1);
+    let dst = Rectangle::from_size(output_size);
+    let mut fb = renderer.bind(target)?;
+    let mut frame = renderer.render(&mut fb, output_size, Transform::Normal)?;
+    frame.clear(Color32F::TRANSPARENT, &[dst])?;
+    RenderElement::<GlowRenderer>::draw(&element, &mut frame, element.src(), dst, &[dst], &[])?;
+    frame.finish()?;
+    Ok(())
+}
+
+/// Rectangles of `region`, clipped to `bounds`
+fn region_rects(
+    region: &RegionAttributes,
+    bounds: Rectangle<i32, Logical>,
+) -> Vec<Rectangle<i32, Logical>> {
+    region
+        .rects
+        .iter()
+        .fold(Vec::new(), |mut rects, (kind, rect)| {
+            match kind {
+                RectangleKind::Add => rects.extend(rect.intersection(bounds)),
+                RectangleKind::Subtract => {
+                    rects = rects
+                        .into_iter()
+                        .flat_map(|r| r.subtract_rect(*rect))
+                        .collect();
+                }
+            }
+            rects
+        })
+}
+
+/// `rect` zoomed by `scale` around `focal_point`, like a `RescaleRenderElement` does
+fn zoom_rect(
+    rect: Rectangle<i32, Physical>,
+    (focal_point, scale): (Point<i32, Physical>, f64),
+) -> Rectangle<i32, Physical> {
+    let mut rect = Rectangle::new(rect.loc - focal_point, rect.size)
+        .to_f64()
+        .upscale(scale)
+        .to_i32_round();
+    rect.loc += focal_point;
+    rect
+}
+
+/// Inserts the blurred `backdrops` into the front-to-back `elements` of `output`,
+/// each blurring the elements behind it.
+///
+/// `zoom` is the focal point and scale the elements got zoomed with, which `wrap` is expected
+/// to apply to the backdrops as well.
+pub fn insert_backdrops<R, E>(
+    renderer: &mut R,
+    output: &Output,
+    clear_color: Color32F,
+    zoom: (Point<i32, Physical>, f64),
+    backdrops: Vec<PendingBackdrop>,
+    elements: &mut Vec<E>,
+    wrap: impl Fn(TextureRenderElement<GlesTexture>) -> Option<E>,
+) where
+    R: Renderer + Bind<GlesTexture> + AsGlowRenderer,
+    R::TextureId: Clone + 'static,
+    R::Error: FromGlesError,
+    E: RenderElement<R>,
+{
+    let scale = output.current_scale().fractional_scale();
+    let output_geometry = Rectangle::from_size(output.geometry().size.as_logical());
+    let size = output_geometry.size.to_physical_precise_round(scale);
+    let name = output.name();
+    let caches = renderer
+        .glow_renderer()
+        .egl_context()
+        .user_data()
+        .get_or_insert(BlurCaches::default)
+        .0
+        .clone();
+    let mut caches = caches.borrow_mut();
+    caches.retain(|(output_name, surface), _| {
+        *output_name != name
+            || backdrops
+                .iter()
+                .any(|backdrop| backdrop.surface.id() == *surface)
+    });
+
+    // bottom-most first, so the backdrops above include the ones below
+    for backdrop in backdrops.into_iter().rev() {
+        let rects = region_rects(&backdrop.region, backdrop.bounds)
+            .into_iter()
+            .filter_map(|rect| {
+                Rectangle::new(backdrop.location + rect.loc, rect.size)
+                    .intersection(output_geometry)
+            })
+            .collect::<Vec<_>>();
+        // where the rectangles end up on screen, which is what gets sampled
+        let zoomed = rects
+            .iter()
+            .map(|rect| zoom_rect(rect.to_physical_precise_round(scale), zoom))
+            .collect::<Vec<_>>();
+        let Some(bounds) = zoomed.iter().copied().reduce(|a, b| a.merge(b)) else {
+            continue;
+        };
+
+        let key = (name.clone(), backdrop.surface.id());
+        if caches
+            .get(&key)
+            .is_none_or(|cache| !cache.matches(size, scale))
+        {
+            match BlurCache::new(renderer.glow_renderer_mut(), size, scale) {
+                Ok(cache) => {
+                    caches.insert(key.clone(), cache);
+                }
+                Err(err) => {
+                    tracing::warn!(?err, "Failed to create blur buffers.");
+                    continue;
+                }
+            }
+        }
+        let cache = caches.get_mut(&key).unwrap();
+        if let Err(err) = cache.update(renderer, &elements[backdrop.index..], bounds, clear_color) {
+            tracing::warn!(?err, "Failed to blur backdrop.");
+            continue;
+        }
+
+        let context_id = renderer.glow_renderer().context_id();
+        let backdrop_elements = rects
+            .into_iter()
+            .zip(zoomed)
+            .enumerate()
+            .filter_map(|(idx, (rect, zoomed))| {
+                // the result has a scale of 1, so its logical space is in pixels
+                let src = Rectangle::<i32, Logical>::new(
+                    (zoomed.loc.x, zoomed.loc.y).into(),
+                    (zoomed.size.w, zoomed.size.h).into(),
+                );
+                let id = cache.ids.entry(idx).or_insert_with(Id::new).clone();
+                wrap(TextureRenderElement::from_static_texture(
+                    id,
+                    context_id.clone(),
+                    rect.to_physical_precise_round(scale).loc.to_f64(),
+                    cache.result.clone(),
+                    1,
+                    Transform::Normal,
+                    None,
+                    Some(src.to_f64()),
+                    Some(rect.size),
+                    None,
+                    Kind::Unspecified,
+                ))
+            })
+            .collect::<Vec<_>>();
+        elements.splice(backdrop.index..backdrop.index, backdrop_elements);
+    }
+}
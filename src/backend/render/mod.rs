@@ -6,7 +6,6 @@ use std::{
     collections::HashMap,
     ops::ControlFlow,
     sync::{Arc, Weak},
-    time::Instant,
 };
 
 #[cfg(feature = "debug")]
@@ -23,14 +22,15 @@ use crate::{
         layout::tiling::ANIMATION_DURATION,
         zoom::ZoomState,
     },
-    utils::{prelude::*, quirks::workspace_overview_is_open},
+    utils::{animation, prelude::*, quirks::workspace_overview_is_open},
     wayland::{
+        consent,
         handlers::{
             compositor::FRAME_TIME_FILTER,
             data_device::get_dnd_icon,
             screencopy::{FrameHolder, SessionData, render_session},
         },
-        protocols::workspace::WorkspaceHandle,
+        protocols::{background_effect::surface_blur_region, workspace::WorkspaceHandle},
     },
 };
 
@@ -76,9 +76,13 @@ use smithay_egui::EguiState;
 
 pub mod animations;
 
+pub mod blur;
 pub mod cursor;
 pub mod element;
-use self::element::{AsGlowRenderer, CosmicElement};
+use self::{
+    blur::{BLUR_DOWN_SHADER, BLUR_UP_SHADER, BlurShaders, PendingBackdrop},
+    element::{AsGlowRenderer, CosmicElement},
+};
 
 use super::kms::Timings;
 
@@ -111,6 +115,7 @@ impl AsMut<GlowRenderer> for RendererRef<'_> {
     }
 }
 
+/// Clear color for crtcs without an output, outputs use [`Shell::clear_color`]
 pub static CLEAR_COLOR: Color32F = Color32F::new(0.153, 0.161, 0.165, 1.0);
 pub static OUTLINE_SHADER: &str = include_str!("./shaders/rounded_outline.frag");
 pub static RECTANGLE_SHADER: &str = include_str!("./shaders/rounded_rectangle.frag");
@@ -128,6 +133,7 @@ pub enum Usage {
     FocusIndicator,
     PotentialGroupIndicator,
     SnappingIndicator,
+    UnresponsiveIndicator,
 }
 
 #[derive(Clone)]
@@ -373,6 +379,7 @@ pub fn init_shaders(renderer: &mut GlesRenderer) -> Result<(), GlesError> {
         if egl_context.user_data().get::<IndicatorShader>().is_some()
             && egl_context.user_data().get::<BackdropShader>().is_some()
             && egl_context.user_data().get::<PostprocessShader>().is_some()
+            && egl_context.user_data().get::<BlurShaders>().is_some()
         {
             return Ok(());
         }
@@ -400,6 +407,14 @@ pub fn init_shaders(renderer: &mut GlesRenderer) -> Result<(), GlesError> {
             UniformName::new("color_mode", UniformType::_1f),
         ],
     )?;
+    let blur_uniforms = [
+        UniformName::new("half_pixel", UniformType::_2f),
+        UniformName::new("offset", UniformType::_1f),
+    ];
+    let blur_shaders = BlurShaders {
+        down: renderer.compile_custom_texture_shader(BLUR_DOWN_SHADER, &blur_uniforms)?,
+        up: renderer.compile_custom_texture_shader(BLUR_UP_SHADER, &blur_uniforms)?,
+    };
 
     let egl_context = renderer.egl_context();
     egl_context
@@ -411,6 +426,7 @@ pub fn init_shaders(renderer: &mut GlesRenderer) -> Result<(), GlesError> {
     egl_context
         .user_data()
         .insert_if_missing(|| PostprocessShader(postprocess_shader));
+    egl_context.user_data().insert_if_missing(|| blur_shaders);
 
     Ok(())
 }
@@ -576,7 +592,7 @@ pub fn output_elements<R>(
     _fps: Option<(&EguiState, &Timings)>,
 ) -> Result<Vec<CosmicElement<R>>, RenderError<R::Error>>
 where
-    R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+    R: Renderer + ImportAll + ImportMem + Bind<GlesTexture> + AsGlowRenderer,
     R::TextureId: Send + Clone + 'static,
     R::Error: FromGlesError,
     CosmicMappedRenderElement<R>: RenderElement<R>,
@@ -674,7 +690,7 @@ pub fn workspace_elements<R>(
     element_filter: ElementFilter,
 ) -> Result<Vec<CosmicElement<R>>, RenderError<R::Error>>
 where
-    R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+    R: Renderer + ImportAll + ImportMem + Bind<GlesTexture> + AsGlowRenderer,
     R::TextureId: Send + Clone + 'static,
     R::Error: FromGlesError,
     CosmicMappedRenderElement<R>: RenderElement<R>,
@@ -703,6 +719,7 @@ where
         cursor_mode,
         element_filter == ElementFilter::ExcludeWorkspaceOverview,
     ));
+    let cursor_elements_len = elements.len();
 
     let shell = shell.read();
     let overview = shell.overview_mode();
@@ -759,28 +776,36 @@ where
             )
         })
         .unwrap_or_else(|| ((0., 0.).into(), 1.));
+    let zoom = (
+        focal_point
+            .as_logical()
+            .to_physical(output.current_scale().fractional_scale())
+            .to_i32_round(),
+        zoom_scale,
+    );
 
     let crop_to_output = |element: WorkspaceRenderElement<R>| {
         CropRenderElement::from_element(
-            RescaleRenderElement::from_element(
-                element,
-                focal_point
-                    .as_logical()
-                    .to_physical(output.current_scale().fractional_scale())
-                    .to_i32_round(),
-                zoom_scale,
-            ),
+            RescaleRenderElement::from_element(element, zoom.0, zoom.1),
             scale,
             Rectangle::from_size(output_size),
         )
     };
 
+    let mut backdrops = Vec::new();
     render_input_order::<()>(&shell, output, previous, current, element_filter, |stage| {
         match stage {
             Stage::ZoomUI => {
                 elements.extend(ZoomState::render(renderer, output));
             }
             Stage::SessionLock(lock_surface) => {
+                if let Some(fallback) = shell
+                    .session_lock
+                    .as_ref()
+                    .and_then(|session_lock| session_lock.fallbacks.get(output))
+                {
+                    elements.extend(fallback.render(renderer, output));
+                }
                 elements.extend(
                     session_lock_elements(renderer, output, lock_surface)
                         .into_iter()
@@ -826,6 +851,16 @@ where
                     .flat_map(crop_to_output)
                     .map(Into::into),
                 );
+                // blurs whatever ends up below, so it is only inserted once all elements are known
+                if let Some(region) = surface_blur_region(layer.wl_surface()) {
+                    backdrops.push(PendingBackdrop::new(
+                        layer.wl_surface().clone(),
+                        region,
+                        layer.bbox(),
+                        location.to_local(output).as_logical(),
+                        elements.len(),
+                    ));
+                }
             }
             Stage::OverrideRedirect { surface, location } => {
                 elements.extend(surface.wl_surface().into_iter().flat_map(|surface| {
@@ -848,14 +883,14 @@ where
             Stage::StickyPopups(layout) => {
                 let alpha = match &overview.0 {
                     OverviewMode::Started(_, started) => {
-                        (1.0 - (Instant::now().duration_since(*started).as_millis()
+                        (1.0 - (animation::now().duration_since(*started).as_millis()
                             / ANIMATION_DURATION.as_millis()) as f32)
                             .max(0.0)
                             * 0.4
                             + 0.6
                     }
                     OverviewMode::Ended(_, ended) => {
-                        ((Instant::now().duration_since(*ended).as_millis()
+                        ((animation::now().duration_since(*ended).as_millis()
                             / ANIMATION_DURATION.as_millis()) as f32)
                             * 0.4
                             + 0.6
@@ -876,14 +911,14 @@ where
             Stage::Sticky(layout) => {
                 let alpha = match &overview.0 {
                     OverviewMode::Started(_, started) => {
-                        (1.0 - (Instant::now().duration_since(*started).as_millis()
+                        (1.0 - (animation::now().duration_since(*started).as_millis()
                             / ANIMATION_DURATION.as_millis()) as f32)
                             .max(0.0)
                             * 0.4
                             + 0.6
                     }
                     OverviewMode::Ended(_, ended) => {
-                        ((Instant::now().duration_since(*ended).as_millis()
+                        ((animation::now().duration_since(*ended).as_millis()
                             / ANIMATION_DURATION.as_millis()) as f32)
                             * 0.4
                             + 0.6
@@ -978,9 +1013,244 @@ where
         ControlFlow::Continue(())
     })?;
 
+    blur::insert_backdrops(
+        renderer,
+        output,
+        shell.clear_color(output),
+        zoom,
+        backdrops,
+        &mut elements,
+        |element| crop_to_output(WorkspaceRenderElement::Backdrop(element)).map(Into::into),
+    );
+
+    let crosshair = shell.crosshair;
+    if crosshair.enabled && element_filter != ElementFilter::LayerShellOnly {
+        let location = last_active_seat.get_pointer().unwrap().current_location();
+        if output.geometry().to_f64().contains(location.as_global()) {
+            let location = location.as_global().to_local(output).to_i32_round();
+            let size = output.geometry().size.as_local();
+            let thickness = crosshair.thickness as i32;
+            output
+                .user_data()
+                .insert_if_missing_threadsafe(|| CrosshairId(Id::new(), Id::new()));
+            let ids = output.user_data().get::<CrosshairId>().unwrap();
+            let lines = [
+                (
+                    ids.0.clone(),
+                    Rectangle::new(
+                        (location.x - thickness / 2, 0).into(),
+                        (thickness, size.h).into(),
+                    ),
+                ),
+                (
+                    ids.1.clone(),
+                    Rectangle::new(
+                        (0, location.y - thickness / 2).into(),
+                        (size.w, thickness).into(),
+                    ),
+                ),
+            ];
+            // below the cursor, above everything else
+            elements.splice(
+                cursor_elements_len..cursor_elements_len,
+                lines.into_iter().map(|(id, line)| {
+                    CosmicElement::Overlay(BackdropShader::element(
+                        renderer,
+                        id,
+                        line,
+                        0.,
+                        crosshair.opacity,
+                        crosshair.color,
+                    ))
+                }),
+            );
+        }
+    }
+
+    let dim_alpha = shell.dim_alpha();
+    if dim_alpha > 0.0 {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(|| DimId(Id::new()));
+        let id = output.user_data().get::<DimId>().unwrap().0.clone();
+        elements.insert(
+            0,
+            CosmicElement::Overlay(BackdropShader::element(
+                renderer,
+                id,
+                Rectangle::from_size(output.geometry().size.as_local()),
+                0.,
+                dim_alpha,
+                [0.0, 0.0, 0.0],
+            )),
+        );
+    }
+
+    if let Some((area, alpha)) = shell.visual_bell(output) {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(|| BellId(Id::new()));
+        let id = output.user_data().get::<BellId>().unwrap().0.clone();
+        elements.insert(
+            0,
+            CosmicElement::Overlay(BackdropShader::element(
+                renderer,
+                id,
+                area,
+                0.,
+                alpha,
+                [1.0, 1.0, 1.0],
+            )),
+        );
+    }
+
+    if let Some(area) = shell.a11y_focus_highlight(output) {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(|| A11yFocusId(Id::new()));
+        let id = output.user_data().get::<A11yFocusId>().unwrap().0.clone();
+        let highlight = shell.focus_highlight;
+        elements.insert(
+            0,
+            CosmicElement::Overlay(IndicatorShader::focus_element(
+                renderer,
+                id,
+                area,
+                highlight.thickness,
+                [0; 4],
+                1.0,
+                highlight.color,
+            )),
+        );
+    }
+
+    if consent::synthetic_input_possible() {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(|| SyntheticInputId(Id::new()));
+        let id = output
+            .user_data()
+            .get::<SyntheticInputId>()
+            .unwrap()
+            .0
+            .clone();
+        let size = output.geometry().size.as_local();
+        elements.insert(
+            0,
+            CosmicElement::Overlay(BackdropShader::element(
+                renderer,
+                id,
+                Rectangle::new(
+                    (size.w - INDICATOR_SIZE * 2, INDICATOR_SIZE).into(),
+                    (INDICATOR_SIZE, INDICATOR_SIZE).into(),
+                ),
+                INDICATOR_SIZE as f32 / 2.,
+                1.0,
+                INDICATOR_COLOR,
+            )),
+        );
+    }
+
+    let osds = shell
+        .osds()
+        .flat_map(|osd| osd.render(renderer, output))
+        .collect::<Vec<_>>();
+    elements.splice(0..0, osds);
+
+    if let Some(cheatsheet) = shell.cheatsheet() {
+        let cheatsheet = cheatsheet.render(renderer, output);
+        elements.splice(0..0, cheatsheet);
+    }
+
+    if let Some(selection) = shell.screenshot_selection() {
+        output.user_data().insert_if_missing_threadsafe(|| {
+            ScreenshotSelectionId(std::array::from_fn(|_| Id::new()))
+        });
+        let ids = &output.user_data().get::<ScreenshotSelectionId>().unwrap().0;
+        let size = output.geometry().size.as_local();
+        let area = selection.area(output);
+        // dim everything but the selected area
+        let dimmed = match area {
+            Some(area) => vec![
+                Rectangle::new((0, 0).into(), (size.w, area.loc.y).into()),
+                Rectangle::new(
+                    (0, area.loc.y + area.size.h).into(),
+                    (size.w, size.h - area.loc.y - area.size.h).into(),
+                ),
+                Rectangle::new((0, area.loc.y).into(), (area.loc.x, area.size.h).into()),
+                Rectangle::new(
+                    (area.loc.x + area.size.w, area.loc.y).into(),
+                    (size.w - area.loc.x - area.size.w, area.size.h).into(),
+                ),
+            ],
+            None => vec![Rectangle::from_size(size)],
+        };
+        for (id, rect) in ids.iter().zip(dimmed) {
+            if rect.is_empty() {
+                continue;
+            }
+            elements.insert(
+                0,
+                CosmicElement::Overlay(BackdropShader::element(
+                    renderer,
+                    id.clone(),
+                    rect,
+                    0.,
+                    SCREENSHOT_DIM_ALPHA,
+                    [0.0, 0.0, 0.0],
+                )),
+            );
+        }
+        if let Some(area) = area {
+            let hint = crate::theme::active_window_hint(shell.theme());
+            elements.insert(
+                0,
+                CosmicElement::Overlay(IndicatorShader::focus_element(
+                    renderer,
+                    ids[4].clone(),
+                    area,
+                    2,
+                    [0; 4],
+                    1.0,
+                    [hint.red, hint.green, hint.blue],
+                )),
+            );
+        }
+        let hint = selection.render_hint(renderer, output);
+        elements.splice(0..0, hint);
+    }
+
+    if let Some(dialog) = shell.consent_dialog() {
+        let dialog = dialog.render(renderer, output);
+        elements.splice(0..0, dialog);
+    }
+
     Ok(elements)
 }
 
+/// Identifies the element dimming an output
+struct DimId(Id);
+
+/// Identifies the flash of the visual bell on an output
+struct BellId(Id);
+
+/// Identifies the lines of the pointer crosshair on an output
+struct CrosshairId(Id, Id);
+
+/// Identifies the highlight of the screen reader focus on an output
+struct A11yFocusId(Id);
+
+/// Identifies the indicator, that synthetic input is possible, on an output
+struct SyntheticInputId(Id);
+
+/// Identifies the dimmed areas and the outline of the screenshot selection on an output
+struct ScreenshotSelectionId([Id; 5]);
+
+const SCREENSHOT_DIM_ALPHA: f32 = 0.4;
+
+const INDICATOR_SIZE: i32 = 8;
+const INDICATOR_COLOR: [f32; 3] = [0.96, 0.58, 0.13];
+
 fn session_lock_elements<R>(
     renderer: &mut R,
     output: &Output,
@@ -1174,6 +1444,7 @@ where
         .map(|((w, start), idx)| (w.handle, idx, start));
     let workspace = (workspace.handle, idx);
     let zoom_state = shell_ref.zoom_state().cloned();
+    let clear_color = shell_ref.clear_color(output);
     std::mem::drop(shell_ref);
 
     let element_filter = if workspace_overview_is_open(output) {
@@ -1303,7 +1574,7 @@ where
                 .collect::<Vec<_>>()
             };
 
-            damage_tracker.render_output(renderer, target, age, &elements, CLEAR_COLOR)?;
+            damage_tracker.render_output(renderer, target, age, &elements, clear_color)?;
         }
 
         result
@@ -1462,7 +1733,13 @@ pub fn render_workspace<'d, R>(
     element_filter: ElementFilter,
 ) -> Result<(RenderOutputResult<'d>, Vec<CosmicElement<R>>), RenderError<R::Error>>
 where
-    R: Renderer + ImportAll + ImportMem + ExportMem + Bind<Dmabuf> + AsGlowRenderer,
+    R: Renderer
+        + ImportAll
+        + ImportMem
+        + ExportMem
+        + Bind<Dmabuf>
+        + Bind<GlesTexture>
+        + AsGlowRenderer,
     R::TextureId: Send + Clone + 'static,
     R::Error: FromGlesError,
     CosmicElement<R>: RenderElement<R>,
@@ -1493,13 +1770,8 @@ where
         );
     }
 
-    let res = damage_tracker.render_output(
-        renderer,
-        target,
-        age,
-        &elements,
-        CLEAR_COLOR, // TODO use a theme neutral color
-    );
+    let clear_color = shell.read().clear_color(output);
+    let res = damage_tracker.render_output(renderer, target, age, &elements, clear_color);
 
     res.map(|res| (res, elements))
 }
@@ -0,0 +1,46 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision mediump float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec2 half_pixel;
+uniform float offset;
+
+// Dual kawase upsample
+void main() {
+    vec2 delta = half_pixel * offset;
+    vec4 color = texture2D(tex, v_coords + vec2(-delta.x * 2.0, 0.0));
+    color += texture2D(tex, v_coords + vec2(-delta.x, delta.y)) * 2.0;
+    color += texture2D(tex, v_coords + vec2(0.0, delta.y * 2.0));
+    color += texture2D(tex, v_coords + vec2(delta.x, delta.y)) * 2.0;
+    color += texture2D(tex, v_coords + vec2(delta.x * 2.0, 0.0));
+    color += texture2D(tex, v_coords + vec2(delta.x, -delta.y)) * 2.0;
+    color += texture2D(tex, v_coords + vec2(0.0, -delta.y * 2.0));
+    color += texture2D(tex, v_coords + vec2(-delta.x, -delta.y)) * 2.0;
+    color /= 12.0;
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0) * alpha;
+#else
+    color = color * alpha;
+#endif
+
+    gl_FragColor = color;
+}
@@ -9,7 +9,7 @@ use crate::{
     },
 };
 use anyhow::Context;
-use cosmic_config::{ConfigGet, CosmicConfigEntry};
+use cosmic_config::{ConfigGet, ConfigSet, CosmicConfigEntry};
 use cosmic_settings_config::window_rules::ApplicationException;
 use cosmic_settings_config::{Shortcuts, shortcuts, window_rules};
 use serde::{Deserialize, Serialize};
@@ -34,7 +34,8 @@ use std::{
     fs::OpenOptions,
     io::Write,
     path::PathBuf,
-    sync::{Arc, atomic::AtomicBool},
+    sync::{Arc, Mutex, atomic::AtomicBool},
+    thread::{self, JoinHandle},
 };
 use tracing::{error, warn};
 
@@ -45,13 +46,19 @@ mod types;
 use cosmic::config::CosmicTk;
 pub use cosmic_comp_config::EdidProduct;
 use cosmic_comp_config::{
-    CosmicCompConfig, KeyboardConfig, TileBehavior, XkbConfig, XwaylandDescaling,
-    XwaylandEavesdropping, ZoomConfig,
+    ClearColor, CosmicCompConfig, DecorationMode, KeyboardConfig, TileBehavior, XkbConfig,
+    XwaylandDescaling, XwaylandEavesdropping, XwaylandOverrideRedirect, ZoomConfig,
+    a11y::{Crosshair, DwellClick, FocusHighlight, SystemBell, VisualBell},
+    clipboard::ClipboardPersistence,
+    hotplug::OutputHook,
+    idle::IdleConfig,
     input::{DeviceState as InputDeviceState, InputConfig, TouchpadOverride},
     output::comp::{
         OutputConfig, OutputInfo, OutputState, OutputsConfig, TransformDef, load_outputs,
     },
-    workspace::WorkspaceConfig,
+    policy::{ProtocolPolicy, ShortcutsInhibitPolicy},
+    rules::WindowRule,
+    workspace::{TilingAlgorithm, WorkspaceConfig},
 };
 pub use key_bindings::{Action, PrivateAction};
 use types::WlXkbConfig;
@@ -70,6 +77,17 @@ pub struct Config {
     pub tiling_exceptions: Vec<ApplicationException>,
     /// System actions from `com.system76.CosmicSettings.Shortcuts`
     pub system_actions: BTreeMap<shortcuts::action::System, String>,
+    /// System-wide keymap from `org.freedesktop.locale1`
+    pub system_keymap: SystemKeymap,
+}
+
+/// X11 keymap settings of `org.freedesktop.locale1`, empty if unset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemKeymap {
+    pub model: String,
+    pub layout: String,
+    pub variant: String,
+    pub options: String,
 }
 
 #[derive(Debug)]
@@ -77,6 +95,7 @@ pub struct DynamicConfig {
     outputs: (Option<PathBuf>, OutputsConfig),
     numlock: (Option<PathBuf>, NumlockStateConfig),
     accessibility_filter: (Option<PathBuf>, ScreenFilter),
+    toplevel_sessions: (Option<PathBuf>, ToplevelSessions),
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
@@ -84,6 +103,55 @@ pub struct NumlockStateConfig {
     pub last_state: bool,
 }
 
+/// Window state of toplevels managed through xx-session-management
+#[derive(Default, Debug, Deserialize, Serialize)]
+pub struct ToplevelSessions {
+    /// Saved toplevels by session id and toplevel name
+    pub sessions: HashMap<String, HashMap<String, SavedToplevel>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedToplevel {
+    /// Connector name of the output the window was on
+    pub output: String,
+    /// Index of the workspace on the output
+    pub workspace: usize,
+    pub tiled: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// Position and size relative to the output, before being maximized
+    pub geometry: Option<(i32, i32, i32, i32)>,
+}
+
+/// Config writes still running on background threads, see [`set_in_background`]
+static PENDING_WRITES: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Writes `key` without blocking the event loop, [`flush_writes`] waits for it to finish
+pub fn set_in_background<T: Serialize + Send + 'static>(
+    config: &cosmic_config::Config,
+    key: &'static str,
+    value: T,
+) {
+    let config = config.clone();
+    let handle = thread::spawn(move || {
+        if let Err(err) = config.set(key, value) {
+            error!(?err, "Failed to update {} key", key);
+        }
+    });
+
+    let mut pending = PENDING_WRITES.lock().unwrap();
+    pending.retain(|handle| !handle.is_finished());
+    pending.push(handle);
+}
+
+/// Waits for config writes started by [`set_in_background`]
+pub fn flush_writes() {
+    let pending = std::mem::take(&mut *PENDING_WRITES.lock().unwrap());
+    for handle in pending {
+        let _ = handle.join();
+    }
+}
+
 pub struct CompOutputConfig<'a>(pub Ref<'a, OutputConfig>);
 
 impl CompOutputConfig<'_> {
@@ -226,6 +294,7 @@ impl Config {
         let settings_context = shortcuts::context().expect("Failed to load shortcuts config");
         let system_actions = shortcuts::system_actions(&settings_context);
         let shortcuts = shortcuts::shortcuts(&settings_context);
+        crate::dbus::keybindings::publish(&shortcuts);
 
         // Listen for updates to the keybindings config.
         match cosmic_config::calloop::ConfigWatchSource::new(&settings_context) {
@@ -236,6 +305,7 @@ impl Config {
                             // Reload the keyboard shortcuts config.
                             "custom" | "defaults" => {
                                 state.common.config.shortcuts = shortcuts::shortcuts(&config);
+                                crate::dbus::keybindings::publish(&state.common.config.shortcuts);
                             }
 
                             "system_actions" => {
@@ -311,6 +381,7 @@ impl Config {
             shortcuts,
             system_actions,
             tiling_exceptions,
+            system_keymap: SystemKeymap::default(),
         }
     }
 
@@ -325,13 +396,37 @@ impl Config {
             .ok();
         let filter = Self::load_filter_state(&filter_path);
 
+        let sessions_path = xdg
+            .place_state_file("cosmic-comp/toplevel_sessions.ron")
+            .ok();
+        let sessions = Self::load_toplevel_sessions(&sessions_path);
+
         DynamicConfig {
             outputs: (output_path, outputs),
             numlock: (numlock_path, numlock),
             accessibility_filter: (filter_path, filter),
+            toplevel_sessions: (sessions_path, sessions),
         }
     }
 
+    fn load_toplevel_sessions(path: &Option<PathBuf>) -> ToplevelSessions {
+        path.as_deref()
+            .filter(|path| path.exists())
+            .and_then(|path| {
+                ron::de::from_reader::<_, ToplevelSessions>(
+                    OpenOptions::new().read(true).open(path).ok()?,
+                )
+                .map_err(|err| {
+                    warn!(?err, "Failed to read toplevel_sessions.ron, resetting..");
+                    if let Err(err) = std::fs::remove_file(path) {
+                        error!(?err, "Failed to remove toplevel_sessions.ron.");
+                    }
+                })
+                .ok()
+            })
+            .unwrap_or_default()
+    }
+
     fn load_numlock(path: &Option<PathBuf>) -> NumlockStateConfig {
         path.as_deref()
             .filter(|path| path.exists())
@@ -590,8 +685,21 @@ impl Config {
             .insert(infos, configs);
     }
 
+    /// Keymap of the user, falling back to the system-wide keymap for unset values
     pub fn xkb_config(&self) -> XkbConfig {
-        self.cosmic_conf.xkb_config.clone()
+        let mut config = self.cosmic_conf.xkb_config.clone();
+        let system = &self.system_keymap;
+        if config.layout.is_empty() {
+            config.layout = system.layout.clone();
+            config.variant = system.variant.clone();
+        }
+        if config.model.is_empty() {
+            config.model = system.model.clone();
+        }
+        if config.options.is_none() && !system.options.is_empty() {
+            config.options = Some(system.options.clone());
+        }
+        config
     }
 
     pub fn read_device(&self, device: &mut InputDevice) {
@@ -617,6 +725,12 @@ impl Config {
         )
     }
 
+    pub fn lock_keys_osd(&self, device: &InputDevice) -> bool {
+        let (device_config, default_config) = self.get_device_config(device);
+        input_config::get_config(device_config.as_ref(), default_config, |x| x.lock_keys_osd)
+            .is_some_and(|(enabled, _)| enabled)
+    }
+
     fn get_device_config(&self, device: &InputDevice) -> (Option<InputConfig>, &InputConfig) {
         let is_touchpad = device.config_tap_finger_count() > 0;
 
@@ -715,6 +829,17 @@ impl DynamicConfig {
             &mut self.accessibility_filter.1,
         )
     }
+
+    pub fn toplevel_sessions(&self) -> &ToplevelSessions {
+        &self.toplevel_sessions.1
+    }
+
+    pub fn toplevel_sessions_mut(&mut self) -> PersistenceGuard<'_, ToplevelSessions> {
+        PersistenceGuard(
+            self.toplevel_sessions.0.clone(),
+            &mut self.toplevel_sessions.1,
+        )
+    }
 }
 
 pub fn xkb_config_to_wl(config: &XkbConfig) -> WlXkbConfig<'_> {
@@ -769,43 +894,56 @@ pub fn change_modifier_state(
     input(smithay_input::KeyState::Released, scan_code);
 }
 
+/// Reloads the keymaps of all seats from [`Config::xkb_config`]
+pub fn update_keymaps(state: &mut State) {
+    let value = state.common.config.xkb_config();
+    let seats = state
+        .common
+        .shell
+        .read()
+        .seats
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    for seat in &seats {
+        if let Some(keyboard) = seat.get_keyboard() {
+            let old_modifier_state = keyboard.modifier_state();
+            keyboard.change_repeat_info(
+                (value.repeat_rate as i32).abs(), // Negative values are illegal
+                (value.repeat_delay as i32).abs(),
+            );
+            if let Err(err) = keyboard.set_xkb_config(state, xkb_config_to_wl(&value)) {
+                error!(?err, "Failed to load provided xkb config");
+                // TODO Revert to default?
+            }
+
+            // Press and release the numlock key to update modifiers.
+            if old_modifier_state.num_lock != keyboard.modifier_state().num_lock {
+                const NUMLOCK_SCANCODE: u32 = 69;
+                change_modifier_state(&keyboard, NUMLOCK_SCANCODE, state);
+            }
+            if old_modifier_state.caps_lock != keyboard.modifier_state().caps_lock {
+                const CAPSLOCK_SCANCODE: u32 = 58;
+                change_modifier_state(&keyboard, CAPSLOCK_SCANCODE, state);
+            }
+        }
+    }
+    for seat in &seats {
+        state.update_keyboard_layout(seat);
+    }
+}
+
 fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut State) {
     for key in &keys {
         match key.as_str() {
             "xkb_config" => {
                 let value = get_config::<XkbConfig>(&config, "xkb_config");
-                let seats = state
-                    .common
-                    .shell
-                    .read()
-                    .seats
-                    .iter()
-                    .cloned()
-                    .collect::<Vec<_>>();
-                for seat in seats.into_iter() {
-                    if let Some(keyboard) = seat.get_keyboard() {
-                        let old_modifier_state = keyboard.modifier_state();
-                        keyboard.change_repeat_info(
-                            (value.repeat_rate as i32).abs(), // Negative values are illegal
-                            (value.repeat_delay as i32).abs(),
-                        );
-                        if let Err(err) = keyboard.set_xkb_config(state, xkb_config_to_wl(&value)) {
-                            error!(?err, "Failed to load provided xkb config");
-                            // TODO Revert to default?
-                        }
-
-                        // Press and release the numlock key to update modifiers.
-                        if old_modifier_state.num_lock != keyboard.modifier_state().num_lock {
-                            const NUMLOCK_SCANCODE: u32 = 69;
-                            change_modifier_state(&keyboard, NUMLOCK_SCANCODE, state);
-                        }
-                        if old_modifier_state.caps_lock != keyboard.modifier_state().caps_lock {
-                            const CAPSLOCK_SCANCODE: u32 = 58;
-                            change_modifier_state(&keyboard, CAPSLOCK_SCANCODE, state);
-                        }
-                    }
-                }
                 state.common.config.cosmic_conf.xkb_config = value;
+                update_keymaps(state);
+            }
+            "keyboard_layout_osd" => {
+                let value = get_config::<bool>(&config, "keyboard_layout_osd");
+                state.common.config.cosmic_conf.keyboard_layout_osd = value;
             }
             "keyboard_config" => {
                 let value = get_config::<KeyboardConfig>(&config, "keyboard_config");
@@ -868,6 +1006,18 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
                     );
                 }
             }
+            "tiling_algorithm" => {
+                let new = get_config::<TilingAlgorithm>(&config, "tiling_algorithm");
+                if new != state.common.config.cosmic_conf.tiling_algorithm {
+                    state.common.config.cosmic_conf.tiling_algorithm = new;
+                    state
+                        .common
+                        .shell
+                        .write()
+                        .workspaces
+                        .update_tiling_algorithm(new);
+                }
+            }
             "active_hint" => {
                 let new = get_config::<bool>(&config, "active_hint");
                 if new != state.common.config.cosmic_conf.active_hint {
@@ -882,6 +1032,43 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
                     state.common.update_xwayland_scale();
                 }
             }
+            "xwayland_upscale" => {
+                let new = get_config::<Vec<String>>(&config, "xwayland_upscale");
+                if new != state.common.config.cosmic_conf.xwayland_upscale {
+                    let previous = state.common.config.cosmic_conf.clone();
+                    state.common.config.cosmic_conf.xwayland_upscale = new;
+                    state.common.update_xwayland_upscale(&previous);
+                }
+            }
+            "xwayland_dpi" => {
+                let new = get_config::<HashMap<String, u32>>(&config, "xwayland_dpi");
+                if new != state.common.config.cosmic_conf.xwayland_dpi {
+                    let previous = state.common.config.cosmic_conf.clone();
+                    state.common.config.cosmic_conf.xwayland_dpi = new;
+                    state.common.update_xwayland_upscale(&previous);
+                }
+            }
+            "decoration_overrides" => {
+                let new =
+                    get_config::<HashMap<String, DecorationMode>>(&config, "decoration_overrides");
+                if new != state.common.config.cosmic_conf.decoration_overrides {
+                    let previous = state.common.config.cosmic_conf.clone();
+                    state.common.config.cosmic_conf.decoration_overrides = new;
+                    state.common.update_decoration_overrides(&previous);
+                }
+            }
+            "xwayland_keyboard_grab" => {
+                let new = get_config::<Option<Vec<String>>>(&config, "xwayland_keyboard_grab");
+                state.common.config.cosmic_conf.xwayland_keyboard_grab = new;
+            }
+            "xwayland_override_redirect" => {
+                let new =
+                    get_config::<XwaylandOverrideRedirect>(&config, "xwayland_override_redirect");
+                if new != state.common.config.cosmic_conf.xwayland_override_redirect {
+                    state.common.config.cosmic_conf.xwayland_override_redirect = new;
+                    state.common.update_config();
+                }
+            }
             "xwayland_eavesdropping" => {
                 let new = get_config::<XwaylandEavesdropping>(&config, "xwayland_eavesdropping");
                 if new != state.common.config.cosmic_conf.xwayland_eavesdropping {
@@ -922,6 +1109,113 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
                     state.common.update_config();
                 }
             }
+            "high_contrast" => {
+                let new = get_config::<bool>(&config, "high_contrast");
+                if new != state.common.config.cosmic_conf.high_contrast {
+                    state.common.config.cosmic_conf.high_contrast = new;
+                    crate::theme::update_theme(state);
+                }
+            }
+            "animation_speed" => {
+                let new = get_config::<f32>(&config, "animation_speed");
+                if new != state.common.config.cosmic_conf.animation_speed {
+                    state.common.config.cosmic_conf.animation_speed = new;
+                    crate::utils::animation::set_speed(new);
+                }
+            }
+            "debug_slow_motion" => {
+                let new = get_config::<bool>(&config, "debug_slow_motion");
+                if new != state.common.config.cosmic_conf.debug_slow_motion {
+                    state.common.config.cosmic_conf.debug_slow_motion = new;
+                    if !new {
+                        crate::utils::animation::set_slow_motion(false);
+                    }
+                }
+            }
+            "output_hooks" => {
+                let new = get_config::<Vec<OutputHook>>(&config, "output_hooks");
+                if new != state.common.config.cosmic_conf.output_hooks {
+                    state.common.config.cosmic_conf.output_hooks = new;
+                }
+            }
+            "clear_color" => {
+                let new = get_config::<ClearColor>(&config, "clear_color");
+                if new != state.common.config.cosmic_conf.clear_color {
+                    state.common.config.cosmic_conf.clear_color = new;
+                    state.common.update_config();
+                    state.schedule_render_all();
+                }
+            }
+            "allow_tearing" => {
+                let new = get_config::<bool>(&config, "allow_tearing");
+                if new != state.common.config.cosmic_conf.allow_tearing {
+                    state.common.config.cosmic_conf.allow_tearing = new;
+                    crate::backend::kms::set_allow_tearing(new);
+                }
+            }
+            "visual_bell" => {
+                let new = get_config::<VisualBell>(&config, "visual_bell");
+                if new != state.common.config.cosmic_conf.visual_bell {
+                    state.common.config.cosmic_conf.visual_bell = new;
+                    state.common.update_config();
+                }
+            }
+            "system_bell" => {
+                state.common.config.cosmic_conf.system_bell =
+                    get_config::<SystemBell>(&config, "system_bell");
+            }
+            "focus_highlight" => {
+                let new = get_config::<FocusHighlight>(&config, "focus_highlight");
+                if new != state.common.config.cosmic_conf.focus_highlight {
+                    state.common.config.cosmic_conf.focus_highlight = new;
+                    state.common.update_config();
+                }
+            }
+            "crosshair" => {
+                let new = get_config::<Crosshair>(&config, "crosshair");
+                if new != state.common.config.cosmic_conf.crosshair {
+                    state.common.config.cosmic_conf.crosshair = new;
+                    state.common.update_config();
+                    state.schedule_render_all();
+                }
+            }
+            "dwell_click" => {
+                let new = get_config::<DwellClick>(&config, "dwell_click");
+                if new != state.common.config.cosmic_conf.dwell_click {
+                    state.common.config.cosmic_conf.dwell_click = new;
+                    if !new.enabled {
+                        state.dwell_click_reset();
+                    }
+                }
+            }
+            "idle" => {
+                let new = get_config::<IdleConfig>(&config, "idle");
+                if new != state.common.config.cosmic_conf.idle {
+                    state.common.config.cosmic_conf.idle = new;
+                    state.reset_idle();
+                }
+            }
+            "privileged_protocols" => {
+                let new = get_config::<ProtocolPolicy>(&config, "privileged_protocols");
+                if new != state.common.config.cosmic_conf.privileged_protocols {
+                    state.common.policy_state.update(&new);
+                    state.common.config.cosmic_conf.privileged_protocols = new;
+                }
+            }
+            "keyboard_shortcuts_inhibit" => {
+                let new =
+                    get_config::<ShortcutsInhibitPolicy>(&config, "keyboard_shortcuts_inhibit");
+                state.common.config.cosmic_conf.keyboard_shortcuts_inhibit = new;
+            }
+            "clipboard_persistence" => {
+                let new = get_config::<ClipboardPersistence>(&config, "clipboard_persistence");
+                state.common.config.cosmic_conf.clipboard_persistence = new;
+            }
+            "window_rules" => {
+                let new = get_config::<Vec<WindowRule>>(&config, "window_rules");
+                state.common.shell.write().update_window_rules(&new);
+                state.common.config.cosmic_conf.window_rules = new;
+            }
             _ => {}
         }
     }
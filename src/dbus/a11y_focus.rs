@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Follows the focus and text caret reported by applications over AT-SPI,
+//! for highlighting what the screen reader is currently reading.
+//!
+//! Applications only emit these events, while someone is registered for them,
+//! so the accessibility bus is only watched while the highlight is enabled.
+//! Extents are requested relative to the application's window, as wayland clients
+//! don't know their position on screen.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::Context;
+use calloop::{InsertError, LoopHandle, RegistrationToken, channel::Sender};
+use futures_executor::ThreadPool;
+use futures_util::stream::StreamExt;
+use smithay::utils::{Logical, Rectangle};
+use tracing::{debug, warn};
+use zbus::{
+    MatchRule, MessageStream,
+    names::UniqueName,
+    proxy::CacheProperties,
+    zvariant::{ObjectPath, OwnedValue},
+};
+
+use crate::state::State;
+
+/// Coordinates relative to the window of the accessible
+const COORD_TYPE_WINDOW: u32 = 1;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static SENDER: OnceLock<Sender<Rectangle<i32, Logical>>> = OnceLock::new();
+
+#[zbus::proxy(
+    interface = "org.a11y.Bus",
+    default_service = "org.a11y.Bus",
+    default_path = "/org/a11y/bus"
+)]
+trait A11yBus {
+    fn get_address(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.a11y.atspi.Registry",
+    default_service = "org.a11y.atspi.Registry",
+    default_path = "/org/a11y/atspi/registry"
+)]
+trait Registry {
+    fn register_event(&self, event: &str) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(interface = "org.a11y.atspi.Component")]
+trait Component {
+    fn get_extents(&self, coord_type: u32) -> zbus::Result<(i32, i32, i32, i32)>;
+}
+
+#[zbus::proxy(interface = "org.a11y.atspi.Text")]
+trait Text {
+    fn get_character_extents(
+        &self,
+        offset: i32,
+        coord_type: u32,
+    ) -> zbus::Result<(i32, i32, i32, i32)>;
+}
+
+pub fn init(evlh: &LoopHandle<'static, State>) -> anyhow::Result<RegistrationToken> {
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(area) = event {
+                let mut shell = state.common.shell.write();
+                shell.set_a11y_focus(area);
+                let outputs = shell.outputs().cloned().collect::<Vec<_>>();
+                std::mem::drop(shell);
+                for output in outputs {
+                    state.backend.schedule_render(&output);
+                }
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    let _ = SENDER.set(tx);
+    Ok(token)
+}
+
+/// Starts or stops following the focus of the screen reader
+pub fn set_enabled(enabled: bool, executor: &ThreadPool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    if !enabled || RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let Some(tx) = SENDER.get().cloned() else {
+        RUNNING.store(false, Ordering::SeqCst);
+        return;
+    };
+
+    executor.spawn_ok(async move {
+        if let Err(err) = watch_focus(tx).await {
+            warn!(?err, "Failed to follow the accessibility focus");
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+async fn watch_focus(tx: Sender<Rectangle<i32, Logical>>) -> zbus::Result<()> {
+    let session = zbus::Connection::session().await?;
+    let address = A11yBusProxy::new(&session).await?.get_address().await?;
+    let conn = zbus::connection::Builder::address(address.as_str())?
+        .build()
+        .await?;
+
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.a11y.atspi.Event.Object")?
+        .build();
+    let mut stream = MessageStream::for_match_rule(rule, &conn, None).await?;
+
+    let registry = RegistryProxy::new(&conn).await?;
+    registry
+        .register_event("object:state-changed:focused")
+        .await?;
+    registry.register_event("object:text-caret-moved").await?;
+
+    while let Some(msg) = stream.next().await {
+        if !ENABLED.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(msg) = msg else {
+            continue;
+        };
+        let header = msg.header();
+        let (Some(member), Some(sender), Some(path)) =
+            (header.member(), header.sender(), header.path())
+        else {
+            continue;
+        };
+        let Ok((kind, detail, _, _, _)) =
+            msg.body()
+                .deserialize::<(String, i32, i32, OwnedValue, HashMap<String, OwnedValue>)>()
+        else {
+            continue;
+        };
+
+        let extents = match member.as_str() {
+            "StateChanged" if kind == "focused" && detail == 1 => {
+                component_extents(&conn, sender, path).await
+            }
+            "TextCaretMoved" => caret_extents(&conn, sender, path, detail).await,
+            _ => continue,
+        };
+        match extents {
+            Ok((x, y, w, h)) if w >= 0 && h > 0 => {
+                // carets are commonly reported without width
+                let area = Rectangle::new((x, y).into(), (w.max(1), h).into());
+                if tx.send(area).is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(err) => debug!(?err, "Failed to get extents of the focused accessible"),
+        }
+    }
+
+    Ok(())
+}
+
+async fn component_extents(
+    conn: &zbus::Connection,
+    sender: &UniqueName<'_>,
+    path: &ObjectPath<'_>,
+) -> zbus::Result<(i32, i32, i32, i32)> {
+    ComponentProxy::builder(conn)
+        .destination(sender.to_owned())?
+        .path(path.to_owned())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?
+        .get_extents(COORD_TYPE_WINDOW)
+        .await
+}
+
+async fn caret_extents(
+    conn: &zbus::Connection,
+    sender: &UniqueName<'_>,
+    path: &ObjectPath<'_>,
+    offset: i32,
+) -> zbus::Result<(i32, i32, i32, i32)> {
+    TextProxy::builder(conn)
+        .destination(sender.to_owned())?
+        .path(path.to_owned())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?
+        .get_character_extents(offset, COORD_TYPE_WINDOW)
+        .await
+}
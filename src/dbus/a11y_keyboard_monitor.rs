@@ -1,6 +1,7 @@
 // https://gitlab.gnome.org/GNOME/mutter/-/blob/main/data/dbus-interfaces/org.freedesktop.a11y.xml
 
 use futures_executor::ThreadPool;
+use futures_util::StreamExt;
 use smithay::{
     backend::input::KeyState,
     input::keyboard::{KeysymHandle, ModifiersState},
@@ -8,6 +9,7 @@ use smithay::{
 use std::{
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex, OnceLock},
+    time::Duration,
 };
 use tracing::debug;
 use xkbcommon::xkb::Keysym;
@@ -51,6 +53,10 @@ impl KeyGrab {
             key,
         }
     }
+
+    fn matches(&self, mods: u32, active_virtual_mods: &HashSet<Keysym>, key: Keysym) -> bool {
+        self.mods == mods && &self.virtual_mods == active_virtual_mods && self.key == key
+    }
 }
 
 #[derive(Debug, Default)]
@@ -61,6 +67,31 @@ struct Client {
     key_grabs: Vec<KeyGrab>,
 }
 
+impl Client {
+    /// Whether key events for `key` are delivered to this client
+    fn receives_key(&self, mods: u32, active_virtual_mods: &HashSet<Keysym>, key: Keysym) -> bool {
+        self.watched
+            || self.grabbed
+            || self
+                .key_grabs
+                .iter()
+                .any(|grab| grab.matches(mods, active_virtual_mods, key))
+    }
+}
+
+/// State of a virtual modifier, following the latching and locking of xkb modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VirtualModState {
+    /// Held down, `used` once another key was pressed with it
+    Held { used: bool },
+    /// Pressed and released alone, applies to the next key press
+    Latched,
+    /// Pressed again while latched, applies until pressed once more
+    Locked,
+    /// Pressed while locked, inactive and forgotten once released
+    Unlocking,
+}
+
 #[derive(Debug, Default)]
 struct Clients(HashMap<UniqueName<'static>, Client>);
 
@@ -70,10 +101,33 @@ impl Clients {
     }
 }
 
+/// Settings of mouse keys, see [`crate::input::mouse_keys`]
+#[derive(Debug, Clone, Copy)]
+pub struct MouseKeysSettings {
+    pub enabled: bool,
+    /// Speed the pointer accelerates to, in logical pixels per second
+    pub max_speed: f64,
+    /// Time a direction key has to be held to reach `max_speed`
+    pub accel_time: Duration,
+}
+
+impl Default for MouseKeysSettings {
+    fn default() -> Self {
+        MouseKeysSettings {
+            enabled: false,
+            max_speed: 800.0,
+            accel_time: Duration::from_millis(1000),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct A11yKeyboardMonitorState {
     executor: ThreadPool,
     clients: Arc<Mutex<Clients>>,
+    mouse_keys: Arc<Mutex<MouseKeysSettings>>,
+    virtual_mod_states: HashMap<Keysym, VirtualModState>,
+    /// Virtual modifiers currently applying to key events
     active_virtual_mods: HashSet<Keysym>,
     conn: Arc<OnceLock<zbus::Connection>>,
     name_owners: Arc<OnceLock<NameOwners>>,
@@ -83,13 +137,15 @@ impl A11yKeyboardMonitorState {
     pub fn new(executor: &ThreadPool) -> Self {
         let clients = Arc::new(Mutex::new(Clients::default()));
         let clients_clone = clients.clone();
+        let mouse_keys = Arc::new(Mutex::new(MouseKeysSettings::default()));
+        let mouse_keys_clone = mouse_keys.clone();
         let conn_cell = Arc::new(OnceLock::new());
         let conn_cell_clone = conn_cell.clone();
         let name_owners_cell = Arc::new(OnceLock::new());
         let name_owners_cell_clone = name_owners_cell.clone();
         let executor_clone = executor.clone();
         executor.spawn_ok(async move {
-            match serve(clients_clone, &executor_clone).await {
+            match serve(clients_clone, mouse_keys_clone, &executor_clone).await {
                 Ok((conn, name_owners)) => {
                     conn_cell_clone.set(conn).unwrap();
                     name_owners_cell_clone.set(name_owners).unwrap();
@@ -102,12 +158,18 @@ impl A11yKeyboardMonitorState {
         Self {
             executor: executor.clone(),
             clients,
+            mouse_keys,
+            virtual_mod_states: HashMap::new(),
             active_virtual_mods: HashSet::new(),
             conn: conn_cell,
             name_owners: name_owners_cell,
         }
     }
 
+    pub fn mouse_keys(&self) -> MouseKeysSettings {
+        *self.mouse_keys.lock().unwrap()
+    }
+
     pub fn has_virtual_mod(&self, keysym: Keysym) -> bool {
         self.clients
             .lock()
@@ -117,12 +179,70 @@ impl A11yKeyboardMonitorState {
             .any(|client| client.virtual_mods.contains(&keysym))
     }
 
-    pub fn add_active_virtual_mod(&mut self, keysym: Keysym) {
-        self.active_virtual_mods.insert(keysym);
+    /// Press of the virtual modifier `keysym`, latched ones become locked and locked ones
+    /// are released
+    pub fn press_virtual_mod(&mut self, keysym: Keysym) {
+        let state = match self.virtual_mod_states.get(&keysym) {
+            None => VirtualModState::Held { used: false },
+            Some(VirtualModState::Latched) => VirtualModState::Locked,
+            Some(VirtualModState::Locked) => VirtualModState::Unlocking,
+            // key repeat
+            Some(state) => *state,
+        };
+        self.set_virtual_mod_state(keysym, Some(state));
+    }
+
+    /// Release of `keysym`, returns whether it was a virtual modifier.
+    ///
+    /// Virtual modifiers released without using them are latched, releasing any other key
+    /// consumes the latched ones.
+    pub fn release_virtual_mod(&mut self, keysym: Keysym) -> bool {
+        let Some(state) = self.virtual_mod_states.get(&keysym).copied() else {
+            if !keysym.is_modifier_key() {
+                let latched = self
+                    .virtual_mod_states
+                    .iter()
+                    .filter(|(_, state)| **state == VirtualModState::Latched)
+                    .map(|(keysym, _)| *keysym)
+                    .collect::<Vec<_>>();
+                for keysym in latched {
+                    self.set_virtual_mod_state(keysym, None);
+                }
+            }
+            return false;
+        };
+
+        let state = match state {
+            VirtualModState::Held { used: false } => Some(VirtualModState::Latched),
+            VirtualModState::Held { used: true } | VirtualModState::Unlocking => None,
+            state => Some(state),
+        };
+        self.set_virtual_mod_state(keysym, state);
+        true
+    }
+
+    /// Press of a key, that isn't a virtual modifier, while virtual modifiers may be held
+    pub fn use_virtual_mods(&mut self) {
+        for state in self.virtual_mod_states.values_mut() {
+            if let VirtualModState::Held { used } = state {
+                *used = true;
+            }
+        }
     }
 
-    pub fn remove_active_virtual_mod(&mut self, keysym: Keysym) -> bool {
-        self.active_virtual_mods.remove(&keysym)
+    fn set_virtual_mod_state(&mut self, keysym: Keysym, state: Option<VirtualModState>) {
+        match state {
+            Some(VirtualModState::Unlocking) | None => {
+                self.active_virtual_mods.remove(&keysym);
+            }
+            Some(_) => {
+                self.active_virtual_mods.insert(keysym);
+            }
+        }
+        match state {
+            Some(state) => self.virtual_mod_states.insert(keysym, state),
+            None => self.virtual_mod_states.remove(&keysym),
+        };
     }
 
     pub fn active_virtual_mods(&self) -> &HashSet<Keysym> {
@@ -147,9 +267,11 @@ impl A11yKeyboardMonitorState {
             .values()
             .flat_map(|client| &client.key_grabs)
             .any(|grab| {
-                grab.mods == modifiers.serialized.depressed
-                    && grab.virtual_mods == self.active_virtual_mods
-                    && grab.key == key
+                grab.matches(
+                    modifiers.serialized.depressed,
+                    &self.active_virtual_mods,
+                    key,
+                )
             })
     }
 
@@ -158,26 +280,30 @@ impl A11yKeyboardMonitorState {
             return;
         };
 
+        let released = match state {
+            KeyState::Pressed => false,
+            KeyState::Released => true,
+        };
+        let unichar = {
+            let xkb = keysym.xkb().lock().unwrap();
+            unsafe { xkb.state() }.key_get_utf32(keysym.raw_code())
+        };
+
         let clients = self.clients.lock().unwrap();
         for (unique_name, client) in clients.0.iter() {
-            if !client.watched && !self.has_key_grab(modifiers, keysym.modified_sym()) {
+            // Only deliver keys to the clients watching the keyboard or grabbing them,
+            // so key strokes don't leak to everyone on the bus.
+            if !client.receives_key(
+                modifiers.serialized.depressed,
+                &self.active_virtual_mods,
+                keysym.modified_sym(),
+            ) {
                 continue;
             }
 
-            let mut signal_context =
-                SignalEmitter::new(conn, "/org/freedesktop/a11y/Manager").unwrap();
-            // Instead of sending signal to all clients, send only to authorized
-            // clients with registed watches.
-            signal_context = signal_context.set_destination(unique_name.clone().into());
-
-            let released = match state {
-                KeyState::Pressed => false,
-                KeyState::Released => true,
-            };
-            let unichar = {
-                let xkb = keysym.xkb().lock().unwrap();
-                unsafe { xkb.state() }.key_get_utf32(keysym.raw_code())
-            };
+            let signal_context = SignalEmitter::new(conn, "/org/freedesktop/a11y/Manager")
+                .unwrap()
+                .set_destination(unique_name.clone().into());
             let future = KeyboardMonitor::key_event(
                 signal_context,
                 released,
@@ -312,12 +438,79 @@ impl KeyboardMonitor {
     ) -> zbus::Result<()>;
 }
 
+/// Moving the pointer with the numeric keypad, for users who can't use a pointing device.
+///
+/// Unlike the keyboard monitor this is a user setting, so any client may change it.
+struct MouseKeys {
+    settings: Arc<Mutex<MouseKeysSettings>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.a11y.MouseKeys")]
+impl MouseKeys {
+    /// Whether the numeric keypad moves the pointer while num lock is off
+    #[zbus(property)]
+    fn enabled(&self) -> bool {
+        self.settings.lock().unwrap().enabled
+    }
+
+    #[zbus(property)]
+    fn set_enabled(&mut self, enabled: bool) {
+        debug!("mouse keys enabled: {}", enabled);
+        self.settings.lock().unwrap().enabled = enabled;
+    }
+
+    /// Speed the pointer accelerates to, in logical pixels per second
+    #[zbus(property)]
+    fn max_speed(&self) -> f64 {
+        self.settings.lock().unwrap().max_speed
+    }
+
+    #[zbus(property)]
+    fn set_max_speed(&mut self, speed: f64) -> zbus::fdo::Result<()> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Invalid speed {speed}"
+            )));
+        }
+        self.settings.lock().unwrap().max_speed = speed;
+        Ok(())
+    }
+
+    /// Milliseconds a direction key has to be held to reach the maximum speed
+    #[zbus(property)]
+    fn acceleration_time(&self) -> u32 {
+        self.settings.lock().unwrap().accel_time.as_millis() as u32
+    }
+
+    #[zbus(property)]
+    fn set_acceleration_time(&mut self, millis: u32) {
+        self.settings.lock().unwrap().accel_time = Duration::from_millis(millis as u64);
+    }
+}
+
 async fn serve(
     clients: Arc<Mutex<Clients>>,
+    mouse_keys: Arc<Mutex<MouseKeysSettings>>,
     executor: &ThreadPool,
 ) -> zbus::Result<(zbus::Connection, NameOwners)> {
     let conn = zbus::Connection::session().await?;
     let name_owners = NameOwners::new(&conn, executor).await?;
+
+    // Drop grabs and watches of clients as soon as they disconnect
+    let lost = name_owners.receive_unique_names_lost().await?;
+    let weak_clients = Arc::downgrade(&clients);
+    executor.spawn_ok(async move {
+        let mut lost = std::pin::pin!(lost);
+        while let Some(name) = lost.next().await {
+            let Some(clients) = weak_clients.upgrade() else {
+                break;
+            };
+            if clients.lock().unwrap().0.remove(&name).is_some() {
+                debug!("removed a11y keyboard monitor client {}", name);
+            }
+        }
+    });
+
     let keyboard_monitor = KeyboardMonitor {
         clients,
         name_owners: name_owners.clone(),
@@ -325,6 +518,14 @@ async fn serve(
     conn.object_server()
         .at("/org/freedesktop/a11y/Manager", keyboard_monitor)
         .await?;
+    conn.object_server()
+        .at(
+            "/org/freedesktop/a11y/Manager",
+            MouseKeys {
+                settings: mouse_keys,
+            },
+        )
+        .await?;
     conn.request_name("org.freedesktop.a11y.Manager").await?;
     Ok((conn, name_owners))
 }
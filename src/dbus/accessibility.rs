@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_comp_config::a11y::Crosshair;
+use cosmic_config::{ConfigGet, ConfigSet};
+use tracing::warn;
+
+/// Accessibility settings of the compositor's own ui.
+///
+/// Settings are stored in the compositor config, so changes persist and apply through the
+/// usual config watcher.
+pub struct Accessibility;
+
+pub(super) fn config() -> zbus::fdo::Result<cosmic_config::Config> {
+    cosmic_config::Config::new("com.system76.CosmicComp", 1)
+        .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+}
+
+#[zbus::interface(name = "com.system76.CosmicComp.Accessibility")]
+impl Accessibility {
+    /// Whether focus indicators, borders and headers are drawn in high contrast
+    #[zbus(property)]
+    fn high_contrast(&self) -> bool {
+        config()
+            .ok()
+            .and_then(|config| config.get::<bool>("high_contrast").ok())
+            .unwrap_or(false)
+    }
+
+    #[zbus(property)]
+    fn set_high_contrast(&mut self, enabled: bool) -> zbus::fdo::Result<()> {
+        config()?.set("high_contrast", enabled).map_err(|err| {
+            warn!(?err, "Failed to set high contrast mode");
+            zbus::fdo::Error::Failed(err.to_string())
+        })
+    }
+
+    /// Whether a crosshair tracking the pointer is drawn above all windows
+    #[zbus(property)]
+    fn crosshair(&self) -> bool {
+        config()
+            .ok()
+            .and_then(|config| config.get::<Crosshair>("crosshair").ok())
+            .is_some_and(|crosshair| crosshair.enabled)
+    }
+
+    #[zbus(property)]
+    fn set_crosshair(&mut self, enabled: bool) -> zbus::fdo::Result<()> {
+        let config = config()?;
+        let crosshair = Crosshair {
+            enabled,
+            ..config.get::<Crosshair>("crosshair").unwrap_or_default()
+        };
+        config.set("crosshair", crosshair).map_err(|err| {
+            warn!(?err, "Failed to toggle the crosshair");
+            zbus::fdo::Error::Failed(err.to_string())
+        })
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::UNIX_EPOCH;
+
+use crate::wayland::audit;
+
+/// Read access to the audit log of privileged protocol usage, see [`crate::wayland::audit`].
+///
+/// There is deliberately no way to clear the log, as any client on the session bus could do so.
+pub struct Audit;
+
+#[zbus::interface(name = "com.system76.CosmicComp.Audit")]
+impl Audit {
+    /// Recorded entries, oldest first, as
+    /// `(unix time in seconds, protocol, event, pid, executable, sandbox engine, app id)`.
+    ///
+    /// Unknown pids are reported as 0 and unknown strings as empty.
+    fn entries(&self) -> Vec<(u64, String, String, u32, String, String, String)> {
+        audit::entries()
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry
+                        .time
+                        .duration_since(UNIX_EPOCH)
+                        .map(|time| time.as_secs())
+                        .unwrap_or_default(),
+                    format!("{:?}", entry.protocol),
+                    entry.event.as_str().to_string(),
+                    entry.pid.unwrap_or_default(),
+                    entry
+                        .executable
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    entry.sandbox_engine.unwrap_or_default(),
+                    entry.app_id.unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Per-output ICC profiles assigned through colord.
+//!
+//! Outputs are registered as temporary display devices, so color management tools can
+//! assign profiles to them. The calibration curves (`vcgt`) of the default profile are
+//! loaded into the gamma ramp of the output's crtc. Gamut mapping of the profile isn't
+//! applied, as the renderer has no color transformations.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+};
+
+use anyhow::Context;
+use calloop::{InsertError, LoopHandle, RegistrationToken, channel::Sender};
+use futures_executor::ThreadPool;
+use futures_util::stream::{self, StreamExt};
+use smithay::output::Output;
+use tracing::{debug, warn};
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::{
+    state::{BackendData, State},
+    utils::{gamma::GammaRamp, prelude::OutputExt},
+};
+
+#[zbus::proxy(
+    interface = "org.freedesktop.ColorManager",
+    default_service = "org.freedesktop.ColorManager",
+    default_path = "/org/freedesktop/ColorManager"
+)]
+trait ColorManager {
+    fn create_device(
+        &self,
+        device_id: &str,
+        scope: &str,
+        properties: HashMap<&str, &str>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn find_device_by_id(&self, device_id: &str) -> zbus::Result<OwnedObjectPath>;
+
+    fn delete_device(&self, object_path: &OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn device_removed(&self, object_path: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.ColorManager.Device",
+    default_service = "org.freedesktop.ColorManager"
+)]
+trait ColorManagerDevice {
+    /// Assigned profiles, the first one being the default
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.ColorManager.Profile",
+    default_service = "org.freedesktop.ColorManager"
+)]
+trait ColorManagerProfile {
+    #[zbus(property)]
+    fn filename(&self) -> zbus::Result<String>;
+}
+
+/// Registered devices by output name
+static DEVICES: LazyLock<Mutex<HashMap<String, OwnedObjectPath>>> = LazyLock::new(Default::default);
+static SENDER: OnceLock<Sender<(String, Option<GammaRamp>)>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+struct DeviceInfo {
+    output: String,
+    vendor: String,
+    model: String,
+    serial: String,
+    embedded: bool,
+}
+
+impl DeviceInfo {
+    /// Same id as used by other compositors, so profiles assigned there are found again
+    fn id(&self) -> String {
+        format!("xrandr-{}-{}-{}", self.vendor, self.model, self.serial)
+    }
+}
+
+enum DeviceEvent {
+    ProfilesChanged,
+    Removed(OwnedObjectPath),
+}
+
+async fn load_profile(
+    conn: &zbus::Connection,
+    path: OwnedObjectPath,
+) -> anyhow::Result<Option<GammaRamp>> {
+    let profile = ColorManagerProfileProxy::builder(conn)
+        .path(path)?
+        .build()
+        .await?;
+    let filename = profile.filename().await?;
+    let data = std::fs::read(&filename).with_context(|| format!("Failed to read {}", filename))?;
+    GammaRamp::from_icc(&data).with_context(|| format!("Failed to parse {}", filename))
+}
+
+async fn watch_device(
+    info: DeviceInfo,
+    tx: Sender<(String, Option<GammaRamp>)>,
+) -> anyhow::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let manager = ColorManagerProxy::new(&conn).await?;
+
+    let id = info.id();
+    let path = match manager.find_device_by_id(&id).await {
+        Ok(path) => path,
+        Err(_) => {
+            let mut properties = HashMap::from([
+                ("Kind", "display"),
+                ("Mode", "physical"),
+                ("Colorspace", "rgb"),
+                ("Vendor", info.vendor.as_str()),
+                ("Model", info.model.as_str()),
+                ("Serial", info.serial.as_str()),
+                ("XRANDR_name", info.output.as_str()),
+            ]);
+            if info.embedded {
+                properties.insert("Embedded", "");
+            }
+            manager.create_device(&id, "temp", properties).await?
+        }
+    };
+    debug!(output = info.output, ?path, "Registered colord device");
+    DEVICES
+        .lock()
+        .unwrap()
+        .insert(info.output.clone(), path.clone());
+
+    let device = ColorManagerDeviceProxy::builder(&conn)
+        .path(path.clone())?
+        .build()
+        .await?;
+    let changes = device
+        .receive_profiles_changed()
+        .await
+        .map(|_| DeviceEvent::ProfilesChanged);
+    let removed = manager
+        .receive_device_removed()
+        .await?
+        .filter_map(|signal| async move { signal.args().ok().map(|args| args.object_path) })
+        .map(DeviceEvent::Removed);
+    let mut events = std::pin::pin!(stream::select(changes, removed));
+
+    let mut current = None;
+    let mut event = Some(DeviceEvent::ProfilesChanged);
+    while let Some(next) = event {
+        match next {
+            DeviceEvent::ProfilesChanged => {
+                let profile = device.profiles().await?.into_iter().next();
+                let ramp = match profile {
+                    Some(profile) => load_profile(&conn, profile).await.unwrap_or_else(|err| {
+                        warn!(?err, output = info.output, "Failed to load ICC profile");
+                        None
+                    }),
+                    None => None,
+                };
+                if ramp != current {
+                    current = ramp.clone();
+                    if tx.send((info.output.clone(), ramp)).is_err() {
+                        break;
+                    }
+                }
+            }
+            DeviceEvent::Removed(removed) if removed == path => break,
+            DeviceEvent::Removed(_) => {}
+        }
+        event = events.next().await;
+    }
+
+    Ok(())
+}
+
+/// Applies gamma ramps loaded from profiles on the event loop
+pub fn init(evlh: &LoopHandle<'static, State>) -> anyhow::Result<RegistrationToken> {
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg((name, ramp)) = event {
+                let Some(output) = state
+                    .common
+                    .output_configuration_state
+                    .outputs()
+                    .find(|o| o.name() == name)
+                else {
+                    return;
+                };
+                let BackendData::Kms(kms) = &mut state.backend else {
+                    return;
+                };
+                if let Err(err) = kms.set_gamma(&output, ramp) {
+                    warn!(?err, "Failed to apply calibration of {}", name);
+                }
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    let _ = SENDER.set(tx);
+    Ok(token)
+}
+
+/// Registers `output` with colord and applies the profiles assigned to it
+pub fn register(output: &Output, executor: &ThreadPool) {
+    let Some(tx) = SENDER.get().cloned() else {
+        return;
+    };
+    let physical = output.physical_properties();
+    let info = DeviceInfo {
+        output: output.name(),
+        vendor: physical.make,
+        model: physical.model,
+        serial: physical.serial_number,
+        embedded: output.is_internal(),
+    };
+
+    executor.spawn_ok(async move {
+        if let Err(err) = watch_device(info, tx).await {
+            debug!(?err, "Failed to register output with colord");
+        }
+    });
+}
+
+/// Removes the device of `output` from colord
+pub fn unregister(output: &Output, executor: &ThreadPool) {
+    let Some(path) = DEVICES.lock().unwrap().remove(&output.name()) else {
+        return;
+    };
+
+    executor.spawn_ok(async move {
+        let result = async {
+            let conn = zbus::Connection::system().await?;
+            ColorManagerProxy::new(&conn)
+                .await?
+                .delete_device(&path)
+                .await
+        }
+        .await;
+        if let Err(err) = result {
+            debug!(?err, "Failed to remove colord device");
+        }
+    });
+}
@@ -0,0 +1,436 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Introspection and control of outputs, workspaces and windows for scripts and tests.
+//!
+//! Windows are identified by the identifier of their ext-foreign-toplevel handle, like in
+//! `com.system76.CosmicComp.Toplevels`. Workspaces are numbered per output starting at 1.
+//!
+//! Focusing, closing and moving windows is subject to the `ToplevelManagement` policy, callers
+//! have to own a name of its `DBusName` matchers, if an allow-list is configured.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use calloop::channel::Sender;
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use futures_channel::oneshot;
+use futures_executor::ThreadPool;
+use smithay::wayland::seat::WaylandFocus;
+use tracing::warn;
+use zbus::{fdo, message::Header, object_server::SignalEmitter};
+
+use crate::{
+    shell::CosmicSurface,
+    utils::prelude::*,
+    wayland::{
+        policy::PolicyState,
+        protocols::{
+            toplevel_info::{Window, toplevel_identifier},
+            toplevel_management::ToplevelManagementHandler,
+        },
+    },
+};
+
+/// `(name, x, y, width, height, scale)` in logical coordinates
+type OutputInfo = (String, i32, i32, i32, i32, f64);
+/// `(output, number, pinned id, active)`
+type WorkspaceInfo = (String, u32, String, bool);
+/// `(identifier, app id, title, output, workspace number, focused)`
+type WindowInfo = (String, String, String, String, u32, bool);
+
+pub enum ControlRequest {
+    Outputs(oneshot::Sender<Vec<OutputInfo>>),
+    Workspaces(oneshot::Sender<Vec<WorkspaceInfo>>),
+    Windows(oneshot::Sender<Vec<WindowInfo>>),
+    Focus {
+        identifier: String,
+        reply: oneshot::Sender<bool>,
+    },
+    Close {
+        identifier: String,
+        reply: oneshot::Sender<bool>,
+    },
+    MoveToWorkspace {
+        identifier: String,
+        output: String,
+        workspace: u32,
+        reply: oneshot::Sender<bool>,
+    },
+}
+
+/// State last announced by signals
+#[derive(Debug, Default)]
+struct Announced {
+    focus: Option<String>,
+    workspaces: HashMap<String, u32>,
+    outputs: Vec<OutputInfo>,
+    /// Serials of the workspace and output configuration state last announced
+    serials: Option<(u32, u32)>,
+}
+
+static ANNOUNCED: LazyLock<Mutex<Announced>> = LazyLock::new(Default::default);
+/// Whether the keyboard focus changed since the last announcement
+static FOCUS_CHANGED: AtomicBool = AtomicBool::new(true);
+
+/// Marks the focus to be announced with the next [`announce_changes`]
+pub fn focus_changed() {
+    FOCUS_CHANGED.store(true, Ordering::SeqCst);
+}
+
+/// Outputs, workspaces and windows of the compositor, see the module documentation.
+pub struct Control {
+    pub requests: Sender<ControlRequest>,
+    pub policy: PolicyState,
+}
+
+impl Control {
+    /// Sends a request to the event loop and waits for its reply
+    async fn request<T>(
+        &self,
+        request: impl FnOnce(oneshot::Sender<T>) -> ControlRequest,
+    ) -> fdo::Result<T> {
+        let (tx, rx) = oneshot::channel();
+        self.requests
+            .send(request(tx))
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        rx.await
+            .map_err(|_| fdo::Error::Failed("Request wasn't handled".into()))
+    }
+
+    /// Fails unless the caller may manage windows of other clients
+    async fn check_caller(&self, header: &Header<'_>) -> fdo::Result<()> {
+        if super::caller_allowed(
+            header,
+            &self.policy,
+            PrivilegedProtocol::ToplevelManagement,
+            None,
+        )
+        .await
+        {
+            Ok(())
+        } else {
+            Err(fdo::Error::AccessDenied("Access denied".to_string()))
+        }
+    }
+
+    fn unknown_window(identifier: &str) -> fdo::Error {
+        fdo::Error::InvalidArgs(format!("Unknown window {}", identifier))
+    }
+}
+
+#[zbus::interface(name = "com.system76.CosmicComp.Control")]
+impl Control {
+    /// Enabled outputs as `(name, x, y, width, height, scale)`
+    async fn outputs(&self) -> fdo::Result<Vec<OutputInfo>> {
+        self.request(ControlRequest::Outputs).await
+    }
+
+    /// Workspaces as `(output, number, pinned id, active)`
+    async fn workspaces(&self) -> fdo::Result<Vec<WorkspaceInfo>> {
+        self.request(ControlRequest::Workspaces).await
+    }
+
+    /// Windows as `(identifier, app id, title, output, workspace number, focused)`.
+    ///
+    /// Sticky windows are reported on workspace 0.
+    async fn windows(&self) -> fdo::Result<Vec<WindowInfo>> {
+        self.request(ControlRequest::Windows).await
+    }
+
+    /// Activate and focus a window, switching to its workspace
+    async fn focus_window(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        identifier: String,
+    ) -> fdo::Result<()> {
+        self.check_caller(&header).await?;
+        let id = identifier.clone();
+        if !self
+            .request(|reply| ControlRequest::Focus { identifier, reply })
+            .await?
+        {
+            return Err(Self::unknown_window(&id));
+        }
+        Ok(())
+    }
+
+    /// Ask a window to close
+    async fn close_window(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        identifier: String,
+    ) -> fdo::Result<()> {
+        self.check_caller(&header).await?;
+        let id = identifier.clone();
+        if !self
+            .request(|reply| ControlRequest::Close { identifier, reply })
+            .await?
+        {
+            return Err(Self::unknown_window(&id));
+        }
+        Ok(())
+    }
+
+    /// Move a window to the workspace `number` of `output`
+    async fn move_window(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        identifier: String,
+        output: String,
+        number: u32,
+    ) -> fdo::Result<()> {
+        self.check_caller(&header).await?;
+        let id = identifier.clone();
+        if !self
+            .request(|reply| ControlRequest::MoveToWorkspace {
+                identifier,
+                output,
+                workspace: number,
+                reply,
+            })
+            .await?
+        {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Unknown window {} or workspace",
+                id
+            )));
+        }
+        Ok(())
+    }
+
+    /// The focused window changed, an empty identifier if no window is focused
+    #[zbus(signal)]
+    async fn focus_changed(ctx: SignalEmitter<'_>, identifier: &str) -> zbus::Result<()>;
+
+    /// The active workspace of `output` changed
+    #[zbus(signal)]
+    async fn active_workspace_changed(
+        ctx: SignalEmitter<'_>,
+        output: &str,
+        number: u32,
+    ) -> zbus::Result<()>;
+
+    /// Outputs were added, removed, moved, resized or rescaled, see `Outputs`
+    #[zbus(signal)]
+    async fn outputs_changed(ctx: SignalEmitter<'_>, outputs: Vec<OutputInfo>) -> zbus::Result<()>;
+}
+
+fn output_infos(common: &Common) -> Vec<OutputInfo> {
+    common
+        .shell
+        .read()
+        .outputs()
+        .map(|output| {
+            let geo = output.geometry();
+            (
+                output.name(),
+                geo.loc.x,
+                geo.loc.y,
+                geo.size.w,
+                geo.size.h,
+                output.current_scale().fractional_scale(),
+            )
+        })
+        .collect()
+}
+
+impl State {
+    fn control_window(&self, identifier: &str) -> Option<CosmicSurface> {
+        self.common
+            .toplevel_info_state
+            .toplevel_for_identifier(identifier)
+            .cloned()
+    }
+
+    pub fn handle_control_request(&mut self, request: ControlRequest) {
+        match request {
+            ControlRequest::Outputs(reply) => {
+                let _ = reply.send(output_infos(&self.common));
+            }
+            ControlRequest::Workspaces(reply) => {
+                let shell = self.common.shell.read();
+                let workspaces = shell
+                    .outputs()
+                    .flat_map(|output| {
+                        let active = shell.workspaces.active_num(output).1;
+                        shell.workspaces.spaces_for_output(output).enumerate().map(
+                            move |(idx, workspace)| {
+                                (
+                                    output.name(),
+                                    idx as u32 + 1,
+                                    workspace.id.clone().unwrap_or_default(),
+                                    idx == active,
+                                )
+                            },
+                        )
+                    })
+                    .collect();
+                let _ = reply.send(workspaces);
+            }
+            ControlRequest::Windows(reply) => {
+                let shell = self.common.shell.read();
+                let windows = self
+                    .common
+                    .toplevel_info_state
+                    .registered_toplevels()
+                    .filter_map(|window| {
+                        let identifier = toplevel_identifier(window)?;
+                        let (output, number) = window
+                            .wl_surface()
+                            .and_then(|surface| shell.workspace_for_surface(&surface))
+                            .map(|(handle, output)| {
+                                let number = shell
+                                    .workspaces
+                                    .spaces_for_output(&output)
+                                    .position(|w| w.handle == handle)
+                                    .map_or(0, |idx| idx as u32 + 1);
+                                (output.name(), number)
+                            })
+                            .unwrap_or_default();
+                        Some((
+                            identifier,
+                            window.app_id(),
+                            window.title(),
+                            output,
+                            number,
+                            Window::is_activated(window),
+                        ))
+                    })
+                    .collect();
+                let _ = reply.send(windows);
+            }
+            ControlRequest::Focus { identifier, reply } => {
+                let Some(window) = self.control_window(&identifier) else {
+                    let _ = reply.send(false);
+                    return;
+                };
+                let dh = self.common.display_handle.clone();
+                self.activate(&dh, &window, None);
+                let _ = reply.send(true);
+            }
+            ControlRequest::Close { identifier, reply } => {
+                let Some(window) = self.control_window(&identifier) else {
+                    let _ = reply.send(false);
+                    return;
+                };
+                window.close();
+                let _ = reply.send(true);
+            }
+            ControlRequest::MoveToWorkspace {
+                identifier,
+                output,
+                workspace,
+                reply,
+            } => {
+                let Some(window) = self.control_window(&identifier) else {
+                    let _ = reply.send(false);
+                    return;
+                };
+                let shell = self.common.shell.read();
+                let target = shell
+                    .outputs()
+                    .find(|o| o.name() == output)
+                    .cloned()
+                    .and_then(|output| {
+                        let handle = shell
+                            .workspaces
+                            .spaces_for_output(&output)
+                            .nth((workspace as usize).checked_sub(1)?)?
+                            .handle;
+                        Some((output, handle))
+                    });
+                std::mem::drop(shell);
+
+                let Some((output, handle)) = target else {
+                    let _ = reply.send(false);
+                    return;
+                };
+                let dh = self.common.display_handle.clone();
+                self.move_to_workspace(&dh, &window, handle, output);
+                let _ = reply.send(true);
+            }
+        }
+    }
+}
+
+/// Emits `FocusChanged`, `ActiveWorkspaceChanged` and `OutputsChanged` for changes since
+/// the last call.
+///
+/// Does nothing, unless the focus changed or the workspace or output configuration serials
+/// moved on.
+pub fn announce_changes(common: &Common, executor: &ThreadPool) {
+    let serials = (
+        common.workspace_state.serial(),
+        common.output_configuration_state.serial(),
+    );
+    let focus_dirty = FOCUS_CHANGED.swap(false, Ordering::SeqCst);
+    if !focus_dirty && ANNOUNCED.lock().unwrap().serials == Some(serials) {
+        return;
+    }
+
+    let focus = common
+        .toplevel_info_state
+        .registered_toplevels()
+        .find(|window| Window::is_activated(*window))
+        .and_then(toplevel_identifier);
+    let shell = common.shell.read();
+    let workspaces = shell
+        .outputs()
+        .map(|output| {
+            (
+                output.name(),
+                shell.workspaces.active_num(output).1 as u32 + 1,
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    std::mem::drop(shell);
+    let outputs = output_infos(common);
+
+    let mut announced = ANNOUNCED.lock().unwrap();
+    let focus_changed = (announced.focus != focus).then(|| focus.clone().unwrap_or_default());
+    let workspace_changes = workspaces
+        .iter()
+        .filter(|(output, number)| announced.workspaces.get(*output) != Some(number))
+        .map(|(output, number)| (output.clone(), *number))
+        .collect::<Vec<_>>();
+    let outputs_changed = (announced.outputs != outputs).then(|| outputs.clone());
+    announced.focus = focus;
+    announced.workspaces = workspaces;
+    announced.outputs = outputs;
+    announced.serials = Some(serials);
+    std::mem::drop(announced);
+
+    if focus_changed.is_none() && workspace_changes.is_empty() && outputs_changed.is_none() {
+        return;
+    }
+    let Some(conn) = super::session_connection() else {
+        return;
+    };
+
+    executor.spawn_ok(async move {
+        let Ok(ctx) = SignalEmitter::new(conn, super::COMP_PATH) else {
+            return;
+        };
+        if let Some(outputs) = outputs_changed {
+            if let Err(err) = Control::outputs_changed(ctx.clone(), outputs).await {
+                warn!(?err, "Failed to announce output changes");
+            }
+        }
+        if let Some(identifier) = focus_changed {
+            if let Err(err) = Control::focus_changed(ctx.clone(), &identifier).await {
+                warn!(?err, "Failed to announce focus change");
+            }
+        }
+        for (output, number) in workspace_changes {
+            if let Err(err) = Control::active_workspace_changed(ctx.clone(), &output, number).await
+            {
+                warn!(?err, "Failed to announce workspace change");
+            }
+        }
+    });
+}
@@ -0,0 +1,562 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! `org.gnome.Mutter.DisplayConfig` compatibility, used by display tools written against GNOME.
+//!
+//! Only the monitor based part of the interface (`GetCurrentState` and `ApplyMonitorsConfig`)
+//! is provided, translated from and to the compositor's output configuration.
+//! Every output is its own monitor with a single connector, outputs mirroring another one
+//! are reported as additional monitors of the mirrored output's logical monitor.
+//! Positions are logical, like our global space, so the layout mode is always logical.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+};
+
+use anyhow::Context;
+use calloop::{InsertError, LoopHandle, RegistrationToken, channel::Sender};
+use cosmic_comp_config::{output::comp::OutputState, policy::PrivilegedProtocol};
+use futures_channel::oneshot;
+use futures_executor::ThreadPool;
+use smithay::{
+    output::{Mode, Output},
+    utils::{Logical, Point, Transform},
+};
+use tracing::{error, warn};
+use zbus::{
+    fdo,
+    message::Header,
+    object_server::SignalEmitter,
+    zvariant::{OwnedValue, Value},
+};
+
+use crate::{
+    state::State,
+    utils::prelude::OutputExt,
+    wayland::{
+        policy::PolicyState,
+        protocols::output_configuration::{
+            ModeConfiguration, OutputConfiguration, OutputConfigurationState,
+        },
+    },
+};
+
+/// Well-known name of the display configuration service
+pub const DISPLAY_CONFIG_NAME: &str = "org.gnome.Mutter.DisplayConfig";
+const DISPLAY_CONFIG_PATH: &str = "/org/gnome/Mutter/DisplayConfig";
+
+/// `layout-mode` of logical monitors, sized in logical pixels
+const LAYOUT_MODE_LOGICAL: u32 = 1;
+/// Scales offered for every mode
+const SUPPORTED_SCALES: &[f64] = &[1.0, 1.25, 1.5, 1.75, 2.0, 2.25, 2.5, 2.75, 3.0];
+
+/// `ApplyMonitorsConfig` methods
+const METHOD_VERIFY: u32 = 0;
+const METHOD_TEMPORARY: u32 = 1;
+const METHOD_PERSISTENT: u32 = 2;
+
+static SNAPSHOT: LazyLock<Mutex<Snapshot>> = LazyLock::new(Default::default);
+static CONNECTION: OnceLock<zbus::Connection> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+struct MonitorMode {
+    id: String,
+    width: i32,
+    height: i32,
+    /// Refresh rate in Hz
+    refresh: f64,
+    current: bool,
+    preferred: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Monitor {
+    connector: String,
+    vendor: String,
+    product: String,
+    serial: String,
+    builtin: bool,
+    modes: Vec<MonitorMode>,
+    /// Connector of the output shown instead, if mirroring
+    mirroring: Option<String>,
+    enabled: bool,
+    position: Point<i32, Logical>,
+    scale: f64,
+    transform: Transform,
+    primary: bool,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    /// Serial of the output configuration the monitors were read from
+    config_serial: Option<u32>,
+    /// Serial handed to clients, changes with the monitors
+    serial: u32,
+    monitors: Vec<Monitor>,
+}
+
+/// A logical monitor of `ApplyMonitorsConfig`
+#[derive(Debug)]
+struct LogicalMonitorConfig {
+    position: Point<i32, Logical>,
+    scale: f64,
+    transform: Transform,
+    primary: bool,
+    /// Connector and mode id of its monitors, the first one is mirrored by the others
+    monitors: Vec<(String, String)>,
+}
+
+/// Configuration requested through `ApplyMonitorsConfig`, answered with its success
+#[derive(Debug)]
+struct ApplyRequest {
+    verify: bool,
+    /// Write the configuration to the config, unless it is temporary
+    persist: bool,
+    logical_monitors: Vec<LogicalMonitorConfig>,
+    reply: oneshot::Sender<bool>,
+}
+
+fn mode_id(mode: &Mode) -> String {
+    format!(
+        "{}x{}@{:.3}",
+        mode.size.w,
+        mode.size.h,
+        mode.refresh as f64 / 1000.
+    )
+}
+
+fn transform_to_dbus(transform: Transform) -> u32 {
+    match transform {
+        Transform::Normal => 0,
+        Transform::_90 => 1,
+        Transform::_180 => 2,
+        Transform::_270 => 3,
+        Transform::Flipped => 4,
+        Transform::Flipped90 => 5,
+        Transform::Flipped180 => 6,
+        Transform::Flipped270 => 7,
+    }
+}
+
+fn transform_from_dbus(transform: u32) -> Option<Transform> {
+    Some(match transform {
+        0 => Transform::Normal,
+        1 => Transform::_90,
+        2 => Transform::_180,
+        3 => Transform::_270,
+        4 => Transform::Flipped,
+        5 => Transform::Flipped90,
+        6 => Transform::Flipped180,
+        7 => Transform::Flipped270,
+        _ => return None,
+    })
+}
+
+fn monitor(output: &Output) -> Monitor {
+    let physical = output.physical_properties();
+    let current = output.current_mode();
+    let preferred = output.preferred_mode();
+    let config = output.config();
+    Monitor {
+        connector: output.name(),
+        vendor: physical.make,
+        product: physical.model,
+        serial: physical.serial_number,
+        builtin: output.is_internal(),
+        modes: output
+            .modes()
+            .iter()
+            .map(|mode| MonitorMode {
+                id: mode_id(mode),
+                width: mode.size.w,
+                height: mode.size.h,
+                refresh: mode.refresh as f64 / 1000.,
+                current: current.as_ref() == Some(mode),
+                preferred: preferred.as_ref() == Some(mode),
+            })
+            .collect(),
+        mirroring: match &config.enabled {
+            OutputState::Mirroring(name) => Some(name.clone()),
+            _ => None,
+        },
+        enabled: config.enabled != OutputState::Disabled,
+        position: output.current_location(),
+        scale: output.current_scale().fractional_scale(),
+        transform: output.current_transform(),
+        primary: config.xwayland_primary,
+    }
+}
+
+/// Updates the monitors served over D-Bus, if the output configuration changed since
+/// and announces the change with `MonitorsChanged`.
+pub fn refresh<D>(output_configuration_state: &OutputConfigurationState<D>, executor: &ThreadPool) {
+    let serial = output_configuration_state.serial();
+    let mut snapshot = SNAPSHOT.lock().unwrap();
+    if snapshot.config_serial == Some(serial) {
+        return;
+    }
+    snapshot.config_serial = Some(serial);
+
+    let monitors = output_configuration_state
+        .outputs()
+        .map(|output| monitor(&output))
+        .collect::<Vec<_>>();
+    if snapshot.monitors == monitors {
+        return;
+    }
+    snapshot.monitors = monitors;
+    snapshot.serial = snapshot.serial.wrapping_add(1);
+    std::mem::drop(snapshot);
+
+    let Some(conn) = CONNECTION.get() else {
+        return;
+    };
+    executor.spawn_ok(async move {
+        let Ok(ctx) = SignalEmitter::new(conn, DISPLAY_CONFIG_PATH) else {
+            return;
+        };
+        if let Err(err) = DisplayConfig::monitors_changed(ctx).await {
+            warn!(?err, "Failed to announce monitor changes");
+        }
+    });
+}
+
+type MonitorSpec = (String, String, String, String);
+type DbusMode = (
+    String,
+    i32,
+    i32,
+    f64,
+    f64,
+    Vec<f64>,
+    HashMap<String, Value<'static>>,
+);
+type DbusMonitor = (MonitorSpec, Vec<DbusMode>, HashMap<String, Value<'static>>);
+type DbusLogicalMonitor = (
+    i32,
+    i32,
+    f64,
+    u32,
+    bool,
+    Vec<MonitorSpec>,
+    HashMap<String, Value<'static>>,
+);
+type DbusLogicalMonitorConfig = (
+    i32,
+    i32,
+    f64,
+    u32,
+    bool,
+    Vec<(String, String, HashMap<String, OwnedValue>)>,
+);
+
+fn monitor_spec(monitor: &Monitor) -> MonitorSpec {
+    (
+        monitor.connector.clone(),
+        monitor.vendor.clone(),
+        monitor.product.clone(),
+        monitor.serial.clone(),
+    )
+}
+
+/// Display configuration of the outputs, see the module documentation.
+pub struct DisplayConfig {
+    requests: Sender<ApplyRequest>,
+    policy: PolicyState,
+}
+
+#[zbus::interface(name = "org.gnome.Mutter.DisplayConfig")]
+impl DisplayConfig {
+    /// Current monitors as `(serial, monitors, logical monitors, properties)`
+    fn get_current_state(
+        &self,
+    ) -> (
+        u32,
+        Vec<DbusMonitor>,
+        Vec<DbusLogicalMonitor>,
+        HashMap<String, Value<'static>>,
+    ) {
+        let snapshot = SNAPSHOT.lock().unwrap();
+
+        let monitors = snapshot
+            .monitors
+            .iter()
+            .map(|monitor| {
+                let modes = monitor
+                    .modes
+                    .iter()
+                    .map(|mode| {
+                        let mut properties = HashMap::new();
+                        if mode.current {
+                            properties.insert(String::from("is-current"), Value::from(true));
+                        }
+                        if mode.preferred {
+                            properties.insert(String::from("is-preferred"), Value::from(true));
+                        }
+                        (
+                            mode.id.clone(),
+                            mode.width,
+                            mode.height,
+                            mode.refresh,
+                            1.0,
+                            SUPPORTED_SCALES.to_vec(),
+                            properties,
+                        )
+                    })
+                    .collect();
+                let properties = HashMap::from([
+                    (
+                        String::from("display-name"),
+                        Value::from(format!("{} {}", monitor.vendor, monitor.product)),
+                    ),
+                    (String::from("is-builtin"), Value::from(monitor.builtin)),
+                ]);
+                (monitor_spec(monitor), modes, properties)
+            })
+            .collect();
+
+        let logical_monitors = snapshot
+            .monitors
+            .iter()
+            .filter(|monitor| monitor.enabled && monitor.mirroring.is_none())
+            .map(|monitor| {
+                let mirrors = snapshot
+                    .monitors
+                    .iter()
+                    .filter(|other| other.mirroring.as_ref() == Some(&monitor.connector));
+                (
+                    monitor.position.x,
+                    monitor.position.y,
+                    monitor.scale,
+                    transform_to_dbus(monitor.transform),
+                    monitor.primary,
+                    std::iter::once(monitor)
+                        .chain(mirrors)
+                        .map(monitor_spec)
+                        .collect(),
+                    HashMap::new(),
+                )
+            })
+            .collect();
+
+        let properties = HashMap::from([
+            (
+                String::from("layout-mode"),
+                Value::from(LAYOUT_MODE_LOGICAL),
+            ),
+            (
+                String::from("supports-changing-layout-mode"),
+                Value::from(false),
+            ),
+            (String::from("global-scale-required"), Value::from(false)),
+        ]);
+
+        (snapshot.serial, monitors, logical_monitors, properties)
+    }
+
+    /// Applies or verifies a configuration based on the state of `serial`.
+    ///
+    /// Monitors missing from `logical_monitors` are disabled. Temporary configurations
+    /// are applied without being stored, reverting them is up to the caller.
+    ///
+    /// Subject to the `OutputManagement` policy, callers have to own a name of its `DBusName`
+    /// matchers, if an allow-list is configured.
+    async fn apply_monitors_config(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        serial: u32,
+        method: u32,
+        logical_monitors: Vec<DbusLogicalMonitorConfig>,
+        _properties: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<()> {
+        if !super::caller_allowed(
+            &header,
+            &self.policy,
+            PrivilegedProtocol::OutputManagement,
+            None,
+        )
+        .await
+        {
+            return Err(fdo::Error::AccessDenied("Access denied".to_string()));
+        }
+        if SNAPSHOT.lock().unwrap().serial != serial {
+            return Err(fdo::Error::AccessDenied(
+                "The requested configuration is based on stale information".into(),
+            ));
+        }
+        if !matches!(method, METHOD_VERIFY | METHOD_TEMPORARY | METHOD_PERSISTENT) {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Unknown method {}",
+                method
+            )));
+        }
+
+        let logical_monitors = logical_monitors
+            .into_iter()
+            .map(|(x, y, scale, transform, primary, monitors)| {
+                if monitors.is_empty() {
+                    return Err(fdo::Error::InvalidArgs(
+                        "Logical monitor without monitors".into(),
+                    ));
+                }
+                if !scale.is_finite() || scale <= 0. {
+                    return Err(fdo::Error::InvalidArgs(format!("Invalid scale {}", scale)));
+                }
+                Ok(LogicalMonitorConfig {
+                    position: Point::from((x, y)),
+                    scale,
+                    transform: transform_from_dbus(transform).ok_or_else(|| {
+                        fdo::Error::InvalidArgs(format!("Invalid transform {}", transform))
+                    })?,
+                    primary,
+                    monitors: monitors
+                        .into_iter()
+                        .map(|(connector, mode_id, _)| (connector, mode_id))
+                        .collect(),
+                })
+            })
+            .collect::<fdo::Result<Vec<_>>>()?;
+
+        let (reply, result) = oneshot::channel();
+        self.requests
+            .send(ApplyRequest {
+                verify: method == METHOD_VERIFY,
+                persist: method == METHOD_PERSISTENT,
+                logical_monitors,
+                reply,
+            })
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        match result.await {
+            Ok(true) => Ok(()),
+            _ => Err(fdo::Error::Failed("Invalid configuration".into())),
+        }
+    }
+
+    #[zbus(property)]
+    fn power_save_mode(&self) -> i32 {
+        // power saving is handled by the idle daemon
+        -1
+    }
+
+    #[zbus(signal)]
+    async fn monitors_changed(ctx: SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+impl State {
+    fn apply_display_config(
+        &mut self,
+        verify: bool,
+        persist: bool,
+        logical_monitors: Vec<LogicalMonitorConfig>,
+    ) -> bool {
+        let outputs = self
+            .common
+            .output_configuration_state
+            .outputs()
+            .collect::<Vec<_>>();
+        let find_output = |connector: &str| outputs.iter().find(|o| o.name() == connector);
+
+        let mut conf = Vec::new();
+        let mut primary = None;
+        for logical_monitor in logical_monitors {
+            let mut mirrored = None;
+            for (connector, id) in logical_monitor.monitors {
+                let Some(output) = find_output(&connector) else {
+                    warn!(connector, "Unknown monitor in display configuration");
+                    return false;
+                };
+                let Some(mode) = output.modes().into_iter().find(|m| mode_id(m) == id) else {
+                    warn!(connector, id, "Unknown mode in display configuration");
+                    return false;
+                };
+                if conf.iter().any(|(o, _)| o == output) {
+                    return false;
+                }
+                if logical_monitor.primary && mirrored.is_none() {
+                    primary = Some(output.clone());
+                }
+                conf.push((
+                    output.clone(),
+                    OutputConfiguration::Enabled {
+                        mirroring: mirrored.clone(),
+                        mode: Some(ModeConfiguration::Mode(mode)),
+                        position: Some(logical_monitor.position),
+                        transform: Some(logical_monitor.transform),
+                        scale: Some(logical_monitor.scale),
+                        adaptive_sync: None,
+                    },
+                ));
+                mirrored.get_or_insert_with(|| output.clone());
+            }
+        }
+        for output in &outputs {
+            if !conf.iter().any(|(o, _)| o == output) {
+                conf.push((output.clone(), OutputConfiguration::Disabled));
+            }
+        }
+
+        if !self.apply_output_configuration(verify, persist, conf) {
+            return false;
+        }
+        if !verify {
+            for output in &outputs {
+                output.config_mut().xwayland_primary = primary.as_ref() == Some(output);
+            }
+            self.common.update_xwayland_primary_output();
+            if persist {
+                self.common
+                    .config
+                    .write_outputs(self.common.output_configuration_state.outputs());
+            }
+        }
+        true
+    }
+}
+
+async fn serve_interface(
+    requests: Sender<ApplyRequest>,
+    policy: PolicyState,
+) -> zbus::Result<zbus::Connection> {
+    zbus::connection::Builder::session()?
+        .serve_at(DISPLAY_CONFIG_PATH, DisplayConfig { requests, policy })?
+        .name(DISPLAY_CONFIG_NAME)?
+        .build()
+        .await
+}
+
+/// Serves `org.gnome.Mutter.DisplayConfig`, applying configurations on the event loop
+pub fn init(
+    evlh: &LoopHandle<'static, State>,
+    executor: &ThreadPool,
+    policy: &PolicyState,
+) -> anyhow::Result<RegistrationToken> {
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(request) = event {
+                let ApplyRequest {
+                    verify,
+                    persist,
+                    logical_monitors,
+                    reply,
+                } = request;
+                let _ = reply.send(state.apply_display_config(verify, persist, logical_monitors));
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+
+    let policy = policy.clone();
+    executor.spawn_ok(async move {
+        match serve_interface(tx, policy).await {
+            Ok(conn) => {
+                let _ = CONNECTION.set(conn);
+            }
+            Err(err) => {
+                error!("Failed to serve `{}`: {}", DISPLAY_CONFIG_NAME, err);
+            }
+        }
+    });
+
+    Ok(token)
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_comp_config::a11y::{DwellClick as DwellClickConfig, DwellClickType};
+use cosmic_config::{ConfigGet, ConfigSet};
+use tracing::warn;
+
+use super::accessibility::config;
+
+/// Dwell clicking for assistive tools, see [`crate::input::dwell_click`].
+///
+/// Like `com.system76.CosmicComp.Accessibility`, settings are stored in the compositor config.
+pub struct DwellClick;
+
+fn get() -> DwellClickConfig {
+    config()
+        .ok()
+        .and_then(|config| config.get::<DwellClickConfig>("dwell_click").ok())
+        .unwrap_or_default()
+}
+
+fn update(f: impl FnOnce(&mut DwellClickConfig)) -> zbus::fdo::Result<()> {
+    let config = config()?;
+    let mut dwell_click = config
+        .get::<DwellClickConfig>("dwell_click")
+        .unwrap_or_default();
+    f(&mut dwell_click);
+    config.set("dwell_click", dwell_click).map_err(|err| {
+        warn!(?err, "Failed to update dwell click settings");
+        zbus::fdo::Error::Failed(err.to_string())
+    })
+}
+
+#[zbus::interface(name = "com.system76.CosmicComp.DwellClick")]
+impl DwellClick {
+    /// Whether resting the pointer clicks
+    #[zbus(property)]
+    fn enabled(&self) -> bool {
+        get().enabled
+    }
+
+    #[zbus(property)]
+    fn set_enabled(&mut self, enabled: bool) -> zbus::fdo::Result<()> {
+        update(|dwell_click| dwell_click.enabled = enabled)
+    }
+
+    /// Milliseconds the pointer has to rest before clicking
+    #[zbus(property)]
+    fn delay(&self) -> u32 {
+        get().delay
+    }
+
+    #[zbus(property)]
+    fn set_delay(&mut self, delay: u32) -> zbus::fdo::Result<()> {
+        update(|dwell_click| dwell_click.delay = delay)
+    }
+
+    /// Distance in logical pixels the pointer may move while resting
+    #[zbus(property)]
+    fn threshold(&self) -> u32 {
+        get().threshold
+    }
+
+    #[zbus(property)]
+    fn set_threshold(&mut self, threshold: u32) -> zbus::fdo::Result<()> {
+        update(|dwell_click| dwell_click.threshold = threshold)
+    }
+
+    /// Click made after resting, one of `single`, `double` or `drag`.
+    ///
+    /// A drag presses the button on one dwell and releases it on the next.
+    #[zbus(property)]
+    fn click_type(&self) -> String {
+        match get().click_type {
+            DwellClickType::Single => "single",
+            DwellClickType::Double => "double",
+            DwellClickType::Drag => "drag",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn set_click_type(&mut self, click_type: String) -> zbus::fdo::Result<()> {
+        let click_type = match click_type.as_str() {
+            "single" => DwellClickType::Single,
+            "double" => DwellClickType::Double,
+            "drag" => DwellClickType::Drag,
+            _ => {
+                return Err(zbus::fdo::Error::InvalidArgs(format!(
+                    "Unknown click type {click_type}"
+                )));
+            }
+        };
+        update(|dwell_click| dwell_click.click_type = click_type)
+    }
+}
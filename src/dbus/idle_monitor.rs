@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! `org.gnome.Mutter.IdleMonitor` compatibility, still used by legacy applications and
+//! caffeine-style tools to query the idle time and to get notified about idleness.
+//!
+//! Idle time is measured from the last input event, independent of the idle sequence in
+//! [`crate::idle`]. Watches are dropped, once the connection adding them leaves the bus.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use calloop::channel::Sender;
+use futures_executor::ThreadPool;
+use futures_util::stream::StreamExt;
+use tracing::{debug, warn};
+use zbus::{
+    fdo,
+    message::Header,
+    names::{BusName, UniqueName},
+    object_server::SignalEmitter,
+};
+
+/// Well-known name of the idle monitor service
+pub const IDLE_MONITOR_NAME: &str = "org.gnome.Mutter.IdleMonitor";
+const IDLE_MONITOR_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+
+#[derive(Debug)]
+struct Watch {
+    owner: UniqueName<'static>,
+    /// Idle time to fire at, `None` for watches firing on the next activity
+    interval: Option<Duration>,
+    /// Idle watches fire once per idle period
+    fired: bool,
+}
+
+#[derive(Debug)]
+struct Watches {
+    next_id: u32,
+    last_activity: Instant,
+    watches: HashMap<u32, Watch>,
+}
+
+static WATCHES: LazyLock<Mutex<Watches>> = LazyLock::new(|| {
+    Mutex::new(Watches {
+        next_id: 1,
+        last_activity: Instant::now(),
+        watches: HashMap::new(),
+    })
+});
+static CONNECTION: OnceLock<zbus::Connection> = OnceLock::new();
+
+fn fire(ids: Vec<u32>, executor: &ThreadPool) {
+    if ids.is_empty() {
+        return;
+    }
+    let Some(conn) = CONNECTION.get() else {
+        return;
+    };
+    executor.spawn_ok(async move {
+        let Ok(ctx) = SignalEmitter::new(conn, IDLE_MONITOR_PATH) else {
+            return;
+        };
+        for id in ids {
+            if let Err(err) = IdleMonitor::watch_fired(ctx.clone(), id).await {
+                warn!(?err, "Failed to fire idle watch");
+            }
+        }
+    });
+}
+
+/// Restarts the idle time and fires user active watches.
+///
+/// Returns whether idle watches are waiting to fire, see [`fire_due`].
+pub fn activity(executor: &ThreadPool) -> bool {
+    let mut watches = WATCHES.lock().unwrap();
+    watches.last_activity = Instant::now();
+    if watches.watches.is_empty() {
+        return false;
+    }
+
+    let mut fired = Vec::new();
+    watches.watches.retain(|id, watch| {
+        if watch.interval.is_none() {
+            fired.push(*id);
+            return false;
+        }
+        watch.fired = false;
+        true
+    });
+    let pending = !watches.watches.is_empty();
+    std::mem::drop(watches);
+
+    fire(fired, executor);
+    pending
+}
+
+/// Fires idle watches, whose interval elapsed, and returns when the next one is due
+pub fn fire_due(executor: &ThreadPool) -> Option<Instant> {
+    let mut watches = WATCHES.lock().unwrap();
+    let last_activity = watches.last_activity;
+    let now = Instant::now();
+
+    let mut fired = Vec::new();
+    let mut next_deadline = None;
+    for (id, watch) in watches.watches.iter_mut() {
+        let Some(interval) = watch.interval.filter(|_| !watch.fired) else {
+            continue;
+        };
+        let deadline = last_activity + interval;
+        if deadline <= now {
+            watch.fired = true;
+            fired.push(*id);
+        } else {
+            next_deadline = Some(next_deadline.map_or(deadline, |d: Instant| d.min(deadline)));
+        }
+    }
+    std::mem::drop(watches);
+
+    fire(fired, executor);
+    next_deadline
+}
+
+pub struct IdleMonitor {
+    /// Requests to schedule idle watches, handled on the event loop
+    pub watch_added: Sender<()>,
+}
+
+impl IdleMonitor {
+    fn add_watch(&self, header: &Header<'_>, interval: Option<Duration>) -> fdo::Result<u32> {
+        let owner = header
+            .sender()
+            .ok_or_else(|| fdo::Error::Failed("Unknown sender".into()))?
+            .to_owned();
+
+        let mut watches = WATCHES.lock().unwrap();
+        let id = watches.next_id;
+        watches.next_id = id.checked_add(1).unwrap_or(1);
+        watches.watches.insert(
+            id,
+            Watch {
+                owner,
+                interval,
+                fired: false,
+            },
+        );
+        std::mem::drop(watches);
+
+        if interval.is_some() {
+            let _ = self.watch_added.send(());
+        }
+        Ok(id)
+    }
+}
+
+#[zbus::interface(name = "org.gnome.Mutter.IdleMonitor")]
+impl IdleMonitor {
+    /// Milliseconds since the last input event
+    fn get_idletime(&self) -> u64 {
+        WATCHES.lock().unwrap().last_activity.elapsed().as_millis() as u64
+    }
+
+    /// Fire `WatchFired` once the idle time reaches `interval` milliseconds
+    fn add_idle_watch(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        interval: u64,
+    ) -> fdo::Result<u32> {
+        if interval == 0 {
+            return Err(fdo::Error::InvalidArgs("Invalid interval 0".into()));
+        }
+        self.add_watch(&header, Some(Duration::from_millis(interval)))
+    }
+
+    /// Fire `WatchFired` once on the next input event
+    fn add_user_active_watch(&self, #[zbus(header)] header: Header<'_>) -> fdo::Result<u32> {
+        self.add_watch(&header, None)
+    }
+
+    fn remove_watch(&self, #[zbus(header)] header: Header<'_>, id: u32) -> fdo::Result<()> {
+        let mut watches = WATCHES.lock().unwrap();
+        // only the connection, that added the watch, may remove it
+        match watches.watches.get(&id) {
+            Some(watch) if Some(&watch.owner) == header.sender() => {
+                watches.watches.remove(&id);
+                Ok(())
+            }
+            _ => Err(fdo::Error::InvalidArgs(format!("Unknown watch {}", id))),
+        }
+    }
+
+    #[zbus(signal)]
+    async fn watch_fired(ctx: SignalEmitter<'_>, id: u32) -> zbus::Result<()>;
+}
+
+/// Serves `org.gnome.Mutter.IdleMonitor` and drops watches of clients leaving the bus
+pub async fn serve(monitor: IdleMonitor) -> zbus::Result<()> {
+    let conn = zbus::connection::Builder::session()?
+        .serve_at(IDLE_MONITOR_PATH, monitor)?
+        .name(IDLE_MONITOR_NAME)?
+        .build()
+        .await?;
+    let _ = CONNECTION.set(conn.clone());
+
+    let dbus = fdo::DBusProxy::new(&conn).await?;
+    let mut stream = dbus.receive_name_owner_changed().await?;
+    while let Some(signal) = stream.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        if let (BusName::Unique(name), None) = (&args.name, &*args.new_owner) {
+            WATCHES.lock().unwrap().watches.retain(|id, watch| {
+                let keep = watch.owner != *name;
+                if !keep {
+                    debug!(id, "Dropping idle watch of vanished client");
+                }
+                keep
+            });
+        }
+    }
+
+    Ok(())
+}
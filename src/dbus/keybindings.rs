@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::{LazyLock, Mutex};
+
+use calloop::channel::Sender;
+use cosmic_settings_config::{Shortcuts, shortcuts::Action};
+
+use crate::{
+    shell::{SeatExt, Shell},
+    state::State,
+};
+
+/// `(binding, action, label)`
+type BindingInfo = (String, String, String);
+
+/// Effective keybindings, updated whenever the shortcuts config is (re)loaded
+static BINDINGS: LazyLock<Mutex<Vec<BindingInfo>>> = LazyLock::new(Default::default);
+
+/// Updates the keybindings reported by `Bindings`.
+///
+/// Disabled bindings are left out, as they don't trigger anything.
+pub fn publish(shortcuts: &Shortcuts) {
+    let mut bindings = shortcuts
+        .iter()
+        .filter(|(_, action)| !matches!(action, Action::Disable))
+        .map(|(binding, action)| {
+            (
+                binding.to_string(),
+                format!("{:?}", action),
+                crate::shell::cheatsheet::action_label(binding, action),
+            )
+        })
+        .collect::<Vec<_>>();
+    bindings.sort();
+    *BINDINGS.lock().unwrap() = bindings;
+}
+
+/// Scratchpad actions, see [`crate::shell::scratchpad`]
+pub enum ScratchpadRequest {
+    /// Hide the focused window in the scratchpad
+    Send,
+    /// Show or hide the next scratchpad window
+    Toggle,
+}
+
+/// Master-stack actions, see [`cosmic_comp_config::workspace::TilingAlgorithm::MasterStack`]
+pub enum MasterStackRequest {
+    /// Move the focused window into the master area
+    Promote,
+    /// Move the focused window into the stack
+    Demote,
+    /// Grow or shrink the master area by per mille of the width
+    ResizeMaster(i32),
+    /// Change the number of windows in the master area
+    ChangeMasters(i32),
+}
+
+/// Keybinding related actions, that can be bound to a spawn shortcut.
+pub struct Keybindings {
+    /// Requests to toggle the cheatsheet, handled on the event loop
+    pub cheatsheet: Sender<()>,
+    /// Requests to toggle the decorations of the focused window
+    pub decorations: Sender<()>,
+    /// Requests to send windows to or summon them from the scratchpad
+    pub scratchpad: Sender<ScratchpadRequest>,
+    /// Requests to rearrange master-stack workspaces
+    pub master_stack: Sender<MasterStackRequest>,
+    /// Requests to switch the tiling algorithm of the active workspace
+    pub tiling_algorithm: Sender<()>,
+}
+
+#[zbus::interface(name = "com.system76.CosmicComp.Keybindings")]
+impl Keybindings {
+    /// Show or hide an overlay listing the keybindings of the active mode
+    fn toggle_cheatsheet(&self) -> zbus::fdo::Result<()> {
+        self.cheatsheet
+            .send(())
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Switch the focused window between server- and client-side decorations.
+    ///
+    /// The choice lasts until the window is closed or the decoration overrides of its
+    /// application are changed in the config.
+    fn toggle_decorations(&self) -> zbus::fdo::Result<()> {
+        self.decorations
+            .send(())
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Hide the focused window in the scratchpad
+    fn send_to_scratchpad(&self) -> zbus::fdo::Result<()> {
+        self.scratchpad
+            .send(ScratchpadRequest::Send)
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Hide the scratchpad window shown on the active workspace, or show the next one
+    /// centered and floating on it.
+    ///
+    /// Hidden windows are shown in the order they were hidden in, so toggling
+    /// repeatedly cycles through all of them.
+    fn toggle_scratchpad(&self) -> zbus::fdo::Result<()> {
+        self.scratchpad
+            .send(ScratchpadRequest::Toggle)
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Switch the active workspace to the next tiling algorithm, cycling from the tree to
+    /// master-stack to dwindle.
+    ///
+    /// The choice overrides the configured algorithm for this workspace and is kept, if the
+    /// workspace is pinned.
+    fn cycle_tiling_algorithm(&self) -> zbus::fdo::Result<()> {
+        self.tiling_algorithm
+            .send(())
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Move the focused window to the front of the master area on master-stack workspaces.
+    ///
+    /// If it already is the first master, it swaps places with the next window instead.
+    fn promote_window(&self) -> zbus::fdo::Result<()> {
+        self.master_stack
+            .send(MasterStackRequest::Promote)
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Move the focused window from the master area to the top of the stack on
+    /// master-stack workspaces
+    fn demote_window(&self) -> zbus::fdo::Result<()> {
+        self.master_stack
+            .send(MasterStackRequest::Demote)
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Grow the master area of the active workspace by `delta` of its width, negative values
+    /// shrink it
+    fn resize_master_area(&self, delta: f64) -> zbus::fdo::Result<()> {
+        self.master_stack
+            .send(MasterStackRequest::ResizeMaster(
+                (delta * 1000.).round() as i32
+            ))
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Add `delta` windows to the master area of the active workspace, negative values
+    /// remove windows. At least one window is always kept.
+    fn change_master_count(&self, delta: i32) -> zbus::fdo::Result<()> {
+        self.master_stack
+            .send(MasterStackRequest::ChangeMasters(delta))
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Keybindings currently in effect as `(binding, action, label)`.
+    ///
+    /// Includes the system defaults merged with the user's overrides. Actions are
+    /// formatted like in the shortcuts config, labels are the ones shown by the cheatsheet.
+    fn bindings(&self) -> Vec<BindingInfo> {
+        BINDINGS.lock().unwrap().clone()
+    }
+}
+
+impl State {
+    pub fn handle_scratchpad_request(&mut self, request: ScratchpadRequest) {
+        let mut shell = self.common.shell.write();
+        let seat = shell.seats.last_active().clone();
+        let output = seat.active_output();
+        let target = match request {
+            ScratchpadRequest::Send => {
+                if !shell.send_to_scratchpad(&seat) {
+                    return;
+                }
+                None
+            }
+            ScratchpadRequest::Toggle => {
+                shell.toggle_scratchpad(&seat, &self.common.event_loop_handle)
+            }
+        };
+        std::mem::drop(shell);
+
+        if let Some(target) = target {
+            Shell::set_focus(self, Some(&target), &seat, None, true);
+        }
+        self.backend.schedule_render(&output);
+    }
+
+    pub fn handle_master_stack_request(&mut self, request: MasterStackRequest) {
+        let mut shell = self.common.shell.write();
+        let seat = shell.seats.last_active().clone();
+        let output = seat.active_output();
+        let Some(workspace) = shell.active_space_mut(&output) else {
+            return;
+        };
+        match request {
+            MasterStackRequest::Promote => workspace.tiling_layer.promote_focused(&seat),
+            MasterStackRequest::Demote => workspace.tiling_layer.demote_focused(&seat),
+            MasterStackRequest::ResizeMaster(delta) => {
+                workspace.tiling_layer.adjust_master_area(delta, 0)
+            }
+            MasterStackRequest::ChangeMasters(delta) => {
+                workspace.tiling_layer.adjust_master_area(0, delta)
+            }
+        }
+        std::mem::drop(shell);
+
+        self.backend.schedule_render(&output);
+    }
+
+    pub fn cycle_tiling_algorithm(&mut self) {
+        let mut shell = self.common.shell.write();
+        let output = shell.seats.last_active().active_output();
+        let default = shell.workspaces.tiling_algorithm();
+        let Some(workspace) = shell.active_space_mut(&output) else {
+            return;
+        };
+        workspace.cycle_tiling_algorithm(default);
+        if workspace.pinned {
+            shell.workspaces.persist(&self.common.config);
+        }
+        std::mem::drop(shell);
+
+        self.backend.schedule_render(&output);
+    }
+}
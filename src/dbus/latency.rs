@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::utils::latency;
+
+/// Benchmark of the latency from input events to their presentation, see [`crate::utils::latency`].
+pub struct Latency;
+
+#[zbus::interface(name = "com.system76.CosmicComp.Latency")]
+impl Latency {
+    /// Whether input events are currently timestamped, enabling discards previous results
+    #[zbus(property)]
+    fn enabled(&self) -> bool {
+        latency::is_enabled()
+    }
+
+    #[zbus(property)]
+    fn set_enabled(&mut self, enabled: bool) {
+        latency::set_enabled(enabled);
+    }
+
+    /// Latency per output as `(output, samples, p50, p90, p99, max)` in milliseconds
+    fn results(&self) -> Vec<(String, u32, f64, f64, f64, f64)> {
+        latency::reports()
+            .into_iter()
+            .map(|report| {
+                (
+                    report.output,
+                    report.samples as u32,
+                    report.p50.as_secs_f64() * 1000.,
+                    report.p90.as_secs_f64() * 1000.,
+                    report.p99.as_secs_f64() * 1000.,
+                    report.max.as_secs_f64() * 1000.,
+                )
+            })
+            .collect()
+    }
+
+    /// Discard all samples, without stopping the measurement
+    fn reset(&self) {
+        latency::reset();
+    }
+}
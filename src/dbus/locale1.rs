@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use futures_util::stream::{self, StreamExt};
+
+use crate::config::SystemKeymap;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.locale1",
+    default_service = "org.freedesktop.locale1",
+    default_path = "/org/freedesktop/locale1"
+)]
+pub trait Locale1 {
+    #[zbus(property, name = "X11Model")]
+    fn x11_model(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "X11Layout")]
+    fn x11_layout(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "X11Variant")]
+    fn x11_variant(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "X11Options")]
+    fn x11_options(&self) -> zbus::Result<String>;
+}
+
+async fn keymap(locale: &Locale1Proxy<'_>) -> zbus::Result<SystemKeymap> {
+    Ok(SystemKeymap {
+        model: locale.x11_model().await?,
+        layout: locale.x11_layout().await?,
+        variant: locale.x11_variant().await?,
+        options: locale.x11_options().await?,
+    })
+}
+
+/// Sends the system-wide keymap and every change of it to `tx`.
+pub async fn watch_keymap(tx: calloop::channel::Sender<SystemKeymap>) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let locale = Locale1Proxy::new(&conn).await?;
+
+    let mut changes = stream::select_all([
+        locale.receive_x11_model_changed().await.map(|_| ()).boxed(),
+        locale
+            .receive_x11_layout_changed()
+            .await
+            .map(|_| ())
+            .boxed(),
+        locale
+            .receive_x11_variant_changed()
+            .await
+            .map(|_| ())
+            .boxed(),
+        locale
+            .receive_x11_options_changed()
+            .await
+            .map(|_| ())
+            .boxed(),
+    ]);
+
+    let mut current = keymap(&locale).await?;
+    if tx.send(current.clone()).is_err() {
+        return Ok(());
+    }
+    while changes.next().await.is_some() {
+        // properties are usually changed together, so read all of them again
+        let keymap = keymap(&locale).await?;
+        if keymap != current {
+            current = keymap;
+            if tx.send(current.clone()).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
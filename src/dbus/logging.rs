@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use tracing::warn;
+
+/// Runtime control over the `tracing` filter, e.g. to enable `cosmic_comp::backend::kms=trace`
+/// while reproducing an issue, without restarting the compositor with `RUST_LOG` set.
+pub struct Logging;
+
+#[zbus::interface(name = "com.system76.CosmicComp.Logging")]
+impl Logging {
+    /// Currently active filter directives
+    fn get_filter(&self) -> zbus::fdo::Result<String> {
+        crate::logger::current_filter()
+            .ok_or_else(|| zbus::fdo::Error::Failed("Logger not initialized".to_string()))
+    }
+
+    /// Replace the active filter directives (same syntax as `RUST_LOG`)
+    fn set_filter(&self, directives: &str) -> zbus::fdo::Result<()> {
+        crate::logger::set_filter(directives).map_err(|err| {
+            warn!(?err, "Failed to set log filter");
+            zbus::fdo::Error::InvalidArgs(format!("{:#}", err))
+        })
+    }
+
+    /// Restore the filter directives used at startup
+    fn reset_filter(&self) -> zbus::fdo::Result<()> {
+        crate::logger::reset_filter()
+            .map_err(|err| zbus::fdo::Error::Failed(format!("{:#}", err)))
+    }
+}
@@ -1,7 +1,15 @@
 use std::os::fd::OwnedFd;
 
 use anyhow::{Context, Result};
-use logind_zbus::manager::{InhibitType::HandleLidSwitch, ManagerProxyBlocking};
+use calloop::channel::Sender;
+use futures_channel::mpsc::UnboundedSender;
+use futures_executor::ThreadPool;
+use futures_util::stream::StreamExt;
+use logind_zbus::manager::{
+    InhibitType::{HandleLidSwitch, Shutdown, Sleep},
+    ManagerProxy, ManagerProxyBlocking,
+};
+use tracing::warn;
 use zbus::blocking::Connection;
 
 pub fn inhibit_lid() -> Result<OwnedFd> {
@@ -17,8 +25,160 @@ pub fn inhibit_lid() -> Result<OwnedFd> {
     Ok(fd.into())
 }
 
+/// Delays shutting down, until the returned fd is dropped or logind's `InhibitDelayMaxSec` passed.
+pub fn inhibit_shutdown() -> Result<OwnedFd> {
+    let conn = Connection::system()?;
+    let proxy = ManagerProxyBlocking::new(&conn)?;
+    let fd = proxy.inhibit(Shutdown, "cosmic-comp", "Saving the session state", "delay")?;
+
+    Ok(fd.into())
+}
+
+/// `PrepareForSleep`, as forwarded by [`watch_prepare_for_sleep`]
+#[derive(Debug)]
+pub enum SleepEvent {
+    /// The system is about to suspend
+    Suspending,
+    /// The system resumed or the watch just started, with a new inhibitor delaying the next
+    /// suspend, until it is dropped or logind's `InhibitDelayMaxSec` passed
+    Resumed(Option<OwnedFd>),
+}
+
+/// Delays suspending, until the returned fd is dropped or logind's `InhibitDelayMaxSec` passed.
+async fn inhibit_sleep(proxy: &ManagerProxy<'_>) -> Option<OwnedFd> {
+    match proxy
+        .inhibit(
+            Sleep,
+            "cosmic-comp",
+            "Locking the session before suspend",
+            "delay",
+        )
+        .await
+    {
+        Ok(fd) => Some(fd.into()),
+        Err(err) => {
+            warn!(?err, "Failed to inhibit sleep");
+            None
+        }
+    }
+}
+
+/// Forwards `PrepareForSleep`, taking a delay inhibitor once subscribed and after every resume
+/// over the same connection, so the event loop never waits for logind.
+pub async fn watch_prepare_for_sleep(tx: Sender<SleepEvent>) -> Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let proxy = ManagerProxy::new(&conn).await?;
+    let mut stream = proxy.receive_prepare_for_sleep().await?;
+    if tx
+        .send(SleepEvent::Resumed(inhibit_sleep(&proxy).await))
+        .is_err()
+    {
+        return Ok(());
+    }
+    while let Some(signal) = stream.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        let event = if args.start {
+            SleepEvent::Suspending
+        } else {
+            SleepEvent::Resumed(inhibit_sleep(&proxy).await)
+        };
+        if tx.send(event).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Forwards `PrepareForShutdown`, sending `false` once subscribed like [`watch_prepare_for_sleep`].
+pub async fn watch_prepare_for_shutdown(tx: Sender<bool>) -> Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let proxy = ManagerProxy::new(&conn).await?;
+    let mut stream = proxy.receive_prepare_for_shutdown().await?;
+    if tx.send(false).is_err() {
+        return Ok(());
+    }
+    while let Some(signal) = stream.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        if tx.send(args.start).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
 pub fn lid_closed() -> Result<bool> {
     let conn = Connection::system()?;
     let proxy = ManagerProxyBlocking::new(&conn)?;
     proxy.lid_closed().context("Failed to talk to logind")
 }
+
+/// Ask logind to lock the current session, which starts the session's screen locker.
+pub fn lock_session() -> Result<()> {
+    let conn = Connection::system()?;
+    conn.call_method(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1/session/auto",
+        Some("org.freedesktop.login1.Session"),
+        "Lock",
+        &(),
+    )
+    .context("Failed to talk to logind")?;
+    Ok(())
+}
+
+/// Sets the `IdleHint` of the current session, used by logind's `IdleAction`, to every value
+/// sent through the returned sender, in order and over a single connection.
+pub fn idle_hint_updater(executor: &ThreadPool) -> UnboundedSender<bool> {
+    let (tx, mut rx) = futures_channel::mpsc::unbounded();
+    executor.spawn_ok(async move {
+        let conn = match zbus::Connection::system().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    "Failed to connect to logind, not reporting the idle hint"
+                );
+                return;
+            }
+        };
+        while let Some(idle) = rx.next().await {
+            if let Err(err) = conn
+                .call_method(
+                    Some("org.freedesktop.login1"),
+                    "/org/freedesktop/login1/session/auto",
+                    Some("org.freedesktop.login1.Session"),
+                    "SetIdleHint",
+                    &(idle,),
+                )
+                .await
+            {
+                warn!(?err, "Failed to update the idle hint");
+            }
+        }
+    });
+    tx
+}
+
+/// Sets the brightness of a backlight or led device through the current session.
+pub fn set_brightness(subsystem: &str, name: &str, brightness: u32) -> Result<()> {
+    let conn = Connection::system()?;
+    conn.call_method(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1/session/auto",
+        Some("org.freedesktop.login1.Session"),
+        "SetBrightness",
+        &(subsystem, name, brightness),
+    )
+    .context("Failed to talk to logind")?;
+    Ok(())
+}
+
+pub fn suspend() -> Result<()> {
+    let conn = Connection::system()?;
+    let proxy = ManagerProxyBlocking::new(&conn)?;
+    proxy.suspend(false).context("Failed to talk to logind")
+}
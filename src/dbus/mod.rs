@@ -1,35 +1,514 @@
 use crate::{
+    idle::IdleInhibitor,
     state::{BackendData, Common, State},
     utils::prelude::OutputExt,
+    wayland::policy::PolicyState,
 };
 use anyhow::{Context, Result};
-use calloop::{InsertError, LoopHandle, RegistrationToken};
-use cosmic_comp_config::output::comp::OutputState;
+use calloop::{InsertError, LoopHandle, RegistrationToken, channel::Sender};
+use cosmic_comp_config::{output::comp::OutputState, policy::PrivilegedProtocol};
 use futures_executor::{ThreadPool, block_on};
-use futures_util::stream::StreamExt;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, OnceLock},
+};
 use tracing::{error, warn};
-use zbus::blocking::{Connection, fdo::DBusProxy};
+use zbus::{
+    blocking::{Connection, fdo::DBusProxy},
+    message::Header,
+    names::WellKnownName,
+};
 
+pub mod a11y_focus;
 pub mod a11y_keyboard_monitor;
+mod accessibility;
+mod audit;
+pub mod colord;
+pub mod control;
+pub mod display_config;
+mod dwell_click;
+pub mod idle_monitor;
+pub mod keybindings;
+mod latency;
+mod locale1;
+mod logging;
 #[cfg(feature = "systemd")]
 pub mod logind;
+mod mpris;
 mod name_owners;
+pub mod night_light;
 mod power;
+mod profiler;
+pub mod remote_session;
+mod screensaver;
+pub mod screenshot_portal;
+pub mod sound;
+pub mod systemd;
+pub mod toplevels;
+mod upower;
+
+/// Well-known name owned by the compositor on the session bus
+pub const COMP_NAME: &str = "com.system76.CosmicComp";
+/// Object path of the compositor's own interfaces
+pub const COMP_PATH: &str = "/com/system76/CosmicComp";
+
+static SESSION_CONNECTION: OnceLock<zbus::Connection> = OnceLock::new();
+static NAME_OWNERS: OnceLock<name_owners::NameOwners> = OnceLock::new();
+/// Watches started before owners of names on the session bus are tracked
+static PENDING_NAME_WATCHES: Mutex<Vec<(WellKnownName<'static>, Sender<bool>)>> =
+    Mutex::new(Vec::new());
+/// Names to track the owners' pids of, set before owners of names are tracked
+static PENDING_TRACKED_PIDS: Mutex<Option<HashSet<WellKnownName<'static>>>> = Mutex::new(None);
+
+/// Session bus connection serving the `com.system76.CosmicComp` interfaces,
+/// once it is established.
+pub fn session_connection() -> Option<&'static zbus::Connection> {
+    SESSION_CONNECTION.get()
+}
+
+/// Pid of the process owning the well-known `name` on the session bus.
+///
+/// Doesn't block, so it can be used in global filters. Only owners of names passed to
+/// [`track_name_owner_pids`] are known, once they were looked up in the background.
+pub fn name_owner_pid(name: &str) -> Option<u32> {
+    let name = zbus::names::WellKnownName::try_from(name).ok()?;
+    NAME_OWNERS.get()?.owner_pid_no_poll(&name)
+}
+
+/// Looks up the pids of the owners of `names` on the session bus eagerly, whenever their
+/// owners change, so [`name_owner_pid`] knows them. Replaces the names tracked before.
+pub fn track_name_owner_pids<'a>(names: impl IntoIterator<Item = &'a str>) {
+    let names = names
+        .into_iter()
+        .filter_map(|name| WellKnownName::try_from(name).ok())
+        .map(WellKnownName::into_owned)
+        .collect::<HashSet<_>>();
+
+    let mut pending = PENDING_TRACKED_PIDS.lock().unwrap();
+    match NAME_OWNERS.get() {
+        Some(name_owners) => name_owners.track_owner_pids(names),
+        None => *pending = Some(names),
+    }
+}
+
+/// Calls `callback` on the event loop with whether the well-known `name` has an owner on the
+/// session bus, once known and whenever it appears, vanishes or changes owners.
+///
+/// The watch ends, once the returned token is removed from the event loop.
+pub fn watch_name<F>(
+    evlh: &LoopHandle<'static, State>,
+    name: &str,
+    mut callback: F,
+) -> Result<RegistrationToken>
+where
+    F: FnMut(bool, &mut State) + 'static,
+{
+    let name = WellKnownName::try_from(name)
+        .with_context(|| format!("Invalid name {}", name))?
+        .into_owned();
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, move |event, _, state| {
+            if let calloop::channel::Event::Msg(owned) = event {
+                callback(owned, state);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+
+    let mut pending = PENDING_NAME_WATCHES.lock().unwrap();
+    match NAME_OWNERS.get() {
+        Some(name_owners) => name_owners.watch(name, tx),
+        None => pending.push((name, tx)),
+    }
+    Ok(token)
+}
+
+/// Whether the sender of a method call may use methods giving the access of `protocol`.
+///
+/// The sender has to own one of the well-known names of the `DBusName` matchers configured for
+/// `protocol` in `privileged_protocols`, or one of `default` without an allow-list.
+///
+/// A `default` of `None` allows every caller, like the globals are advertised to every client,
+/// that isn't sandboxed. Sandboxes already filter, which names their apps may talk to.
+pub async fn caller_allowed(
+    header: &Header<'_>,
+    policy: &PolicyState,
+    protocol: PrivilegedProtocol,
+    default: Option<&[WellKnownName<'_>]>,
+) -> bool {
+    let (Some(sender), Some(name_owners)) = (header.sender(), NAME_OWNERS.get()) else {
+        return false;
+    };
+    match (policy.dbus_names(protocol), default) {
+        (Some(names), _) => {
+            let names = names
+                .iter()
+                .filter_map(|name| WellKnownName::try_from(name.as_str()).ok())
+                .collect::<Vec<_>>();
+            name_owners.check_owner(sender, &names).await
+        }
+        (None, Some(default)) => name_owners.check_owner(sender, default).await,
+        (None, None) => true,
+    }
+}
+
+async fn serve_interfaces(
+    cheatsheet: Sender<()>,
+    decorations: Sender<()>,
+    scratchpad: Sender<keybindings::ScratchpadRequest>,
+    master_stack: Sender<keybindings::MasterStackRequest>,
+    tiling_algorithm: Sender<()>,
+    control: Sender<control::ControlRequest>,
+    screenshot: Sender<screenshot_portal::ScreenshotRequest>,
+    policy: PolicyState,
+) -> zbus::Result<zbus::Connection> {
+    zbus::connection::Builder::session()?
+        .serve_at(COMP_PATH, accessibility::Accessibility)?
+        .serve_at(COMP_PATH, audit::Audit)?
+        .serve_at(
+            COMP_PATH,
+            control::Control {
+                requests: control,
+                policy,
+            },
+        )?
+        .serve_at(COMP_PATH, dwell_click::DwellClick)?
+        .serve_at(
+            COMP_PATH,
+            keybindings::Keybindings {
+                cheatsheet,
+                decorations,
+                scratchpad,
+                master_stack,
+                tiling_algorithm,
+            },
+        )?
+        .serve_at(COMP_PATH, latency::Latency)?
+        .serve_at(COMP_PATH, logging::Logging)?
+        .serve_at(COMP_PATH, night_light::NightLight)?
+        .serve_at(COMP_PATH, profiler::Profiler)?
+        .serve_at(COMP_PATH, toplevels::Toplevels)?
+        .serve_at(
+            screenshot_portal::PORTAL_PATH,
+            screenshot_portal::Screenshot {
+                requests: screenshot,
+            },
+        )?
+        .name(COMP_NAME)?
+        .build()
+        .await
+}
 
 pub fn init(
     evlh: &LoopHandle<'static, State>,
     executor: &ThreadPool,
+    policy: &PolicyState,
 ) -> Result<Vec<RegistrationToken>> {
     let mut tokens = Vec::new();
 
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(()) = event {
+                let output = state.common.shell.write().toggle_cheatsheet(
+                    &state.common.config,
+                    state.common.event_loop_handle.clone(),
+                );
+                state.backend.schedule_render(&output);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let (decorations_tx, decorations_rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(decorations_rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(()) = event {
+                state.toggle_focused_decorations();
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let (scratchpad_tx, scratchpad_rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(scratchpad_rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(request) = event {
+                state.handle_scratchpad_request(request);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let (master_stack_tx, master_stack_rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(master_stack_rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(request) = event {
+                state.handle_master_stack_request(request);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let (tiling_algorithm_tx, tiling_algorithm_rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(tiling_algorithm_rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(()) = event {
+                state.cycle_tiling_algorithm();
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let (control_tx, control_rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(control_rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(request) = event {
+                state.handle_control_request(request);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let (screenshot_tx, screenshot_rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(screenshot_rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(request) = event {
+                state.handle_screenshot_request(request);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    tokens.push(token);
+
+    let executor_clone = executor.clone();
+    let policy_clone = policy.clone();
+    executor.spawn_ok(async move {
+        match serve_interfaces(
+            tx,
+            decorations_tx,
+            scratchpad_tx,
+            master_stack_tx,
+            tiling_algorithm_tx,
+            control_tx,
+            screenshot_tx,
+            policy_clone,
+        )
+        .await
+        {
+            Ok(conn) => {
+                match name_owners::NameOwners::new(&conn, &executor_clone).await {
+                    Ok(name_owners) => {
+                        let mut pending = PENDING_NAME_WATCHES.lock().unwrap();
+                        for (name, tx) in pending.drain(..) {
+                            name_owners.watch(name, tx);
+                        }
+                        let mut pending_pids = PENDING_TRACKED_PIDS.lock().unwrap();
+                        if let Some(names) = pending_pids.take() {
+                            name_owners.track_owner_pids(names);
+                        }
+                        let _ = NAME_OWNERS.set(name_owners);
+                    }
+                    Err(err) => {
+                        warn!(?err, "Failed to track owners of names on the session bus");
+                    }
+                }
+                let _ = SESSION_CONNECTION.set(conn);
+            }
+            Err(err) => {
+                error!("Failed to serve `{}`: {}", COMP_NAME, err);
+            }
+        }
+    });
+
+    tokens.push(a11y_focus::init(evlh)?);
+    tokens.push(display_config::init(evlh, executor, policy)?);
+    tokens.push(colord::init(evlh)?);
+    tokens.push(night_light::init(evlh)?);
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(on_battery) = event {
+                state.set_on_battery(on_battery);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    executor.spawn_ok(async move {
+        if let Err(err) = upower::watch_on_battery(tx).await {
+            tracing::info!(?err, "Failed to connect to org.freedesktop.UPower");
+        }
+    });
+    tokens.push(token);
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(critical) = event {
+                state.set_battery_critical(critical);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    executor.spawn_ok(async move {
+        if let Err(err) = upower::watch_battery_critical(tx).await {
+            tracing::info!(?err, "Failed to watch the battery level");
+        }
+    });
+    tokens.push(token);
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(power_saver) = event {
+                state.set_power_saver(power_saver);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    executor.spawn_ok(async move {
+        if let Err(err) = upower::watch_power_saver(tx).await {
+            tracing::info!(
+                ?err,
+                "Failed to connect to org.freedesktop.UPower.PowerProfiles"
+            );
+        }
+    });
+    tokens.push(token);
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(keymap) = event {
+                let previous = state.common.config.xkb_config();
+                state.common.config.system_keymap = keymap;
+                if state.common.config.xkb_config() != previous {
+                    crate::config::update_keymaps(state);
+                }
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    executor.spawn_ok(async move {
+        if let Err(err) = locale1::watch_keymap(tx).await {
+            tracing::info!(?err, "Failed to connect to org.freedesktop.locale1");
+        }
+    });
+    tokens.push(token);
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(inhibited) = event {
+                state
+                    .common
+                    .idle_state
+                    .set_inhibited(IdleInhibitor::ScreenSaver, inhibited);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    let screensaver = screensaver::ScreenSaver::new(tx);
+    executor.spawn_ok(async move {
+        if let Err(err) = screensaver::serve(screensaver).await {
+            tracing::info!(?err, "Failed to serve `{}`", screensaver::SCREENSAVER_NAME);
+        }
+    });
+    tokens.push(token);
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(()) = event {
+                state.schedule_idle_watches();
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    let idle_monitor = idle_monitor::IdleMonitor { watch_added: tx };
+    executor.spawn_ok(async move {
+        if let Err(err) = idle_monitor::serve(idle_monitor).await {
+            tracing::info!(
+                ?err,
+                "Failed to serve `{}`",
+                idle_monitor::IDLE_MONITOR_NAME
+            );
+        }
+    });
+    tokens.push(token);
+
+    #[cfg(feature = "systemd")]
+    {
+        let (tx, rx) = calloop::channel::channel();
+        let token = evlh
+            .insert_source(rx, |event, _, state| {
+                if let calloop::channel::Event::Msg(event) = event {
+                    state.prepare_for_sleep(event);
+                }
+            })
+            .map_err(|InsertError { error, .. }| error)
+            .with_context(|| "Failed to add channel to event_loop")?;
+        executor.spawn_ok(async move {
+            if let Err(err) = logind::watch_prepare_for_sleep(tx).await {
+                warn!(?err, "Failed to watch for suspend");
+            }
+        });
+        tokens.push(token);
+
+        let (tx, rx) = calloop::channel::channel();
+        let token = evlh
+            .insert_source(rx, |event, _, state| {
+                if let calloop::channel::Event::Msg(start) = event {
+                    state.prepare_for_shutdown(start);
+                }
+            })
+            .map_err(|InsertError { error, .. }| error)
+            .with_context(|| "Failed to add channel to event_loop")?;
+        executor.spawn_ok(async move {
+            if let Err(err) = logind::watch_prepare_for_shutdown(tx).await {
+                warn!(?err, "Failed to watch for shutdown");
+            }
+        });
+        tokens.push(token);
+    }
+
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(playing) = event {
+                state
+                    .common
+                    .idle_state
+                    .set_inhibited(IdleInhibitor::Media, playing);
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    executor.spawn_ok(async move {
+        if let Err(err) = mpris::watch_playback(tx).await {
+            warn!(?err, "Failed to watch media playback");
+        }
+    });
+    tokens.push(token);
+
     match block_on(power::init()) {
         Ok(power_daemon) => {
             let (tx, rx) = calloop::channel::channel();
 
             let token = evlh
                 .insert_source(rx, |event, _, state| match event {
-                    calloop::channel::Event::Msg(_) => {
+                    calloop::channel::Event::Msg(power::PowerDaemonEvent::Graphics(mode)) => {
+                        state.set_graphics_mode(mode);
+                    }
+                    calloop::channel::Event::Msg(power::PowerDaemonEvent::HotPlug) => {
                         let nodes = match &mut state.backend {
                             BackendData::Kms(kms) => {
                                 kms.drm_devices.keys().cloned().collect::<Vec<_>>()
@@ -64,12 +543,8 @@ pub fn init(
 
             // start helper thread
             executor.spawn_ok(async move {
-                if let Ok(mut msg_iter) = power_daemon.receive_hot_plug_detect().await {
-                    while let Some(msg) = msg_iter.next().await {
-                        if tx.send(msg).is_err() {
-                            break;
-                        }
-                    }
+                if let Err(err) = power::watch(power_daemon, tx).await {
+                    tracing::info!(?err, "Failed to watch com.system76.PowerDaemon");
                 }
             });
 
@@ -83,23 +558,66 @@ pub fn init(
     Ok(tokens)
 }
 
-/// Updated the D-Bus activation environment with `WAYLAND_DISPLAY` and
-/// `DISPLAY` variables.
-pub fn ready(common: &Common) -> Result<()> {
-    let conn = Connection::session()?;
-    let proxy = DBusProxy::new(&conn)?;
+/// Variables of our own environment, that are forwarded to activated services, if set.
+const IMPORTED_VARIABLES: &[&str] = &["XDG_CURRENT_DESKTOP", "XDG_SESSION_DESKTOP"];
 
-    proxy.update_activation_environment(HashMap::from([
-        ("WAYLAND_DISPLAY", common.socket.to_str().unwrap()),
+fn activation_environment(common: &Common) -> HashMap<String, String> {
+    let mut env = HashMap::from([
+        (
+            String::from("WAYLAND_DISPLAY"),
+            common.socket.to_string_lossy().into_owned(),
+        ),
         (
-            "DISPLAY",
-            &common
+            String::from("DISPLAY"),
+            common
                 .xwayland_state
                 .as_ref()
                 .map(|s| format!(":{}", s.display))
                 .unwrap_or_default(),
         ),
-    ]))?;
+        (String::from("XDG_SESSION_TYPE"), String::from("wayland")),
+    ]);
+    for name in IMPORTED_VARIABLES {
+        if let Ok(value) = std::env::var(name) {
+            env.insert(name.to_string(), value);
+        }
+    }
+    env
+}
+
+/// Updated the D-Bus and systemd activation environment with `WAYLAND_DISPLAY`,
+/// `DISPLAY` and session related variables.
+pub fn ready(common: &Common) -> Result<()> {
+    let conn = Connection::session()?;
+    let env = activation_environment(common);
+
+    let proxy = DBusProxy::new(&conn)?;
+    proxy.update_activation_environment(
+        env.iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect(),
+    )?;
+
+    // Services started by the systemd user manager don't inherit the D-Bus activation environment.
+    // Empty variables, like `DISPLAY` without Xwayland, are unset instead of exported empty,
+    // so services don't pick up a stale value of a previous session either.
+    let (unset, set): (Vec<_>, Vec<_>) = env.iter().partition(|(_, value)| value.is_empty());
+    let assignments = set
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>();
+    if let Err(err) = systemd::set_environment(&conn, &assignments) {
+        warn!(?err, "Failed to update the systemd user environment");
+    }
+    let unset = unset
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    if !unset.is_empty() {
+        if let Err(err) = systemd::unset_environment(&conn, &unset) {
+            warn!(?err, "Failed to update the systemd user environment");
+        }
+    }
 
     Ok(())
 }
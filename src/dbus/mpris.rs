@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Tracking of media playback through MPRIS, so playing media can inhibit the idle sequence
+
+use futures_util::stream::{self, StreamExt};
+use std::collections::HashMap;
+use zbus::{
+    MatchRule, MessageStream, Proxy, fdo,
+    message::Type as MessageType,
+    names::{BusName, UniqueName},
+    zvariant::OwnedValue,
+};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+enum Event {
+    PropertiesChanged(zbus::Message),
+    NameOwnerChanged(fdo::NameOwnerChanged),
+}
+
+/// Sends whether any media player is currently playing to `tx`, whenever that changes.
+pub async fn watch_playback(tx: calloop::channel::Sender<bool>) -> zbus::Result<()> {
+    let conn = zbus::Connection::session().await?;
+    let dbus = fdo::DBusProxy::new(&conn).await?;
+
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path(MPRIS_PATH)?
+        .build();
+    let properties_changed = MessageStream::for_match_rule(rule, &conn, None).await?;
+    let name_owner_changed = dbus.receive_name_owner_changed().await?;
+
+    // playback state by the unique name of the player
+    let mut playing = HashMap::<UniqueName<'static>, bool>::new();
+    for name in dbus.list_names().await? {
+        if !name.as_str().starts_with(MPRIS_PREFIX) {
+            continue;
+        }
+        let Ok(owner) = dbus.get_name_owner(name.inner().clone()).await else {
+            continue;
+        };
+        let status = Proxy::new(&conn, name.inner().clone(), MPRIS_PATH, PLAYER_INTERFACE)
+            .await?
+            .get_property::<String>("PlaybackStatus")
+            .await;
+        if let Ok(status) = status {
+            playing.insert(owner.into_inner(), status == "Playing");
+        }
+    }
+
+    let mut any_playing = playing.values().any(|p| *p);
+    if tx.send(any_playing).is_err() {
+        return Ok(());
+    }
+
+    let mut events = stream::select(
+        properties_changed.filter_map(|msg| async move { msg.ok().map(Event::PropertiesChanged) }),
+        name_owner_changed.map(Event::NameOwnerChanged),
+    );
+    while let Some(event) = events.next().await {
+        match event {
+            Event::PropertiesChanged(msg) => {
+                let header = msg.header();
+                let Some(sender) = header.sender() else {
+                    continue;
+                };
+                let Ok((interface, changed, _)) =
+                    msg.body()
+                        .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+                else {
+                    continue;
+                };
+                if interface != PLAYER_INTERFACE {
+                    continue;
+                }
+                if let Some(Ok(status)) = changed
+                    .get("PlaybackStatus")
+                    .map(|value| String::try_from(value.clone()))
+                {
+                    playing.insert(sender.to_owned(), status == "Playing");
+                }
+            }
+            Event::NameOwnerChanged(signal) => {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                if let (BusName::Unique(name), None) = (&args.name, &*args.new_owner) {
+                    playing.remove(&name.to_owned());
+                }
+            }
+        }
+
+        let now_playing = playing.values().any(|p| *p);
+        if now_playing != any_playing {
+            any_playing = now_playing;
+            if tx.send(any_playing).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
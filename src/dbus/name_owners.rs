@@ -2,8 +2,9 @@
 //!
 //! Compare to Mutter's `MetaDbusAccessChecker`
 
+use calloop::channel::Sender;
 use futures_executor::ThreadPool;
-use futures_util::{StreamExt, stream::FuturesUnordered};
+use futures_util::{Stream, StreamExt, stream::FuturesUnordered};
 use std::{
     collections::{HashMap, HashSet},
     future::{Future, poll_fn},
@@ -20,11 +21,20 @@ struct Inner {
     dbus: fdo::DBusProxy<'static>,
     name_owners: HashMap<WellKnownName<'static>, Option<UniqueName<'static>>>,
     unique_names: HashSet<UniqueName<'static>>,
+    /// Pids of unique names, looked up for owners of queried well-known names
+    pids: HashMap<UniqueName<'static>, u32>,
     stream: fdo::NameOwnerChangedStream,
     // Waker from `update_task` is stored, so that task will still be woken after
     // polling elsewhere.
     waker: Waker,
     enforce: bool,
+    executor: ThreadPool,
+    /// Watches of well-known names, told whether the name has an owner on every change
+    watchers: Vec<(WellKnownName<'static>, Sender<bool>)>,
+    /// Well-known names, whose owners' pids are looked up whenever they change
+    tracked_pids: HashSet<WellKnownName<'static>>,
+    /// Reference to itself, to spawn lookups from `update_if_needed`
+    this: Weak<Mutex<Inner>>,
 }
 
 impl Drop for Inner {
@@ -47,6 +57,7 @@ impl Inner {
                         self.unique_names.insert(name.to_owned());
                     } else {
                         self.unique_names.remove(&name.to_owned());
+                        self.pids.remove(&name.to_owned());
                     }
                 }
                 BusName::WellKnown(name) => {
@@ -56,10 +67,26 @@ impl Inner {
                     } else {
                         self.name_owners.remove(&name.to_owned());
                     }
+                    let owned = args.new_owner.is_some();
+                    // watches end, once their receiver was dropped
+                    self.watchers
+                        .retain(|(watched, tx)| *watched != name || tx.send(owned).is_ok());
+                    if owned && self.tracked_pids.contains(&name.to_owned()) {
+                        self.spawn_pid_lookup(name.to_owned());
+                    }
                 }
             }
         }
     }
+
+    fn spawn_pid_lookup(&self, name: WellKnownName<'static>) {
+        if let Some(inner) = self.this.upgrade() {
+            let name_owners = NameOwners(inner);
+            self.executor.spawn_ok(async move {
+                name_owners.poll_owner_pid(&name).await;
+            });
+        }
+    }
 }
 
 /// This task polls the steam regularly, to make sure events on the stream aren't just
@@ -109,18 +136,24 @@ impl NameOwners {
             })
             .collect();
 
-        let inner = Arc::new(Mutex::new(Inner {
-            dbus,
-            name_owners,
-            unique_names,
-            stream,
-            waker: Waker::noop().clone(),
-            enforce,
-        }));
-
-        if enforce {
-            executor.spawn_ok(update_task(Arc::downgrade(&inner)));
-        }
+        let inner = Arc::new_cyclic(|this| {
+            Mutex::new(Inner {
+                dbus,
+                name_owners,
+                unique_names,
+                pids: HashMap::new(),
+                stream,
+                waker: Waker::noop().clone(),
+                enforce,
+                executor: executor.clone(),
+                watchers: Vec::new(),
+                tracked_pids: HashSet::new(),
+                this: this.clone(),
+            })
+        });
+
+        // even without enforcing owners, watches need the stream to be processed
+        executor.spawn_ok(update_task(Arc::downgrade(&inner)));
 
         Ok(NameOwners(inner))
     }
@@ -160,6 +193,59 @@ impl NameOwners {
         }
     }
 
+    /// Pid of the process owning the well-known name `name`, if already known.
+    ///
+    /// Never blocks, so only pids of owners of names passed to [`Self::track_owner_pids`]
+    /// are known, once looked up.
+    pub fn owner_pid_no_poll(&self, name: &WellKnownName<'_>) -> Option<u32> {
+        let mut inner = self.0.lock().unwrap();
+        inner.update_if_needed();
+
+        let owner = inner.name_owners.get(name)?.as_ref()?;
+        inner.pids.get(owner).copied()
+    }
+
+    /// Looks up the pids of the owners of `names` right away and whenever they change owners,
+    /// replacing the names tracked before.
+    pub fn track_owner_pids(&self, names: HashSet<WellKnownName<'static>>) {
+        let mut inner = self.0.lock().unwrap();
+        inner.update_if_needed();
+
+        let new_names = names
+            .difference(&inner.tracked_pids)
+            .filter(|name| inner.name_owners.contains_key(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in new_names {
+            inner.spawn_pid_lookup(name);
+        }
+        inner.tracked_pids = names;
+    }
+
+    async fn poll_owner_pid(&self, name: &WellKnownName<'_>) {
+        self.poll_name_owners(std::slice::from_ref(name)).await;
+
+        let (dbus, owner) = {
+            let inner = self.0.lock().unwrap();
+            let Some(Some(owner)) = inner.name_owners.get(name).cloned() else {
+                return;
+            };
+            if inner.pids.contains_key(&owner) {
+                return;
+            }
+            (inner.dbus.clone(), owner)
+        };
+        if let Ok(pid) = dbus
+            .get_connection_unix_process_id(BusName::Unique(owner.as_ref()))
+            .await
+        {
+            let mut inner = self.0.lock().unwrap();
+            if inner.unique_names.contains(&owner) {
+                inner.pids.insert(owner, pid);
+            }
+        }
+    }
+
     /// Lazily populate `name_owenrs` with owners of well known names
     /// from `names` that were advertised by `ListNames`.
     ///
@@ -190,6 +276,33 @@ impl NameOwners {
         }
     }
 
+    /// Sends whether the well-known `name` has an owner to `tx`, right away and whenever it
+    /// appears, vanishes or changes owners, until the receiver of `tx` is dropped.
+    pub fn watch(&self, name: WellKnownName<'static>, tx: Sender<bool>) {
+        let mut inner = self.0.lock().unwrap();
+        inner.update_if_needed();
+
+        if tx.send(inner.name_owners.contains_key(&name)).is_ok() {
+            inner.watchers.push((name, tx));
+        }
+    }
+
+    /// Stream of unique names leaving the bus, so state of disconnected callers can be
+    /// cleaned up right away.
+    pub async fn receive_unique_names_lost(
+        &self,
+    ) -> zbus::Result<impl Stream<Item = UniqueName<'static>> + use<>> {
+        let dbus = self.0.lock().unwrap().dbus.clone();
+        let stream = dbus.receive_name_owner_changed().await?;
+        Ok(stream.filter_map(|signal| async move {
+            let args = signal.args().ok()?;
+            match args.name {
+                BusName::Unique(name) if args.new_owner.is_none() => Some(name.to_owned()),
+                _ => None,
+            }
+        }))
+    }
+
     /// Check if the unique name `name` owns at least one of the well-known names in `allowed_names`.
     pub async fn check_owner(
         &self,
@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Color temperature and brightness of outputs, for night light tools like wlsunset.
+//!
+//! Adjustments are applied through the gamma ramps of the outputs' crtcs, on top of any
+//! calibration loaded by [`super::colord`], and are therefore only available with the kms
+//! backend. They last until the compositor exits, tools are expected to set them again.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+};
+
+use anyhow::Context;
+use calloop::{InsertError, LoopHandle, RegistrationToken, channel::Sender};
+use smithay::output::Output;
+use tracing::warn;
+
+use crate::{
+    state::{BackendData, State},
+    utils::gamma,
+};
+
+/// Color temperature of unadjusted outputs, the whitepoint of sRGB
+const NEUTRAL_TEMPERATURE: u32 = 6500;
+const MIN_TEMPERATURE: u32 = 1000;
+const MAX_TEMPERATURE: u32 = 10000;
+/// Lowest brightness, so outputs can't be turned black
+const MIN_BRIGHTNESS: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Adjustment {
+    temperature: u32,
+    brightness: f64,
+}
+
+impl Default for Adjustment {
+    fn default() -> Self {
+        Adjustment {
+            temperature: NEUTRAL_TEMPERATURE,
+            brightness: 1.,
+        }
+    }
+}
+
+/// Adjustments by output name, the empty name applying to outputs without their own
+static ADJUSTMENTS: LazyLock<Mutex<HashMap<String, Adjustment>>> = LazyLock::new(Default::default);
+static SENDER: OnceLock<Sender<()>> = OnceLock::new();
+
+fn adjustment(output: &str) -> Adjustment {
+    let adjustments = ADJUSTMENTS.lock().unwrap();
+    adjustments
+        .get(output)
+        .or_else(|| adjustments.get(""))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Applies the adjustment of `output`, also used for newly added outputs
+pub fn apply(state: &mut State, output: &Output) {
+    let Adjustment {
+        temperature,
+        brightness,
+    } = adjustment(&output.name());
+    let color = gamma::whitepoint(temperature).map(|channel| channel * brightness);
+
+    let BackendData::Kms(kms) = &mut state.backend else {
+        return;
+    };
+    if let Err(err) = kms.set_gamma_color(output, color) {
+        warn!(?err, "Failed to adjust the color of {}", output.name());
+    }
+}
+
+/// Applies changed adjustments on the event loop
+pub fn init(evlh: &LoopHandle<'static, State>) -> anyhow::Result<RegistrationToken> {
+    let (tx, rx) = calloop::channel::channel();
+    let token = evlh
+        .insert_source(rx, |event, _, state| {
+            if let calloop::channel::Event::Msg(()) = event {
+                let outputs = state
+                    .common
+                    .output_configuration_state
+                    .outputs()
+                    .collect::<Vec<_>>();
+                for output in &outputs {
+                    apply(state, output);
+                }
+            }
+        })
+        .map_err(|InsertError { error, .. }| error)
+        .with_context(|| "Failed to add channel to event_loop")?;
+    let _ = SENDER.set(tx);
+    Ok(token)
+}
+
+fn update(output: &str, adjustment: Option<Adjustment>) -> zbus::fdo::Result<()> {
+    let mut adjustments = ADJUSTMENTS.lock().unwrap();
+    match adjustment {
+        Some(adjustment) => adjustments.insert(output.to_string(), adjustment),
+        None => adjustments.remove(output),
+    };
+    std::mem::drop(adjustments);
+
+    SENDER
+        .get()
+        .and_then(|tx| tx.send(()).ok())
+        .ok_or_else(|| zbus::fdo::Error::Failed("Compositor is shutting down".into()))
+}
+
+/// Night light for tools adjusting the color temperature of outputs over the day.
+///
+/// An empty output name sets the adjustment of all outputs without their own.
+pub struct NightLight;
+
+#[zbus::interface(name = "com.system76.CosmicComp.NightLight")]
+impl NightLight {
+    /// Tint `output` to the color `temperature` in Kelvin and scale its `brightness`
+    fn set_color(&self, output: &str, temperature: u32, brightness: f64) -> zbus::fdo::Result<()> {
+        if !(MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&temperature) {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Temperature must be between {}K and {}K",
+                MIN_TEMPERATURE, MAX_TEMPERATURE
+            )));
+        }
+        if !(MIN_BRIGHTNESS..=1.).contains(&brightness) {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Brightness must be between {} and 1",
+                MIN_BRIGHTNESS
+            )));
+        }
+        update(
+            output,
+            Some(Adjustment {
+                temperature,
+                brightness,
+            }),
+        )
+    }
+
+    /// Color temperature and brightness `output` is adjusted to
+    fn get_color(&self, output: &str) -> (u32, f64) {
+        let adjustment = adjustment(output);
+        (adjustment.temperature, adjustment.brightness)
+    }
+
+    /// Remove the adjustment of `output`
+    fn reset(&self, output: &str) -> zbus::fdo::Result<()> {
+        update(output, None)
+    }
+}
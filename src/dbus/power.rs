@@ -18,6 +18,10 @@
 //!
 //! …consequently `zbus-xmlgen` did not generate code for the above interfaces.
 
+use std::time::Duration;
+
+use async_io::Timer;
+use futures_util::stream::{self, StreamExt};
 use zbus::Connection;
 
 #[zbus::proxy(
@@ -85,3 +89,67 @@ pub async fn init() -> anyhow::Result<PowerDaemonProxy<'static>> {
     proxy.0.introspect().await?;
     Ok(proxy)
 }
+
+/// How often the graphics mode is read again, while graphics are switchable
+const GRAPHICS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Events of the power daemon affecting gpus and outputs
+pub enum PowerDaemonEvent {
+    /// A display was connected to a port of the discrete gpu
+    HotPlug,
+    /// The graphics mode, initially and after every switch
+    Graphics(String),
+}
+
+/// Sends hotplug events and graphics modes of the power daemon to `tx`.
+///
+/// The daemon neither signals graphics switches nor exposes the mode as a property, so it is
+/// polled every [`GRAPHICS_POLL_INTERVAL`] instead, if graphics are switchable at all.
+pub async fn watch(
+    power_daemon: PowerDaemonProxy<'static>,
+    tx: calloop::channel::Sender<PowerDaemonEvent>,
+) -> zbus::Result<()> {
+    enum Wakeup {
+        HotPlug,
+        Poll,
+    }
+
+    let hotplugs = power_daemon
+        .receive_hot_plug_detect()
+        .await?
+        .map(|_| Wakeup::HotPlug);
+
+    let mut mode = power_daemon.get_graphics().await.ok();
+    if let Some(mode) = mode.clone() {
+        if tx.send(PowerDaemonEvent::Graphics(mode)).is_err() {
+            return Ok(());
+        }
+    }
+
+    let switchable = mode.is_some() && power_daemon.get_switchable().await.unwrap_or(false);
+    let polls = if switchable {
+        Timer::interval(GRAPHICS_POLL_INTERVAL).boxed()
+    } else {
+        stream::pending().boxed()
+    }
+    .map(|_| Wakeup::Poll);
+
+    let mut wakeups = stream::select(hotplugs, polls);
+    while let Some(wakeup) = wakeups.next().await {
+        let event = match wakeup {
+            Wakeup::HotPlug => PowerDaemonEvent::HotPlug,
+            Wakeup::Poll => match power_daemon.get_graphics().await {
+                Ok(current) if Some(&current) != mode.as_ref() => {
+                    mode = Some(current.clone());
+                    PowerDaemonEvent::Graphics(current)
+                }
+                _ => continue,
+            },
+        };
+        if tx.send(event).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::utils::profiler;
+
+/// Toggle the pipeline spans of [`crate::utils::profiler`] while the compositor is running.
+pub struct Profiler;
+
+#[zbus::interface(name = "com.system76.CosmicComp.Profiler")]
+impl Profiler {
+    /// Whether input, layout, render and page-flip spans are currently recorded
+    #[zbus(property)]
+    fn enabled(&self) -> bool {
+        profiler::is_enabled()
+    }
+
+    #[zbus(property)]
+    fn set_enabled(&mut self, enabled: bool) {
+        profiler::set_enabled(enabled);
+    }
+
+    /// Whether cosmic-comp was built with a profiler backend
+    #[zbus(property)]
+    fn available(&self) -> bool {
+        cfg!(feature = "profile-with-tracy")
+    }
+}
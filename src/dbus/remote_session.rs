@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Control of a headless session by a remote desktop daemon.
+//!
+//! When started with `COSMIC_BACKEND=headless` as a regular session, the compositor has no
+//! seat and only virtual outputs. A daemon bridging to remote clients captures these outputs
+//! through the screencopy protocols (like any screencast through PipeWire) and forwards the
+//! input it receives, e.g. over libei, to this interface.
+//!
+//! Only the daemon may call it, it has to own [`REMOTE_DESKTOP_NAME`] or a name configured
+//! for virtual input through a `DBusName` matcher in `privileged_protocols`.
+
+use std::sync::OnceLock;
+
+use calloop::channel::Sender;
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use futures_executor::ThreadPool;
+use smithay::utils::{Point, Size};
+use tracing::error;
+use zbus::{message::Header, names::WellKnownName};
+
+use crate::{backend::headless::HeadlessCommand, dbus::COMP_PATH, wayland::policy::PolicyState};
+
+/// Well-known name owned by the compositor, while running a headless session
+pub const REMOTE_SESSION_NAME: &str = "com.system76.CosmicComp.RemoteSession";
+/// Well-known name the remote desktop daemon owns by default
+pub const REMOTE_DESKTOP_NAME: &str = "com.system76.CosmicRemoteDesktop";
+
+static ALLOWED_NAMES: &[WellKnownName] = &[WellKnownName::from_static_str_unchecked(
+    REMOTE_DESKTOP_NAME,
+)];
+
+static CONNECTION: OnceLock<zbus::Connection> = OnceLock::new();
+
+/// Virtual outputs and input of a headless session, see the module documentation.
+pub struct RemoteSession {
+    commands: Sender<HeadlessCommand>,
+    policy: PolicyState,
+}
+
+impl RemoteSession {
+    async fn send(&self, header: &Header<'_>, command: HeadlessCommand) -> zbus::fdo::Result<()> {
+        if !super::caller_allowed(
+            header,
+            &self.policy,
+            PrivilegedProtocol::VirtualInput,
+            Some(ALLOWED_NAMES),
+        )
+        .await
+        {
+            return Err(zbus::fdo::Error::AccessDenied("Access denied".to_string()));
+        }
+        self.commands
+            .send(command)
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))
+    }
+}
+
+#[zbus::interface(name = "com.system76.CosmicComp.RemoteSession")]
+impl RemoteSession {
+    /// Add a virtual output, that can be captured by its name
+    async fn add_output(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        name: String,
+        width: i32,
+        height: i32,
+    ) -> zbus::fdo::Result<()> {
+        if width <= 0 || height <= 0 {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Invalid output size {}x{}",
+                width, height
+            )));
+        }
+        self.send(
+            &header,
+            HeadlessCommand::AddOutput {
+                name,
+                size: Size::from((width, height)),
+            },
+        )
+        .await
+    }
+
+    /// Remove a virtual output, moving its windows to the remaining ones
+    async fn remove_output(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        name: String,
+    ) -> zbus::fdo::Result<()> {
+        self.send(&header, HeadlessCommand::RemoveOutput { name })
+            .await
+    }
+
+    /// Move the pointer by the given delta in logical pixels
+    async fn pointer_motion(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        dx: f64,
+        dy: f64,
+    ) -> zbus::fdo::Result<()> {
+        self.send(
+            &header,
+            HeadlessCommand::PointerMotionRelative(Point::from((dx, dy))),
+        )
+        .await
+    }
+
+    /// Move the pointer to a position in the global compositor space
+    async fn pointer_motion_absolute(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        x: f64,
+        y: f64,
+    ) -> zbus::fdo::Result<()> {
+        self.send(
+            &header,
+            HeadlessCommand::PointerMotionAbsolute(Point::from((x, y))),
+        )
+        .await
+    }
+
+    /// Press or release a pointer button, given as evdev button code
+    async fn pointer_button(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        button: u32,
+        pressed: bool,
+    ) -> zbus::fdo::Result<()> {
+        self.send(&header, HeadlessCommand::PointerButton { button, pressed })
+            .await
+    }
+
+    /// Press or release a key, given as evdev keycode
+    async fn key(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        keycode: u32,
+        pressed: bool,
+    ) -> zbus::fdo::Result<()> {
+        self.send(&header, HeadlessCommand::Key { keycode, pressed })
+            .await
+    }
+
+    async fn touch_down(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        id: u32,
+        x: f64,
+        y: f64,
+    ) -> zbus::fdo::Result<()> {
+        self.send(
+            &header,
+            HeadlessCommand::TouchDown {
+                id,
+                position: Point::from((x, y)),
+            },
+        )
+        .await
+    }
+
+    async fn touch_motion(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        id: u32,
+        x: f64,
+        y: f64,
+    ) -> zbus::fdo::Result<()> {
+        self.send(
+            &header,
+            HeadlessCommand::TouchMotion {
+                id,
+                position: Point::from((x, y)),
+            },
+        )
+        .await
+    }
+
+    async fn touch_up(&self, #[zbus(header)] header: Header<'_>, id: u32) -> zbus::fdo::Result<()> {
+        self.send(&header, HeadlessCommand::TouchUp { id }).await
+    }
+}
+
+async fn serve_interface(
+    commands: Sender<HeadlessCommand>,
+    policy: PolicyState,
+) -> zbus::Result<zbus::Connection> {
+    zbus::connection::Builder::session()?
+        .serve_at(COMP_PATH, RemoteSession { commands, policy })?
+        .name(REMOTE_SESSION_NAME)?
+        .build()
+        .await
+}
+
+/// Serves `com.system76.CosmicComp.RemoteSession`, forwarding requests to `commands`
+pub fn serve(commands: Sender<HeadlessCommand>, policy: PolicyState, executor: &ThreadPool) {
+    executor.spawn_ok(async move {
+        match serve_interface(commands, policy).await {
+            Ok(conn) => {
+                let _ = CONNECTION.set(conn);
+            }
+            Err(err) => {
+                error!("Failed to serve `{}`: {}", REMOTE_SESSION_NAME, err);
+            }
+        }
+    });
+}
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! `org.freedesktop.ScreenSaver` inhibition, still used by many media players and games
+//! instead of the idle-inhibit protocol or the inhibit portal.
+//!
+//! Inhibitors are tracked per connection and dropped, once the connection inhibiting
+//! vanishes from the bus, so crashing clients don't keep the screen on.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use calloop::channel::Sender;
+use futures_util::stream::StreamExt;
+use tracing::debug;
+use zbus::{
+    fdo,
+    message::Header,
+    names::{BusName, UniqueName},
+};
+
+/// Well-known name of the screensaver service
+pub const SCREENSAVER_NAME: &str = "org.freedesktop.ScreenSaver";
+
+#[derive(Debug)]
+struct Inhibitors {
+    next_cookie: u32,
+    /// Owner and application name by cookie
+    cookies: HashMap<u32, (UniqueName<'static>, String)>,
+    /// Whether any inhibitor is active, handled on the event loop
+    inhibited: Sender<bool>,
+}
+
+impl Inhibitors {
+    fn changed(&self, was_inhibited: bool) {
+        let inhibited = !self.cookies.is_empty();
+        if inhibited != was_inhibited {
+            let _ = self.inhibited.send(inhibited);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenSaver(Arc<Mutex<Inhibitors>>);
+
+impl ScreenSaver {
+    pub fn new(inhibited: Sender<bool>) -> ScreenSaver {
+        ScreenSaver(Arc::new(Mutex::new(Inhibitors {
+            next_cookie: 1,
+            cookies: HashMap::new(),
+            inhibited,
+        })))
+    }
+
+    /// Drops the inhibitors of `owner`, which left the bus
+    fn remove_owner(&self, owner: &UniqueName<'_>) {
+        let mut inhibitors = self.0.lock().unwrap();
+        let was_inhibited = !inhibitors.cookies.is_empty();
+        inhibitors.cookies.retain(|_, (o, application)| {
+            let keep = o != owner;
+            if !keep {
+                debug!(application, "Dropping inhibitor of vanished client");
+            }
+            keep
+        });
+        inhibitors.changed(was_inhibited);
+    }
+}
+
+#[zbus::interface(name = "org.freedesktop.ScreenSaver")]
+impl ScreenSaver {
+    fn inhibit(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        application_name: String,
+        reason_for_inhibit: String,
+    ) -> fdo::Result<u32> {
+        let owner = header
+            .sender()
+            .ok_or_else(|| fdo::Error::Failed("Unknown sender".into()))?
+            .to_owned();
+        debug!(application_name, reason_for_inhibit, "Inhibiting idle");
+
+        let mut inhibitors = self.0.lock().unwrap();
+        let was_inhibited = !inhibitors.cookies.is_empty();
+        let cookie = inhibitors.next_cookie;
+        inhibitors.next_cookie = cookie.checked_add(1).unwrap_or(1);
+        inhibitors.cookies.insert(cookie, (owner, application_name));
+        inhibitors.changed(was_inhibited);
+        Ok(cookie)
+    }
+
+    fn un_inhibit(&self, #[zbus(header)] header: Header<'_>, cookie: u32) -> fdo::Result<()> {
+        let mut inhibitors = self.0.lock().unwrap();
+        let was_inhibited = !inhibitors.cookies.is_empty();
+        // only the connection, that took the inhibitor, may release it
+        match inhibitors.cookies.get(&cookie) {
+            Some((owner, _)) if Some(owner) == header.sender() => {
+                inhibitors.cookies.remove(&cookie);
+            }
+            _ => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "Unknown cookie {}",
+                    cookie
+                )));
+            }
+        }
+        inhibitors.changed(was_inhibited);
+        Ok(())
+    }
+
+    /// The compositor doesn't draw a screensaver, locking is handled by the session lock
+    fn get_active(&self) -> bool {
+        false
+    }
+}
+
+/// Serves `org.freedesktop.ScreenSaver` and drops inhibitors of clients leaving the bus
+pub async fn serve(screensaver: ScreenSaver) -> zbus::Result<()> {
+    let conn = zbus::connection::Builder::session()?
+        .serve_at("/org/freedesktop/ScreenSaver", screensaver.clone())?
+        .serve_at("/ScreenSaver", screensaver.clone())?
+        .name(SCREENSAVER_NAME)?
+        .build()
+        .await?;
+
+    let dbus = fdo::DBusProxy::new(&conn).await?;
+    let mut stream = dbus.receive_name_owner_changed().await?;
+    while let Some(signal) = stream.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        if let (BusName::Unique(name), None) = (&args.name, &*args.new_owner) {
+            screensaver.remove_owner(name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Backend of the xdg-desktop-portal `Screenshot` portal.
+//!
+//! Screenshots are rendered by the compositor itself, so no helper process is involved.
+//! The active output is captured, interactive requests let the user select an area, a window
+//! or an output first, see [`crate::shell::screenshot_selection`].
+//!
+//! The portal file doesn't claim any desktop through `UseIn`, so it doesn't compete with
+//! xdg-desktop-portal-cosmic. It is used once selected in `portals.conf`, like
+//! `org.freedesktop.impl.portal.Screenshot=cosmic-comp`.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use calloop::channel::Sender;
+use futures_channel::oneshot;
+use zbus::{
+    message::Header,
+    names::WellKnownName,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+};
+
+/// Object path portal backends are served at
+pub const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+const RESPONSE_SUCCESS: u32 = 0;
+const RESPONSE_CANCELLED: u32 = 1;
+const RESPONSE_OTHER: u32 = 2;
+
+/// Only the portal frontend may call the backend, it asks for the user's permission
+static ALLOWED_NAMES: &[WellKnownName] = &[WellKnownName::from_static_str_unchecked(
+    "org.freedesktop.portal.Desktop",
+)];
+
+/// Request to take a screenshot
+pub struct ScreenshotRequest {
+    /// Let the user select what to capture
+    pub interactive: bool,
+    pub reply: oneshot::Sender<ScreenshotResponse>,
+}
+
+#[derive(Debug)]
+pub enum ScreenshotResponse {
+    /// Path of the saved image
+    Saved(PathBuf),
+    Cancelled,
+    Failed,
+}
+
+pub struct Screenshot {
+    pub requests: Sender<ScreenshotRequest>,
+}
+
+#[zbus::interface(name = "org.freedesktop.impl.portal.Screenshot")]
+impl Screenshot {
+    async fn screenshot(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        _handle: OwnedObjectPath,
+        app_id: &str,
+        _parent_window: &str,
+        options: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        let allowed = match (header.sender(), super::NAME_OWNERS.get()) {
+            (Some(sender), Some(name_owners)) => {
+                name_owners.check_owner(sender, ALLOWED_NAMES).await
+            }
+            _ => false,
+        };
+        if !allowed {
+            return Err(zbus::fdo::Error::AccessDenied("Access denied".to_string()));
+        }
+
+        let interactive = options
+            .get("interactive")
+            .and_then(|value| value.downcast_ref::<bool>().ok())
+            .unwrap_or(false);
+        let (tx, rx) = oneshot::channel();
+        let request = ScreenshotRequest {
+            interactive,
+            reply: tx,
+        };
+        if self.requests.send(request).is_err() {
+            return Ok((RESPONSE_OTHER, HashMap::new()));
+        }
+        let path = match rx.await {
+            Ok(ScreenshotResponse::Saved(path)) => path,
+            Ok(ScreenshotResponse::Cancelled) => return Ok((RESPONSE_CANCELLED, HashMap::new())),
+            Ok(ScreenshotResponse::Failed) | Err(_) => {
+                return Ok((RESPONSE_OTHER, HashMap::new()));
+            }
+        };
+        tracing::debug!(app_id, ?path, "Screenshot taken through portal");
+
+        let uri = format!("file://{}", path.display());
+        let uri = OwnedValue::try_from(Value::from(uri))
+            .map_err(|err| zbus::fdo::Error::Failed(err.to_string()))?;
+        Ok((
+            RESPONSE_SUCCESS,
+            HashMap::from([(String::from("uri"), uri)]),
+        ))
+    }
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        1
+    }
+}
@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Event sounds of the sound theme, played by the OSD
+
+use futures_executor::ThreadPool;
+use tracing::debug;
+
+#[zbus::proxy(
+    interface = "com.system76.CosmicOsd",
+    default_service = "com.system76.CosmicOsd",
+    default_path = "/com/system76/CosmicOsd"
+)]
+trait CosmicOsd {
+    fn play_sound(&self, name: &str) -> zbus::Result<()>;
+}
+
+/// Plays the sound `name` of the sound theme, without waiting for it to be played
+pub fn play_sound(executor: &ThreadPool, name: &'static str) {
+    let Some(conn) = super::session_connection().cloned() else {
+        return;
+    };
+    executor.spawn_ok(async move {
+        let result = async { CosmicOsdProxy::new(&conn).await?.play_sound(name).await }.await;
+        if let Err(err) = result {
+            debug!(?err, "Failed to play sound {}", name);
+        }
+    });
+}
@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: GPL-3.0-only
+//! # DBus interface proxy for: `org.freedesktop.systemd1.Manager`
+//!
+//! Only the subset of methods used by cosmic-comp is declared here.
+
+use anyhow::{Context, Result};
+use zbus::{
+    blocking::Connection,
+    zvariant::{OwnedObjectPath, Value},
+};
+
+#[zbus::proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+pub trait Manager {
+    /// StartTransientUnit method
+    fn start_transient_unit(
+        &self,
+        name: &str,
+        mode: &str,
+        properties: &[(&str, Value<'_>)],
+        aux: &[(&str, &[(&str, Value<'_>)])],
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    /// SetEnvironment method
+    fn set_environment(&self, assignments: &[&str]) -> zbus::Result<()>;
+
+    /// UnsetEnvironment method
+    fn unset_environment(&self, names: &[&str]) -> zbus::Result<()>;
+}
+
+/// Add or update `NAME=value` assignments in the environment block of the user manager.
+pub fn set_environment(conn: &Connection, assignments: &[String]) -> Result<()> {
+    let proxy = ManagerProxyBlocking::new(conn)?;
+    let assignments = assignments.iter().map(String::as_str).collect::<Vec<_>>();
+    proxy
+        .set_environment(&assignments)
+        .context("Failed to talk to systemd")
+}
+
+/// Remove variables from the environment block of the user manager.
+pub fn unset_environment(conn: &Connection, names: &[&str]) -> Result<()> {
+    let proxy = ManagerProxyBlocking::new(conn)?;
+    proxy
+        .unset_environment(names)
+        .context("Failed to talk to systemd")
+}
+
+/// Move the process `pid` into a new transient scope unit `unit_name`
+/// below `app.slice` of the user manager.
+///
+/// Uses the compositor's session bus connection, once it is established.
+pub fn start_app_scope(unit_name: &str, description: &str, pid: u32) -> Result<()> {
+    let conn = match crate::dbus::session_connection() {
+        Some(conn) => Connection::from(conn.clone()),
+        // spawned before the connection is established, e.g. by the kiosk mode
+        None => Connection::session()?,
+    };
+    let proxy = ManagerProxyBlocking::new(&conn)?;
+    proxy
+        .start_transient_unit(
+            unit_name,
+            "fail",
+            &[
+                ("Description", Value::from(description)),
+                ("Slice", Value::from("app.slice")),
+                ("PIDs", Value::from(vec![pid])),
+                ("CollectMode", Value::from("inactive-or-failed")),
+            ],
+            &[],
+        )
+        .with_context(|| format!("Failed to start transient unit {}", unit_name))?;
+    Ok(())
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashMap;
+
+use futures_executor::ThreadPool;
+use tracing::warn;
+use zbus::object_server::SignalEmitter;
+
+use crate::wayland::toplevel_metadata::{self, ToplevelMetadata};
+
+/// Metadata of toplevels, see [`crate::wayland::toplevel_metadata`].
+///
+/// Toplevels are identified by the identifier of their ext-foreign-toplevel handle,
+/// which cosmic toplevel handles can be created from.
+pub struct Toplevels;
+
+type Metadata = (u32, String, String, String, bool);
+
+fn to_dbus(metadata: ToplevelMetadata) -> Metadata {
+    (
+        metadata.pid.unwrap_or_default(),
+        metadata.sandbox_engine.unwrap_or_default(),
+        metadata.app_id.unwrap_or_default(),
+        metadata.instance_id.unwrap_or_default(),
+        metadata.urgent,
+    )
+}
+
+#[zbus::interface(name = "com.system76.CosmicComp.Toplevels")]
+impl Toplevels {
+    /// Metadata of the toplevel as `(pid, sandbox engine, app id, instance id, urgent)`.
+    ///
+    /// Unknown pids are reported as 0 and unknown strings as empty.
+    fn toplevel(&self, identifier: &str) -> zbus::fdo::Result<Metadata> {
+        toplevel_metadata::get(identifier)
+            .map(to_dbus)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("Unknown toplevel {identifier}")))
+    }
+
+    /// Tag of the toplevel set through xdg-toplevel-tag and its description,
+    /// empty for untagged toplevels
+    fn tag(&self, identifier: &str) -> zbus::fdo::Result<(String, String)> {
+        toplevel_metadata::get(identifier)
+            .map(|metadata| {
+                (
+                    metadata.tag.unwrap_or_default(),
+                    metadata.tag_description.unwrap_or_default(),
+                )
+            })
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("Unknown toplevel {identifier}")))
+    }
+
+    /// Identifier of the toplevel with the handle `app_id/tag`.
+    ///
+    /// Unlike identifiers, handles of tagged toplevels stay the same across restarts
+    /// of the application, so scripts can target specific windows with them.
+    fn toplevel_for_handle(&self, handle: &str) -> zbus::fdo::Result<String> {
+        toplevel_metadata::find_handle(handle)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("Unknown handle {handle}")))
+    }
+
+    /// Metadata of all toplevels by identifier, see `Toplevel`
+    fn toplevels(&self) -> HashMap<String, Metadata> {
+        toplevel_metadata::all()
+            .into_iter()
+            .map(|(identifier, metadata)| (identifier, to_dbus(metadata)))
+            .collect()
+    }
+
+    /// A toplevel requested attention or was activated since
+    #[zbus(signal)]
+    async fn urgency_changed(
+        ctx: SignalEmitter<'_>,
+        identifier: &str,
+        urgent: bool,
+    ) -> zbus::Result<()>;
+}
+
+/// Emits `UrgencyChanged` for all changes recorded since the last call
+pub fn announce_urgency_changes(executor: &ThreadPool) {
+    let changes = toplevel_metadata::take_urgency_changes();
+    if changes.is_empty() {
+        return;
+    }
+    let Some(conn) = super::session_connection() else {
+        return;
+    };
+
+    executor.spawn_ok(async move {
+        let Ok(ctx) = SignalEmitter::new(conn, super::COMP_PATH) else {
+            return;
+        };
+        for (identifier, urgent) in changes {
+            if let Err(err) = Toplevels::urgency_changed(ctx.clone(), &identifier, urgent).await {
+                warn!(?err, "Failed to announce urgency change");
+            }
+        }
+    });
+}
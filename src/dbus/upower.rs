@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use futures_util::stream::StreamExt;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+pub trait UPower {
+    /// OnBattery property
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+/// Sends the current `OnBattery` state and every change of it to `tx`.
+pub async fn watch_on_battery(tx: calloop::channel::Sender<bool>) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let upower = UPowerProxy::new(&conn).await?;
+
+    let mut changes = upower.receive_on_battery_changed().await;
+    if tx.send(upower.on_battery().await?).is_err() {
+        return Ok(());
+    }
+    while let Some(change) = changes.next().await {
+        if let Ok(on_battery) = change.get().await {
+            if tx.send(on_battery).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+)]
+pub trait Device {
+    /// WarningLevel property
+    #[zbus(property)]
+    fn warning_level(&self) -> zbus::Result<u32>;
+}
+
+/// `WarningLevel` of the battery, once its charge is critically low
+const WARNING_LEVEL_CRITICAL: u32 = 4;
+
+/// Sends whether the battery is critically low, initially and on every change, to `tx`.
+///
+/// The composite display device is watched, so systems with multiple batteries only warn
+/// once their combined charge is critical.
+pub async fn watch_battery_critical(tx: calloop::channel::Sender<bool>) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let device = DeviceProxy::new(&conn).await?;
+
+    let mut changes = device.receive_warning_level_changed().await;
+    if tx
+        .send(device.warning_level().await? >= WARNING_LEVEL_CRITICAL)
+        .is_err()
+    {
+        return Ok(());
+    }
+    while let Some(change) = changes.next().await {
+        if let Ok(level) = change.get().await {
+            if tx.send(level >= WARNING_LEVEL_CRITICAL).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.PowerProfiles",
+    default_service = "org.freedesktop.UPower.PowerProfiles",
+    default_path = "/org/freedesktop/UPower/PowerProfiles"
+)]
+pub trait PowerProfiles {
+    /// ActiveProfile property
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+}
+
+/// Sends whether the `power-saver` profile is active, initially and on every change, to `tx`.
+pub async fn watch_power_saver(tx: calloop::channel::Sender<bool>) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let profiles = PowerProfilesProxy::new(&conn).await?;
+
+    let mut changes = profiles.receive_active_profile_changed().await;
+    if tx
+        .send(profiles.active_profile().await? == "power-saver")
+        .is_err()
+    {
+        return Ok(());
+    }
+    while let Some(change) = changes.next().await {
+        if let Ok(profile) = change.get().await {
+            if tx.send(profile == "power-saver").is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,440 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Compositor-managed idle sequence.
+//!
+//! After configurable periods of inactivity the screen is dimmed, the session locked,
+//! the outputs turned off and finally the system suspended. This replaces the need for an
+//! external idle daemon and is disabled by default.
+//!
+//! Independent of the idle sequence, the session is reported idle to logind after a period
+//! without activity, so logind's own `IdleAction` and session tracking tools keep working.
+
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use calloop::{
+    RegistrationToken,
+    timer::{TimeoutAction, Timer},
+};
+use cosmic_comp_config::idle::IdleTimeouts;
+use tracing::{debug, warn};
+
+use crate::{
+    state::{BackendData, State},
+    wayland::handlers::output_power,
+};
+
+/// Brightness outputs are dimmed to, while the battery is critically low
+const CRITICAL_BATTERY_BRIGHTNESS: f64 = 0.5;
+/// Longest dim timeout in seconds, while the battery is critically low
+const CRITICAL_BATTERY_DIM: u32 = 30;
+/// Longest screen off timeout in seconds, while the battery is critically low
+const CRITICAL_BATTERY_SCREEN_OFF: u32 = 60;
+/// Inactivity after which the session is reported idle, unless an idle action comes first
+const IDLE_HINT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Sources preventing the idle sequence from progressing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdleInhibitor {
+    /// Visible surfaces using the idle-inhibit protocol
+    Surface,
+    /// Inhibitors registered through `org.freedesktop.ScreenSaver`
+    ScreenSaver,
+    /// A media player is playing
+    Media,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum IdleAction {
+    Dim,
+    Lock,
+    ScreenOff,
+    Suspend,
+}
+
+impl IdleAction {
+    const ALL: [IdleAction; 4] = [
+        IdleAction::Dim,
+        IdleAction::Lock,
+        IdleAction::ScreenOff,
+        IdleAction::Suspend,
+    ];
+
+    fn timeout(&self, timeouts: &IdleTimeouts) -> Option<Duration> {
+        let secs = match self {
+            IdleAction::Dim => timeouts.dim,
+            IdleAction::Lock => timeouts.lock,
+            IdleAction::ScreenOff => timeouts.screen_off,
+            IdleAction::Suspend => timeouts.suspend,
+        }?;
+        Some(Duration::from_secs(secs as u64))
+    }
+}
+
+#[derive(Debug)]
+pub struct IdleState {
+    last_activity: Instant,
+    applied: HashSet<IdleAction>,
+    inhibitors: HashSet<IdleInhibitor>,
+    on_battery: bool,
+    /// Power-saver profile of power-profiles-daemon is active
+    power_saver: bool,
+    /// UPower warns about a critically low battery
+    battery_critical: bool,
+    /// Idleness last reported to logind
+    idle_hint: bool,
+    /// Timer reporting the session idle to logind
+    idle_hint_timer: Option<RegistrationToken>,
+    #[cfg(feature = "systemd")]
+    idle_hint_tx: Option<futures_channel::mpsc::UnboundedSender<bool>>,
+    timer: Option<RegistrationToken>,
+    /// Timer of the `org.gnome.Mutter.IdleMonitor` idle watches
+    watch_timer: Option<RegistrationToken>,
+}
+
+impl IdleState {
+    pub fn new() -> IdleState {
+        IdleState {
+            last_activity: Instant::now(),
+            applied: HashSet::new(),
+            inhibitors: HashSet::new(),
+            on_battery: false,
+            power_saver: false,
+            battery_critical: false,
+            idle_hint: false,
+            idle_hint_timer: None,
+            #[cfg(feature = "systemd")]
+            idle_hint_tx: None,
+            timer: None,
+            watch_timer: None,
+        }
+    }
+
+    pub fn set_inhibited(&mut self, inhibitor: IdleInhibitor, inhibited: bool) {
+        let changed = if inhibited {
+            self.inhibitors.insert(inhibitor)
+        } else {
+            self.inhibitors.remove(&inhibitor)
+        };
+        if changed && self.inhibitors.is_empty() {
+            // start counting once the last inhibitor is gone
+            self.last_activity = Instant::now();
+        }
+    }
+
+    pub fn is_inhibited(&self) -> bool {
+        !self.inhibitors.is_empty()
+    }
+
+    /// The battery is critically low and discharging, UPower keeps warning after AC returned
+    /// until it is charged above the threshold again
+    fn critical_battery(&self) -> bool {
+        self.battery_critical && self.on_battery
+    }
+}
+
+impl State {
+    /// Restart the idle sequence after user activity
+    pub fn notify_idle_activity(&mut self) {
+        if crate::dbus::idle_monitor::activity(&self.common.async_executor) {
+            self.schedule_idle_watches();
+        }
+
+        self.common.idle_state.last_activity = Instant::now();
+        self.set_idle_hint(false);
+        if self.common.idle_state.idle_hint_timer.is_none() {
+            self.rearm_idle_hint_timer();
+        }
+
+        let idle = &self.common.idle_state;
+        if idle.applied.is_empty() && idle.timer.is_some() {
+            return;
+        }
+        if self.idle_timeouts().is_none() {
+            return;
+        }
+
+        self.undo_idle_actions();
+        self.rearm_idle_timer();
+    }
+
+    /// Fires the due idle watches of [`crate::dbus::idle_monitor`] on time
+    pub fn schedule_idle_watches(&mut self) {
+        if self.common.idle_state.watch_timer.is_some() {
+            return;
+        }
+        match self
+            .common
+            .event_loop_handle
+            .insert_source(Timer::immediate(), |_, _, state| {
+                match crate::dbus::idle_monitor::fire_due(&state.common.async_executor) {
+                    Some(deadline) => TimeoutAction::ToInstant(deadline),
+                    None => {
+                        state.common.idle_state.watch_timer = None;
+                        TimeoutAction::Drop
+                    }
+                }
+            }) {
+            Ok(token) => self.common.idle_state.watch_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to schedule idle watches"),
+        }
+    }
+
+    pub fn set_on_battery(&mut self, on_battery: bool) {
+        if self.common.idle_state.on_battery != on_battery {
+            let critical = self.common.idle_state.critical_battery();
+            self.common.idle_state.on_battery = on_battery;
+            if critical != self.common.idle_state.critical_battery() {
+                self.update_critical_battery();
+            } else if self.idle_timeouts().is_some() {
+                self.rearm_idle_timer();
+            }
+            self.update_power_saving();
+        }
+    }
+
+    pub fn set_battery_critical(&mut self, battery_critical: bool) {
+        let critical = self.common.idle_state.critical_battery();
+        self.common.idle_state.battery_critical = battery_critical;
+        if critical != self.common.idle_state.critical_battery() {
+            self.update_critical_battery();
+        }
+    }
+
+    /// Dims the outputs through their gamma ramps and shortens the idle sequence, while the
+    /// battery is critically low, and restores the normal policy afterwards
+    fn update_critical_battery(&mut self) {
+        let critical = self.common.idle_state.critical_battery();
+        if critical {
+            warn!("Battery is critically low, dimming outputs");
+        } else {
+            debug!("Battery is no longer critically low");
+        }
+
+        if let BackendData::Kms(kms) = &mut self.backend {
+            kms.set_gamma_brightness(if critical {
+                CRITICAL_BATTERY_BRIGHTNESS
+            } else {
+                1.
+            });
+        }
+        self.reset_idle();
+    }
+
+    pub fn set_power_saver(&mut self, power_saver: bool) {
+        if self.common.idle_state.power_saver != power_saver {
+            self.common.idle_state.power_saver = power_saver;
+            self.update_power_saving();
+        }
+    }
+
+    /// Throttle rendering and shorten animations while on battery or in power-saver mode
+    fn update_power_saving(&mut self) {
+        let idle = &self.common.idle_state;
+        let enabled = idle.on_battery || idle.power_saver;
+        crate::utils::animation::set_power_saving(enabled);
+        crate::backend::kms::set_power_saving(enabled);
+        self.schedule_render_all();
+    }
+
+    /// Apply a changed idle configuration
+    pub fn reset_idle(&mut self) {
+        self.common.idle_state.last_activity = Instant::now();
+        self.undo_idle_actions();
+        self.set_idle_hint(false);
+        self.rearm_idle_hint_timer();
+        if self.idle_timeouts().is_some() {
+            self.rearm_idle_timer();
+        } else if let Some(token) = self.common.idle_state.timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+    }
+
+    fn rearm_idle_timer(&mut self) {
+        if let Some(token) = self.common.idle_state.timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+        match self
+            .common
+            .event_loop_handle
+            .insert_source(Timer::immediate(), |_, _, state| match state.idle_tick() {
+                Some(deadline) => TimeoutAction::ToInstant(deadline),
+                None => {
+                    state.common.idle_state.timer = None;
+                    TimeoutAction::Drop
+                }
+            }) {
+            Ok(token) => self.common.idle_state.timer = Some(token),
+            Err(err) => warn!(?err, "Failed to schedule idle timer"),
+        }
+    }
+
+    /// Timeouts of the idle sequence, `None` if it is disabled.
+    ///
+    /// While the battery is critically low, outputs are dimmed and turned off sooner,
+    /// even if the idle sequence is disabled otherwise.
+    fn idle_timeouts(&self) -> Option<IdleTimeouts> {
+        let config = self.common.config.cosmic_conf.idle;
+        let idle = &self.common.idle_state;
+        let critical = idle.critical_battery();
+        let mut timeouts = match (config.enabled, idle.on_battery) {
+            (true, true) => config.battery,
+            (true, false) => config.ac,
+            (false, _) if critical => IdleTimeouts::default(),
+            (false, _) => return None,
+        };
+        if critical {
+            timeouts.dim = Some(
+                timeouts
+                    .dim
+                    .map_or(CRITICAL_BATTERY_DIM, |dim| dim.min(CRITICAL_BATTERY_DIM)),
+            );
+            timeouts.screen_off = Some(
+                timeouts
+                    .screen_off
+                    .map_or(CRITICAL_BATTERY_SCREEN_OFF, |screen_off| {
+                        screen_off.min(CRITICAL_BATTERY_SCREEN_OFF)
+                    }),
+            );
+        }
+        Some(timeouts)
+    }
+
+    /// Applies all due idle actions and returns the deadline of the next one
+    fn idle_tick(&mut self) -> Option<Instant> {
+        let timeouts = self.idle_timeouts()?;
+
+        let now = Instant::now();
+        if self.common.idle_state.is_inhibited() {
+            self.common.idle_state.last_activity = now;
+        }
+        let last_activity = self.common.idle_state.last_activity;
+
+        let mut next_deadline = None;
+        for action in IdleAction::ALL {
+            let Some(timeout) = action.timeout(&timeouts) else {
+                continue;
+            };
+            if self.common.idle_state.applied.contains(&action) {
+                continue;
+            }
+            let deadline = last_activity + timeout;
+            if deadline <= now {
+                self.common.idle_state.applied.insert(action);
+                self.apply_idle_action(action);
+            } else {
+                next_deadline = Some(next_deadline.map_or(deadline, |d: Instant| d.min(deadline)));
+            }
+        }
+
+        next_deadline
+    }
+
+    fn apply_idle_action(&mut self, action: IdleAction) {
+        debug!(?action, "Idle timeout reached");
+        match action {
+            IdleAction::Dim => {
+                self.common.shell.write().set_dimmed(true);
+                self.schedule_render_all();
+            }
+            IdleAction::Lock => {
+                if self.common.shell.read().session_lock.is_some() {
+                    return;
+                }
+                #[cfg(feature = "systemd")]
+                std::thread::spawn(|| {
+                    if let Err(err) = crate::dbus::logind::lock_session() {
+                        warn!(?err, "Failed to lock the session");
+                    }
+                });
+                #[cfg(not(feature = "systemd"))]
+                warn!("Locking the session requires logind support");
+            }
+            IdleAction::ScreenOff => {
+                output_power::set_all_surfaces_dpms_off(self);
+            }
+            IdleAction::Suspend => {
+                #[cfg(feature = "systemd")]
+                std::thread::spawn(|| {
+                    if let Err(err) = crate::dbus::logind::suspend() {
+                        warn!(?err, "Failed to suspend");
+                    }
+                });
+                #[cfg(not(feature = "systemd"))]
+                warn!("Suspending requires logind support");
+            }
+        }
+    }
+
+    /// Reverts the actions, that don't revert themselves on activity
+    fn undo_idle_actions(&mut self) {
+        let applied = std::mem::take(&mut self.common.idle_state.applied);
+        if applied.contains(&IdleAction::Dim) {
+            self.common.shell.write().set_dimmed(false);
+            self.schedule_render_all();
+        }
+        // outputs are turned on by any input event and the lock screen stays until unlocked
+    }
+
+    fn rearm_idle_hint_timer(&mut self) {
+        if let Some(token) = self.common.idle_state.idle_hint_timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+        match self
+            .common
+            .event_loop_handle
+            .insert_source(Timer::immediate(), |_, _, state| {
+                match state.idle_hint_tick() {
+                    Some(deadline) => TimeoutAction::ToInstant(deadline),
+                    None => {
+                        state.common.idle_state.idle_hint_timer = None;
+                        TimeoutAction::Drop
+                    }
+                }
+            }) {
+            Ok(token) => self.common.idle_state.idle_hint_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to schedule idle hint timer"),
+        }
+    }
+
+    /// Reports the session idle once inactive for [`IDLE_HINT_TIMEOUT`] or until the first
+    /// idle action, otherwise returns when to check again
+    fn idle_hint_tick(&mut self) -> Option<Instant> {
+        let timeout = self
+            .idle_timeouts()
+            .into_iter()
+            .flat_map(|timeouts| IdleAction::ALL.map(|action| action.timeout(&timeouts)))
+            .flatten()
+            .fold(IDLE_HINT_TIMEOUT, Duration::min);
+
+        let now = Instant::now();
+        if self.common.idle_state.is_inhibited() {
+            return Some(now + timeout);
+        }
+        let deadline = self.common.idle_state.last_activity + timeout;
+        if deadline > now {
+            return Some(deadline);
+        }
+        self.set_idle_hint(true);
+        None
+    }
+
+    /// Reports changed idleness to logind
+    fn set_idle_hint(&mut self, idle: bool) {
+        if std::mem::replace(&mut self.common.idle_state.idle_hint, idle) == idle {
+            return;
+        }
+        #[cfg(feature = "systemd")]
+        {
+            let executor = &self.common.async_executor;
+            let tx = self
+                .common
+                .idle_state
+                .idle_hint_tx
+                .get_or_insert_with(|| crate::dbus::logind::idle_hint_updater(executor));
+            let _ = tx.unbounded_send(idle);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Virtual pointer used by accessibility features, like mouse keys and dwell clicking.
+//!
+//! The device shares its id with the device controlling it, so events go through the regular
+//! input pipeline on that device's seat without being registered separately.
+
+use std::{path::PathBuf, time::Duration};
+
+use smithay::backend::input::{
+    ButtonState, Device, DeviceCapability, Event, InputBackend, InputEvent, PointerButtonEvent,
+    PointerMotionEvent, UnusedEvent,
+};
+
+use crate::state::State;
+
+pub const BTN_LEFT: u32 = 0x110;
+pub const BTN_RIGHT: u32 = 0x111;
+pub const BTN_MIDDLE: u32 = 0x112;
+
+impl State {
+    /// Moves the pointer relatively by `delta`
+    pub fn a11y_pointer_motion(&mut self, device: A11yPointerDevice, delta: (f64, f64)) {
+        let event = A11yPointerEvent {
+            device,
+            time: self.a11y_pointer_time(),
+            delta,
+            button: 0,
+            pressed: false,
+        };
+        self.process_input_event(InputEvent::<A11yPointerInput>::PointerMotion { event });
+    }
+
+    pub fn a11y_pointer_button(
+        &mut self,
+        device: A11yPointerDevice,
+        button: u32,
+        state: ButtonState,
+    ) {
+        let event = A11yPointerEvent {
+            device,
+            time: self.a11y_pointer_time(),
+            delta: (0., 0.),
+            button,
+            pressed: state == ButtonState::Pressed,
+        };
+        self.process_input_event(InputEvent::<A11yPointerInput>::PointerButton { event });
+    }
+
+    fn a11y_pointer_time(&self) -> u64 {
+        Duration::from(self.common.clock.now()).as_micros() as u64
+    }
+}
+
+/// Input backend of the events generated by accessibility features
+#[derive(Debug)]
+pub struct A11yPointerInput;
+
+/// Virtual pointer with the id of the device controlling it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct A11yPointerDevice(String);
+
+impl A11yPointerDevice {
+    /// Virtual pointer on the same seat as `device`
+    pub fn for_device<D: Device>(device: &D) -> Self {
+        A11yPointerDevice(device.id())
+    }
+}
+
+impl Device for A11yPointerDevice {
+    fn id(&self) -> String {
+        self.0.clone()
+    }
+
+    fn name(&self) -> String {
+        "Accessibility pointer".to_string()
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        capability == DeviceCapability::Pointer
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Relative motion or button event, depending on the event it is sent as
+#[derive(Debug, Clone)]
+pub struct A11yPointerEvent {
+    device: A11yPointerDevice,
+    time: u64,
+    delta: (f64, f64),
+    button: u32,
+    pressed: bool,
+}
+
+impl InputBackend for A11yPointerInput {
+    type Device = A11yPointerDevice;
+    type KeyboardKeyEvent = UnusedEvent;
+    type PointerAxisEvent = UnusedEvent;
+    type PointerButtonEvent = A11yPointerEvent;
+    type PointerMotionEvent = A11yPointerEvent;
+    type PointerMotionAbsoluteEvent = UnusedEvent;
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+impl Event<A11yPointerInput> for A11yPointerEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> A11yPointerDevice {
+        self.device.clone()
+    }
+}
+
+impl PointerButtonEvent<A11yPointerInput> for A11yPointerEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        if self.pressed {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Released
+        }
+    }
+}
+
+impl PointerMotionEvent<A11yPointerInput> for A11yPointerEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.1
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.delta.1
+    }
+}
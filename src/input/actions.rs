@@ -13,7 +13,6 @@ use crate::{
     },
 };
 use cosmic_comp_config::{TileBehavior, workspace::WorkspaceLayout};
-use cosmic_config::ConfigSet;
 use cosmic_settings_config::shortcuts;
 use cosmic_settings_config::shortcuts::action::{Direction, FocusDirection};
 use smithay::{
@@ -22,9 +21,9 @@ use smithay::{
 };
 #[cfg(not(feature = "debug"))]
 use tracing::info;
-use tracing::{error, warn};
+use tracing::warn;
 
-use std::{os::unix::process::CommandExt, thread};
+use std::os::unix::process::CommandExt;
 
 use super::gestures;
 
@@ -972,12 +971,11 @@ impl State {
                             shell_ref.seats.iter(),
                         );
                     }
-                    let config = self.common.config.cosmic_helper.clone();
-                    thread::spawn(move || {
-                        if let Err(err) = config.set("autotile", autotile) {
-                            error!(?err, "Failed to update autotile key");
-                        }
-                    });
+                    crate::config::set_in_background(
+                        &self.common.config.cosmic_helper,
+                        "autotile",
+                        autotile,
+                    );
                 } else {
                     let output = seat.active_output();
                     let mut shell = self.common.shell.write();
@@ -1067,6 +1065,8 @@ impl State {
 
         std::thread::spawn(move || match cmd.spawn() {
             Ok(mut child) => {
+                #[cfg(feature = "systemd")]
+                crate::systemd::spawn_scope(child.id(), &command);
                 let _res = child.wait();
             }
             Err(err) => {
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Dwell clicking, clicking once the pointer rests for a configured delay.
+//!
+//! Only one click is made per rest, the pointer has to move further than the configured
+//! threshold before the next one. Clicks are sent through an [`A11yPointerDevice`] on the seat
+//! of the device that moved the pointer last.
+
+use std::time::Duration;
+
+use calloop::{
+    RegistrationToken,
+    timer::{TimeoutAction, Timer},
+};
+use cosmic_comp_config::a11y::DwellClickType;
+use smithay::{
+    backend::input::{ButtonState, Device},
+    utils::{Logical, Point},
+};
+
+use super::a11y_pointer::{A11yPointerDevice, BTN_LEFT};
+use crate::state::State;
+
+#[derive(Debug, Default)]
+pub struct DwellClickState {
+    /// Position the pointer rests around
+    anchor: Option<Point<f64, Logical>>,
+    /// Whether the pointer already clicked at `anchor`
+    clicked: bool,
+    /// Whether a drag is in progress, waiting for the next dwell to release the button
+    dragging: bool,
+    device: Option<A11yPointerDevice>,
+    timer: Option<RegistrationToken>,
+}
+
+impl State {
+    /// Restarts the dwell timer, if the pointer moved away from where it rested
+    pub fn dwell_click_motion<D: Device>(&mut self, device: &D) {
+        let config = self.common.config.cosmic_conf.dwell_click;
+        if !config.enabled {
+            return;
+        }
+
+        let Some(position) = self
+            .common
+            .shell
+            .read()
+            .seats
+            .for_device(device)
+            .and_then(|seat| seat.get_pointer())
+            .map(|pointer| pointer.current_location())
+        else {
+            return;
+        };
+
+        let dwell = &mut self.common.dwell_click_state;
+        dwell.device = Some(A11yPointerDevice::for_device(device));
+        let moved = dwell.anchor.is_none_or(|anchor| {
+            let distance = anchor - position;
+            distance.x.hypot(distance.y) > config.threshold as f64
+        });
+        if !moved {
+            return;
+        }
+
+        dwell.anchor = Some(position);
+        dwell.clicked = false;
+        if let Some(token) = dwell.timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+        dwell.timer = self
+            .common
+            .event_loop_handle
+            .insert_source(
+                Timer::from_duration(Duration::from_millis(config.delay as u64)),
+                |_, _, state| {
+                    state.common.dwell_click_state.timer = None;
+                    state.dwell_click();
+                    TimeoutAction::Drop
+                },
+            )
+            .ok();
+    }
+
+    /// Stops waiting for a dwell and releases a dragged button, after dwell clicking was disabled
+    pub fn dwell_click_reset(&mut self) {
+        let dwell = &mut self.common.dwell_click_state;
+        dwell.anchor = None;
+        dwell.clicked = false;
+        if let Some(token) = dwell.timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+        if std::mem::take(&mut self.common.dwell_click_state.dragging) {
+            self.dwell_click_button(ButtonState::Released);
+        }
+    }
+
+    fn dwell_click(&mut self) {
+        let dwell = &mut self.common.dwell_click_state;
+        if dwell.clicked {
+            return;
+        }
+        dwell.clicked = true;
+
+        if dwell.dragging {
+            dwell.dragging = false;
+            self.dwell_click_button(ButtonState::Released);
+            return;
+        }
+        match self.common.config.cosmic_conf.dwell_click.click_type {
+            DwellClickType::Single => {
+                self.dwell_click_button(ButtonState::Pressed);
+                self.dwell_click_button(ButtonState::Released);
+            }
+            DwellClickType::Double => {
+                for _ in 0..2 {
+                    self.dwell_click_button(ButtonState::Pressed);
+                    self.dwell_click_button(ButtonState::Released);
+                }
+            }
+            DwellClickType::Drag => {
+                self.common.dwell_click_state.dragging = true;
+                self.dwell_click_button(ButtonState::Pressed);
+            }
+        }
+    }
+
+    fn dwell_click_button(&mut self, state: ButtonState) {
+        if let Some(device) = self.common.dwell_click_state.device.clone() {
+            self.a11y_pointer_button(device, BTN_LEFT, state);
+        }
+    }
+}
@@ -9,6 +9,7 @@ use crate::{
             cosmic_modifiers_from_smithay,
         },
     },
+    fl,
     input::gestures::{GestureState, SwipeAction},
     shell::{
         LastModifierChange, SeatExt, Trigger,
@@ -21,6 +22,7 @@ use crate::{
             floating::ResizeGrabMarker,
             tiling::{NodeDesc, SwapWindowGrab, TilingLayout},
         },
+        screenshot_selection::ScreenshotTarget,
         zoom::ZoomState,
     },
     utils::{float::NextDown, prelude::*, quirks::workspace_overview_is_open},
@@ -77,11 +79,16 @@ use std::{
     cell::RefCell,
     collections::HashSet,
     ops::ControlFlow,
+    sync::Mutex,
     time::{Duration, Instant},
 };
 
+pub mod a11y_pointer;
 pub mod actions;
+pub mod dwell_click;
 pub mod gestures;
+pub mod mouse_keys;
+pub mod virtual_pointer;
 
 /// Used for debouncing focus updates due to pointer motion, if after the focus change is
 /// triggered the event will cancel if the pointer moves to the original target
@@ -100,6 +107,9 @@ pub struct SupressedKeys(RefCell<Vec<(Keycode, Option<RegistrationToken>)>>);
 pub struct SupressedButtons(RefCell<HashSet<u32>>);
 #[derive(Default, Debug)]
 pub struct ModifiersShortcutQueue(RefCell<Option<shortcuts::Binding>>);
+/// Name of the keyboard layout last seen active on a seat
+#[derive(Default, Debug)]
+struct ActiveLayout(Mutex<Option<String>>);
 
 impl SupressedKeys {
     fn add(&self, keysym: &KeysymHandle, token: impl Into<Option<RegistrationToken>>) {
@@ -124,6 +134,15 @@ impl SupressedKeys {
                 .collect::<Vec<_>>(),
         )
     }
+
+    /// Forgets all suppressed keys, returning the tokens of their repeat timers
+    fn clear(&self) -> Vec<RegistrationToken> {
+        self.0
+            .borrow_mut()
+            .drain(..)
+            .filter_map(|(_, token)| token)
+            .collect()
+    }
 }
 
 impl SupressedButtons {
@@ -159,14 +178,59 @@ impl ModifiersShortcutQueue {
 }
 
 impl State {
+    /// Releases all held keys, once their release events can't reach us anymore.
+    ///
+    /// Clients would otherwise keep repeating keys held while switching VTs.
+    pub fn release_all_keys(&mut self) {
+        let seats = self
+            .common
+            .shell
+            .read()
+            .seats
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let time = self.common.clock.now().as_millis();
+        for seat in seats {
+            for token in seat.supressed_keys().clear() {
+                self.common.event_loop_handle.remove(token);
+            }
+            let keyboard = seat.get_keyboard().unwrap();
+            for keycode in keyboard.pressed_keys() {
+                let serial = SERIAL_COUNTER.next_serial();
+                keyboard.input(
+                    self,
+                    keycode,
+                    KeyState::Released,
+                    serial,
+                    time,
+                    |_, _, _| FilterResult::<()>::Forward,
+                );
+            }
+        }
+    }
+
     #[profiling::function]
     pub fn process_input_event<B: InputBackend>(&mut self, event: InputEvent<B>)
     where
         <B as InputBackend>::Device: 'static,
     {
+        let _span = crate::utils::profiler::span("input");
         crate::wayland::handlers::output_power::set_all_surfaces_dpms_on(self);
+        self.notify_idle_activity();
+        if crate::utils::latency::is_enabled() {
+            self.record_input_latency(&event);
+        }
 
         use smithay::backend::input::Event;
+        match &event {
+            InputEvent::PointerMotion { event, .. } => self.dwell_click_motion(&event.device()),
+            InputEvent::PointerMotionAbsolute { event, .. } => {
+                self.dwell_click_motion(&event.device())
+            }
+            _ => {}
+        }
+
         match event {
             InputEvent::DeviceAdded { device } => {
                 let shell = self.common.shell.read();
@@ -283,6 +347,12 @@ impl State {
                         }
                         self.handle_action(action, &seat, serial, time, pattern, None)
                     }
+                    self.update_keyboard_layout(&seat);
+                    self.update_lock_keys(&seat, &event.device(), previous_modifiers);
+                    if self.common.config.cosmic_conf.debug_slow_motion {
+                        let modifiers = seat.get_keyboard().unwrap().modifier_state();
+                        crate::utils::animation::set_slow_motion(modifiers.shift);
+                    }
 
                     // If we want to track numlock state so it can be reused on the next boot...
                     if let NumlockState::LastBoot =
@@ -724,7 +794,33 @@ impl State {
                         };
                         if let Some(target) = under {
                             if let Some(surface) = target.toplevel().map(Cow::into_owned) {
-                                if seat.get_keyboard().unwrap().modifier_state().logo
+                                let unresponsive = self
+                                    .common
+                                    .shell
+                                    .read()
+                                    .element_for_surface(&surface)
+                                    .map(|mapped| mapped.active_window())
+                                    .filter(|window| window.is_unresponsive());
+                                if let Some(window) = unresponsive {
+                                    // clicks on frozen windows open the force-quit dialog instead
+                                    pass_event = false;
+                                    seat.supressed_buttons().add(button);
+                                    let seat_clone = seat.clone();
+                                    self.common.event_loop_handle.insert_idle(move |state| {
+                                        let res =
+                                            state.common.shell.read().unresponsive_menu_request(
+                                                &window,
+                                                &seat_clone,
+                                                &state.common.event_loop_handle,
+                                            );
+                                        if let Some((grab, focus)) = res {
+                                            seat_clone
+                                                .get_pointer()
+                                                .unwrap()
+                                                .set_grab(state, grab, serial, focus);
+                                        }
+                                    });
+                                } else if seat.get_keyboard().unwrap().modifier_state().logo
                                     && !shortcuts_inhibited
                                 {
                                     let seat_clone = seat.clone();
@@ -1535,41 +1631,110 @@ impl State {
             InputEvent::SwitchToggle { event } => {
                 #[cfg(feature = "systemd")]
                 if event.switch() == Some(Switch::Lid) && self.common.inhibit_lid_fd.is_some() {
-                    let backend = self.backend.lock();
-                    let output = backend
-                        .all_outputs()
-                        .iter()
-                        .find(|o| o.is_internal())
-                        .cloned();
-                    let closed = event.state() == SwitchState::On;
+                    self.set_lid_closed(event.state() == SwitchState::On);
+                }
+            }
+        }
+    }
 
-                    if closed {
-                        backend
-                            .disable_internal_output(&mut self.common.output_configuration_state);
-                    } else {
-                        backend.enable_internal_output(&mut self.common.output_configuration_state);
-                    }
-                    std::mem::drop(backend);
+    /// Shows the name of the active keyboard layout of `seat`, if it changed since the last call
+    pub fn update_keyboard_layout(&mut self, seat: &Seat<State>) {
+        let Some(keyboard) = seat.get_keyboard() else {
+            return;
+        };
+        let name = keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            xkb.layout_name(xkb.active_layout()).to_string()
+        });
+        let previous = seat
+            .user_data()
+            .get_or_insert_threadsafe(ActiveLayout::default)
+            .0
+            .lock()
+            .unwrap()
+            .replace(name.clone());
+
+        // nothing to report for the initial layout
+        if previous.is_none_or(|previous| previous == name)
+            || !self.common.config.cosmic_conf.keyboard_layout_osd
+        {
+            return;
+        }
 
-                    if let Err(err) = self.refresh_output_config() {
-                        if !closed {
-                            warn!(?err, "Failed to re-enable internal connector");
-                            if let Some(output) = output {
-                                use cosmic_comp_config::output::comp::OutputState;
+        let output = seat.focused_or_active_output();
+        self.common.shell.write().show_osd(
+            &output,
+            "input-keyboard-symbolic",
+            name,
+            self.common.event_loop_handle.clone(),
+        );
+        self.backend.schedule_render(&output);
+    }
 
-                                output.config_mut().enabled = OutputState::Disabled;
-                                if let Err(err) = self.refresh_output_config() {
-                                    error!("Unrecoverable output configuration error: {}", err);
-                                }
-                            }
-                        } else {
-                            // Disabling an output should never fail.
-                            error!("Unrecoverable output configuration error: {}", err);
-                        }
-                    }
-                }
+    /// Attaches the time of `event` to the active output for [`crate::utils::latency`]
+    fn record_input_latency<B: InputBackend>(&self, event: &InputEvent<B>) {
+        use smithay::backend::input::Event;
+
+        let time = match event {
+            InputEvent::Keyboard { event, .. } => event.time(),
+            InputEvent::PointerMotion { event, .. } => event.time(),
+            InputEvent::PointerMotionAbsolute { event, .. } => event.time(),
+            InputEvent::PointerButton { event, .. } => event.time(),
+            InputEvent::PointerAxis { event, .. } => event.time(),
+            InputEvent::TouchDown { event, .. } => event.time(),
+            InputEvent::TouchMotion { event, .. } => event.time(),
+            InputEvent::TouchUp { event, .. } => event.time(),
+            _ => return,
+        };
+        let output = self.common.shell.read().seats.last_active().active_output();
+        crate::utils::latency::input(&output.name(), Duration::from_micros(time));
+    }
+
+    /// Shows the new state of caps lock or num lock, if `device` toggled either and has the osd enabled
+    fn update_lock_keys<D: Device + 'static>(
+        &mut self,
+        seat: &Seat<State>,
+        device: &D,
+        previous: ModifiersState,
+    ) {
+        let current = seat.get_keyboard().unwrap().modifier_state();
+        let label = if current.caps_lock != previous.caps_lock {
+            if current.caps_lock {
+                fl!("caps-lock-on")
+            } else {
+                fl!("caps-lock-off")
+            }
+        } else if current.num_lock != previous.num_lock {
+            if current.num_lock {
+                fl!("num-lock-on")
+            } else {
+                fl!("num-lock-off")
             }
+        } else {
+            return;
+        };
+
+        let config = &self.common.config;
+        let enabled = match <dyn Any>::downcast_ref::<InputDevice>(device) {
+            Some(device) => config.lock_keys_osd(device),
+            None => config
+                .cosmic_conf
+                .input_default
+                .lock_keys_osd
+                .unwrap_or(false),
+        };
+        if !enabled {
+            return;
         }
+
+        let output = seat.focused_or_active_output();
+        self.common.shell.write().show_osd(
+            &output,
+            "input-keyboard-symbolic",
+            label,
+            self.common.event_loop_handle.clone(),
+        );
+        self.backend.schedule_render(&output);
     }
 
     /// Determine is key event should be intercepted as a key binding, or forwarded to surface
@@ -1612,6 +1777,46 @@ impl State {
             .a11y_keyboard_monitor_state
             .key_event(modifiers, &handle, event.state());
 
+        // The consent dialog takes all key presses, until it is answered
+        if event.state() == KeyState::Pressed {
+            if let Some(dialog) = shell.consent_dialog_mut() {
+                if dialog.key_press(handle.modified_sym(), modifiers.shift) {
+                    self.backend.schedule_render(dialog.output());
+                }
+                seat.supressed_keys().add(&handle, None);
+                return FilterResult::Intercept(None);
+            }
+        }
+
+        // The screenshot selection takes all key presses, until it ended
+        if event.state() == KeyState::Pressed && shell.screenshot_selection().is_some() {
+            let target = match handle.modified_sym() {
+                Keysym::Escape => Some(None),
+                Keysym::Return | Keysym::KP_Enter => {
+                    Some(Some(ScreenshotTarget::Output(seat.active_output())))
+                }
+                _ => None,
+            };
+            if let Some(target) = target {
+                self.common
+                    .event_loop_handle
+                    .insert_idle(move |state| state.finish_screenshot_selection(target));
+            }
+            seat.supressed_keys().add(&handle, None);
+            return FilterResult::Intercept(None);
+        }
+
+        // Any key press dismisses the cheatsheet, escape without reaching the client
+        if event.state() == KeyState::Pressed {
+            if let Some(output) = shell.hide_cheatsheet() {
+                self.backend.schedule_render(&output);
+                if handle.modified_sym() == Keysym::Escape {
+                    seat.supressed_keys().add(&handle, None);
+                    return FilterResult::Intercept(None);
+                }
+            }
+        }
+
         // Leave move overview mode, if any modifier was released
         if let Some(Trigger::KeyboardMove(action_modifiers)) =
             shell.overview_mode().0.active_trigger()
@@ -1778,7 +1983,7 @@ impl State {
             let removed = self
                 .common
                 .a11y_keyboard_monitor_state
-                .remove_active_virtual_mod(handle.modified_sym());
+                .release_virtual_mod(handle.modified_sym());
             // If `Caps_Lock` is a virtual modifier, and is in locked state, clear it
             if removed
                 && handle.modified_sym() == Keysym::Caps_Lock
@@ -1812,7 +2017,7 @@ impl State {
         {
             self.common
                 .a11y_keyboard_monitor_state
-                .add_active_virtual_mod(handle.modified_sym());
+                .press_virtual_mod(handle.modified_sym());
 
             tracing::debug!(
                 "active virtual mods: {:?}",
@@ -1822,6 +2027,20 @@ impl State {
             );
             seat.supressed_keys().add(&handle, None);
 
+            return FilterResult::Intercept(None);
+        } else if event.state() == KeyState::Pressed {
+            self.common.a11y_keyboard_monitor_state.use_virtual_mods();
+        }
+
+        // Move the pointer with the numpad
+        if self.mouse_keys_input(
+            &event.device(),
+            modifiers,
+            event.key_code(),
+            handle.modified_sym(),
+            event.state(),
+        ) {
+            seat.supressed_keys().add(&handle, None);
             return FilterResult::Intercept(None);
         }
 
@@ -1849,6 +2068,33 @@ impl State {
             return FilterResult::Intercept(None);
         }
 
+        // Handle keyboard backlight keys
+        #[cfg(feature = "systemd")]
+        if event.state() == KeyState::Pressed {
+            use crate::utils::kbd_backlight::{self, BacklightChange};
+
+            let change = match handle.modified_sym() {
+                Keysym::XF86_KbdBrightnessUp => Some(BacklightChange::Up),
+                Keysym::XF86_KbdBrightnessDown => Some(BacklightChange::Down),
+                Keysym::XF86_KbdLightOnOff => Some(BacklightChange::Toggle),
+                _ => None,
+            };
+            if let Some(change) = change {
+                if let Some(percent) = kbd_backlight::change(change) {
+                    let output = seat.focused_or_active_output();
+                    self.common.shell.write().show_osd(
+                        &output,
+                        "keyboard-brightness-symbolic",
+                        fl!("keyboard-brightness", percent = percent),
+                        self.common.event_loop_handle.clone(),
+                    );
+                    self.backend.schedule_render(&output);
+                }
+                seat.supressed_keys().add(&handle, None);
+                return FilterResult::Intercept(None);
+            }
+        }
+
         if event.state() == KeyState::Pressed
             && (self.common.a11y_keyboard_monitor_state.has_keyboard_grab()
                 || self
@@ -1862,6 +2108,29 @@ impl State {
             return FilterResult::Intercept(None);
         }
 
+        // Super+Escape always reaches us and lifts the shortcuts inhibitor of the focused surface
+        if shortcuts_inhibited
+            && event.state() == KeyState::Pressed
+            && handle.modified_sym() == Keysym::Escape
+            && modifiers.logo
+            && !modifiers.alt
+            && !modifiers.ctrl
+            && !modifiers.shift
+        {
+            if let Some(inhibitor) = current_focus
+                .as_ref()
+                .and_then(|focus| focus.wl_surface())
+                .and_then(|surface| seat.keyboard_shortcuts_inhibitor_for_surface(&surface))
+            {
+                inhibitor.inactivate();
+            }
+            self.common
+                .event_loop_handle
+                .insert_idle(|state| state.update_shortcuts_inhibit_indicator());
+            seat.supressed_keys().add(&handle, None);
+            return FilterResult::Intercept(None);
+        }
+
         // handle the rest of the global shortcuts
         let mut clear_queue = true;
         if !shortcuts_inhibited {
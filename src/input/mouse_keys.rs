@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Mouse keys, moving the pointer and clicking with the numeric keypad while num lock is off.
+//!
+//! The direction keys move the pointer, accelerating while they are held. `5` clicks, `+`
+//! double clicks, `0` presses the button for dragging and `.` releases it again. `/`, `*`
+//! and `-` select the left, middle and right button respectively.
+//!
+//! Presses are turned into events of an [`A11yPointerDevice`] on the keyboard's seat.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use calloop::{
+    RegistrationToken,
+    timer::{TimeoutAction, Timer},
+};
+use smithay::{
+    backend::input::{ButtonState, Device, KeyState},
+    input::keyboard::ModifiersState,
+};
+use xkbcommon::xkb::{Keycode, Keysym};
+
+use super::a11y_pointer::{A11yPointerDevice, BTN_LEFT, BTN_MIDDLE, BTN_RIGHT};
+use crate::state::State;
+
+/// Interval pointer motion is generated in, while a direction key is held
+const MOTION_INTERVAL: Duration = Duration::from_millis(10);
+/// Speed relative to the maximum speed, when a direction key is first pressed
+const INITIAL_SPEED_FACTOR: f64 = 0.1;
+
+#[derive(Debug, Default)]
+pub struct MouseKeysState {
+    /// Directions of the held keys
+    held: HashMap<Keycode, (f64, f64)>,
+    button: Option<u32>,
+    /// The button is pressed until `5` is released
+    clicking: bool,
+    /// The button is pressed until `.` is pressed
+    dragging: bool,
+    device: Option<A11yPointerDevice>,
+    motion_start: Option<Instant>,
+    timer: Option<RegistrationToken>,
+}
+
+enum Key {
+    Move(f64, f64),
+    Click,
+    DoubleClick,
+    Press,
+    Release,
+    Select(u32),
+}
+
+fn key(keysym: Keysym) -> Option<Key> {
+    Some(match keysym {
+        Keysym::KP_Home => Key::Move(-1., -1.),
+        Keysym::KP_Up => Key::Move(0., -1.),
+        Keysym::KP_Prior => Key::Move(1., -1.),
+        Keysym::KP_Left => Key::Move(-1., 0.),
+        Keysym::KP_Right => Key::Move(1., 0.),
+        Keysym::KP_End => Key::Move(-1., 1.),
+        Keysym::KP_Down => Key::Move(0., 1.),
+        Keysym::KP_Next => Key::Move(1., 1.),
+        Keysym::KP_Begin => Key::Click,
+        Keysym::KP_Add => Key::DoubleClick,
+        Keysym::KP_Insert => Key::Press,
+        Keysym::KP_Delete => Key::Release,
+        Keysym::KP_Divide => Key::Select(BTN_LEFT),
+        Keysym::KP_Multiply => Key::Select(BTN_MIDDLE),
+        Keysym::KP_Subtract => Key::Select(BTN_RIGHT),
+        _ => return None,
+    })
+}
+
+impl State {
+    /// Translates numpad keys into pointer input, if mouse keys are enabled.
+    ///
+    /// Returns whether the key press was consumed, releases are never consumed to keep
+    /// the tracking of suppressed keys intact.
+    pub fn mouse_keys_input<D: Device>(
+        &mut self,
+        device: &D,
+        modifiers: &ModifiersState,
+        keycode: Keycode,
+        keysym: Keysym,
+        state: KeyState,
+    ) -> bool {
+        let settings = self.common.a11y_keyboard_monitor_state.mouse_keys();
+        if !settings.enabled {
+            self.mouse_keys_reset();
+            return false;
+        }
+
+        if state == KeyState::Released {
+            let mouse_keys = &mut self.common.mouse_keys_state;
+            if mouse_keys.held.remove(&keycode).is_some() && mouse_keys.held.is_empty() {
+                mouse_keys.motion_start = None;
+            }
+            if keysym == Keysym::KP_Begin && std::mem::take(&mut mouse_keys.clicking) {
+                self.mouse_keys_button(ButtonState::Released);
+            }
+            return false;
+        }
+
+        if modifiers.num_lock || modifiers.ctrl || modifiers.alt || modifiers.logo {
+            return false;
+        }
+        let Some(key) = key(keysym) else {
+            return false;
+        };
+
+        self.common.mouse_keys_state.device = Some(A11yPointerDevice::for_device(device));
+        match key {
+            Key::Move(x, y) => {
+                let mouse_keys = &mut self.common.mouse_keys_state;
+                mouse_keys.held.insert(keycode, (x, y));
+                mouse_keys.motion_start.get_or_insert_with(Instant::now);
+                if mouse_keys.timer.is_none() {
+                    mouse_keys.timer = self
+                        .common
+                        .event_loop_handle
+                        .insert_source(Timer::immediate(), |_, _, state| state.mouse_keys_motion())
+                        .ok();
+                }
+            }
+            Key::Click => {
+                let mouse_keys = &mut self.common.mouse_keys_state;
+                if !mouse_keys.dragging && !mouse_keys.clicking {
+                    mouse_keys.clicking = true;
+                    self.mouse_keys_button(ButtonState::Pressed);
+                }
+            }
+            Key::DoubleClick => {
+                if !self.common.mouse_keys_state.dragging {
+                    for _ in 0..2 {
+                        self.mouse_keys_button(ButtonState::Pressed);
+                        self.mouse_keys_button(ButtonState::Released);
+                    }
+                }
+            }
+            Key::Press => {
+                if !self.common.mouse_keys_state.dragging {
+                    self.common.mouse_keys_state.dragging = true;
+                    self.mouse_keys_button(ButtonState::Pressed);
+                }
+            }
+            Key::Release => {
+                if self.common.mouse_keys_state.dragging {
+                    self.common.mouse_keys_state.dragging = false;
+                    self.mouse_keys_button(ButtonState::Released);
+                }
+            }
+            Key::Select(button) => {
+                if !self.common.mouse_keys_state.dragging {
+                    self.common.mouse_keys_state.button = Some(button);
+                }
+            }
+        }
+        true
+    }
+
+    /// Stops any motion and releases a dragged button, after mouse keys were disabled
+    fn mouse_keys_reset(&mut self) {
+        let mouse_keys = &mut self.common.mouse_keys_state;
+        mouse_keys.held.clear();
+        mouse_keys.motion_start = None;
+        if let Some(token) = mouse_keys.timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+        if std::mem::take(&mut self.common.mouse_keys_state.dragging) {
+            self.mouse_keys_button(ButtonState::Released);
+        }
+    }
+
+    fn mouse_keys_motion(&mut self) -> TimeoutAction {
+        let settings = self.common.a11y_keyboard_monitor_state.mouse_keys();
+        let mouse_keys = &mut self.common.mouse_keys_state;
+        let (Some(start), Some(device)) = (mouse_keys.motion_start, mouse_keys.device.clone())
+        else {
+            mouse_keys.timer = None;
+            return TimeoutAction::Drop;
+        };
+
+        // Opposite keys cancel each other out
+        let (x, y) = mouse_keys
+            .held
+            .values()
+            .fold((0., 0.), |(x, y), (dx, dy)| (x + dx, y + dy));
+        let (x, y) = (f64::clamp(x, -1., 1.), f64::clamp(y, -1., 1.));
+
+        let progress = if settings.accel_time.is_zero() {
+            1.
+        } else {
+            (start.elapsed().as_secs_f64() / settings.accel_time.as_secs_f64()).min(1.)
+        };
+        let speed =
+            settings.max_speed * (INITIAL_SPEED_FACTOR + (1. - INITIAL_SPEED_FACTOR) * progress);
+        let distance = speed * MOTION_INTERVAL.as_secs_f64();
+
+        self.a11y_pointer_motion(device, (x * distance, y * distance));
+        TimeoutAction::ToDuration(MOTION_INTERVAL)
+    }
+
+    fn mouse_keys_button(&mut self, state: ButtonState) {
+        let mouse_keys = &self.common.mouse_keys_state;
+        let Some(device) = mouse_keys.device.clone() else {
+            return;
+        };
+        let button = mouse_keys.button.unwrap_or(BTN_LEFT);
+        self.a11y_pointer_button(device, button, state);
+    }
+}
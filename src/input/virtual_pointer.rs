@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Input devices of wlr virtual pointers, used by remote control and automation tools.
+//!
+//! Each virtual pointer is its own device on the seat it was created for, events go through
+//! the regular input pipeline.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisRelativeDirection, AxisSource, ButtonState, Device,
+    DeviceCapability, Event, InputBackend, PointerAxisEvent, PointerButtonEvent,
+    PointerMotionAbsoluteEvent, PointerMotionEvent, UnusedEvent,
+};
+
+static NEXT_DEVICE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Input backend of the events of virtual pointers
+#[derive(Debug)]
+pub struct VirtualPointerInput;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VirtualPointerDevice(u32);
+
+impl VirtualPointerDevice {
+    /// Device with an id not used by any other virtual pointer
+    pub fn unique() -> Self {
+        VirtualPointerDevice(NEXT_DEVICE_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Device for VirtualPointerDevice {
+    fn id(&self) -> String {
+        format!("virtual-pointer-{}", self.0)
+    }
+
+    fn name(&self) -> String {
+        "Virtual pointer".to_string()
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        capability == DeviceCapability::Pointer
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Motion, absolute motion, button or axis event, depending on the event it is sent as
+#[derive(Debug, Clone)]
+pub struct VirtualPointerEvent {
+    pub device: VirtualPointerDevice,
+    /// Timestamp in microseconds
+    pub time: u64,
+    /// Relative motion, or the absolute position within `extent`
+    pub position: (f64, f64),
+    pub extent: (f64, f64),
+    pub button: u32,
+    pub pressed: bool,
+    pub axis_source: AxisSource,
+    /// Scroll amounts of the horizontal and vertical axis
+    pub axis: [Option<f64>; 2],
+    pub axis_v120: [Option<f64>; 2],
+}
+
+impl VirtualPointerEvent {
+    pub fn new(device: VirtualPointerDevice, time: u64) -> Self {
+        VirtualPointerEvent {
+            device,
+            time,
+            position: (0., 0.),
+            extent: (1., 1.),
+            button: 0,
+            pressed: false,
+            axis_source: AxisSource::Wheel,
+            axis: [None; 2],
+            axis_v120: [None; 2],
+        }
+    }
+}
+
+/// Index of `axis` in [`VirtualPointerEvent::axis`]
+pub fn axis_index(axis: Axis) -> usize {
+    match axis {
+        Axis::Horizontal => 0,
+        Axis::Vertical => 1,
+    }
+}
+
+impl InputBackend for VirtualPointerInput {
+    type Device = VirtualPointerDevice;
+    type KeyboardKeyEvent = UnusedEvent;
+    type PointerAxisEvent = VirtualPointerEvent;
+    type PointerButtonEvent = VirtualPointerEvent;
+    type PointerMotionEvent = VirtualPointerEvent;
+    type PointerMotionAbsoluteEvent = VirtualPointerEvent;
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+impl Event<VirtualPointerInput> for VirtualPointerEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> VirtualPointerDevice {
+        self.device.clone()
+    }
+}
+
+impl PointerMotionEvent<VirtualPointerInput> for VirtualPointerEvent {
+    fn delta_x(&self) -> f64 {
+        self.position.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.position.1
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.position.0
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.position.1
+    }
+}
+
+impl AbsolutePositionEvent<VirtualPointerInput> for VirtualPointerEvent {
+    fn x(&self) -> f64 {
+        self.position.0
+    }
+
+    fn y(&self) -> f64 {
+        self.position.1
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        self.position.0 * width as f64 / self.extent.0
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        self.position.1 * height as f64 / self.extent.1
+    }
+}
+
+impl PointerMotionAbsoluteEvent<VirtualPointerInput> for VirtualPointerEvent {}
+
+impl PointerButtonEvent<VirtualPointerInput> for VirtualPointerEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        if self.pressed {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Released
+        }
+    }
+}
+
+impl PointerAxisEvent<VirtualPointerInput> for VirtualPointerEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        self.axis[axis_index(axis)]
+    }
+
+    fn amount_v120(&self, axis: Axis) -> Option<f64> {
+        self.axis_v120[axis_index(axis)]
+    }
+
+    fn source(&self) -> AxisSource {
+        self.axis_source
+    }
+
+    fn relative_direction(&self, _axis: Axis) -> AxisRelativeDirection {
+        AxisRelativeDirection::Identical
+    }
+}
@@ -28,12 +28,14 @@ use clap_lex::RawArgs;
 
 use std::error::Error;
 
+pub mod autostart;
 pub mod backend;
 pub mod config;
 pub mod dbus;
 #[cfg(feature = "debug")]
 pub mod debug;
 pub mod hooks;
+pub mod idle;
 pub mod input;
 mod logger;
 pub mod session;
@@ -59,11 +61,11 @@ impl State {
         self.ready.call_once(|| {
             // potentially tell systemd we are setup now
             if let state::BackendData::Kms(_) = &self.backend {
-                #[cfg(feature = "systemd")]
-                systemd::ready(&self.common);
                 if let Err(err) = dbus::ready(&self.common) {
                     error!(?err, "Failed to update the D-Bus activation environment");
                 }
+                #[cfg(feature = "systemd")]
+                systemd::ready();
             }
 
             // potentially tell the session we are setup now
@@ -89,17 +91,30 @@ impl State {
                 };
 
                 info!("Running {:?}", exec);
-                command
+                let child = command
                     .spawn()
                     .map_err(|err| {
                         // TODO: replace with `inspect_err` once stable
                         error!(?err, "Error running kiosk child.");
                         err
                     })
-                    .ok()
+                    .ok();
+                #[cfg(feature = "systemd")]
+                if let Some(child) = child.as_ref() {
+                    let pid = child.id();
+                    std::thread::spawn(move || systemd::spawn_scope(pid, &exec));
+                }
+                child
             } else {
                 None
             };
+
+            let kms = matches!(&self.backend, state::BackendData::Kms(_));
+            if self.common.kiosk_child.is_none() && autostart::enabled(kms) {
+                self.run_autostart();
+            }
+
+            self.reset_idle();
         });
     }
 }
@@ -108,6 +123,7 @@ pub fn run(hooks: crate::hooks::Hooks) -> Result<(), Box<dyn Error>> {
     let raw_args = RawArgs::from_args();
     let mut cursor = raw_args.cursor();
     let git_hash = option_env!("GIT_HASH").unwrap_or("unknown");
+    let mut headless = None;
 
     // Parse the arguments
     while let Some(arg) = raw_args.next_os(&mut cursor) {
@@ -124,6 +140,18 @@ pub fn run(hooks: crate::hooks::Hooks) -> Result<(), Box<dyn Error>> {
                 );
                 return Ok(());
             }
+            Some("--screenshot") => {
+                let scene = raw_args.next_os(&mut cursor);
+                let output = raw_args.next_os(&mut cursor);
+                let (Some(scene), Some(output)) = (scene, output) else {
+                    eprintln!("Usage: cosmic-comp --screenshot <scene.ron> <output.png>");
+                    process::exit(1);
+                };
+                headless = Some(backend::headless::HeadlessMode::Screenshot {
+                    scene: scene.into(),
+                    output: output.into(),
+                });
+            }
             _ => {}
         }
     }
@@ -133,8 +161,7 @@ pub fn run(hooks: crate::hooks::Hooks) -> Result<(), Box<dyn Error>> {
     info!("Cosmic starting up!");
 
     profiling::register_thread!("Main Thread");
-    #[cfg(feature = "profile-with-tracy")]
-    tracy_client::Client::start();
+    utils::profiler::init();
 
     utils::rlimit::increase_nofile_limit();
 
@@ -142,6 +169,30 @@ pub fn run(hooks: crate::hooks::Hooks) -> Result<(), Box<dyn Error>> {
     hooks::HOOKS.set(hooks)
         .expect("Hooks global has already been initialized. Running multiple instances of COSMIC in one process is not supported.");
 
+    run_compositor(headless)
+}
+
+/// Run cosmic-comp on the headless backend, controlled through `commands`.
+///
+/// Unlike [`run`] this may be called repeatedly from the same process (e.g. by the wlcs
+/// integration), as long as only one instance is running at a time. The function returns,
+/// once [`HeadlessCommand::Stop`](backend::headless::HeadlessCommand::Stop) was received
+/// or the sending side of `commands` was dropped.
+pub fn run_headless(
+    commands: calloop::channel::Channel<backend::headless::HeadlessCommand>,
+) -> Result<(), Box<dyn Error>> {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        if let Err(err) = logger::init_logger() {
+            eprintln!("Failed to initialize logger: {:?}", err);
+        }
+        let _ = hooks::HOOKS.set(Default::default());
+    });
+
+    run_compositor(Some(backend::headless::HeadlessMode::Commands(commands)))
+}
+
+fn run_compositor(headless: Option<backend::headless::HeadlessMode>) -> Result<(), Box<dyn Error>> {
     // init event loop
     let mut event_loop = EventLoop::try_new().with_context(|| "Failed to initialize event loop")?;
     // init wayland
@@ -154,7 +205,10 @@ pub fn run(hooks: crate::hooks::Hooks) -> Result<(), Box<dyn Error>> {
         event_loop.get_signal(),
     );
     // init backend
-    backend::init_backend_auto(&display, &mut event_loop, &mut state)?;
+    match headless {
+        Some(mode) => backend::init_backend_headless(&display, &mut event_loop, &mut state, mode)?,
+        None => backend::init_backend_auto(&display, &mut event_loop, &mut state)?,
+    }
 
     if let Err(err) = theme::watch_theme(event_loop.handle()) {
         warn!(?err, "Failed to watch theme");
@@ -222,11 +276,19 @@ pub fn run(hooks: crate::hooks::Hooks) -> Result<(), Box<dyn Error>> {
         let _ = child.kill();
     }
 
+    let failure = match &mut state.backend {
+        state::BackendData::Headless(headless) => headless.failure.take(),
+        _ => None,
+    };
+
     // drop eventloop & state before logger
     std::mem::drop(event_loop);
     std::mem::drop(state);
 
-    Ok(())
+    match failure {
+        Some(err) => Err(err.into()),
+        None => Ok(()),
+    }
 }
 
 fn print_help(version: &str, git_rev: &str) {
@@ -239,8 +301,9 @@ Designed for the COSMIC™ desktop environment, cosmic-comp is a Wayland Composi
 Project home page: https://github.com/pop-os/cosmic-comp
 
 Options:
-  -h, --help     Show this message
-  -v, --version  Show the version of cosmic-comp"#
+  -h, --help                           Show this message
+  -v, --version                        Show the version of cosmic-comp
+      --screenshot <scene> <output>    Render a scene headless to a PNG file and exit"#
     );
 }
 
@@ -257,7 +320,7 @@ fn init_wayland_display(
     event_loop
         .handle()
         .insert_source(source, |client_stream, _, state| {
-            let client_state = state.new_client_state();
+            let client_state = state.new_client_state(&client_stream);
             if let Err(err) = state
                 .common
                 .display_handle
@@ -310,6 +373,7 @@ fn refresh(state: &mut State) {
     }
 
     state.common.refresh();
+    state.refresh_session_lock_fallback();
     state::Common::refresh_focus(state);
     OverlapNotifyState::refresh(state);
     state.common.update_x11_stacking_order();
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Default size cap per log file in MiB, if `COSMIC_COMP_LOG_FILE_SIZE` isn't set.
+const DEFAULT_MAX_SIZE_MIB: u64 = 8;
+/// Number of rotated files (`cosmic-comp.log.1`, ...) kept besides the active one.
+const ROTATED_FILES: usize = 3;
+
+/// Log file under `$XDG_STATE_HOME/cosmic-comp`, that is rotated once it exceeds a size cap.
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_size: u64,
+}
+
+impl RotatingFile {
+    /// Opens the log file, if file logging was requested via `COSMIC_COMP_LOG_FILE`.
+    pub fn from_env() -> Option<io::Result<Self>> {
+        if !crate::utils::env::bool_var("COSMIC_COMP_LOG_FILE").unwrap_or(false) {
+            return None;
+        }
+
+        let max_size = std::env::var("COSMIC_COMP_LOG_FILE_SIZE")
+            .ok()
+            .and_then(|size| size.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MAX_SIZE_MIB)
+            .max(1)
+            * 1024
+            * 1024;
+
+        Some(
+            xdg::BaseDirectories::new()
+                .place_state_file("cosmic-comp/cosmic-comp.log")
+                .and_then(|path| Self::open(path, max_size)),
+        )
+    }
+
+    fn open(path: PathBuf, max_size: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            written,
+            max_size,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for index in (1..ROTATED_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let len = self.file.write(buf)?;
+        self.written += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
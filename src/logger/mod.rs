@@ -1,12 +1,20 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use tracing::{debug, info, warn};
 use tracing_journald as journald;
-use tracing_subscriber::{EnvFilter, filter::Directive, fmt, prelude::*};
+use tracing_subscriber::{EnvFilter, Registry, filter::Directive, fmt, prelude::*, reload};
+
+mod file;
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static INITIAL_FILTER: OnceLock<String> = OnceLock::new();
 
 pub fn init_logger() -> Result<()> {
     let level = if cfg!(debug_assertions) {
@@ -27,22 +35,44 @@ pub fn init_logger() -> Result<()> {
         .add_directive(Directive::from_str(&format!("smithay={level}")).unwrap())
         .add_directive(Directive::from_str(&format!("cosmic_comp={level}")).unwrap());
 
+    let _ = INITIAL_FILTER.set(filter.to_string());
+    let (filter, filter_handle) = reload::Layer::new(filter);
+    let _ = FILTER_HANDLE.set(filter_handle);
+
     let fmt_layer = fmt::layer().compact();
 
+    let (file_layer, file_err) = match file::RotatingFile::from_env() {
+        Some(Ok(file)) => (
+            Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file)),
+            ),
+            None,
+        ),
+        Some(Err(err)) => (None, Some(err)),
+        None => (None, None),
+    };
+
     match journald::layer() {
         Ok(journald_layer) => tracing_subscriber::registry()
+            .with(filter)
             .with(fmt_layer)
+            .with(file_layer)
             .with(journald_layer)
-            .with(filter)
             .init(),
         Err(err) => {
             tracing_subscriber::registry()
-                .with(fmt_layer)
                 .with(filter)
+                .with(fmt_layer)
+                .with(file_layer)
                 .init();
             warn!(?err, "Failed to init journald logging.");
         }
     };
+    if let Some(err) = file_err {
+        warn!(?err, "Failed to open log file.");
+    }
     log_panics::init();
 
     info!("Version: {}", std::env!("CARGO_PKG_VERSION"));
@@ -55,3 +85,29 @@ pub fn init_logger() -> Result<()> {
 
     Ok(())
 }
+
+/// Returns the currently active filter directives.
+pub fn current_filter() -> Option<String> {
+    FILTER_HANDLE
+        .get()?
+        .with_current(|filter| filter.to_string())
+        .ok()
+}
+
+/// Replace the active filter with the given directives (same syntax as `RUST_LOG`).
+pub fn set_filter(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(directives).context("Invalid filter directives")?;
+    FILTER_HANDLE
+        .get()
+        .context("Logger not initialized")?
+        .reload(filter)
+        .context("Failed to reload filter")?;
+    info!("Log filter changed to \"{}\"", directives);
+    Ok(())
+}
+
+/// Restore the filter, that was active at startup.
+pub fn reset_filter() -> Result<()> {
+    let initial = INITIAL_FILTER.get().context("Logger not initialized")?;
+    set_filter(initial)
+}
@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Overlay listing the keybindings of the active mode, grouped by category.
+//!
+//! Generated from the parsed shortcuts config, so it always matches what the compositor reacts to.
+
+use calloop::LoopHandle;
+use cosmic::{
+    Apply,
+    iced::widget::{column, container, row},
+    iced_core::{Alignment, Background, Border, Color, Length},
+    theme,
+    widget::text,
+};
+use cosmic_settings_config::shortcuts::{self, action::ResizeDirection};
+use smithay::{
+    backend::renderer::{ImportMem, Renderer, element::AsRenderElements},
+    output::Output,
+    utils::{Point, Rectangle},
+};
+
+use crate::{
+    config::Config,
+    fl,
+    state::State,
+    utils::iced::{IcedElement, Program},
+};
+
+pub type CheatsheetElement = IcedElement<CheatsheetInternal>;
+
+#[derive(Debug)]
+pub struct Cheatsheet {
+    element: CheatsheetElement,
+    output: Output,
+}
+
+impl Cheatsheet {
+    /// Cheatsheet for the default mode or resize mode, if `resize_direction` is set
+    pub fn new(
+        output: &Output,
+        config: &Config,
+        resize_direction: Option<ResizeDirection>,
+        evlh: LoopHandle<'static, State>,
+        theme: cosmic::Theme,
+    ) -> Cheatsheet {
+        let groups = match resize_direction {
+            Some(direction) => resize_mode_group(direction),
+            None => shortcut_groups(config),
+        };
+        let element = IcedElement::new(CheatsheetInternal { groups }, (1, 1), evlh, theme);
+        let size = element.minimum_size();
+        element.resize(size);
+        element.output_enter(output, Rectangle::from_size(size));
+
+        Cheatsheet {
+            element,
+            output: output.clone(),
+        }
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn render<R, C>(&self, renderer: &mut R, output: &Output) -> Vec<C>
+    where
+        C: From<<CheatsheetElement as AsRenderElements<R>>::RenderElement>,
+        R: Renderer + ImportMem,
+        R::TextureId: Send + Clone + 'static,
+    {
+        if output != &self.output {
+            return Vec::new();
+        }
+
+        let size = self.element.current_size().to_f64();
+        let output_geo = output.geometry().to_f64();
+        let scale = output.current_scale().fractional_scale();
+        let location = Point::from((
+            (output_geo.size.w / 2. - size.w / 2.).max(0.),
+            (output_geo.size.h / 2. - size.h / 2.).max(0.),
+        ))
+        .to_physical(scale)
+        .to_i32_round();
+
+        self.element
+            .render_elements(renderer, location, scale.into(), 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    Windows,
+    Workspaces,
+    Outputs,
+    System,
+}
+
+impl Category {
+    fn title(&self) -> String {
+        match self {
+            Category::Windows => fl!("keybindings-windows"),
+            Category::Workspaces => fl!("keybindings-workspaces"),
+            Category::Outputs => fl!("keybindings-outputs"),
+            Category::System => fl!("keybindings-system"),
+        }
+    }
+}
+
+fn category(action: &shortcuts::Action) -> Option<Category> {
+    use shortcuts::Action;
+
+    Some(match action {
+        Action::Disable => return None,
+        Action::Workspace(_)
+        | Action::NextWorkspace
+        | Action::PreviousWorkspace
+        | Action::LastWorkspace
+        | Action::MoveToWorkspace(_)
+        | Action::MoveToNextWorkspace
+        | Action::MoveToPreviousWorkspace
+        | Action::MoveToLastWorkspace
+        | Action::SendToWorkspace(_)
+        | Action::SendToNextWorkspace
+        | Action::SendToPreviousWorkspace
+        | Action::SendToLastWorkspace => Category::Workspaces,
+        Action::NextOutput
+        | Action::PreviousOutput
+        | Action::SwitchOutput(_)
+        | Action::MoveToOutput(_)
+        | Action::MoveToNextOutput
+        | Action::MoveToPreviousOutput
+        | Action::SendToOutput(_)
+        | Action::SendToNextOutput
+        | Action::SendToPreviousOutput
+        | Action::MigrateWorkspaceToOutput(_)
+        | Action::MigrateWorkspaceToNextOutput
+        | Action::MigrateWorkspaceToPreviousOutput => Category::Outputs,
+        Action::Spawn(_)
+        | Action::System(_)
+        | Action::Terminate
+        | Action::Debug
+        | Action::ZoomIn
+        | Action::ZoomOut => Category::System,
+        _ => Category::Windows,
+    })
+}
+
+fn shortcut_groups(config: &Config) -> Vec<(String, Vec<(String, String)>)> {
+    let mut groups = Vec::<(Category, Vec<(String, String)>)>::new();
+    for (binding, action) in config.shortcuts.iter() {
+        let Some(category) = category(action) else {
+            continue;
+        };
+        let entry = (binding.to_string(), action_label(binding, action));
+        match groups.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, entries)) => entries.push(entry),
+            None => groups.push((category, vec![entry])),
+        }
+    }
+
+    groups.sort_by_key(|(category, _)| *category);
+    groups
+        .into_iter()
+        .map(|(category, mut entries)| {
+            entries.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+            (category.title(), entries)
+        })
+        .collect()
+}
+
+fn resize_mode_group(direction: ResizeDirection) -> Vec<(String, Vec<(String, String)>)> {
+    let label = match direction {
+        ResizeDirection::Outwards => fl!("grow-window"),
+        ResizeDirection::Inwards => fl!("shrink-window"),
+    };
+    vec![(
+        fl!("resize-mode"),
+        vec![
+            (String::from("← ↓ ↑ →"), label.clone()),
+            (String::from("H J K L"), label),
+        ],
+    )]
+}
+
+/// Label of a binding, its description or a readable form of its action
+pub fn action_label(binding: &shortcuts::Binding, action: &shortcuts::Action) -> String {
+    if let Some(description) = binding.description.as_ref() {
+        return description.clone();
+    }
+    match action {
+        shortcuts::Action::Spawn(command) => command.clone(),
+        shortcuts::Action::System(system) => humanize(&format!("{:?}", system)),
+        action => humanize(&format!("{:?}", action)),
+    }
+}
+
+/// Turns the debug representation of an action, like `MoveToWorkspace(2)`, into words
+fn humanize(name: &str) -> String {
+    let mut label = String::with_capacity(name.len() + 8);
+    for c in name.chars() {
+        match c {
+            '(' => label.push(' '),
+            ')' => {}
+            c if c.is_uppercase() && !label.is_empty() => {
+                if !label.ends_with(' ') {
+                    label.push(' ');
+                }
+                label.extend(c.to_lowercase());
+            }
+            c => label.push(c),
+        }
+    }
+    label
+}
+
+pub struct CheatsheetInternal {
+    groups: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Program for CheatsheetInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        row(self
+            .groups
+            .iter()
+            .map(|(title, entries)| {
+                let mut items: Vec<cosmic::Element<'_, Self::Message>> =
+                    vec![text::heading(title).into()];
+                items.extend(entries.iter().map(|(keys, label)| {
+                    row(vec![
+                        text::body(keys).width(Length::Fixed(160.)).into(),
+                        text::body(label).into(),
+                    ])
+                    .spacing(16)
+                    .into()
+                }));
+                column(items).spacing(8).into()
+            })
+            .collect::<Vec<_>>())
+        .spacing(32)
+        .align_y(Alignment::Start)
+        .padding(24)
+        .apply(container)
+        .class(theme::Container::custom(|theme| container::Style {
+            icon_color: Some(Color::from(theme.cosmic().background.on)),
+            text_color: Some(Color::from(theme.cosmic().background.on)),
+            background: Some(Background::Color(theme.cosmic().background.base.into())),
+            border: Border {
+                radius: 18.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            shadow: Default::default(),
+        }))
+        .width(Length::Shrink)
+        .height(Length::Shrink)
+        .into()
+    }
+}
@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Dialog asking whether an app may use a privileged protocol.
+//!
+//! Shown centered on the active output and answered with the keyboard only, so clients can't
+//! trick the user into granting access by moving something under the pointer, and any pointer
+//! grab in progress is left alone. Key presses are ignored for [`ARMING_DELAY`] after the dialog
+//! appears, so typing meant for a window doesn't answer it.
+
+use std::time::{Duration, Instant};
+
+use calloop::{
+    LoopHandle,
+    timer::{TimeoutAction, Timer},
+};
+use cosmic::{
+    Apply,
+    iced::widget::{column, container, row},
+    iced_core::{Alignment, Background, Border, Color, Length},
+    theme,
+    widget::text,
+};
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use smithay::{
+    backend::renderer::{ImportMem, Renderer, element::AsRenderElements},
+    input::Seat,
+    output::Output,
+    utils::{Point, Rectangle, Serial},
+};
+use tracing::warn;
+use xkbcommon::xkb::Keysym;
+
+use crate::{
+    fl,
+    state::State,
+    utils::iced::{IcedElement, Program},
+    wayland::consent::{ConsentAnswer, ConsentReply},
+};
+
+/// Time after showing the dialog, in which key presses are ignored
+pub const ARMING_DELAY: Duration = Duration::from_millis(750);
+
+/// Answers in the order of the buttons
+const ANSWERS: [ConsentAnswer; 3] = [
+    ConsentAnswer::AllowOnce,
+    ConsentAnswer::AlwaysAllow,
+    ConsentAnswer::Deny,
+];
+
+pub type ConsentDialogElement = IcedElement<ConsentDialogInternal>;
+
+#[derive(Debug)]
+pub struct ConsentDialog {
+    element: ConsentDialogElement,
+    output: Output,
+    reply: ConsentReply,
+    shown_at: Instant,
+    selected: usize,
+}
+
+impl ConsentDialog {
+    pub fn new(
+        reply: ConsentReply,
+        output: &Output,
+        evlh: LoopHandle<'static, State>,
+        theme: cosmic::Theme,
+    ) -> ConsentDialog {
+        let app = reply.app_name().to_string();
+        let title = match reply.protocol() {
+            PrivilegedProtocol::DataControl => fl!("consent-clipboard", app = app),
+            PrivilegedProtocol::VirtualInput => fl!("consent-virtual-input", app = app),
+            _ => fl!("consent-screencopy", app = app),
+        };
+        // denying is the default, if the user just confirms
+        let selected = ANSWERS.len() - 1;
+
+        let element = IcedElement::new(
+            ConsentDialogInternal {
+                title,
+                protocol: reply.protocol(),
+                selected,
+                armed: false,
+            },
+            (1, 1),
+            evlh.clone(),
+            theme,
+        );
+        let size = element.minimum_size();
+        element.resize(size);
+        element.output_enter(output, Rectangle::from_size(size));
+
+        let element_clone = element.clone();
+        let output_clone = output.clone();
+        if let Err(err) =
+            evlh.insert_source(Timer::from_duration(ARMING_DELAY), move |_, _, state| {
+                element_clone.queue_message(ConsentDialogMessage::Arm);
+                state.backend.schedule_render(&output_clone);
+                TimeoutAction::Drop
+            })
+        {
+            warn!(?err, "Failed to schedule arming the consent dialog");
+        }
+
+        ConsentDialog {
+            element,
+            output: output.clone(),
+            reply,
+            shown_at: Instant::now(),
+            selected,
+        }
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn reply(&self) -> &ConsentReply {
+        &self.reply
+    }
+
+    fn is_armed(&self) -> bool {
+        self.shown_at.elapsed() >= ARMING_DELAY
+    }
+
+    /// Moves the selection or answers for a pressed `keysym`.
+    ///
+    /// Returns whether the dialog needs to be redrawn.
+    pub fn key_press(&mut self, keysym: Keysym, shift: bool) -> bool {
+        if !self.is_armed() {
+            return false;
+        }
+
+        let selected = match keysym {
+            Keysym::Escape => {
+                self.reply.answer(ConsentAnswer::Dismissed);
+                return false;
+            }
+            Keysym::Return | Keysym::KP_Enter | Keysym::space => {
+                self.reply.answer(ANSWERS[self.selected]);
+                return false;
+            }
+            Keysym::Left | Keysym::Up | Keysym::ISO_Left_Tab => self.selected.saturating_sub(1),
+            Keysym::Tab if shift => self.selected.saturating_sub(1),
+            Keysym::Right | Keysym::Down | Keysym::Tab => {
+                (self.selected + 1).min(ANSWERS.len() - 1)
+            }
+            _ => return false,
+        };
+        if selected == self.selected {
+            return false;
+        }
+
+        self.selected = selected;
+        self.element
+            .queue_message(ConsentDialogMessage::Select(selected));
+        true
+    }
+
+    pub fn render<R, C>(&self, renderer: &mut R, output: &Output) -> Vec<C>
+    where
+        C: From<<ConsentDialogElement as AsRenderElements<R>>::RenderElement>,
+        R: Renderer + ImportMem,
+        R::TextureId: Send + Clone + 'static,
+    {
+        if output != &self.output {
+            return Vec::new();
+        }
+
+        let size = self.element.current_size().to_f64();
+        let output_geo = output.geometry().to_f64();
+        let scale = output.current_scale().fractional_scale();
+        let location = Point::from((
+            (output_geo.size.w / 2. - size.w / 2.).max(0.),
+            (output_geo.size.h / 2. - size.h / 2.).max(0.),
+        ))
+        .to_physical(scale)
+        .to_i32_round();
+
+        self.element
+            .render_elements(renderer, location, scale.into(), 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ConsentDialogMessage {
+    Select(usize),
+    Arm,
+}
+
+pub struct ConsentDialogInternal {
+    title: String,
+    protocol: PrivilegedProtocol,
+    selected: usize,
+    armed: bool,
+}
+
+impl Program for ConsentDialogInternal {
+    type Message = ConsentDialogMessage;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, State>,
+        _last_seat: Option<&(Seat<State>, Serial)>,
+    ) -> cosmic::Task<Self::Message> {
+        match message {
+            ConsentDialogMessage::Select(selected) => self.selected = selected,
+            ConsentDialogMessage::Arm => self.armed = true,
+        }
+        cosmic::Task::none()
+    }
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        let armed = self.armed;
+        let buttons = ANSWERS
+            .iter()
+            .enumerate()
+            .map(|(idx, answer)| {
+                let label = match answer {
+                    // only capture sessions are held until answered, other grants
+                    // last until the compositor exits
+                    ConsentAnswer::AllowOnce if self.protocol == PrivilegedProtocol::Screencopy => {
+                        fl!("consent-allow-once")
+                    }
+                    ConsentAnswer::AllowOnce => fl!("consent-allow-until-logout"),
+                    ConsentAnswer::AlwaysAllow => fl!("consent-always-allow"),
+                    _ => fl!("consent-deny"),
+                };
+                let selected = idx == self.selected;
+                text::body(label)
+                    .apply(container)
+                    .padding([8, 16])
+                    .class(theme::Container::custom(move |theme| {
+                        let cosmic = theme.cosmic();
+                        let (background, on) = if selected {
+                            (cosmic.accent_color(), cosmic.accent.on)
+                        } else {
+                            (
+                                cosmic.background.component.base,
+                                cosmic.background.component.on,
+                            )
+                        };
+                        let mut on = Color::from(on);
+                        if !armed {
+                            on.a *= 0.5;
+                        }
+                        container::Style {
+                            icon_color: Some(on),
+                            text_color: Some(on),
+                            background: Some(Background::Color(background.into())),
+                            border: Border {
+                                radius: 8.0.into(),
+                                width: 0.0,
+                                color: Color::TRANSPARENT,
+                            },
+                            shadow: Default::default(),
+                        }
+                    }))
+                    .into()
+            })
+            .collect::<Vec<cosmic::Element<'_, Self::Message>>>();
+
+        column(vec![
+            text::heading(&self.title).into(),
+            text::caption(fl!("consent-keyboard-hint")).into(),
+            row(buttons).spacing(8).into(),
+        ])
+        .spacing(16)
+        .align_x(Alignment::Center)
+        .padding(24)
+        .apply(container)
+        .class(theme::Container::custom(|theme| container::Style {
+            icon_color: Some(Color::from(theme.cosmic().background.on)),
+            text_color: Some(Color::from(theme.cosmic().background.on)),
+            background: Some(Background::Color(theme.cosmic().background.base.into())),
+            border: Border {
+                radius: 18.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            shadow: Default::default(),
+        }))
+        .width(Length::Shrink)
+        .height(Length::Shrink)
+        .into()
+    }
+}
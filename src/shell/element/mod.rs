@@ -1,5 +1,8 @@
 use crate::{
-    backend::render::element::{AsGlowRenderer, FromGlesError},
+    backend::render::{
+        BackdropShader, Key, Usage,
+        element::{AsGlowRenderer, FromGlesError},
+    },
     state::State,
     utils::{iced::IcedElementInternal, prelude::*},
 };
@@ -799,6 +802,31 @@ impl CosmicMapped {
             _ => unreachable!(),
         });
 
+        let window = self.active_window();
+        if window.is_unresponsive() {
+            let geometry = self.active_window_geometry();
+            let radius = self
+                .corner_radius(geometry.size, 0)
+                .into_iter()
+                .max()
+                .unwrap_or(0);
+            let geometry = Rectangle::new(
+                location.to_f64().to_logical(scale).to_i32_round() + geometry.loc,
+                geometry.size,
+            );
+            elements.insert(
+                0,
+                CosmicMappedRenderElement::Overlay(BackdropShader::element(
+                    renderer,
+                    Key::Window(Usage::UnresponsiveIndicator, self.key()),
+                    geometry.as_local(),
+                    radius as f32,
+                    0.5 * alpha,
+                    [0.1, 0.1, 0.1],
+                )),
+            );
+        }
+
         elements.into_iter().map(C::from).collect()
     }
 
@@ -34,7 +34,7 @@ use smithay::{
             ImportAll, ImportMem, Renderer,
             element::{
                 AsRenderElements, memory::MemoryRenderBufferRenderElement,
-                surface::WaylandSurfaceRenderElement,
+                solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement,
             },
         },
     },
@@ -209,6 +209,8 @@ impl CosmicStack {
 
     pub fn remove_window(&self, window: &CosmicSurface) {
         self.0.with_program(|p| {
+            // indices of the remaining tabs shift
+            *p.potential_drag.lock().unwrap() = None;
             let mut windows = p.windows.lock().unwrap();
             if windows.len() == 1 {
                 p.override_alive.store(false, Ordering::SeqCst);
@@ -237,6 +239,8 @@ impl CosmicStack {
 
     pub fn remove_idx(&self, idx: usize) -> Option<CosmicSurface> {
         let window = self.0.with_program(|p| {
+            // indices of the remaining tabs shift
+            *p.potential_drag.lock().unwrap() = None;
             let mut windows = p.windows.lock().unwrap();
             if windows.len() == 1 {
                 p.override_alive.store(false, Ordering::SeqCst);
@@ -798,6 +802,7 @@ pub enum Message {
     Menu,
     TabMenu(usize),
     PotentialTabDragStart(usize),
+    TabDragOver(usize),
     Activate(usize),
     Close(usize),
     ScrollForward,
@@ -881,6 +886,32 @@ impl Program for CosmicStackInternal {
             Message::PotentialTabDragStart(idx) => {
                 *self.potential_drag.lock().unwrap() = Some(idx);
             }
+            Message::TabDragOver(idx) => {
+                // move the dragged tab along with the cursor, while it stays on the tab bar
+                let mut potential_drag = self.potential_drag.lock().unwrap();
+                let mut windows = self.windows.lock().unwrap();
+                if let Some(dragged) = potential_drag.filter(|dragged| *dragged != idx) {
+                    if dragged >= windows.len() {
+                        *potential_drag = None;
+                    } else if idx < windows.len() {
+                        let window = windows.remove(dragged);
+                        windows.insert(idx, window);
+                        *potential_drag = Some(idx);
+
+                        let active = self.active.load(Ordering::SeqCst);
+                        let active = if active == dragged {
+                            idx
+                        } else if dragged < active && active <= idx {
+                            active - 1
+                        } else if idx <= active && active < dragged {
+                            active + 1
+                        } else {
+                            active
+                        };
+                        self.active.store(active, Ordering::SeqCst);
+                    }
+                }
+            }
             Message::Activate(idx) => {
                 *self.potential_drag.lock().unwrap() = None;
                 if let Some(surface) = self.windows.lock().unwrap().get(idx).cloned() {
@@ -1057,6 +1088,7 @@ impl Decorations<CosmicStackInternal, Message> for DefaultDecorations {
         };
         let active = stack.active.load(Ordering::SeqCst);
         let group_focused = stack.group_focused.load(Ordering::SeqCst);
+        let dragging = stack.potential_drag.lock().unwrap().is_some();
 
         let elements = vec![
             cosmic_widget::icon::from_name("window-stack-symbolic")
@@ -1086,14 +1118,19 @@ impl Decorations<CosmicStackInternal, Message> for DefaultDecorations {
                     windows.iter().enumerate().map(|(i, w)| {
                         let user_data = w.user_data();
                         user_data.insert_if_missing(Id::unique);
-                        Tab::new(
+                        let tab = Tab::new(
                             w.title(),
                             w.app_id(),
                             user_data.get::<Id>().unwrap().clone(),
                         )
                         .on_press(Message::PotentialTabDragStart(i))
                         .on_right_click(Message::TabMenu(i))
-                        .on_close(Message::Close(i))
+                        .on_close(Message::Close(i));
+                        if dragging {
+                            tab.on_drag_over(Message::TabDragOver(i))
+                        } else {
+                            tab
+                        }
                     }),
                     active,
                     windows[active].is_activated(false),
@@ -1652,4 +1689,5 @@ render_elements! {
     pub CosmicStackRenderElement<R> where R: ImportAll + ImportMem;
     Header = MemoryRenderBufferRenderElement<R>,
     Window = WaylandSurfaceRenderElement<R>,
+    SolidColor = SolidColorRenderElement,
 }
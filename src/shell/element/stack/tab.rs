@@ -112,6 +112,7 @@ pub struct Tab<Message: TabMessage> {
     close_message: Option<Message>,
     press_message: Option<Message>,
     right_click_message: Option<Message>,
+    drag_over_message: Option<Message>,
     rule_theme: TabRuleTheme,
     background_theme: TabBackgroundTheme,
     active: bool,
@@ -127,6 +128,7 @@ impl<Message: TabMessage + 'static> Tab<Message> {
             close_message: None,
             press_message: None,
             right_click_message: None,
+            drag_over_message: None,
             rule_theme: TabRuleTheme::Default,
             background_theme: TabBackgroundTheme::Default,
             active: false,
@@ -143,6 +145,12 @@ impl<Message: TabMessage + 'static> Tab<Message> {
         self
     }
 
+    /// Message published whenever the cursor moves over the tab
+    pub fn on_drag_over(mut self, message: Message) -> Self {
+        self.drag_over_message = Some(message);
+        self
+    }
+
     pub fn on_close(mut self, message: Message) -> Self {
         self.close_message = Some(message);
         self
@@ -217,6 +225,7 @@ impl<Message: TabMessage + 'static> Tab<Message> {
             elements: items,
             press_message: self.press_message,
             right_click_message: self.right_click_message,
+            drag_over_message: self.drag_over_message,
         }
     }
 }
@@ -236,6 +245,7 @@ pub(super) struct TabInternal<'a, Message: TabMessage> {
     elements: Vec<cosmic::Element<'a, Message>>,
     press_message: Option<Message>,
     right_click_message: Option<Message>,
+    drag_over_message: Option<Message>,
 }
 
 impl<Message> Widget<Message, cosmic::Theme, cosmic::Renderer> for TabInternal<'_, Message>
@@ -356,6 +366,11 @@ where
             .fold(event::Status::Ignored, event::Status::merge);
 
         if status == event::Status::Ignored && cursor.is_over(layout.bounds()) {
+            if matches!(event, event::Event::Mouse(mouse::Event::CursorMoved { .. })) {
+                if let Some(message) = self.drag_over_message.clone() {
+                    shell.publish(message);
+                }
+            }
             if matches!(
                 event,
                 event::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
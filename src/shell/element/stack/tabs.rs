@@ -1,4 +1,5 @@
 use super::tab::{MIN_ACTIVE_TAB_WIDTH, Tab, TabBackgroundTheme, TabMessage, TabRuleTheme};
+use crate::utils::animation;
 use cosmic::{
     Apply,
     iced::{Element, id::Id, widget},
@@ -71,7 +72,7 @@ impl Scrollable for State {
     fn scroll_to(&mut self, offset: AbsoluteOffset) {
         let new_offset = Offset::Absolute(offset.x.max(0.0));
         self.scroll_animation = Some(ScrollAnimationState {
-            start_time: Instant::now(),
+            start_time: animation::now(),
             start: self.offset_x,
             end: new_offset,
             extra: Offset::Absolute(0.),
@@ -86,7 +87,7 @@ impl Scrollable for State {
         _content_bounds: Rectangle,
     ) {
         self.scroll_animation = Some(ScrollAnimationState {
-            start_time: Instant::now(),
+            start_time: animation::now(),
             start: self.offset_x,
             end: self.offset_x,
             extra: Offset::Absolute(offset.x.max(0.0)),
@@ -233,7 +234,7 @@ where
 
 impl State {
     fn next_tab_animation(&self) -> Option<&TabAnimationState> {
-        let now = Instant::now();
+        let now = animation::now();
 
         self.tab_animations
             .iter()
@@ -243,7 +244,7 @@ impl State {
     pub fn offset(&self, bounds: Rectangle, content_bounds: Size) -> Vector {
         if let Some(animation) = self.scroll_animation {
             let percentage = {
-                let percentage = Instant::now()
+                let percentage = animation::now()
                     .duration_since(animation.start_time)
                     .as_millis() as f32
                     / SCROLL_ANIMATION_DURATION.as_millis() as f32;
@@ -268,7 +269,7 @@ impl State {
     }
 
     pub fn cleanup_old_animations(&mut self) {
-        let start_time = Instant::now();
+        let start_time = animation::now();
 
         if let Some(animation) = self.scroll_animation.as_ref() {
             if start_time.duration_since(animation.start_time) > SCROLL_ANIMATION_DURATION {
@@ -555,7 +556,7 @@ where
                 let tab_animation = state.next_tab_animation();
 
                 let percentage = if let Some(animation) = tab_animation {
-                    let percentage = Instant::now()
+                    let percentage = animation::now()
                         .duration_since(animation.start_time)
                         .as_millis() as f32
                         / TAB_ANIMATION_DURATION.as_millis() as f32;
@@ -781,7 +782,7 @@ where
 
         if unknown_keys || changes.is_some() {
             if !scrolling || !matches!(changes, Some(Difference::Focus)) {
-                let start_time = Instant::now();
+                let start_time = animation::now();
 
                 State::discard_expired_tab_animations(&mut state.tab_animations, start_time);
 
@@ -834,7 +835,7 @@ where
                     };
 
                     state.scroll_animation = Some(ScrollAnimationState {
-                        start_time: Instant::now(),
+                        start_time: animation::now(),
                         start: Offset::Absolute(offset.x),
                         end: Offset::Absolute(new_offset.x),
                         extra: Offset::Absolute(0.),
@@ -12,12 +12,16 @@ use std::{
 
 use smithay::{
     backend::renderer::{
-        ImportAll, Renderer,
+        Color32F, ImportAll, Renderer,
         element::{
-            AsRenderElements, Kind, RenderElementStates,
-            surface::{WaylandSurfaceRenderElement, render_elements_from_surface_tree},
+            AsRenderElements, Id, Kind, RenderElementStates,
+            solid::SolidColorRenderElement,
+            surface::{
+                KindEvaluation, WaylandSurfaceRenderElement, render_elements_from_surface_tree,
+            },
             utils::select_dmabuf_feedback,
         },
+        utils::RendererSurfaceStateUserData,
     },
     desktop::{
         PopupManager, Window, WindowSurface, WindowSurfaceType, space::SpaceElement,
@@ -37,7 +41,7 @@ use smithay::{
             },
         },
         wayland_protocols_misc::server_decoration::server::org_kde_kwin_server_decoration::Mode as KdeMode,
-        wayland_server::protocol::wl_surface::WlSurface,
+        wayland_server::{DisplayHandle, Resource, protocol::wl_surface::WlSurface},
     },
     utils::{
         IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial, Size, user_data::UserDataMap,
@@ -45,20 +49,24 @@ use smithay::{
     wayland::{
         compositor::{SurfaceData, TraversalAction, with_states, with_surface_tree_downward},
         seat::WaylandFocus,
+        security_context::SecurityContext,
         shell::xdg::{
             SurfaceCachedState, ToplevelCachedState, ToplevelSurface, XdgToplevelSurfaceData,
         },
+        single_pixel_buffer::get_single_pixel_buffer,
     },
     xwayland::{X11Surface, xwm::X11Relatable},
 };
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::{
-    state::{State, SurfaceDmabufFeedback},
+    state::{ClientState, State, SurfaceDmabufFeedback},
     utils::prelude::*,
     wayland::handlers::{
         compositor::FRAME_TIME_FILTER,
-        decoration::{KdeDecorationData, PreferredDecorationMode},
+        decoration::{DecorationOverride, KdeDecorationData, PreferredDecorationMode},
+        toplevel_tag::{ToplevelTag, toplevel_tag},
+        xdg_shell::ping,
     },
 };
 
@@ -107,9 +115,20 @@ struct Minimized(AtomicBool);
 #[derive(Default)]
 struct Sticky(AtomicBool);
 
+#[derive(Default)]
+struct Urgent(AtomicBool);
+
+/// Opacity set by a window rule, multiplied with the alpha the window is rendered at
+#[derive(Default)]
+struct Opacity(Mutex<Option<f32>>);
+
 #[derive(Default)]
 struct GlobalGeometry(Mutex<Option<Rectangle<i32, Global>>>);
 
+/// Factor an X11 surface is upscaled by, while the client renders at scale 1
+#[derive(Default)]
+struct XwaylandUpscale(Mutex<Option<f64>>);
+
 impl CosmicSurface {
     pub fn title(&self) -> String {
         match self.0.underlying_surface() {
@@ -166,10 +185,25 @@ impl CosmicSurface {
         }
     }
 
+    /// Tag of the window set through xdg-toplevel-tag
+    pub fn tag(&self) -> ToplevelTag {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => toplevel_tag(toplevel.wl_surface()),
+            WindowSurface::X11(_) => ToplevelTag::default(),
+        }
+    }
+
     pub fn pending_size(&self) -> Option<Size<i32, Logical>> {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => toplevel.with_pending_state(|state| state.size),
-            WindowSurface::X11(surface) => Some(surface.geometry().size),
+            WindowSurface::X11(surface) => Some(
+                surface
+                    .geometry()
+                    .size
+                    .to_f64()
+                    .upscale(self.upscale())
+                    .to_i32_round(),
+            ),
         }
     }
 
@@ -202,11 +236,51 @@ impl CosmicSurface {
                 toplevel.with_pending_state(|state| state.size = Some(geo.size.as_logical()))
             }
             WindowSurface::X11(surface) => {
-                let _ = surface.configure(geo.as_logical());
+                let mut geo = geo.as_logical();
+                geo.size = geo.size.to_f64().downscale(self.upscale()).to_i32_round();
+                let _ = surface.configure(geo);
             }
         }
     }
 
+    /// Factor the X11 surface is rendered upscaled by, `1.0` for surfaces rendering at the client scale
+    pub fn upscale(&self) -> f64 {
+        self.0
+            .user_data()
+            .get::<XwaylandUpscale>()
+            .and_then(|upscale| *upscale.0.lock().unwrap())
+            .unwrap_or(1.0)
+    }
+
+    pub fn is_upscaled(&self) -> bool {
+        self.0
+            .user_data()
+            .get::<XwaylandUpscale>()
+            .is_some_and(|upscale| upscale.0.lock().unwrap().is_some())
+    }
+
+    /// Let an X11 surface render at scale 1 and upscale it by `factor`, usually the Xwayland client scale.
+    ///
+    /// Keeps the size of the window, but the client will see it `factor` times smaller.
+    pub fn set_upscale(&self, factor: Option<f64>) {
+        let Some(surface) = self.x11_surface() else {
+            return;
+        };
+
+        let mut geo = surface.geometry();
+        let size = geo.size.to_f64().upscale(self.upscale());
+        *self
+            .0
+            .user_data()
+            .get_or_insert_threadsafe(XwaylandUpscale::default)
+            .0
+            .lock()
+            .unwrap() = factor;
+
+        geo.size = size.downscale(factor.unwrap_or(1.0)).to_i32_round();
+        let _ = surface.configure(geo);
+    }
+
     pub fn set_bounds(&self, size: impl Into<Option<Size<i32, Logical>>>) {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => {
@@ -226,6 +300,9 @@ impl CosmicSurface {
     }
 
     pub fn set_activated(&self, activated: bool) {
+        if activated {
+            self.set_urgent(false);
+        }
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => toplevel.with_pending_state(|state| {
                 if activated {
@@ -285,7 +362,8 @@ impl CosmicSurface {
                         }
                     })
                 } else {
-                    let previous_mode = PreferredDecorationMode::mode(&self.0);
+                    let previous_mode = DecorationOverride::mode(&self.0)
+                        .or_else(|| PreferredDecorationMode::mode(&self.0));
                     toplevel.with_pending_state(|pending| {
                         pending.decoration_mode = previous_mode;
                     });
@@ -302,6 +380,42 @@ impl CosmicSurface {
         }
     }
 
+    /// Decoration mode forced on the window, regardless of what the client requests
+    pub fn decoration_override(&self) -> Option<DecorationMode> {
+        DecorationOverride::mode(&self.0)
+    }
+
+    /// Forces the decoration mode of the window, or lets the client choose it again if `None`.
+    ///
+    /// Only Wayland windows can be overridden. The configure has to be sent by the caller.
+    pub fn set_decoration_override(&self, mode: Option<DecorationMode>) {
+        let WindowSurface::Wayland(toplevel) = self.0.underlying_surface() else {
+            return;
+        };
+
+        DecorationOverride::update(&self.0, mode);
+        let mode = mode.or_else(|| PreferredDecorationMode::mode(&self.0));
+        toplevel.with_pending_state(|pending| {
+            pending.decoration_mode = mode;
+        });
+        with_states(toplevel.wl_surface(), |data| {
+            if let Some(kde_data) = data.data_map.get::<KdeDecorationData>() {
+                let mut kde_data = kde_data.lock().unwrap();
+                if let Some(mode) = mode {
+                    kde_data.mode = Some(match mode {
+                        DecorationMode::ServerSide => KdeMode::Server,
+                        _ => KdeMode::Client,
+                    });
+                }
+                if let Some(mode) = kde_data.mode {
+                    for obj in kde_data.objs.iter() {
+                        obj.mode(mode);
+                    }
+                }
+            }
+        })
+    }
+
     pub fn is_resizing(&self, pending: bool) -> Option<bool> {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(toplevel) => {
@@ -443,6 +557,43 @@ impl CosmicSurface {
             .store(sticky, Ordering::SeqCst);
     }
 
+    /// Whether the window requested attention since it was last activated
+    pub fn is_urgent(&self) -> bool {
+        self.0
+            .user_data()
+            .get_or_insert_threadsafe(Urgent::default)
+            .0
+            .load(Ordering::SeqCst)
+    }
+
+    pub fn set_urgent(&self, urgent: bool) {
+        self.0
+            .user_data()
+            .get_or_insert_threadsafe(Urgent::default)
+            .0
+            .store(urgent, Ordering::SeqCst);
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.0
+            .user_data()
+            .get_or_insert_threadsafe(Opacity::default)
+            .0
+            .lock()
+            .unwrap()
+            .unwrap_or(1.0)
+    }
+
+    pub fn set_opacity(&self, opacity: f32) {
+        *self
+            .0
+            .user_data()
+            .get_or_insert_threadsafe(Opacity::default)
+            .0
+            .lock()
+            .unwrap() = Some(opacity.clamp(0.0, 1.0));
+    }
+
     pub fn set_suspended(&self, suspended: bool) {
         if let WindowSurface::Wayland(window) = self.0.underlying_surface() {
             window.with_pending_state(|state| {
@@ -586,6 +737,45 @@ impl CosmicSurface {
         }
     }
 
+    /// Whether the client stopped answering pings
+    pub fn is_unresponsive(&self) -> bool {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => ping::is_unresponsive(toplevel),
+            WindowSurface::X11(_) => false,
+        }
+    }
+
+    /// Keep waiting for an unresponsive client
+    pub fn wait_for_response(&self) {
+        if let WindowSurface::Wayland(toplevel) = self.0.underlying_surface() {
+            ping::wait_for(toplevel);
+        }
+    }
+
+    /// Terminate the process of an unresponsive client
+    pub fn kill_client(&self, dh: &DisplayHandle) {
+        let Some(credentials) = self
+            .wl_surface()
+            .and_then(|surface| dh.get_client(surface.id()).ok())
+            .and_then(|client| client.get_credentials(dh).ok())
+        else {
+            return;
+        };
+
+        tracing::info!(
+            pid = credentials.pid,
+            app_id = self.app_id(),
+            "Killing client"
+        );
+        if unsafe { libc::kill(credentials.pid, libc::SIGKILL) } != 0 {
+            tracing::warn!(
+                pid = credentials.pid,
+                err = ?std::io::Error::last_os_error(),
+                "Failed to kill client"
+            );
+        }
+    }
+
     pub fn has_surface(&self, surface: &WlSurface, surface_type: WindowSurfaceType) -> bool {
         let Some(toplevel) = self.wl_surface() else {
             return false;
@@ -626,15 +816,20 @@ impl CosmicSurface {
         surface_type: WindowSurfaceType,
     ) -> Option<(PointerFocusTarget, Point<f64, Logical>)> {
         if let Some(xsurface) = self.x11_surface() {
+            let upscale = self.upscale();
             xsurface
-                .surface_under(relative_pos, Point::default(), surface_type)
+                .surface_under(
+                    relative_pos.downscale(upscale),
+                    Point::default(),
+                    surface_type,
+                )
                 .map(|(_surface, surface_offset)| {
                     (
                         PointerFocusTarget::X11Surface {
                             surface: xsurface.clone(),
                             toplevel: Some(self.clone()),
                         },
-                        surface_offset.to_f64(),
+                        surface_offset.to_f64().upscale(upscale),
                     )
                 })
         } else {
@@ -769,52 +964,41 @@ impl CosmicSurface {
     where
         R: Renderer + ImportAll,
         R::TextureId: Clone + 'static,
-        C: From<WaylandSurfaceRenderElement<R>>,
+        C: From<WaylandSurfaceRenderElement<R>> + From<SolidColorRenderElement>,
     {
-        match self.0.underlying_surface() {
-            WindowSurface::Wayland(toplevel) => {
-                let surface = toplevel.wl_surface();
+        let Some((surface, scale)) = self.render_surface(scale) else {
+            return Vec::new();
+        };
 
-                render_elements_from_surface_tree(
-                    renderer,
-                    surface,
-                    location,
-                    scale,
-                    alpha,
-                    scanout_override
-                        .map(|val| {
-                            if val {
-                                Kind::ScanoutCandidate
-                            } else {
-                                Kind::Unspecified
-                            }
-                            .into()
-                        })
-                        .unwrap_or(FRAME_TIME_FILTER),
-                )
-            }
-            WindowSurface::X11(surface) => {
-                let Some(surface) = surface.wl_surface() else {
-                    return Vec::new();
-                };
+        surface_tree_elements(
+            renderer,
+            &surface,
+            location,
+            scale,
+            alpha * self.opacity(),
+            scanout_override
+                .map(|val| {
+                    if val {
+                        Kind::ScanoutCandidate
+                    } else {
+                        Kind::Unspecified
+                    }
+                    .into()
+                })
+                .unwrap_or(FRAME_TIME_FILTER),
+        )
+    }
 
-                render_elements_from_surface_tree(
-                    renderer,
-                    &surface,
-                    location,
-                    scale,
-                    alpha,
-                    scanout_override
-                        .map(|val| {
-                            if val {
-                                Kind::ScanoutCandidate
-                            } else {
-                                Kind::Unspecified
-                            }
-                            .into()
-                        })
-                        .unwrap_or(FRAME_TIME_FILTER),
-                )
+    /// Root surface to render and the scale to render it at, including the Xwayland upscale
+    fn render_surface(&self, scale: Scale<f64>) -> Option<(WlSurface, Scale<f64>)> {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => Some((toplevel.wl_surface().clone(), scale)),
+            WindowSurface::X11(surface) => {
+                let upscale = self.upscale();
+                Some((
+                    surface.wl_surface()?,
+                    Scale::from((scale.x * upscale, scale.y * upscale)),
+                ))
             }
         }
     }
@@ -822,6 +1006,37 @@ impl CosmicSurface {
     pub fn x11_surface(&self) -> Option<&X11Surface> {
         self.0.x11_surface()
     }
+
+    /// Applies `f` to the state of the wayland client owning the window, `None` for X11 windows
+    fn client_state<T>(&self, f: impl FnOnce(&ClientState) -> Option<T>) -> Option<T> {
+        let toplevel = self.0.toplevel()?;
+        let client = toplevel.wl_surface().client()?;
+        f(client.get_data::<ClientState>()?)
+    }
+
+    /// Pid of the process owning the window, if known
+    pub fn pid(&self) -> Option<u32> {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(_) => self.client_state(|data| data.pid),
+            WindowSurface::X11(surface) => surface.pid(),
+        }
+    }
+
+    /// Security context of the client, that created the window
+    pub fn security_context(&self) -> Option<SecurityContext> {
+        self.client_state(|data| data.security_context.clone())
+    }
+
+    /// Sandbox engine the window's client runs in, as attached through its security context
+    /// or detected from its process
+    pub fn sandbox_engine(&self) -> Option<String> {
+        self.client_state(|data| {
+            data.security_context
+                .as_ref()
+                .and_then(|context| context.sandbox_engine.clone())
+                .or_else(|| data.detected_sandbox.map(String::from))
+        })
+    }
 }
 
 impl IsAlive for CosmicSurface {
@@ -833,14 +1048,20 @@ impl IsAlive for CosmicSurface {
 impl SpaceElement for CosmicSurface {
     fn geometry(&self) -> Rectangle<i32, Logical> {
         SpaceElement::geometry(&self.0)
+            .to_f64()
+            .upscale(self.upscale())
+            .to_i32_round()
     }
 
     fn bbox(&self) -> Rectangle<i32, Logical> {
         SpaceElement::bbox(&self.0)
+            .to_f64()
+            .upscale(self.upscale())
+            .to_i32_round()
     }
 
     fn is_in_input_region(&self, point: &Point<f64, smithay::utils::Logical>) -> bool {
-        SpaceElement::is_in_input_region(&self.0, point)
+        SpaceElement::is_in_input_region(&self.0, &point.downscale(self.upscale()))
     }
 
     fn z_index(&self) -> u8 {
@@ -961,10 +1182,102 @@ where
         scale: Scale<f64>,
         alpha: f32,
     ) -> Vec<C> {
-        self.0.render_elements(renderer, location, scale, alpha)
+        if self.is_upscaled() {
+            let Some((surface, scale)) = self.render_surface(scale) else {
+                return Vec::new();
+            };
+            return render_elements_from_surface_tree(
+                renderer,
+                &surface,
+                location,
+                scale,
+                alpha * self.opacity(),
+                FRAME_TIME_FILTER,
+            );
+        }
+        self.0
+            .render_elements(renderer, location, scale, alpha * self.opacity())
     }
 }
 
+/// Like [`render_elements_from_surface_tree`], but draws surfaces with a single-pixel buffer
+/// attached as solid color elements, instead of uploading and scaling a texture for them.
+fn surface_tree_elements<R, C>(
+    renderer: &mut R,
+    surface: &WlSurface,
+    location: Point<i32, Physical>,
+    scale: Scale<f64>,
+    alpha: f32,
+    kind: KindEvaluation,
+) -> Vec<C>
+where
+    R: Renderer + ImportAll,
+    R::TextureId: Clone + 'static,
+    C: From<WaylandSurfaceRenderElement<R>> + From<SolidColorRenderElement>,
+{
+    let mut elements = Vec::new();
+
+    with_surface_tree_downward(
+        surface,
+        location.to_f64(),
+        |_, states, location| {
+            let data = states.data_map.get::<RendererSurfaceStateUserData>();
+            match data.and_then(|data| data.lock().unwrap().view()) {
+                Some(view) => {
+                    TraversalAction::DoChildren(*location + view.offset.to_f64().to_physical(scale))
+                }
+                None => TraversalAction::SkipChildren,
+            }
+        },
+        |surface, states, location| {
+            let Some(data) = states.data_map.get::<RendererSurfaceStateUserData>() else {
+                return;
+            };
+            let (view, color, commit) = {
+                let data = data.lock().unwrap();
+                let Some(view) = data.view() else {
+                    return;
+                };
+                let color = data
+                    .buffer()
+                    .and_then(|buffer| get_single_pixel_buffer(buffer).ok())
+                    .map(|buffer| buffer.rgba32f());
+                (view, color, data.current_commit())
+            };
+            let location = *location + view.offset.to_f64().to_physical(scale);
+            let kind = match kind {
+                KindEvaluation::Static(kind) => kind,
+                KindEvaluation::Dynamic(eval) => eval(states),
+            };
+
+            if let Some([r, g, b, a]) = color {
+                // single-pixel buffers are premultiplied
+                let geometry =
+                    Rectangle::new(location, view.dst.to_f64().to_physical(scale)).to_i32_round();
+                elements.push(C::from(SolidColorRenderElement::new(
+                    Id::from_wayland_resource(surface),
+                    geometry,
+                    commit,
+                    Color32F::new(r * alpha, g * alpha, b * alpha, a * alpha),
+                    kind,
+                )));
+                return;
+            }
+
+            match WaylandSurfaceRenderElement::from_surface(
+                renderer, surface, states, location, alpha, kind,
+            ) {
+                Ok(Some(element)) => elements.push(C::from(element)),
+                Ok(None) => {}
+                Err(err) => warn!(?err, "Failed to import surface"),
+            }
+        },
+        |_, _, _| true,
+    );
+
+    elements
+}
+
 fn with_toplevel_state<T, F: FnOnce(Option<&smithay::wayland::shell::xdg::ToplevelState>) -> T>(
     toplevel: &ToplevelSurface,
     pending: bool,
@@ -20,7 +20,7 @@ use smithay::{
             ImportAll, ImportMem, Renderer,
             element::{
                 AsRenderElements, memory::MemoryRenderBufferRenderElement,
-                surface::WaylandSurfaceRenderElement,
+                solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement,
             },
         },
     },
@@ -949,4 +949,5 @@ render_elements! {
     pub CosmicWindowRenderElement<R> where R: ImportAll + ImportMem;
     Header = MemoryRenderBufferRenderElement<R>,
     Window = WaylandSurfaceRenderElement<R>,
+    SolidColor = SolidColorRenderElement,
 }
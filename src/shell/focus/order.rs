@@ -1,4 +1,4 @@
-use std::{ops::ControlFlow, time::Instant};
+use std::{collections::HashSet, ops::ControlFlow};
 
 use cosmic_comp_config::workspace::WorkspaceLayout;
 use keyframe::{ease, functions::EaseInOutCubic};
@@ -18,6 +18,7 @@ use crate::{
         layout::{floating::FloatingLayout, tiling::ANIMATION_DURATION},
     },
     utils::{
+        animation,
         geometry::*,
         prelude::OutputExt,
         quirks::{WORKSPACE_OVERVIEW_NAMESPACE, workspace_overview_is_open},
@@ -153,7 +154,7 @@ fn render_input_order_internal<R: 'static>(
                         EaseInOutCubic,
                         0.0,
                         1.0,
-                        Instant::now().duration_since(*st).as_millis() as f32
+                        animation::now().duration_since(*st).as_millis() as f32
                             / ANIMATION_DURATION.as_millis() as f32,
                     ),
                 ),
@@ -167,7 +168,8 @@ fn render_input_order_internal<R: 'static>(
                     forward,
                 } => (
                     *forward,
-                    (spring.value_at(Instant::now().duration_since(*start)) as f32).clamp(0.0, 1.0),
+                    (spring.value_at(animation::now().duration_since(*start)) as f32)
+                        .clamp(0.0, 1.0),
                 ),
             };
 
@@ -214,16 +216,41 @@ fn render_input_order_internal<R: 'static>(
         // overlay redirect windows
         // they need to be over sticky windows, because they could be popups of sticky windows,
         // and we can't differenciate that.
+        let config = shell.override_redirect;
+        let x11_focused = !config.hide_without_focus
+            || seat
+                .get_keyboard()
+                .unwrap()
+                .current_focus()
+                .and_then(|target| target.active_window())
+                .is_some_and(|window| window.x11_surface().is_some());
+        // pids of X11 applications with a window visible on this output
+        let visible_apps = config.follow_app.then(|| {
+            workspace
+                .mapped()
+                .chain(set.sticky_layer.mapped())
+                .flat_map(|mapped| mapped.windows().map(|(window, _)| window))
+                .chain(workspace.get_fullscreen().cloned())
+                .filter_map(|window| window.x11_surface().and_then(|surface| surface.pid()))
+                .collect::<HashSet<_>>()
+        });
         for (surface, location) in shell
             .override_redirect_windows
             .iter()
             .rev()
+            .filter(|_| x11_focused)
             .filter(|or| {
-                (*or)
-                    .geometry()
-                    .as_global()
-                    .intersection(output.geometry())
-                    .is_some()
+                let geometry = (*or).geometry().as_global();
+                if config.confine_to_output {
+                    output.geometry().contains(geometry.loc)
+                } else {
+                    geometry.intersection(output.geometry()).is_some()
+                }
+            })
+            .filter(|or| {
+                visible_apps
+                    .as_ref()
+                    .is_none_or(|pids| or.pid().is_none_or(|pid| pids.contains(&pid)))
             })
             .map(|or| (or, or.geometry().loc.as_global()))
         {
@@ -213,6 +213,17 @@ impl PointerFocusTarget {
         }
     }
 
+    /// Factor surface-local locations have to be scaled down by for upscaled X11 surfaces
+    fn upscale(&self) -> f64 {
+        match self {
+            PointerFocusTarget::X11Surface {
+                toplevel: Some(surface),
+                ..
+            } => surface.upscale(),
+            _ => 1.0,
+        }
+    }
+
     pub fn is_client(&self, client: &Client) -> bool {
         match self {
             PointerFocusTarget::WlSurface { surface, .. } => {
@@ -343,6 +354,10 @@ impl PointerTarget<State> for PointerFocusTarget {
             }
         }
 
+        let event = &PointerMotionEvent {
+            location: event.location.downscale(self.upscale()),
+            ..*event
+        };
         self.inner_pointer_target().enter(seat, data, event);
     }
     fn motion(&self, seat: &Seat<State>, data: &mut State, event: &PointerMotionEvent) {
@@ -365,6 +380,10 @@ impl PointerTarget<State> for PointerFocusTarget {
             }
         }
 
+        let event = &PointerMotionEvent {
+            location: event.location.downscale(self.upscale()),
+            ..*event
+        };
         self.inner_pointer_target().motion(seat, data, event);
     }
     fn relative_motion(&self, seat: &Seat<State>, data: &mut State, event: &RelativeMotionEvent) {
@@ -462,6 +481,10 @@ impl PointerTarget<State> for PointerFocusTarget {
 
 impl TouchTarget<State> for PointerFocusTarget {
     fn down(&self, seat: &Seat<State>, data: &mut State, event: &DownEvent, seq: Serial) {
+        let event = &DownEvent {
+            location: event.location.downscale(self.upscale()),
+            ..*event
+        };
         self.inner_touch_target().down(seat, data, event, seq);
     }
 
@@ -470,6 +493,10 @@ impl TouchTarget<State> for PointerFocusTarget {
     }
 
     fn motion(&self, seat: &Seat<State>, data: &mut State, event: &TouchMotionEvent, seq: Serial) {
+        let event = &TouchMotionEvent {
+            location: event.location.downscale(self.upscale()),
+            ..*event
+        };
         self.inner_touch_target().motion(seat, data, event, seq);
     }
 
@@ -245,7 +245,9 @@ pub fn window_items(
     let screenshot_clone = window.clone();
     let stack_clone = window.clone();
     let sticky_clone = window.clone();
+    let upscale_clone = window.clone();
     let close_clone = window.clone();
+    let active = window.active_window();
 
     vec![
         (!is_stacked).then_some(
@@ -544,6 +546,16 @@ pub fn window_items(
             })
             .toggled(is_sticky),
         ),
+        active.x11_surface().is_some().then_some(
+            Item::new(fl!("window-menu-xwayland-upscale"), move |handle| {
+                let window = upscale_clone.active_window();
+                let _ = handle.insert_idle(move |state| {
+                    let scale = state.common.xwayland_scale.unwrap_or(1.);
+                    window.set_upscale((!window.is_upscaled()).then_some(scale));
+                });
+            })
+            .toggled(active.is_upscaled()),
+        ),
         Some(Item::Separator),
         if is_stacked {
             Some(Item::new(fl!("window-menu-close-all"), move |_handle| {
@@ -671,3 +683,27 @@ pub fn fullscreen_items(window: &CosmicSurface, config: &Config) -> impl Iterato
     .into_iter()
     .flatten()
 }
+
+pub fn unresponsive_items(window: &CosmicSurface) -> impl Iterator<Item = Item> {
+    let kill_clone = window.clone();
+    let wait_clone = window.clone();
+
+    [
+        Item::new(
+            fl!("unresponsive-window", app = window.title()),
+            |_handle| {},
+        )
+        .disabled(true),
+        Item::Separator,
+        Item::new(fl!("unresponsive-window-force-quit"), move |handle| {
+            let window = kill_clone.clone();
+            let _ = handle.insert_idle(move |state| {
+                window.kill_client(&state.common.display_handle);
+            });
+        }),
+        Item::new(fl!("unresponsive-window-wait"), move |_handle| {
+            wait_clone.wait_for_response();
+        }),
+    ]
+    .into_iter()
+}
@@ -13,7 +13,7 @@ use crate::{
         focus::target::{KeyboardFocusTarget, PointerFocusTarget},
         layout::floating::TiledCorners,
     },
-    utils::prelude::*,
+    utils::{animation, prelude::*},
     wayland::protocols::toplevel_info::{toplevel_enter_output, toplevel_enter_workspace},
 };
 
@@ -77,7 +77,7 @@ impl MoveGrabState {
     {
         let scale = if self.previous == ManagedLayer::Tiling {
             0.6 + ((1.0
-                - (Instant::now().duration_since(self.start).as_millis() as f64
+                - (animation::now().duration_since(self.start).as_millis() as f64
                     / RESCALE_ANIMATION_DURATION)
                     .min(1.0))
                 * 0.4)
@@ -732,7 +732,7 @@ impl MoveGrab {
                 - start_data.location().as_global().to_i32_round())
             .as_logical(),
             indicator_thickness,
-            start: Instant::now(),
+            start: animation::now(),
             stacking_indicator: None,
             snapping_zone: None,
             previous: previous_layer,
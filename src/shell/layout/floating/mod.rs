@@ -40,7 +40,7 @@ use crate::{
         grabs::{GrabStartData, ReleaseMode, ResizeEdge},
     },
     state::State,
-    utils::{prelude::*, tween::EaseRectangle},
+    utils::{animation, prelude::*, tween::EaseRectangle},
     wayland::handlers::xdg_shell::popup::get_popup_toplevel,
 };
 
@@ -92,7 +92,7 @@ impl Animation {
         match self {
             Animation::Tiled { .. } => 1.0,
             Animation::Minimize { start, .. } => {
-                let percentage = Instant::now()
+                let percentage = animation::now()
                     .duration_since(*start)
                     .min(MINIMIZE_ANIMATION_DURATION)
                     .as_secs_f32()
@@ -100,7 +100,7 @@ impl Animation {
                 1.0 - ((percentage - 0.5).max(0.0) * 2.0)
             }
             Animation::Unminimize { start, .. } => {
-                let percentage = Instant::now()
+                let percentage = animation::now()
                     .duration_since(*start)
                     .min(MINIMIZE_ANIMATION_DURATION)
                     .as_secs_f32()
@@ -151,7 +151,7 @@ impl Animation {
         };
         let previous_rect = *self.previous_geometry();
         let start = *self.start();
-        let now = Instant::now();
+        let now = animation::now();
         let progress =
             now.duration_since(start).min(duration).as_secs_f64() / duration.as_secs_f64();
 
@@ -373,7 +373,7 @@ impl FloatingLayout {
                 self.animations.insert(
                     mapped.clone(),
                     Animation::Tiled {
-                        start: Instant::now(),
+                        start: animation::now(),
                         previous_geometry,
                     },
                 );
@@ -599,7 +599,7 @@ impl FloatingLayout {
             self.animations.insert(
                 mapped.clone(),
                 Animation::Tiled {
-                    start: Instant::now(),
+                    start: animation::now(),
                     previous_geometry,
                 },
             );
@@ -638,7 +638,7 @@ impl FloatingLayout {
         self.animations.insert(
             mapped,
             Animation::Unminimize {
-                start: Instant::now(),
+                start: animation::now(),
                 previous_geometry: from,
                 target_geometry,
             },
@@ -657,7 +657,7 @@ impl FloatingLayout {
             self.animations.insert(
                 window.clone(),
                 Animation::Minimize {
-                    start: Instant::now(),
+                    start: animation::now(),
                     previous_geometry: if window.is_maximized(false) {
                         let output = self.space.outputs().next().unwrap();
                         let layers = layer_map_for_output(output);
@@ -1376,7 +1376,7 @@ impl FloatingLayout {
                 Animation::Tiled { .. } => ANIMATION_DURATION,
                 _ => MINIMIZE_ANIMATION_DURATION,
             };
-            Instant::now().duration_since(*anim.start()) < duration
+            animation::now().duration_since(*anim.start()) < duration
         });
         if self.animations.is_empty() != was_empty {
             self.dirty.store(true, Ordering::SeqCst);
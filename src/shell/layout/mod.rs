@@ -33,10 +33,7 @@ pub fn is_dialog(window: &CosmicSurface) -> bool {
         WindowSurface::X11(surface) => {
             if surface.is_override_redirect()
                 || surface.is_popup()
-                || !matches!(
-                    surface.window_type(),
-                    None | Some(WmWindowType::Normal) | Some(WmWindowType::Utility)
-                )
+                || !matches!(surface.window_type(), None | Some(WmWindowType::Normal))
             {
                 return true;
             }
@@ -54,6 +51,16 @@ pub fn is_dialog(window: &CosmicSurface) -> bool {
     false
 }
 
+/// X11 docks, desktop windows and splash screens, which are never tiled and don't take focus when mapped
+pub fn is_x11_auxiliary(window: &CosmicSurface) -> bool {
+    window.x11_surface().is_some_and(|surface| {
+        matches!(
+            surface.window_type(),
+            Some(WmWindowType::Dock) | Some(WmWindowType::Desktop) | Some(WmWindowType::Splash)
+        )
+    })
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct TilingExceptions {
     app_ids: RegexSet,
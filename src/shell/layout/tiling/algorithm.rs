@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Fixed arrangements of the tiling tree, used instead of splitting it freely.
+//!
+//! The windows keep their nodes, only the groups above them are rebuilt. That way the
+//! windows animate from their previous positions like with any other change of the tree.
+//! Dwindle only decides how new windows are inserted and never rebuilds the tree.
+
+use cosmic_comp_config::workspace::TilingAlgorithm;
+use id_tree::{InsertBehavior, MoveBehavior, Node, NodeId, RemoveBehavior, Tree};
+use smithay::{desktop::layer_map_for_output, output::Output, utils::Rectangle};
+
+use super::Data;
+use crate::{shell::layout::Orientation, utils::prelude::*};
+
+/// Bounds of the master-stack ratio, in per mille of the width
+pub(super) const MIN_RATIO: u32 = 50;
+pub(super) const MAX_RATIO: u32 = 950;
+
+/// Windows and placeholders of `tree` from left to right and top to bottom
+pub(super) fn leaves(tree: &Tree<Data>) -> Vec<NodeId> {
+    tree.root_node_id()
+        .into_iter()
+        .flat_map(|root| tree.traverse_pre_order_ids(root).unwrap())
+        .filter(|id| !tree.get(id).unwrap().data().is_group())
+        .collect()
+}
+
+/// Per mille of the width taken by the master column, if `tree` is split into masters and stack
+pub(super) fn master_ratio(tree: &Tree<Data>, masters: usize) -> Option<u32> {
+    let root = tree.get(tree.root_node_id()?).ok()?;
+    match root.data() {
+        Data::Group {
+            orientation: Orientation::Vertical,
+            sizes,
+            ..
+        } if sizes.len() == 2 && leaves(tree).len() > masters => {
+            let total: i32 = sizes.iter().sum();
+            (total > 0).then(|| (sizes[0] as i64 * 1000 / total as i64).max(0) as u32)
+        }
+        _ => None,
+    }
+}
+
+/// Orientation of the group a new window splits `node` with, for the dwindle algorithm.
+///
+/// Alternates with the orientation of the parent, starting along the longer side of the output.
+pub(super) fn dwindle_orientation(
+    tree: &Tree<Data>,
+    node: &NodeId,
+    output: &Output,
+) -> Orientation {
+    match tree.get(node).unwrap().parent() {
+        Some(parent) => match tree.get(parent).unwrap().data().orientation() {
+            Orientation::Horizontal => Orientation::Vertical,
+            Orientation::Vertical => Orientation::Horizontal,
+        },
+        None => {
+            let output_size = output.geometry().size;
+            if output_size.w > output_size.h {
+                Orientation::Vertical
+            } else {
+                Orientation::Horizontal
+            }
+        }
+    }
+}
+
+/// Rebuilds the groups of `tree` in the shape of `algorithm`, with the windows in the
+/// given `order`.
+///
+/// `order` has to contain all leaves of the tree. Does nothing for algorithms other than
+/// [`TilingAlgorithm::MasterStack`].
+pub(super) fn arrange(
+    tree: &mut Tree<Data>,
+    output: &Output,
+    algorithm: TilingAlgorithm,
+    order: &[NodeId],
+) {
+    let zone = layer_map_for_output(output).non_exclusive_zone().as_local();
+    arrange_in(tree, zone, algorithm, order)
+}
+
+fn arrange_in(
+    tree: &mut Tree<Data>,
+    zone: Rectangle<i32, Local>,
+    algorithm: TilingAlgorithm,
+    order: &[NodeId],
+) {
+    let TilingAlgorithm::MasterStack { masters, ratio } = algorithm else {
+        return;
+    };
+    let Some(root) = tree.root_node_id().cloned() else {
+        return;
+    };
+    if order.is_empty() {
+        let _ = tree.remove_node(root, RemoveBehavior::DropChildren);
+        return;
+    }
+    if !tree.get(&root).unwrap().data().is_group() {
+        // a single window, already in shape
+        return;
+    }
+    let masters = (masters as usize).max(1);
+
+    // detach the leaves, keeping their ids, and drop all old groups including the root,
+    // so the new root is inserted into an empty tree
+    let old_groups = tree
+        .traverse_pre_order_ids(&root)
+        .unwrap()
+        .filter(|id| tree.get(id).unwrap().data().is_group())
+        .collect::<Vec<_>>();
+    for id in old_groups {
+        tree.remove_node(id, RemoveBehavior::OrphanChildren)
+            .unwrap();
+    }
+
+    if order.len() <= masters {
+        let root = insert_group(tree, None, Orientation::Horizontal, zone, order.len());
+        gather(tree, &root, order);
+    } else {
+        let master_width = zone.size.w * ratio.clamp(MIN_RATIO, MAX_RATIO) as i32 / 1000;
+        let root = tree
+            .insert(
+                Node::new(Data::Group {
+                    orientation: Orientation::Vertical,
+                    sizes: vec![master_width, zone.size.w - master_width],
+                    last_geometry: zone,
+                    alive: Default::default(),
+                    pill_indicator: None,
+                }),
+                InsertBehavior::AsRoot,
+            )
+            .unwrap();
+        let (master_order, stack_order) = order.split_at(masters);
+        let master = insert_group(
+            tree,
+            Some(&root),
+            Orientation::Horizontal,
+            zone,
+            master_order.len(),
+        );
+        let stack = insert_group(
+            tree,
+            Some(&root),
+            Orientation::Horizontal,
+            zone,
+            stack_order.len(),
+        );
+        gather(tree, &master, master_order);
+        gather(tree, &stack, stack_order);
+    }
+}
+
+/// Inserts a group for `len` equally sized children, groups of a single child are flattened
+/// by `update_positions`.
+fn insert_group(
+    tree: &mut Tree<Data>,
+    parent: Option<&NodeId>,
+    orientation: Orientation,
+    geometry: Rectangle<i32, Local>,
+    len: usize,
+) -> NodeId {
+    let len = len.max(1) as i32;
+    let length = match orientation {
+        Orientation::Horizontal => geometry.size.h,
+        Orientation::Vertical => geometry.size.w,
+    };
+    let mut sizes = vec![length / len; len as usize];
+    *sizes.last_mut().unwrap() += length - (length / len) * len;
+
+    tree.insert(
+        Node::new(Data::Group {
+            orientation,
+            sizes,
+            last_geometry: geometry,
+            alive: Default::default(),
+            pill_indicator: None,
+        }),
+        match parent {
+            Some(parent) => InsertBehavior::UnderNode(parent),
+            None => InsertBehavior::AsRoot,
+        },
+    )
+    .unwrap()
+}
+
+/// Moves `leaves` below `group` in order
+fn gather(tree: &mut Tree<Data>, group: &NodeId, leaves: &[NodeId]) {
+    for leaf in leaves {
+        tree.move_node(leaf, MoveBehavior::ToParent(group)).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cosmic_comp_config::workspace::TilingAlgorithm;
+    use id_tree::{InsertBehavior, Node, NodeId, Tree};
+    use smithay::{backend::renderer::element::Id, utils::Rectangle};
+
+    use super::{arrange_in, leaves, master_ratio};
+    use crate::{
+        shell::layout::{
+            Orientation,
+            tiling::{Data, PlaceholderType},
+        },
+        utils::prelude::*,
+    };
+
+    fn zone() -> Rectangle<i32, Local> {
+        Rectangle::from_size((1000, 800).into())
+    }
+
+    fn leaf() -> Node<Data> {
+        Node::new(Data::Placeholder {
+            id: Id::new(),
+            last_geometry: Rectangle::default(),
+            type_: PlaceholderType::DropZone,
+        })
+    }
+
+    fn group(orientation: Orientation, sizes: Vec<i32>) -> Node<Data> {
+        Node::new(Data::Group {
+            orientation,
+            sizes,
+            last_geometry: zone(),
+            alive: Default::default(),
+            pill_indicator: None,
+        })
+    }
+
+    /// Tree of `len` leaves, split in nested groups like freely tiled windows
+    fn nested_tree(len: usize) -> (Tree<Data>, Vec<NodeId>) {
+        let mut tree = Tree::new();
+        let mut parent = tree
+            .insert(
+                group(Orientation::Vertical, vec![500, 500]),
+                InsertBehavior::AsRoot,
+            )
+            .unwrap();
+        let mut ids = Vec::new();
+        for idx in 0..len {
+            ids.push(
+                tree.insert(leaf(), InsertBehavior::UnderNode(&parent))
+                    .unwrap(),
+            );
+            if len - idx > 2 {
+                parent = tree
+                    .insert(
+                        group(Orientation::Horizontal, vec![400, 400]),
+                        InsertBehavior::UnderNode(&parent),
+                    )
+                    .unwrap();
+            }
+        }
+        (tree, ids)
+    }
+
+    fn children(tree: &Tree<Data>, id: &NodeId) -> Vec<NodeId> {
+        tree.children_ids(id).unwrap().cloned().collect()
+    }
+
+    fn sizes(tree: &Tree<Data>, id: &NodeId) -> Vec<i32> {
+        match tree.get(id).unwrap().data() {
+            Data::Group { sizes, .. } => sizes.clone(),
+            _ => panic!("Not a group"),
+        }
+    }
+
+    #[test]
+    fn test_arrange_master_stack() {
+        let (mut tree, ids) = nested_tree(4);
+        let order = vec![
+            ids[2].clone(),
+            ids[0].clone(),
+            ids[1].clone(),
+            ids[3].clone(),
+        ];
+        arrange_in(
+            &mut tree,
+            zone(),
+            TilingAlgorithm::MasterStack {
+                masters: 1,
+                ratio: 600,
+            },
+            &order,
+        );
+
+        let root = tree.root_node_id().unwrap().clone();
+        assert!(tree.get(&root).unwrap().parent().is_none());
+        assert_eq!(
+            tree.get(&root).unwrap().data().orientation(),
+            Orientation::Vertical
+        );
+        assert_eq!(sizes(&tree, &root), vec![600, 400]);
+
+        let columns = children(&tree, &root);
+        assert_eq!(columns.len(), 2);
+        assert_eq!(children(&tree, &columns[0]), vec![ids[2].clone()]);
+        assert_eq!(
+            children(&tree, &columns[1]),
+            vec![ids[0].clone(), ids[1].clone(), ids[3].clone()]
+        );
+        assert_eq!(sizes(&tree, &columns[1]).iter().sum::<i32>(), 800);
+
+        // only the new groups and the leaves are left
+        assert_eq!(tree.traverse_pre_order_ids(&root).unwrap().count(), 3 + 4);
+        assert_eq!(leaves(&tree), order);
+    }
+
+    #[test]
+    fn test_arrange_only_masters() {
+        let (mut tree, ids) = nested_tree(3);
+        arrange_in(
+            &mut tree,
+            zone(),
+            TilingAlgorithm::MasterStack {
+                masters: 3,
+                ratio: 500,
+            },
+            &ids,
+        );
+
+        let root = tree.root_node_id().unwrap().clone();
+        assert_eq!(
+            tree.get(&root).unwrap().data().orientation(),
+            Orientation::Horizontal
+        );
+        assert_eq!(children(&tree, &root), ids);
+        assert_eq!(tree.traverse_pre_order_ids(&root).unwrap().count(), 1 + 3);
+    }
+
+    #[test]
+    fn test_arrange_single_and_empty() {
+        let mut tree = Tree::new();
+        let id = tree.insert(leaf(), InsertBehavior::AsRoot).unwrap();
+        arrange_in(
+            &mut tree,
+            zone(),
+            TilingAlgorithm::DEFAULT_MASTER_STACK,
+            &[id.clone()],
+        );
+        assert_eq!(tree.root_node_id(), Some(&id));
+
+        let (mut tree, _) = nested_tree(2);
+        arrange_in(
+            &mut tree,
+            zone(),
+            TilingAlgorithm::DEFAULT_MASTER_STACK,
+            &[],
+        );
+        assert!(tree.root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_arrange_other_algorithms() {
+        let (mut tree, ids) = nested_tree(3);
+        let before = leaves(&tree);
+        let order = ids.into_iter().rev().collect::<Vec<_>>();
+        arrange_in(&mut tree, zone(), TilingAlgorithm::Tree, &order);
+        arrange_in(&mut tree, zone(), TilingAlgorithm::Dwindle, &order);
+        assert_eq!(leaves(&tree), before);
+    }
+
+    #[test]
+    fn test_master_ratio() {
+        let (mut tree, ids) = nested_tree(3);
+        arrange_in(
+            &mut tree,
+            zone(),
+            TilingAlgorithm::MasterStack {
+                masters: 1,
+                ratio: 300,
+            },
+            &ids,
+        );
+        assert_eq!(master_ratio(&tree, 1), Some(300));
+        // not split into masters and stack, with as many masters as windows
+        assert_eq!(master_ratio(&tree, 3), None);
+
+        let (tree, _) = nested_tree(2);
+        assert_eq!(master_ratio(&tree, 1), Some(500));
+
+        let mut tree = Tree::new();
+        assert_eq!(master_ratio(&tree, 1), None);
+        tree.insert(leaf(), InsertBehavior::AsRoot).unwrap();
+        assert_eq!(master_ratio(&tree, 1), None);
+    }
+}
@@ -30,7 +30,7 @@ use smithay::{
     utils::{IsAlive, Logical, Point, Serial},
 };
 
-use super::super::{Data, TilingLayout};
+use super::super::{Data, same_nodes};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ResizeForkTarget {
@@ -232,35 +232,11 @@ impl ResizeForkGrab {
                 return false;
             };
             let tiling_layer = &mut workspace.tiling_layer;
-            let gaps = tiling_layer.gaps();
 
             let mut tree = tiling_layer.queue.trees.back().unwrap().0.copy_clone();
             match &mut self.old_tree {
                 Some(old_tree) => {
-                    // it would be so nice to just `zip` here, but `zip` just returns `None` once either returns `None`.
-                    let mut iter_a = old_tree
-                        .root_node_id()
-                        .into_iter()
-                        .flat_map(|root_id| old_tree.traverse_pre_order_ids(root_id).unwrap());
-                    let mut iter_b = tree
-                        .root_node_id()
-                        .into_iter()
-                        .flat_map(|root_id| tree.traverse_pre_order_ids(root_id).unwrap());
-
-                    // so lets do it manually
-                    let mut equal = true;
-                    let mut a = iter_a.next();
-                    let mut b = iter_b.next();
-                    while a.is_some() || b.is_some() {
-                        equal = a == b;
-                        if !equal {
-                            break;
-                        }
-                        a = iter_a.next();
-                        b = iter_b.next();
-                    }
-
-                    if !equal {
+                    if !same_nodes(old_tree, &tree) {
                         *old_tree = tree.copy_clone();
                         self.accumulated_delta = 0.0;
                     } else {
@@ -329,18 +305,7 @@ impl ResizeForkGrab {
                     _ => unreachable!(),
                 }
 
-                let should_configure = force
-                    || tree
-                        .traverse_pre_order(&self.node)
-                        .unwrap()
-                        .all(|node| match node.data() {
-                            Data::Mapped { mapped, .. } => mapped.latest_size_committed(),
-                            _ => true,
-                        });
-                if should_configure {
-                    let blocker = TilingLayout::update_positions(&output, &mut tree, gaps);
-                    tiling_layer.queue.push_tree(tree, None, blocker);
-                }
+                tiling_layer.resize_fork(tree, self.node.clone(), force);
             } else {
                 return true;
             }
@@ -25,7 +25,7 @@ use crate::{
         grabs::ResizeEdge,
         layout::Orientation,
     },
-    utils::{prelude::*, tween::EaseRectangle},
+    utils::{animation, prelude::*, tween::EaseRectangle},
     wayland::{
         handlers::xdg_shell::popup::get_popup_toplevel,
         protocols::{
@@ -38,6 +38,7 @@ use crate::{
     },
 };
 
+use cosmic_comp_config::workspace::TilingAlgorithm;
 use cosmic_settings_config::shortcuts::action::{FocusDirection, ResizeDirection};
 use id_tree::{InsertBehavior, MoveBehavior, Node, NodeId, NodeIdError, RemoveBehavior, Tree};
 use keyframe::{
@@ -71,6 +72,7 @@ use std::{
 use tracing::trace;
 use wayland_backend::server::ClientId;
 
+mod algorithm;
 mod blocker;
 mod grabs;
 pub use self::blocker::*;
@@ -112,6 +114,32 @@ impl TargetZone {
 struct TreeQueue {
     trees: VecDeque<(Tree<Data>, Duration, Option<TilingBlocker>)>,
     animation_start: Option<Instant>,
+    /// Latest size of a fork being resized interactively, that wasn't configured yet
+    pending_resize: Option<PendingResize>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingResize {
+    tree: Tree<Data>,
+    node: NodeId,
+    since: Instant,
+}
+
+impl PendingResize {
+    /// Whether the windows of the fork committed their last configure or took too long to do so
+    fn is_ready(&self) -> bool {
+        self.since.elapsed() >= RESIZE_TIMEOUT
+            || self
+                .tree
+                .traverse_pre_order(&self.node)
+                .map(|mut nodes| {
+                    nodes.all(|node| match node.data() {
+                        Data::Mapped { mapped, .. } => mapped.latest_size_committed(),
+                        _ => true,
+                    })
+                })
+                .unwrap_or(true)
+    }
 }
 
 impl TreeQueue {
@@ -133,6 +161,7 @@ pub struct TilingLayout {
     backdrop_id: Id,
     swapping_stack_surface_id: Id,
     last_overview_hover: Option<(Option<Instant>, TargetZone)>,
+    algorithm: TilingAlgorithm,
     pub theme: cosmic::Theme,
 }
 
@@ -345,7 +374,7 @@ pub struct RestoreTilingState {
 }
 
 impl TilingLayout {
-    pub fn new(theme: cosmic::Theme, output: &Output) -> TilingLayout {
+    pub fn new(theme: cosmic::Theme, output: &Output, algorithm: TilingAlgorithm) -> TilingLayout {
         TilingLayout {
             queue: TreeQueue {
                 trees: {
@@ -354,15 +383,45 @@ impl TilingLayout {
                     queue
                 },
                 animation_start: None,
+                pending_resize: None,
             },
             output: output.clone(),
             backdrop_id: Id::new(),
             swapping_stack_surface_id: Id::new(),
             last_overview_hover: None,
+            algorithm,
             theme,
         }
     }
 
+    pub fn algorithm(&self) -> TilingAlgorithm {
+        self.algorithm
+    }
+
+    /// Switches to `algorithm`, rearranging the windows right away
+    pub fn set_algorithm(&mut self, algorithm: TilingAlgorithm) {
+        if self.algorithm == algorithm {
+            return;
+        }
+        self.algorithm = algorithm;
+
+        let gaps = self.gaps();
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        let order = algorithm::leaves(&tree);
+        algorithm::arrange(&mut tree, &self.output, self.algorithm, &order);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+    }
+
+    /// Keeps the master ratio the user resized the tree to, before it is rearranged
+    fn sync_master_ratio(&mut self, tree: &Tree<Data>) {
+        if let TilingAlgorithm::MasterStack { masters, ratio } = &mut self.algorithm {
+            if let Some(current) = algorithm::master_ratio(tree, *masters as usize) {
+                *ratio = current;
+            }
+        }
+    }
+
     pub fn set_output(&mut self, output: &Output) {
         let gaps = self.gaps();
         let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
@@ -414,14 +473,39 @@ impl TilingLayout {
             ANIMATION_DURATION
         };
 
+        let window: CosmicMapped = window.into();
+        self.sync_master_ratio(&tree);
+        let (node, orientation) = match self.algorithm {
+            TilingAlgorithm::Dwindle if direction.is_none() => {
+                let node = last_active.or_else(|| algorithm::leaves(&tree).pop());
+                let orientation = node
+                    .as_ref()
+                    .map(|node| algorithm::dwindle_orientation(&tree, node, &self.output));
+                (node, orientation)
+            }
+            _ => (last_active, None),
+        };
         TilingLayout::map_to_tree(
             &mut tree,
-            window,
+            window.clone(),
             &self.output,
-            last_active,
+            node,
+            orientation,
             direction,
             minimize_rect,
         );
+        if matches!(self.algorithm, TilingAlgorithm::MasterStack { .. }) {
+            // new windows become the first master
+            let new_id = window.tiling_node_id.lock().unwrap().clone().unwrap();
+            let order = std::iter::once(new_id.clone())
+                .chain(
+                    algorithm::leaves(&tree)
+                        .into_iter()
+                        .filter(|id| id != &new_id),
+                )
+                .collect::<Vec<_>>();
+            algorithm::arrange(&mut tree, &self.output, self.algorithm, &order);
+        }
         let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
         self.queue.push_tree(tree, duration, blocker);
     }
@@ -438,6 +522,9 @@ impl TilingLayout {
         window.output_enter(&self.output, window.bbox());
         window.set_bounds(self.output.geometry().size.as_logical());
 
+        // the previous position is lost, once the tree is rearranged
+        let tiling_state =
+            tiling_state.filter(|_| !matches!(self.algorithm, TilingAlgorithm::MasterStack { .. }));
         if let Some(RestoreTilingState {
             parent,
             sibling,
@@ -543,10 +630,11 @@ impl TilingLayout {
         window: impl Into<CosmicMapped>,
         output: &Output,
         node: Option<NodeId>,
+        orientation: Option<Orientation>,
         direction: Option<Direction>,
         minimize_rect: Option<Rectangle<i32, Local>>,
     ) {
-        let window = window.into();
+        let window: CosmicMapped = window.into();
         let new_window = Node::new(Data::Mapped {
             mapped: window.clone(),
             last_geometry: Rectangle::from_size((100, 100).into()),
@@ -575,14 +663,14 @@ impl TilingLayout {
                 tree.insert(new_window, InsertBehavior::AsRoot).unwrap()
             }
         } else if let Some(ref node_id) = node {
-            let orientation = {
+            let orientation = orientation.unwrap_or_else(|| {
                 let window_size = tree.get(node_id).unwrap().data().geometry().size;
                 if window_size.w > window_size.h {
                     Orientation::Vertical
                 } else {
                     Orientation::Horizontal
                 }
-            };
+            });
             let new_id = tree.insert(new_window, InsertBehavior::AsRoot).unwrap();
             TilingLayout::new_group(tree, node_id, &new_id, orientation).unwrap();
             new_id
@@ -1407,7 +1495,12 @@ impl TilingLayout {
             {
                 let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
 
+                self.sync_master_ratio(&tree);
                 TilingLayout::unmap_internal(&mut tree, &node_id);
+                if matches!(self.algorithm, TilingAlgorithm::MasterStack { .. }) {
+                    let order = algorithm::leaves(&tree);
+                    algorithm::arrange(&mut tree, &self.output, self.algorithm, &order);
+                }
 
                 let duration = if minimizing {
                     MINIMIZE_ANIMATION_DURATION
@@ -2179,6 +2272,7 @@ impl TilingLayout {
                     Some(current_node),
                     None,
                     None,
+                    None,
                 );
 
                 let node = window.tiling_node_id.lock().unwrap().clone().unwrap();
@@ -2302,6 +2396,78 @@ impl TilingLayout {
         None
     }
 
+    /// Moves the focused window to the front of the master area, or swaps it with the next
+    /// window, if it already is the first master. Only applies to the master-stack algorithm.
+    pub fn promote_focused(&mut self, seat: &Seat<State>) {
+        self.reorder_focused(seat, |order, idx, _| {
+            if idx == 0 {
+                if order.len() > 1 {
+                    order.swap(0, 1);
+                }
+            } else {
+                let id = order.remove(idx);
+                order.insert(0, id);
+            }
+        });
+    }
+
+    /// Moves the focused window from the master area to the front of the stack.
+    /// Only applies to the master-stack algorithm.
+    pub fn demote_focused(&mut self, seat: &Seat<State>) {
+        self.reorder_focused(seat, |order, idx, masters| {
+            if idx < masters && order.len() > masters {
+                let id = order.remove(idx);
+                order.insert(masters, id);
+            }
+        });
+    }
+
+    fn reorder_focused(
+        &mut self,
+        seat: &Seat<State>,
+        reorder: impl FnOnce(&mut Vec<NodeId>, usize, usize),
+    ) {
+        let TilingAlgorithm::MasterStack { masters, .. } = self.algorithm else {
+            return;
+        };
+        let Some(target) = seat.get_keyboard().unwrap().current_focus() else {
+            return;
+        };
+        let gaps = self.gaps();
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        let Some((node_id, FocusedNodeData::Window(_))) =
+            TilingLayout::currently_focused_node(&tree, target)
+        else {
+            return;
+        };
+
+        self.sync_master_ratio(&tree);
+        let mut order = algorithm::leaves(&tree);
+        let Some(idx) = order.iter().position(|id| id == &node_id) else {
+            return;
+        };
+        reorder(&mut order, idx, (masters as usize).max(1));
+        algorithm::arrange(&mut tree, &self.output, self.algorithm, &order);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+    }
+
+    /// Grows the master area by `delta` per mille of the output's width and changes the number
+    /// of masters by `masters_delta`. Only applies to the master-stack algorithm.
+    pub fn adjust_master_area(&mut self, delta: i32, masters_delta: i32) {
+        let tree = self.queue.trees.back().unwrap().0.copy_clone();
+        self.sync_master_ratio(&tree);
+        let TilingAlgorithm::MasterStack { masters, ratio } = self.algorithm else {
+            return;
+        };
+        self.set_algorithm(TilingAlgorithm::MasterStack {
+            masters: masters.saturating_add_signed(masters_delta).max(1),
+            ratio: ratio
+                .saturating_add_signed(delta)
+                .clamp(algorithm::MIN_RATIO, algorithm::MAX_RATIO),
+        });
+    }
+
     pub fn recalculate(&mut self) {
         let gaps = self.gaps();
 
@@ -2327,10 +2493,48 @@ impl TilingLayout {
     }
 
     pub fn animations_going(&self) -> bool {
-        self.queue.animation_start.is_some()
+        self.queue.animation_start.is_some() || self.queue.pending_resize.is_some()
+    }
+
+    /// Resizes the fork `node` to its sizes in `tree`.
+    ///
+    /// Unless `force` is set, this waits for the windows of the fork to commit their previous
+    /// size first, replacing any resize still waiting, so slow clients get one configure per
+    /// frame they draw instead of one per motion event.
+    fn resize_fork(&mut self, tree: Tree<Data>, node: NodeId, force: bool) {
+        let since = self
+            .queue
+            .pending_resize
+            .take()
+            .map_or_else(Instant::now, |pending| pending.since);
+        let pending = PendingResize { tree, node, since };
+        if force || pending.is_ready() {
+            self.apply_resize(pending);
+        } else {
+            self.queue.pending_resize = Some(pending);
+        }
+    }
+
+    fn apply_resize(&mut self, pending: PendingResize) {
+        let mut tree = pending.tree;
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, self.gaps());
+        self.queue.push_tree(tree, None, blocker);
     }
 
     pub fn update_animation_state(&mut self) -> HashMap<ClientId, Client> {
+        if self
+            .queue
+            .pending_resize
+            .as_ref()
+            .is_some_and(PendingResize::is_ready)
+        {
+            let pending = self.queue.pending_resize.take().unwrap();
+            // the layout might have changed since
+            if same_nodes(&pending.tree, &self.queue.trees.back().unwrap().0) {
+                self.apply_resize(pending);
+            }
+        }
+
         let mut clients = HashMap::new();
         let mut ready_trees = 0;
         for (_, _, blocker) in self.queue.trees.iter().skip(1) {
@@ -2349,7 +2553,7 @@ impl TilingLayout {
         }
 
         if let Some(start) = self.queue.animation_start {
-            let duration_since_start = Instant::now().duration_since(start);
+            let duration_since_start = animation::now().duration_since(start);
             if duration_since_start
                 >= self
                     .queue
@@ -2404,7 +2608,7 @@ impl TilingLayout {
             *duration = other_duration
                 .map(|other| other.max(*duration))
                 .unwrap_or(*duration);
-            self.queue.animation_start = Some(Instant::now());
+            self.queue.animation_start = Some(animation::now());
         }
 
         clients
@@ -2752,6 +2956,7 @@ impl TilingLayout {
                     None,
                     None,
                     None,
+                    None,
                 );
                 window
             }
@@ -3658,16 +3863,16 @@ impl TilingLayout {
                             let overdue = if let Some(instant) = instant {
                                 match old_target_zone {
                                     TargetZone::InitialPlaceholder(_) => {
-                                        Instant::now().duration_since(*instant)
+                                        animation::now().duration_since(*instant)
                                             > INITIAL_MOUSE_ANIMATION_DELAY
                                     }
                                     _ => {
-                                        Instant::now().duration_since(*instant)
+                                        animation::now().duration_since(*instant)
                                             > MOUSE_ANIMATION_DELAY
                                     }
                                 }
                             } else {
-                                *instant = Some(Instant::now());
+                                *instant = Some(animation::now());
                                 false
                             };
 
@@ -3987,7 +4192,7 @@ impl TilingLayout {
             if *duration == Duration::ZERO {
                 1.0
             } else {
-                let now = Instant::now();
+                let now = animation::now();
                 let total = duration.as_millis() as f32;
                 let since = now.duration_since(animation_start).as_millis() as f32;
                 let percentage = since / total;
@@ -4142,7 +4347,7 @@ impl TilingLayout {
             .then(|| &self.queue.trees.front().unwrap().0);
 
         let percentage = if let Some(animation_start) = self.queue.animation_start {
-            let percentage = Instant::now().duration_since(animation_start).as_millis() as f32
+            let percentage = animation::now().duration_since(animation_start).as_millis() as f32
                 / duration.as_millis() as f32;
             ease(EaseInOutCubic, 0.0, 1.0, percentage)
         } else {
@@ -4247,6 +4452,29 @@ const WINDOW_BACKDROP_BORDER: i32 = 4;
 const WINDOW_BACKDROP_GAP: i32 = 12;
 
 const MAX_SWAP_WINDOW_SIZE: (i32, i32) = (360, 240);
+/// Time an interactive resize waits for slow windows, before configuring them again anyway
+const RESIZE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Whether both trees consist of the same nodes in the same order
+fn same_nodes(a: &Tree<Data>, b: &Tree<Data>) -> bool {
+    let mut iter_a = a
+        .root_node_id()
+        .into_iter()
+        .flat_map(|root_id| a.traverse_pre_order_ids(root_id).unwrap());
+    let mut iter_b = b
+        .root_node_id()
+        .into_iter()
+        .flat_map(|root_id| b.traverse_pre_order_ids(root_id).unwrap());
+
+    // `zip` would stop once either tree runs out of nodes
+    loop {
+        match (iter_a.next(), iter_b.next()) {
+            (None, None) => return true,
+            (node_a, node_b) if node_a != node_b => return false,
+            _ => {}
+        }
+    }
+}
 
 fn swap_factor(size: Size<i32, Logical>) -> f64 {
     let target_w = std::cmp::min(size.w, MAX_SWAP_WINDOW_SIZE.0);
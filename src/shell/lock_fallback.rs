@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Lock screen drawn by the compositor itself, while the session is locked without a screen
+//! locker covering an output.
+//!
+//! It only tells how to recover, unlocking is left to another session or virtual terminal.
+
+use calloop::LoopHandle;
+use cosmic::{
+    Apply,
+    iced::widget::{column, container},
+    iced_core::{Alignment, Background, Color, Length},
+    theme,
+    widget::text,
+};
+use smithay::{
+    backend::renderer::{ImportMem, Renderer, element::AsRenderElements},
+    output::Output,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+use crate::{
+    fl,
+    state::State,
+    utils::{
+        iced::{IcedElement, Program},
+        prelude::*,
+    },
+};
+
+pub type LockFallbackElement = IcedElement<LockFallbackInternal>;
+
+#[derive(Debug)]
+pub struct LockFallback {
+    element: LockFallbackElement,
+    size: Size<i32, Logical>,
+}
+
+impl LockFallback {
+    /// Lock screen covering all of `output`
+    pub fn new(
+        output: &Output,
+        evlh: LoopHandle<'static, State>,
+        theme: cosmic::Theme,
+    ) -> LockFallback {
+        let size = output.geometry().size.as_logical();
+        let element = IcedElement::new(LockFallbackInternal, size, evlh, theme);
+        element.output_enter(output, Rectangle::from_size(size));
+        LockFallback { element, size }
+    }
+
+    /// Whether `output` changed its size since the lock screen was created
+    pub fn is_outdated(&self, output: &Output) -> bool {
+        self.size != output.geometry().size.as_logical()
+    }
+
+    pub fn render<R, C>(&self, renderer: &mut R, output: &Output) -> Vec<C>
+    where
+        C: From<<LockFallbackElement as AsRenderElements<R>>::RenderElement>,
+        R: Renderer + ImportMem,
+        R::TextureId: Send + Clone + 'static,
+    {
+        let scale = output.current_scale().fractional_scale();
+        self.element
+            .render_elements(renderer, Point::from((0, 0)), scale.into(), 1.0)
+    }
+}
+
+pub struct LockFallbackInternal;
+
+impl Program for LockFallbackInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        column(vec![
+            text::title2(fl!("lock-fallback-title")).into(),
+            text::body(fl!("lock-fallback-hint")).into(),
+        ])
+        .spacing(16)
+        .align_x(Alignment::Center)
+        .apply(container)
+        .align_x(Alignment::Center)
+        .align_y(Alignment::Center)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .class(theme::Container::custom(|theme| container::Style {
+            text_color: Some(Color::from(theme.cosmic().background.on)),
+            background: Some(Background::Color(theme.cosmic().background.base.into())),
+            ..Default::default()
+        }))
+        .into()
+    }
+}
@@ -6,12 +6,12 @@ use layout::TilingExceptions;
 use std::{
     collections::HashMap,
     sync::{Mutex, atomic::Ordering},
-    thread,
     time::{Duration, Instant},
 };
 use wayland_backend::server::ClientId;
 
 use crate::{
+    idle::IdleInhibitor,
     shell::{focus::FocusTarget, grabs::fullscreen_items, layout::tiling::PlaceholderType},
     wayland::{
         handlers::data_device::{self, get_dnd_icon},
@@ -19,16 +19,20 @@ use crate::{
     },
 };
 use cosmic_comp_config::{
-    TileBehavior, ZoomConfig, ZoomMovement,
-    workspace::{PinnedWorkspace, WorkspaceLayout, WorkspaceMode},
+    ClearColor, TileBehavior, XwaylandOverrideRedirect, ZoomConfig, ZoomMovement,
+    a11y::{BellScope, Crosshair, FocusHighlight, VisualBell},
+    rules::{WindowRule, WindowRuleActions},
+    workspace::{PinnedWorkspace, TilingAlgorithm, WorkspaceLayout, WorkspaceMode},
 };
-use cosmic_config::ConfigSet;
 use cosmic_protocols::workspace::v2::server::zcosmic_workspace_handle_v2::TilingState;
 use cosmic_settings_config::shortcuts::action::{Direction, FocusDirection, ResizeDirection};
 use cosmic_settings_config::{shortcuts, window_rules::ApplicationException};
 use keyframe::{ease, functions::EaseInOutCubic};
 use smithay::{
-    backend::{input::TouchSlot, renderer::element::RenderElementStates},
+    backend::{
+        input::TouchSlot,
+        renderer::{Color32F, element::RenderElementStates},
+    },
     desktop::{
         LayerSurface, PopupKind, WindowSurface, WindowSurfaceType, layer_map_for_output,
         space::SpaceElement,
@@ -50,27 +54,31 @@ use smithay::{
     },
     utils::{IsAlive, Logical, Point, Rectangle, Serial, Size},
     wayland::{
-        compositor::{SurfaceAttributes, with_states},
+        compositor::{SurfaceAttributes, get_parent, with_states},
         seat::WaylandFocus,
         session_lock::LockSurface,
         shell::wlr_layer::{KeyboardInteractivity, Layer, LayerSurfaceCachedState},
         xdg_activation::XdgActivationState,
         xwayland_keyboard_grab::XWaylandKeyboardGrab,
     },
-    xwayland::X11Surface,
+    xwayland::{X11Surface, xwm::WmWindowType},
 };
-use tracing::error;
 
 use crate::{
     backend::render::animations::spring::{Spring, SpringParams},
-    config::Config,
-    utils::{prelude::*, quirks::WORKSPACE_OVERVIEW_NAMESPACE},
+    config::{Config, SavedToplevel},
+    fl,
+    utils::{animation, prelude::*, quirks::WORKSPACE_OVERVIEW_NAMESPACE},
     wayland::{
+        consent::ConsentReply,
         handlers::{
-            toplevel_management::minimize_rectangle, xdg_activation::ActivationContext,
+            session_management::{RestoredToplevel, floating_geometry},
+            toplevel_management::minimize_rectangle,
+            xdg_activation::ActivationContext,
             xdg_shell::popup::get_popup_toplevel,
         },
         protocols::{
+            output_configuration::head_is_enabled,
             toplevel_info::{
                 ToplevelInfoState, toplevel_enter_output, toplevel_enter_workspace,
                 toplevel_leave_output, toplevel_leave_workspace,
@@ -82,14 +90,26 @@ use crate::{
     },
 };
 
+pub mod cheatsheet;
+pub mod consent_dialog;
 pub mod element;
 pub mod focus;
 pub mod grabs;
 pub mod layout;
+pub mod lock_fallback;
+pub mod osd;
+pub mod rules;
+pub mod scratchpad;
+pub mod screenshot_selection;
 mod seats;
 mod workspace;
 pub mod zoom;
+use self::cheatsheet::Cheatsheet;
+use self::consent_dialog::ConsentDialog;
 pub use self::element::{CosmicMapped, CosmicMappedRenderElement, CosmicSurface};
+use self::lock_fallback::LockFallback;
+use self::osd::Osd;
+use self::screenshot_selection::ScreenshotSelection;
 pub use self::seats::*;
 pub use self::workspace::*;
 use self::zoom::{OutputZoomState, ZoomState};
@@ -103,7 +123,7 @@ use self::{
     focus::target::{KeyboardFocusTarget, PointerFocusTarget},
     grabs::{
         GrabStartData, Item, MenuGrab, MoveGrab, ReleaseMode, ResizeEdge, ResizeGrab, tab_items,
-        window_items,
+        unresponsive_items, window_items,
     },
     layout::{
         floating::{FloatingLayout, ResizeState},
@@ -112,6 +132,9 @@ use self::{
 };
 
 const ANIMATION_DURATION: Duration = Duration::from_millis(200);
+const DIM_DURATION: Duration = Duration::from_secs(2);
+const DIM_ALPHA: f32 = 0.6;
+const BELL_DURATION: Duration = Duration::from_millis(250);
 const GESTURE_MAX_LENGTH: f64 = 150.0;
 const GESTURE_POSITION_THRESHOLD: f64 = 0.5;
 const GESTURE_VELOCITY_THRESHOLD: f64 = 0.02;
@@ -138,13 +161,13 @@ impl OverviewMode {
     pub fn alpha(&self) -> Option<f32> {
         match self {
             OverviewMode::Started(_, start) => {
-                let percentage = Instant::now().duration_since(*start).as_millis() as f32
+                let percentage = animation::now().duration_since(*start).as_millis() as f32
                     / ANIMATION_DURATION.as_millis() as f32;
                 Some(ease(EaseInOutCubic, 0.0, 1.0, percentage))
             }
             OverviewMode::Active(_) => Some(1.0),
             OverviewMode::Ended(_, end) => {
-                let percentage = Instant::now().duration_since(*end).as_millis() as f32
+                let percentage = animation::now().duration_since(*end).as_millis() as f32
                     / ANIMATION_DURATION.as_millis() as f32;
                 if percentage < 1.0 {
                     Some(ease(EaseInOutCubic, 1.0, 0.0, percentage))
@@ -191,13 +214,13 @@ impl ResizeMode {
     pub fn alpha(&self) -> Option<f32> {
         match self {
             ResizeMode::Started(_, start, _) => {
-                let percentage = Instant::now().duration_since(*start).as_millis() as f32
+                let percentage = animation::now().duration_since(*start).as_millis() as f32
                     / ANIMATION_DURATION.as_millis() as f32;
                 Some(ease(EaseInOutCubic, 0.0, 1.0, percentage))
             }
             ResizeMode::Active(_, _) => Some(1.0),
             ResizeMode::Ended(end, _) => {
-                let percentage = Instant::now().duration_since(*end).as_millis() as f32
+                let percentage = animation::now().duration_since(*end).as_millis() as f32
                     / ANIMATION_DURATION.as_millis() as f32;
                 if percentage < 1.0 {
                     Some(ease(EaseInOutCubic, 1.0, 0.0, percentage))
@@ -258,6 +281,23 @@ pub struct PendingLayer {
     pub output: Output,
 }
 
+/// Flash of the visual bell
+#[derive(Debug)]
+struct Bell {
+    /// Flash only this window instead of the whole output
+    window: Option<CosmicMapped>,
+    output: Output,
+    rung: Instant,
+}
+
+/// Widget focused according to the screen reader
+#[derive(Debug)]
+struct A11yFocus {
+    window: CosmicMapped,
+    /// Relative to the window
+    area: Rectangle<i32, Logical>,
+}
+
 #[derive(Debug)]
 pub struct Shell {
     pub workspaces: Workspaces,
@@ -273,6 +313,11 @@ pub struct Shell {
 
     theme: cosmic::Theme,
     pub active_hint: bool,
+    pub override_redirect: XwaylandOverrideRedirect,
+    pub visual_bell: VisualBell,
+    pub focus_highlight: FocusHighlight,
+    pub crosshair: Crosshair,
+    pub clear_color: ClearColor,
     overview_mode: OverviewMode,
     swap_indicator: Option<SwapIndicator>,
     resize_mode: ResizeMode,
@@ -285,8 +330,21 @@ pub struct Shell {
         Output,
     )>,
     resize_indicator: Option<ResizeIndicator>,
+    /// Shows the active keybinding mode, while it isn't the default one
+    mode_indicator: Option<Osd>,
+    /// Shows that the focused window inhibits shortcuts
+    shortcuts_inhibit_indicator: Option<Osd>,
     zoom_state: Option<ZoomState>,
     tiling_exceptions: TilingExceptions,
+    window_rules: rules::WindowRules,
+    scratchpad: scratchpad::Scratchpad,
+    dimmed_since: Option<Instant>,
+    osd: Option<Osd>,
+    bell: Option<Bell>,
+    cheatsheet: Option<Cheatsheet>,
+    consent_dialog: Option<ConsentDialog>,
+    screenshot_selection: Option<ScreenshotSelection>,
+    a11y_focus: Option<A11yFocus>,
 
     #[cfg(feature = "debug")]
     pub debug_active: bool,
@@ -296,6 +354,9 @@ pub struct Shell {
 pub struct SessionLock {
     pub ext_session_lock: ExtSessionLockV1,
     pub surfaces: HashMap<Output, LockSurface>,
+    pub locked_at: Instant,
+    /// Built-in lock screens of outputs not covered by the screen locker
+    pub fallbacks: HashMap<Output, LockFallback>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -325,7 +386,7 @@ impl WorkspaceDelta {
     pub fn new_gesture_end(delta: f64, velocity: f64, forward: bool) -> Self {
         let params: SpringParams = SpringParams::new(1.0, 1000.0, 0.0001);
         WorkspaceDelta::GestureEnd {
-            start: Instant::now(),
+            start: animation::now(),
             forward,
             spring: Spring {
                 from: delta,
@@ -337,7 +398,7 @@ impl WorkspaceDelta {
     }
 
     pub fn new_shortcut() -> Self {
-        WorkspaceDelta::Shortcut(Instant::now())
+        WorkspaceDelta::Shortcut(animation::now())
     }
 
     pub fn is_animating(&self) -> bool {
@@ -354,6 +415,7 @@ pub struct WorkspaceSet {
     pub active: usize,
     pub group: WorkspaceGroupHandle,
     tiling_enabled: bool,
+    tiling_algorithm: TilingAlgorithm,
     output: Output,
     theme: cosmic::Theme,
     pub sticky_layer: FloatingLayout,
@@ -367,6 +429,7 @@ fn create_workspace(
     group_handle: &WorkspaceGroupHandle,
     active: bool,
     tiling: bool,
+    tiling_algorithm: TilingAlgorithm,
     theme: cosmic::Theme,
 ) -> Workspace {
     let workspace_handle = state
@@ -391,7 +454,13 @@ fn create_workspace(
             | WorkspaceCapabilities::Pin
             | WorkspaceCapabilities::Move,
     );
-    Workspace::new(workspace_handle, output.clone(), tiling, theme.clone())
+    Workspace::new(
+        workspace_handle,
+        output.clone(),
+        tiling,
+        tiling_algorithm,
+        theme.clone(),
+    )
 }
 
 fn create_workspace_from_pinned(
@@ -400,6 +469,7 @@ fn create_workspace_from_pinned(
     output: &Output,
     group_handle: &WorkspaceGroupHandle,
     active: bool,
+    tiling_algorithm: TilingAlgorithm,
     theme: cosmic::Theme,
 ) -> Workspace {
     let workspace_handle = state
@@ -424,7 +494,13 @@ fn create_workspace_from_pinned(
             | WorkspaceCapabilities::Pin
             | WorkspaceCapabilities::Move,
     );
-    Workspace::from_pinned(pinned, workspace_handle, output.clone(), theme.clone())
+    Workspace::from_pinned(
+        pinned,
+        workspace_handle,
+        output.clone(),
+        tiling_algorithm,
+        theme.clone(),
+    )
 }
 
 /* We will probably need this again at some point
@@ -458,6 +534,7 @@ impl WorkspaceSet {
         state: &mut WorkspaceUpdateGuard<'_, State>,
         output: &Output,
         tiling_enabled: bool,
+        tiling_algorithm: TilingAlgorithm,
         theme: cosmic::Theme,
     ) -> WorkspaceSet {
         let group_handle = state.create_workspace_group();
@@ -468,6 +545,7 @@ impl WorkspaceSet {
             active: 0,
             group: group_handle,
             tiling_enabled,
+            tiling_algorithm,
             theme,
             sticky_layer,
             minimized_windows: Vec::new(),
@@ -561,14 +639,14 @@ impl WorkspaceSet {
         if let Some((_, start)) = self.previously_active {
             match start {
                 WorkspaceDelta::Shortcut(st) => {
-                    if Instant::now().duration_since(st).as_millis() as f32
+                    if animation::now().duration_since(st).as_millis() as f32
                         >= ANIMATION_DURATION.as_millis() as f32
                     {
                         self.previously_active = None;
                     }
                 }
                 WorkspaceDelta::GestureEnd { start, spring, .. } => {
-                    if Instant::now().duration_since(start).as_millis()
+                    if animation::now().duration_since(start).as_millis()
                         > spring.duration().as_millis()
                     {
                         self.previously_active = None;
@@ -589,6 +667,7 @@ impl WorkspaceSet {
             &self.group,
             false,
             self.tiling_enabled,
+            self.tiling_algorithm,
             self.theme.clone(),
         );
         workspace_set_idx(
@@ -717,6 +796,7 @@ pub struct Workspaces {
     mode: WorkspaceMode,
     autotile: bool,
     autotile_behavior: TileBehavior,
+    tiling_algorithm: TilingAlgorithm,
     theme: cosmic::Theme,
     // Persisted workspace to add on first `output_add`
     persisted_workspaces: Vec<PinnedWorkspace>,
@@ -731,6 +811,7 @@ impl Workspaces {
             mode: config.cosmic_conf.workspaces.workspace_mode,
             autotile: config.cosmic_conf.autotile,
             autotile_behavior: config.cosmic_conf.autotile_behavior,
+            tiling_algorithm: config.cosmic_conf.tiling_algorithm,
             theme,
             persisted_workspaces: config.cosmic_conf.pinned_workspaces.clone(),
         }
@@ -753,7 +834,13 @@ impl Workspaces {
                 set
             })
             .unwrap_or_else(|| {
-                WorkspaceSet::new(workspace_state, output, self.autotile, self.theme.clone())
+                WorkspaceSet::new(
+                    workspace_state,
+                    output,
+                    self.autotile,
+                    self.tiling_algorithm,
+                    self.theme.clone(),
+                )
             });
         workspace_state.add_group_output(&set.group, output);
 
@@ -765,6 +852,7 @@ impl Workspaces {
                 output,
                 &set.group,
                 false,
+                self.tiling_algorithm,
                 self.theme.clone(),
             );
             set.workspaces.push(workspace);
@@ -1088,6 +1176,7 @@ impl Workspaces {
                                     &set.group,
                                     false,
                                     config.cosmic_conf.autotile,
+                                    self.tiling_algorithm,
                                     self.theme.clone(),
                                 ),
                             );
@@ -1350,6 +1439,27 @@ impl Workspaces {
         }
     }
 
+    /// Switches all workspaces without an algorithm of their own to `algorithm`, discarding
+    /// changes to the master area made at runtime
+    pub fn update_tiling_algorithm(&mut self, algorithm: TilingAlgorithm) {
+        self.tiling_algorithm = algorithm;
+        for set in self.sets.values_mut().chain(self.backup_set.as_mut()) {
+            set.tiling_algorithm = algorithm;
+            for workspace in set
+                .workspaces
+                .iter_mut()
+                .filter(|workspace| workspace.tiling_algorithm.is_none())
+            {
+                workspace.tiling_layer.set_algorithm(algorithm);
+            }
+        }
+    }
+
+    /// Configured algorithm of workspaces without one of their own
+    pub fn tiling_algorithm(&self) -> TilingAlgorithm {
+        self.tiling_algorithm
+    }
+
     pub fn update_autotile<'a>(
         &mut self,
         autotile: bool,
@@ -1367,12 +1477,55 @@ impl Workspaces {
             .flat_map(|set| &set.workspaces)
             .flat_map(|w| w.to_pinned())
             .collect();
-        let config = config.cosmic_helper.clone();
-        thread::spawn(move || {
-            if let Err(err) = config.set("pinned_workspaces", pinned_workspaces) {
-                error!(?err, "Failed to update pinned_workspaces key");
-            }
-        });
+        crate::config::set_in_background(
+            &config.cosmic_helper,
+            "pinned_workspaces",
+            pinned_workspaces,
+        );
+    }
+
+    /// Snapshot of the window of `surface`, to be restored through session management
+    pub fn saved_toplevel(&self, surface: &WlSurface) -> Option<SavedToplevel> {
+        self.sets.values().find_map(|set| {
+            set.workspaces
+                .iter()
+                .enumerate()
+                .find_map(|(idx, workspace)| {
+                    let fullscreen = workspace
+                        .get_fullscreen()
+                        .is_some_and(|window| window == surface);
+                    let mapped = workspace.element_for_surface(surface);
+                    if !fullscreen && mapped.is_none() {
+                        return None;
+                    }
+
+                    let original_geometry = mapped.and_then(|mapped| {
+                        mapped
+                            .maximized_state
+                            .lock()
+                            .unwrap()
+                            .as_ref()
+                            .map(|state| state.original_geometry)
+                    });
+                    let geometry = original_geometry
+                        .or_else(|| mapped.and_then(|mapped| workspace.element_geometry(mapped)));
+                    Some(SavedToplevel {
+                        output: workspace.output().name(),
+                        workspace: idx,
+                        tiled: workspace.is_tiled(surface),
+                        maximized: original_geometry.is_some(),
+                        fullscreen,
+                        geometry: geometry.map(|geometry| {
+                            (
+                                geometry.loc.x,
+                                geometry.loc.y,
+                                geometry.size.w,
+                                geometry.size.h,
+                            )
+                        }),
+                    })
+                })
+        })
     }
 }
 
@@ -1402,6 +1555,7 @@ impl Common {
 
         std::mem::drop(shell);
         self.refresh(); // fixes indicies of any moved workspaces
+        self.run_output_hooks(output, true);
     }
 
     pub fn remove_output(&mut self, output: &Output) {
@@ -1416,12 +1570,53 @@ impl Common {
 
         std::mem::drop(shell);
         self.refresh(); // cleans up excess of workspaces and empty workspaces
+        self.run_output_hooks(output, false);
+    }
+
+    /// Spawns the commands of all hooks matching `output`, once it was added or removed
+    fn run_output_hooks(&self, output: &Output, connected: bool) {
+        let commands = self
+            .config
+            .cosmic_conf
+            .output_hooks
+            .iter()
+            .filter(|hook| workspace::output_matches(&hook.output, output, false))
+            .filter_map(|hook| {
+                if connected {
+                    hook.connected.clone()
+                } else {
+                    hook.disconnected.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+        if commands.is_empty() {
+            return;
+        }
+
+        // spawning needs a workspace to activate, so wait until the outputs settled
+        self.event_loop_handle.insert_idle(move |state| {
+            if state.common.shell.read().outputs().next().is_none() {
+                return;
+            }
+            for command in commands {
+                state.spawn_command(command);
+            }
+        });
     }
 
     pub fn update_config(&mut self) {
         let mut shell = self.shell.write();
         let shell_ref = &mut *shell;
         shell_ref.active_hint = self.config.cosmic_conf.active_hint;
+        shell_ref.override_redirect = self.config.cosmic_conf.xwayland_override_redirect;
+        shell_ref.visual_bell = self.config.cosmic_conf.visual_bell;
+        shell_ref.focus_highlight = self.config.cosmic_conf.focus_highlight;
+        shell_ref.crosshair = self.config.cosmic_conf.crosshair;
+        shell_ref.clear_color = self.config.cosmic_conf.clear_color.clone();
+        crate::dbus::a11y_focus::set_enabled(
+            self.config.cosmic_conf.focus_highlight.enabled,
+            &self.async_executor,
+        );
         if let Some(zoom_state) = shell_ref.zoom_state.as_mut() {
             zoom_state.increment = self.config.cosmic_conf.accessibility_zoom.increment;
             zoom_state.movement = self.config.cosmic_conf.accessibility_zoom.view_moves;
@@ -1445,6 +1640,7 @@ impl Common {
 
     #[profiling::function]
     pub fn refresh(&mut self) {
+        let _span = crate::utils::profiler::span("layout");
         self.xdg_activation_state
             .retain_tokens(|_, data| data.timestamp.elapsed() < ACTIVATION_TOKEN_EXPIRE_TIME);
         self.shell.write().refresh(
@@ -1453,6 +1649,12 @@ impl Common {
         );
         self.popups.cleanup();
         self.toplevel_info_state.refresh(&self.workspace_state);
+        crate::dbus::toplevels::announce_urgency_changes(&self.async_executor);
+        crate::dbus::control::announce_changes(self, &self.async_executor);
+        crate::dbus::display_config::refresh(
+            &self.output_configuration_state,
+            &self.async_executor,
+        );
         self.refresh_idle_inhibit();
         self.a11y_keyboard_monitor_state.refresh();
     }
@@ -1460,12 +1662,31 @@ impl Common {
     pub fn refresh_idle_inhibit(&mut self) {
         self.idle_inhibiting_surfaces.retain(|s| s.alive());
 
+        // Inhibitors only count while their surface is visible. The primary scanout output
+        // is only kept by surfaces presented in the last frame, so it already excludes
+        // surfaces on hidden workspaces and surfaces fully occluded by opaque windows.
+        let shell = self.shell.read();
         let is_inhibited = self.idle_inhibiting_surfaces.iter().any(|surface| {
-            with_states(surface, |states| {
-                surface_primary_scanout_output(surface, states).is_some()
-            })
+            let output = with_states(surface, |states| {
+                surface_primary_scanout_output(surface, states)
+            });
+            if !output.is_some_and(|output| head_is_enabled(&output)) {
+                return false;
+            }
+
+            let mut root = surface.clone();
+            while let Some(parent) = get_parent(&root) {
+                root = parent;
+            }
+            shell
+                .element_for_surface(&root)
+                .is_none_or(|mapped| !mapped.is_minimized())
         });
-        self.idle_notifier_state.set_is_inhibited(is_inhibited);
+        std::mem::drop(shell);
+        self.idle_state
+            .set_inhibited(IdleInhibitor::Surface, is_inhibited);
+        self.idle_notifier_state
+            .set_is_inhibited(self.idle_state.is_inhibited());
     }
 
     #[profiling::function]
@@ -1527,9 +1748,10 @@ impl Common {
 
 impl Shell {
     pub fn new(config: &Config) -> Self {
-        let theme = cosmic::theme::system_preference();
+        let theme = crate::theme::compositor_theme(config.cosmic_conf.high_contrast);
 
         let tiling_exceptions = layout::TilingExceptions::new(config.tiling_exceptions.iter());
+        let window_rules = rules::WindowRules::new(&config.cosmic_conf.window_rules);
 
         Shell {
             workspaces: Workspaces::new(config, theme.clone()),
@@ -1545,13 +1767,29 @@ impl Shell {
 
             theme,
             active_hint: config.cosmic_conf.active_hint,
+            override_redirect: config.cosmic_conf.xwayland_override_redirect,
+            visual_bell: config.cosmic_conf.visual_bell,
+            focus_highlight: config.cosmic_conf.focus_highlight,
+            crosshair: config.cosmic_conf.crosshair,
+            clear_color: config.cosmic_conf.clear_color.clone(),
             overview_mode: OverviewMode::None,
             swap_indicator: None,
             resize_mode: ResizeMode::None,
             resize_state: None,
             resize_indicator: None,
+            mode_indicator: None,
+            shortcuts_inhibit_indicator: None,
             zoom_state: None,
             tiling_exceptions,
+            window_rules,
+            scratchpad: scratchpad::Scratchpad::default(),
+            dimmed_since: None,
+            osd: None,
+            bell: None,
+            cheatsheet: None,
+            consent_dialog: None,
+            screenshot_selection: None,
+            a11y_focus: None,
 
             #[cfg(feature = "debug")]
             debug_active: false,
@@ -2123,6 +2361,11 @@ impl Shell {
                         .is_some_and(|state| state.lock().unwrap().is_animating())
                 })
             })
+            || self
+                .dimmed_since
+                .is_some_and(|since| since.elapsed() < DIM_DURATION)
+            || self.osd.is_some()
+            || self.bell.is_some()
     }
 
     pub fn update_animations(&mut self) -> HashMap<ClientId, Client> {
@@ -2149,7 +2392,7 @@ impl Shell {
                 if matches!(trigger, Trigger::KeyboardSwap(_, _)) {
                     self.swap_indicator = Some(swap_indicator(evlh, self.theme.clone()));
                 }
-                self.overview_mode = OverviewMode::Started(trigger, Instant::now());
+                self.overview_mode = OverviewMode::Started(trigger, animation::now());
             }
         } else if matches!(
             self.overview_mode,
@@ -2159,19 +2402,21 @@ impl Shell {
                 if let OverviewMode::Started(trigger, start) = self.overview_mode.clone() {
                     (
                         ANIMATION_DURATION
-                            - Instant::now().duration_since(start).min(ANIMATION_DURATION),
+                            - animation::now()
+                                .duration_since(start)
+                                .min(ANIMATION_DURATION),
                         Some(trigger),
                     )
                 } else {
                     (Duration::ZERO, self.overview_mode.active_trigger().cloned())
                 };
-            self.overview_mode = OverviewMode::Ended(trigger, Instant::now() - reverse_duration);
+            self.overview_mode = OverviewMode::Ended(trigger, animation::now() - reverse_duration);
         }
     }
 
     pub fn overview_mode(&self) -> (OverviewMode, Option<SwapIndicator>) {
         if let OverviewMode::Started(trigger, timestamp) = &self.overview_mode {
-            if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION {
+            if animation::now().duration_since(*timestamp) > ANIMATION_DURATION {
                 return (
                     OverviewMode::Active(trigger.clone()),
                     self.swap_indicator.clone(),
@@ -2179,7 +2424,7 @@ impl Shell {
             }
         }
         if let OverviewMode::Ended(_, timestamp) = &self.overview_mode {
-            if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION {
+            if animation::now().duration_since(*timestamp) > ANIMATION_DURATION {
                 return (OverviewMode::None, None);
             }
         }
@@ -2198,16 +2443,27 @@ impl Shell {
                 *old_pattern = pattern;
                 *old_direction = direction;
             } else {
-                self.resize_mode = ResizeMode::Started(pattern, Instant::now(), direction);
+                self.resize_mode = ResizeMode::Started(pattern, animation::now(), direction);
             }
             self.resize_indicator = Some(resize_indicator(
                 direction,
                 config,
-                evlh,
+                evlh.clone(),
                 self.theme.clone(),
             ));
+            if self.mode_indicator.is_none() {
+                let output = self.seats.last_active().focused_or_active_output();
+                self.mode_indicator = Some(Osd::persistent(
+                    &output,
+                    "view-fullscreen-symbolic",
+                    fl!("resize-mode"),
+                    evlh,
+                    self.theme.clone(),
+                ));
+            }
         } else if let Some(direction) = self.resize_mode.active_direction() {
-            self.resize_mode = ResizeMode::Ended(Instant::now(), direction);
+            self.resize_mode = ResizeMode::Ended(animation::now(), direction);
+            self.mode_indicator = None;
             if let Some((_, direction, edge, _, _, _)) = self.resize_state.as_ref() {
                 self.finish_resize(*direction, *edge);
             }
@@ -2216,7 +2472,7 @@ impl Shell {
 
     pub fn resize_mode(&self) -> (ResizeMode, Option<ResizeIndicator>) {
         if let ResizeMode::Started(binding, timestamp, direction) = &self.resize_mode {
-            if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION {
+            if animation::now().duration_since(*timestamp) > ANIMATION_DURATION {
                 return (
                     ResizeMode::Active(binding.clone(), *direction),
                     self.resize_indicator.clone(),
@@ -2224,7 +2480,7 @@ impl Shell {
             }
         }
         if let ResizeMode::Ended(timestamp, _) = self.resize_mode {
-            if Instant::now().duration_since(timestamp) > ANIMATION_DURATION {
+            if animation::now().duration_since(timestamp) > ANIMATION_DURATION {
                 return (ResizeMode::None, None);
             }
         }
@@ -2351,6 +2607,243 @@ impl Shell {
         self.zoom_state.as_ref()
     }
 
+    /// Dim all outputs, fading in over [`DIM_DURATION`]
+    pub fn set_dimmed(&mut self, dimmed: bool) {
+        if dimmed {
+            self.dimmed_since.get_or_insert_with(Instant::now);
+        } else {
+            self.dimmed_since = None;
+        }
+    }
+
+    /// Briefly shows `label` on `output`, replacing any osd still visible
+    pub fn show_osd(
+        &mut self,
+        output: &Output,
+        icon: &'static str,
+        label: impl Into<String>,
+        evlh: LoopHandle<'static, State>,
+    ) {
+        self.osd = Some(Osd::new(output, icon, label, evlh, self.theme.clone()));
+    }
+
+    /// Shows the indicator, that the focused window inhibits shortcuts, on `output`
+    /// or hides it.
+    ///
+    /// Returns the outputs to redraw.
+    pub fn set_shortcuts_inhibit_indicator(
+        &mut self,
+        output: Option<&Output>,
+        evlh: LoopHandle<'static, State>,
+    ) -> Vec<Output> {
+        let previous = self
+            .shortcuts_inhibit_indicator
+            .as_ref()
+            .map(|osd| osd.output().clone());
+        if previous.as_ref() == output {
+            return Vec::new();
+        }
+
+        self.shortcuts_inhibit_indicator = output.map(|output| {
+            Osd::persistent(
+                output,
+                "input-keyboard-symbolic",
+                fl!("shortcuts-inhibited"),
+                evlh,
+                self.theme.clone(),
+            )
+        });
+        previous.into_iter().chain(output.cloned()).collect()
+    }
+
+    /// Osds to render, topmost first
+    pub fn osds(&self) -> impl Iterator<Item = &Osd> {
+        self.osd
+            .iter()
+            .chain(self.mode_indicator.iter())
+            .chain(self.shortcuts_inhibit_indicator.iter())
+    }
+
+    /// Shows the keybindings of the active mode on the focused output or hides them again.
+    ///
+    /// Returns the output to redraw.
+    pub fn toggle_cheatsheet(
+        &mut self,
+        config: &Config,
+        evlh: LoopHandle<'static, State>,
+    ) -> Output {
+        if let Some(output) = self.hide_cheatsheet() {
+            return output;
+        }
+
+        let output = self.seats.last_active().focused_or_active_output();
+        self.cheatsheet = Some(Cheatsheet::new(
+            &output,
+            config,
+            self.resize_mode.active_direction(),
+            evlh,
+            self.theme.clone(),
+        ));
+        output
+    }
+
+    /// Hides the cheatsheet, returning the output it was shown on
+    pub fn hide_cheatsheet(&mut self) -> Option<Output> {
+        self.cheatsheet
+            .take()
+            .map(|cheatsheet| cheatsheet.output().clone())
+    }
+
+    pub fn cheatsheet(&self) -> Option<&Cheatsheet> {
+        self.cheatsheet.as_ref()
+    }
+
+    /// Shows the consent dialog for `reply` on the active output, returning the output to redraw
+    pub fn show_consent_dialog(
+        &mut self,
+        reply: ConsentReply,
+        evlh: LoopHandle<'static, State>,
+    ) -> Output {
+        let output = self.seats.last_active().active_output();
+        self.consent_dialog = Some(ConsentDialog::new(reply, &output, evlh, self.theme.clone()));
+        output
+    }
+
+    pub fn hide_consent_dialog(&mut self) -> Option<ConsentDialog> {
+        self.consent_dialog.take()
+    }
+
+    pub fn consent_dialog(&self) -> Option<&ConsentDialog> {
+        self.consent_dialog.as_ref()
+    }
+
+    pub fn consent_dialog_mut(&mut self) -> Option<&mut ConsentDialog> {
+        self.consent_dialog.as_mut()
+    }
+
+    pub fn set_screenshot_selection(&mut self, selection: ScreenshotSelection) {
+        self.screenshot_selection = Some(selection);
+    }
+
+    pub fn take_screenshot_selection(&mut self) -> Option<ScreenshotSelection> {
+        self.screenshot_selection.take()
+    }
+
+    pub fn screenshot_selection(&self) -> Option<&ScreenshotSelection> {
+        self.screenshot_selection.as_ref()
+    }
+
+    pub fn screenshot_selection_mut(&mut self) -> Option<&mut ScreenshotSelection> {
+        self.screenshot_selection.as_mut()
+    }
+
+    /// Flashes the window of `surface` or the focused output, if the visual bell is enabled
+    pub fn ring_bell(&mut self, surface: Option<&WlSurface>) {
+        if !self.visual_bell.enabled {
+            return;
+        }
+
+        let visible_output = surface.and_then(|surface| self.visible_output_for_surface(surface));
+        let window = match self.visual_bell.scope {
+            BellScope::Window => visible_output
+                .and(surface)
+                .and_then(|surface| self.element_for_surface(surface))
+                .cloned(),
+            BellScope::Output => None,
+        };
+        let output = visible_output
+            .cloned()
+            .unwrap_or_else(|| self.seats.last_active().focused_or_active_output());
+
+        self.bell = Some(Bell {
+            window,
+            output,
+            rung: animation::now(),
+        });
+    }
+
+    /// Area and opacity of the visual bell flashing on `output`
+    pub fn visual_bell(&self, output: &Output) -> Option<(Rectangle<i32, Local>, f32)> {
+        let bell = self.bell.as_ref().filter(|bell| &bell.output == output)?;
+        let progress =
+            animation::now().duration_since(bell.rung).as_secs_f32() / BELL_DURATION.as_secs_f32();
+        if progress >= 1.0 {
+            return None;
+        }
+
+        let area = bell
+            .window
+            .as_ref()
+            .and_then(|window| self.element_geometry(window))
+            .map(|geometry| geometry.to_local(output))
+            .unwrap_or_else(|| Rectangle::from_size(output.geometry().size.as_local()));
+        let alpha = ease(EaseInOutCubic, self.visual_bell.intensity, 0.0, progress);
+        Some((area, alpha))
+    }
+
+    /// Color to clear `output` with, before rendering any surfaces
+    pub fn clear_color(&self, output: &Output) -> Color32F {
+        /// Brightness of the clear color on locked or dimmed outputs
+        const DIM_FACTOR: f32 = 0.3;
+
+        let config = &self.clear_color;
+        let [r, g, b] = self
+            .workspaces
+            .active(output)
+            .and_then(|(_, workspace)| workspace.id.as_ref())
+            .and_then(|id| config.workspaces.get(id))
+            .or_else(|| config.outputs.get(&output.name()))
+            .copied()
+            .unwrap_or(config.color);
+        let factor = if config.dim_inactive
+            && (self.session_lock.is_some() || self.dimmed_since.is_some())
+        {
+            DIM_FACTOR
+        } else {
+            1.0
+        };
+        Color32F::new(r * factor, g * factor, b * factor, 1.0)
+    }
+
+    /// Moves the screen reader focus to `area` of the window focused by the keyboard
+    pub fn set_a11y_focus(&mut self, area: Rectangle<i32, Logical>) {
+        let focus = self
+            .seats
+            .last_active()
+            .get_keyboard()
+            .unwrap()
+            .current_focus();
+        self.a11y_focus = match focus {
+            Some(KeyboardFocusTarget::Element(window)) => Some(A11yFocus { window, area }),
+            _ => None,
+        };
+    }
+
+    /// Area to highlight as focused by the screen reader on `output`
+    pub fn a11y_focus_highlight(&self, output: &Output) -> Option<Rectangle<i32, Local>> {
+        if !self.focus_highlight.enabled {
+            return None;
+        }
+        let focus = self.a11y_focus.as_ref()?;
+        let geometry = self.element_geometry(&focus.window)?;
+        let area = Rectangle::new(
+            geometry.loc + focus.area.loc.as_global(),
+            focus.area.size.as_global(),
+        );
+        geometry
+            .intersection(area)
+            .filter(|area| output.geometry().overlaps(*area))
+            .map(|area| area.to_local(output))
+    }
+
+    /// Opacity of the black overlay dimming the outputs
+    pub fn dim_alpha(&self) -> f32 {
+        self.dimmed_since.map_or(0.0, |since| {
+            let progress = since.elapsed().as_secs_f32() / DIM_DURATION.as_secs_f32();
+            ease(EaseInOutCubic, 0.0, DIM_ALPHA, progress.min(1.0))
+        })
+    }
+
     fn refresh(
         &mut self,
         xdg_activation_state: &XdgActivationState,
@@ -2358,12 +2851,12 @@ impl Shell {
     ) {
         match &self.overview_mode {
             OverviewMode::Started(trigger, timestamp)
-                if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION =>
+                if animation::now().duration_since(*timestamp) > ANIMATION_DURATION =>
             {
                 self.overview_mode = OverviewMode::Active(trigger.clone());
             }
             OverviewMode::Ended(_, timestamp)
-                if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION =>
+                if animation::now().duration_since(*timestamp) > ANIMATION_DURATION =>
             {
                 self.overview_mode = OverviewMode::None;
                 self.swap_indicator = None;
@@ -2373,12 +2866,12 @@ impl Shell {
 
         match &self.resize_mode {
             ResizeMode::Started(binding, timestamp, direction)
-                if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION =>
+                if animation::now().duration_since(*timestamp) > ANIMATION_DURATION =>
             {
                 self.resize_mode = ResizeMode::Active(binding.clone(), *direction);
             }
             ResizeMode::Ended(timestamp, _)
-                if Instant::now().duration_since(*timestamp) > ANIMATION_DURATION =>
+                if animation::now().duration_since(*timestamp) > ANIMATION_DURATION =>
             {
                 self.resize_mode = ResizeMode::None;
                 self.resize_indicator = None;
@@ -2386,6 +2879,18 @@ impl Shell {
             _ => {}
         }
 
+        if self.osd.as_ref().is_some_and(Osd::is_expired) {
+            self.osd = None;
+        }
+        self.scratchpad.refresh();
+        if self
+            .bell
+            .as_ref()
+            .is_some_and(|bell| animation::now().duration_since(bell.rung) > BELL_DURATION)
+        {
+            self.bell = None;
+        }
+
         if self.zoom_state.is_some() {
             let mut all_outputs_off = true;
             for output in self.outputs() {
@@ -2649,11 +3154,47 @@ impl Shell {
             _ => None,
         };
 
+        let restored = window
+            .user_data()
+            .get::<RestoredToplevel>()
+            .and_then(RestoredToplevel::take);
+        let restored_handle = restored.as_ref().and_then(|restored| {
+            self.workspaces
+                .sets
+                .iter()
+                .find(|(output, _)| output.name() == restored.output)
+                .and_then(|(_, set)| set.workspaces.get(restored.workspace))
+                .map(|workspace| workspace.handle)
+        });
+        let rule = self.window_rules.actions(&window);
+        let rule_handle = rule.workspace.and_then(|idx| {
+            let set = match rule.output.as_ref() {
+                Some(name) => self
+                    .workspaces
+                    .sets
+                    .iter()
+                    .find(|(output, _)| &output.name() == name)
+                    .map(|(_, set)| set)?,
+                None => self.workspaces.sets.get(&seat.active_output())?,
+            };
+            set.workspaces
+                .get((idx as usize).checked_sub(1)?)
+                .map(|workspace| workspace.handle)
+        });
+        let rule_output = rule.output.as_ref().and_then(|name| {
+            self.outputs()
+                .find(|output| &output.name() == name)
+                .cloned()
+        });
+        let target_handle = rule_handle.or(workspace_handle).or(restored_handle);
+
         let should_be_fullscreen = output.is_some();
-        let mut output = output.unwrap_or_else(|| seat.active_output());
+        let mut output = output
+            .or(rule_output)
+            .unwrap_or_else(|| seat.active_output());
 
         // this is beyond stupid, just to make the borrow checker happy
-        let workspace = if let Some(handle) = workspace_handle.filter(|handle| {
+        let workspace = if let Some(handle) = target_handle.filter(|handle| {
             self.workspaces
                 .spaces()
                 .any(|space| &space.handle == handle)
@@ -2670,7 +3211,7 @@ impl Shell {
         }
 
         let active_handle = self.active_space(&output).unwrap().handle;
-        let workspace = if let Some(handle) = workspace_handle.filter(|handle| {
+        let workspace = if let Some(handle) = target_handle.filter(|handle| {
             self.workspaces
                 .spaces()
                 .any(|space| &space.handle == handle)
@@ -2694,7 +3235,22 @@ impl Shell {
             && (workspace_output != seat.active_output() || active_handle != workspace.handle);
         let workspace_handle = workspace.handle;
         let is_dialog = layout::is_dialog(&window);
-        let floating_exception = layout::has_floating_exception(&self.tiling_exceptions, &window);
+        let is_auxiliary = layout::is_x11_auxiliary(&window);
+        // restored windows keep being tiled
+        let floating_exception = rule.floating.unwrap_or_else(|| {
+            layout::has_floating_exception(&self.tiling_exceptions, &window)
+                && !restored.as_ref().is_some_and(|restored| restored.tiled)
+        });
+        if let Some(opacity) = rule.opacity {
+            window.set_opacity(opacity);
+        }
+        if let Some(size) = rule.size {
+            // wayland toplevels got the size with their initial configure
+            if window.x11_surface().is_some() {
+                let geometry = window.geometry();
+                window.set_geometry(Rectangle::new(geometry.loc, size.into()).as_global(), 0);
+            }
+        }
 
         if should_be_fullscreen {
             if let Some((surface, state, _)) = workspace.map_fullscreen(&window, &seat, None, None)
@@ -2737,7 +3293,33 @@ impl Shell {
         }
 
         let workspace_empty = workspace.mapped().next().is_none();
-        if is_dialog || floating_exception || !workspace.tiling_enabled {
+        if window
+            .x11_surface()
+            .is_some_and(|surface| matches!(surface.window_type(), Some(WmWindowType::Splash)))
+        {
+            // keep splash screens centered, instead of cascading them like other windows
+            let zone = layer_map_for_output(&workspace_output).non_exclusive_zone();
+            let size = mapped.geometry().size;
+            let position = Point::<i32, Logical>::from((
+                zone.loc.x + (zone.size.w - size.w) / 2,
+                zone.loc.y + (zone.size.h - size.h) / 2,
+            ));
+            workspace
+                .floating_layer
+                .map(mapped.clone(), position.as_local());
+        } else if let Some(geometry) = restored.as_ref().and_then(floating_geometry) {
+            workspace
+                .floating_layer
+                .map(mapped.clone(), geometry.loc.as_local());
+        } else if let Some(position) = rule
+            .position
+            .filter(|_| is_dialog || floating_exception || !workspace.tiling_enabled)
+        {
+            workspace.floating_layer.map(
+                mapped.clone(),
+                Point::<i32, Logical>::from(position).as_local(),
+            );
+        } else if is_dialog || floating_exception || !workspace.tiling_enabled {
             workspace.floating_layer.map(mapped.clone(), None);
         } else {
             for mapped in workspace
@@ -2763,8 +3345,11 @@ impl Shell {
             self.maximize_request(&mapped, &seat, false, loop_handle);
         }
 
-        let new_target = if (workspace_output == seat.active_output()
-            && active_handle == workspace_handle)
+        let new_target = if is_auxiliary {
+            None
+        } else if rule.no_focus_steal == Some(true) {
+            None
+        } else if (workspace_output == seat.active_output() && active_handle == workspace_handle)
             || parent_is_sticky
         {
             // TODO: enforce focus stealing prevention by also checking the same rules as for the else case.
@@ -2899,6 +3484,16 @@ impl Shell {
                 return;
             }
         }
+
+        if let Some(surface) = self.scratchpad.remove(surface) {
+            toplevel_info.remove_toplevel(&surface);
+            self.pending_windows.push(PendingWindow {
+                surface,
+                seat: seat.clone(),
+                fullscreen: None,
+                maximized: false,
+            });
+        }
     }
 
     pub fn move_current(
@@ -3477,6 +4072,33 @@ impl Shell {
         Some((grab, Focus::Keep))
     }
 
+    /// Opens the force-quit dialog for an unresponsive `window` at the current pointer location
+    pub fn unresponsive_menu_request(
+        &self,
+        window: &CosmicSurface,
+        seat: &Seat<State>,
+        evlh: &LoopHandle<'static, State>,
+    ) -> Option<(MenuGrab, Focus)> {
+        let Some(GrabStartData::Pointer(start_data)) = check_grab_preconditions(seat, None, None)
+        else {
+            return None;
+        };
+        let global_position = start_data.location.to_i32_round().as_global();
+
+        let grab = MenuGrab::new(
+            GrabStartData::Pointer(start_data),
+            seat,
+            unresponsive_items(window),
+            global_position,
+            MenuAlignment::CORNER,
+            None,
+            evlh.clone(),
+            self.theme.clone(),
+        );
+
+        Some((grab, Focus::Keep))
+    }
+
     pub fn move_request(
         &mut self,
         surface: &WlSurface,
@@ -4104,6 +4726,91 @@ impl Shell {
         }
     }
 
+    /// Hides the active window of `mapped` in the scratchpad
+    fn hide_in_scratchpad(&mut self, mapped: &CosmicMapped) -> bool {
+        let window = mapped.active_window();
+        let Some(workspace) = self.space_for_mut(mapped) else {
+            return false;
+        };
+        let Some((surface, _)) = workspace.unmap_surface(&window) else {
+            return false;
+        };
+        toplevel_leave_workspace(&surface, &workspace.handle);
+        toplevel_leave_output(&surface, &workspace.output);
+        if self.scratchpad.shown() == Some(mapped) {
+            self.scratchpad.set_shown(None);
+        }
+        self.scratchpad.hide(surface);
+        true
+    }
+
+    /// Sends the focused window of `seat` to the scratchpad
+    pub fn send_to_scratchpad(&mut self, seat: &Seat<State>) -> bool {
+        let Some(KeyboardFocusTarget::Element(mapped)) =
+            seat.get_keyboard().unwrap().current_focus()
+        else {
+            return false;
+        };
+        self.hide_in_scratchpad(&mapped)
+    }
+
+    /// Hides the shown scratchpad window, if it is on the active workspace of `seat`.
+    /// Otherwise shows the next scratchpad window centered and floating on that workspace.
+    #[must_use]
+    pub fn toggle_scratchpad(
+        &mut self,
+        seat: &Seat<State>,
+        loop_handle: &LoopHandle<'static, State>,
+    ) -> Option<KeyboardFocusTarget> {
+        let output = seat.active_output();
+        let active_handle = self.active_space(&output)?.handle;
+
+        if let Some(shown) = self.scratchpad.shown().cloned() {
+            let on_active = self
+                .space_for(&shown)
+                .map(|workspace| workspace.handle == active_handle);
+            match on_active {
+                Some(true) => {
+                    self.hide_in_scratchpad(&shown);
+                    return None;
+                }
+                // summon it from the other workspace instead of the next one
+                Some(false) => {
+                    self.hide_in_scratchpad(&shown);
+                }
+                // e.g. made sticky, it isn't a scratchpad window anymore
+                None => self.scratchpad.set_shown(None),
+            }
+        }
+
+        let window = self.scratchpad.next()?;
+        let mapped = CosmicMapped::from(CosmicWindow::new(
+            window.clone(),
+            loop_handle.clone(),
+            self.theme.clone(),
+        ));
+        #[cfg(feature = "debug")]
+        {
+            mapped.set_debug(self.debug_active);
+        }
+
+        let workspace = self.active_space_mut(&output).unwrap(); // checked above
+        let zone = layer_map_for_output(&output).non_exclusive_zone();
+        let size = mapped.geometry().size;
+        let position = Point::<i32, Logical>::from((
+            zone.loc.x + (zone.size.w - size.w) / 2,
+            zone.loc.y + (zone.size.h - size.h) / 2,
+        ));
+        workspace
+            .floating_layer
+            .map(mapped.clone(), position.as_local());
+        toplevel_enter_output(&window, &output);
+        toplevel_enter_workspace(&window, &workspace.handle);
+        self.scratchpad.set_shown(Some(mapped.clone()));
+
+        Some(KeyboardFocusTarget::from(mapped))
+    }
+
     pub fn unminimize_request<S>(
         &mut self,
         surface: &S,
@@ -4746,6 +5453,15 @@ impl Shell {
         self.tiling_exceptions = layout::TilingExceptions::new(exceptions);
     }
 
+    pub fn update_window_rules(&mut self, rules: &[WindowRule]) {
+        self.window_rules = rules::WindowRules::new(rules);
+    }
+
+    /// Actions of the window rules matching `window`
+    pub fn window_rule_actions(&self, window: &CosmicSurface) -> WindowRuleActions {
+        self.window_rules.actions(window)
+    }
+
     pub fn take_presentation_feedback(
         &self,
         output: &Output,
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! On-screen displays drawn by the compositor itself,
+//! giving feedback for changes, that otherwise rely on a panel applet.
+//!
+//! Osds either fade out on their own or persist until removed, like the indicator of an active
+//! keybinding mode.
+
+use std::time::{Duration, Instant};
+
+use calloop::LoopHandle;
+use cosmic::{
+    Apply,
+    iced::widget::{container, horizontal_space, row},
+    iced_core::{Alignment, Background, Border, Color, Length},
+    theme,
+    widget::{icon::from_name, text},
+};
+use keyframe::{ease, functions::EaseInOutCubic};
+use smithay::{
+    backend::renderer::{ImportMem, Renderer, element::AsRenderElements},
+    output::Output,
+    utils::{Point, Rectangle},
+};
+
+use crate::{
+    state::State,
+    utils::iced::{IcedElement, Program},
+};
+
+/// How long an osd stays visible, including fading out
+const OSD_DURATION: Duration = Duration::from_millis(1500);
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+pub type OsdElement = IcedElement<OsdInternal>;
+
+#[derive(Debug)]
+pub struct Osd {
+    element: OsdElement,
+    output: Output,
+    /// `None` for persistent osds
+    shown: Option<Instant>,
+}
+
+impl Osd {
+    /// Osd fading out on its own
+    pub fn new(
+        output: &Output,
+        icon: &'static str,
+        label: impl Into<String>,
+        evlh: LoopHandle<'static, State>,
+        theme: cosmic::Theme,
+    ) -> Osd {
+        let mut osd = Osd::persistent(output, icon, label, evlh, theme);
+        osd.shown = Some(Instant::now());
+        osd
+    }
+
+    /// Osd staying visible until dropped
+    pub fn persistent(
+        output: &Output,
+        icon: &'static str,
+        label: impl Into<String>,
+        evlh: LoopHandle<'static, State>,
+        theme: cosmic::Theme,
+    ) -> Osd {
+        let element = IcedElement::new(
+            OsdInternal {
+                icon,
+                label: label.into(),
+            },
+            (1, 1),
+            evlh,
+            theme,
+        );
+        let size = element.minimum_size();
+        element.resize(size);
+        element.output_enter(output, Rectangle::from_size(size));
+
+        Osd {
+            element,
+            output: output.clone(),
+            shown: None,
+        }
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.shown
+            .is_some_and(|shown| shown.elapsed() > OSD_DURATION)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.shown.map_or(1.0, |shown| {
+            let remaining = OSD_DURATION.saturating_sub(shown.elapsed());
+            let progress = remaining.as_secs_f32() / FADE_DURATION.as_secs_f32();
+            ease(EaseInOutCubic, 0.0, 1.0, progress.min(1.0))
+        })
+    }
+
+    pub fn render<R, C>(&self, renderer: &mut R, output: &Output) -> Vec<C>
+    where
+        C: From<<OsdElement as AsRenderElements<R>>::RenderElement>,
+        R: Renderer + ImportMem,
+        R::TextureId: Send + Clone + 'static,
+    {
+        if output != &self.output {
+            return Vec::new();
+        }
+
+        let size = self.element.current_size().to_f64();
+        let output_geo = output.geometry().to_f64();
+        let scale = output.current_scale().fractional_scale();
+        // persistent osds stay out of the way at the top of the output
+        let y = if self.shown.is_some() {
+            output_geo.size.h / 4. * 3. - size.h / 2.
+        } else {
+            size.h / 2.
+        };
+        let location = Point::from((output_geo.size.w / 2. - size.w / 2., y))
+            .to_physical(scale)
+            .to_i32_round();
+
+        self.element
+            .render_elements(renderer, location, scale.into(), self.alpha())
+    }
+}
+
+pub struct OsdInternal {
+    icon: &'static str,
+    label: String,
+}
+
+impl Program for OsdInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        row(vec![
+            from_name(self.icon).size(32).prefer_svg(true).icon().into(),
+            horizontal_space().width(16).into(),
+            text::title3(&self.label).into(),
+        ])
+        .align_y(Alignment::Center)
+        .apply(container)
+        .align_x(Alignment::Center)
+        .align_y(Alignment::Center)
+        .padding(16)
+        .apply(container)
+        .class(theme::Container::custom(|theme| container::Style {
+            icon_color: Some(Color::from(theme.cosmic().background.on)),
+            text_color: Some(Color::from(theme.cosmic().background.on)),
+            background: Some(Background::Color(theme.cosmic().background.base.into())),
+            border: Border {
+                radius: 18.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            shadow: Default::default(),
+        }))
+        .width(Length::Shrink)
+        .height(Length::Shrink)
+        .into()
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Window rules from the `window_rules` config, applied when toplevels are mapped.
+
+use cosmic_comp_config::rules::{WindowRule, WindowRuleActions};
+use regex::Regex;
+use tracing::warn;
+
+use super::CosmicSurface;
+
+#[derive(Debug)]
+struct CompiledRule {
+    app_id: Option<Regex>,
+    title: Option<Regex>,
+    class: Option<Regex>,
+    actions: WindowRuleActions,
+}
+
+impl CompiledRule {
+    fn new(rule: &WindowRule) -> Result<Self, regex::Error> {
+        let compile = |pattern: &Option<String>| pattern.as_deref().map(Regex::new).transpose();
+        Ok(CompiledRule {
+            app_id: compile(&rule.app_id)?,
+            title: compile(&rule.title)?,
+            class: compile(&rule.class)?,
+            actions: rule.actions.clone(),
+        })
+    }
+
+    fn matches(&self, window: &CosmicSurface) -> bool {
+        if let Some(app_id) = self.app_id.as_ref() {
+            if !app_id.is_match(&window.app_id()) {
+                return false;
+            }
+        }
+        if let Some(title) = self.title.as_ref() {
+            if !title.is_match(&window.title()) {
+                return false;
+            }
+        }
+        if let Some(class) = self.class.as_ref() {
+            if !window
+                .x11_surface()
+                .is_some_and(|surface| class.is_match(&surface.class()))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WindowRules(Vec<CompiledRule>);
+
+impl WindowRules {
+    pub fn new(rules: &[WindowRule]) -> Self {
+        WindowRules(
+            rules
+                .iter()
+                .filter_map(|rule| match CompiledRule::new(rule) {
+                    Ok(compiled) => Some(compiled),
+                    Err(e) => {
+                        warn!(?rule, "Invalid regex in window rule: {}", e);
+                        None
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Actions of all rules matching `window`, later rules taking precedence
+    pub fn actions(&self, window: &CosmicSurface) -> WindowRuleActions {
+        self.0.iter().filter(|rule| rule.matches(window)).fold(
+            WindowRuleActions::default(),
+            |mut actions, rule| {
+                actions.merge(&rule.actions);
+                actions
+            },
+        )
+    }
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Windows hidden away from all workspaces, to be summoned onto the current one on demand.
+//!
+//! Hidden windows keep their toplevel handles, but aren't part of any workspace or layer.
+//! At most one of them is shown at a time, as a floating window on the workspace it was
+//! summoned to. Hiding it again puts it at the back of the queue, so toggling repeatedly
+//! cycles through all of them.
+
+use std::collections::VecDeque;
+
+use smithay::utils::IsAlive;
+
+use super::{CosmicMapped, CosmicSurface};
+
+#[derive(Debug, Default)]
+pub struct Scratchpad {
+    hidden: VecDeque<CosmicSurface>,
+    /// Scratchpad window currently shown on a workspace
+    shown: Option<CosmicMapped>,
+}
+
+impl Scratchpad {
+    /// Adds a window, that was just unmapped from its workspace, to the back of the queue
+    pub fn hide(&mut self, window: CosmicSurface) {
+        window.set_activated(false);
+        window.send_configure();
+        self.hidden.push_back(window);
+    }
+
+    /// Takes the next hidden window to show
+    pub fn next(&mut self) -> Option<CosmicSurface> {
+        self.hidden.pop_front()
+    }
+
+    pub fn shown(&self) -> Option<&CosmicMapped> {
+        self.shown.as_ref()
+    }
+
+    pub fn set_shown(&mut self, mapped: Option<CosmicMapped>) {
+        self.shown = mapped;
+    }
+
+    /// Removes `surface` from the hidden windows
+    pub fn remove<S>(&mut self, surface: &S) -> Option<CosmicSurface>
+    where
+        CosmicSurface: PartialEq<S>,
+    {
+        let idx = self.hidden.iter().position(|window| window == surface)?;
+        self.hidden.remove(idx)
+    }
+
+    pub fn refresh(&mut self) {
+        self.hidden.retain(IsAlive::alive);
+        self.shown.take_if(|mapped| !mapped.alive());
+    }
+}
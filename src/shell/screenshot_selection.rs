@@ -0,0 +1,388 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Interactive selection of what to capture for a screenshot.
+//!
+//! Outputs are dimmed, except for the area being selected. Dragging selects an area of the output
+//! the drag started on, clicking selects the window under the pointer or the whole output,
+//! if there is none. Enter captures the active output and Escape or a right click cancels.
+
+use calloop::LoopHandle;
+use cosmic::{
+    Apply,
+    iced::widget::container,
+    iced_core::{Background, Border, Color, Length},
+    theme,
+    widget::text,
+};
+use futures_channel::oneshot;
+use smithay::{
+    backend::{
+        input::ButtonState,
+        renderer::{ImportMem, Renderer, element::AsRenderElements},
+    },
+    input::{
+        Seat,
+        pointer::{
+            AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent,
+            GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+            GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent,
+            GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab, PointerInnerHandle,
+            RelativeMotionEvent,
+        },
+    },
+    output::Output,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::{
+    dbus::screenshot_portal::ScreenshotResponse,
+    fl,
+    shell::{
+        CosmicSurface, Shell,
+        focus::target::{KeyboardFocusTarget, PointerFocusTarget},
+    },
+    state::State,
+    utils::{
+        iced::{IcedElement, Program},
+        prelude::*,
+    },
+};
+
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+
+/// Drags smaller than this in both directions count as clicks
+const CLICK_THRESHOLD: f64 = 4.;
+
+/// What a screenshot is taken of
+#[derive(Debug, Clone)]
+pub enum ScreenshotTarget {
+    Output(Output),
+    /// Area of an output, in output local coordinates
+    Region(Output, Rectangle<i32, Local>),
+    Window(CosmicSurface),
+}
+
+pub type ScreenshotHintElement = IcedElement<ScreenshotHintInternal>;
+
+#[derive(Debug)]
+pub struct ScreenshotSelection {
+    hint: ScreenshotHintElement,
+    hint_output: Output,
+    reply: oneshot::Sender<ScreenshotResponse>,
+    /// Where the left button was pressed, if dragging
+    start: Option<Point<f64, Global>>,
+    current: Point<f64, Global>,
+}
+
+impl ScreenshotSelection {
+    pub fn new(
+        reply: oneshot::Sender<ScreenshotResponse>,
+        seat: &Seat<State>,
+        evlh: LoopHandle<'static, State>,
+        theme: cosmic::Theme,
+    ) -> ScreenshotSelection {
+        let hint_output = seat.active_output();
+        let hint = IcedElement::new(
+            ScreenshotHintInternal {
+                text: fl!("screenshot-selection-hint"),
+            },
+            (1, 1),
+            evlh,
+            theme,
+        );
+        let size = hint.minimum_size();
+        hint.resize(size);
+        hint.output_enter(&hint_output, Rectangle::from_size(size));
+
+        ScreenshotSelection {
+            hint,
+            hint_output,
+            reply,
+            start: None,
+            current: seat.get_pointer().unwrap().current_location().as_global(),
+        }
+    }
+
+    pub fn reply(self, response: ScreenshotResponse) {
+        let _ = self.reply.send(response);
+    }
+
+    /// Selected area on `output`, if any
+    pub fn area(&self, output: &Output) -> Option<Rectangle<i32, Local>> {
+        let start = self.start?;
+        let loc =
+            Point::<f64, Global>::from((start.x.min(self.current.x), start.y.min(self.current.y)));
+        let size = (
+            (start.x - self.current.x).abs(),
+            (start.y - self.current.y).abs(),
+        );
+        Rectangle::new(loc, size.into())
+            .to_i32_round()
+            .intersection(output.geometry())
+            .map(|area| area.to_local(output))
+    }
+
+    pub fn render_hint<R, C>(&self, renderer: &mut R, output: &Output) -> Vec<C>
+    where
+        C: From<<ScreenshotHintElement as AsRenderElements<R>>::RenderElement>,
+        R: Renderer + ImportMem,
+        R::TextureId: Send + Clone + 'static,
+    {
+        if output != &self.hint_output {
+            return Vec::new();
+        }
+
+        let size = self.hint.current_size().to_f64();
+        let output_geo = output.geometry().to_f64();
+        let scale = output.current_scale().fractional_scale();
+        let location =
+            Point::<f64, Logical>::from(((output_geo.size.w / 2. - size.w / 2.).max(0.), 32.))
+                .to_physical(scale)
+                .to_i32_round();
+
+        self.hint
+            .render_elements(renderer, location, scale.into(), 1.0)
+    }
+
+    /// Target of the click or drag, that just ended
+    fn target(&self, shell: &Shell, seat: &Seat<State>) -> Option<ScreenshotTarget> {
+        let start = self.start?;
+        let output = shell
+            .outputs()
+            .find(|output| output.geometry().to_f64().contains(start))?
+            .clone();
+
+        if (start.x - self.current.x).abs() >= CLICK_THRESHOLD
+            || (start.y - self.current.y).abs() >= CLICK_THRESHOLD
+        {
+            let area = self.area(&output)?;
+            return Some(ScreenshotTarget::Region(output, area));
+        }
+
+        Some(
+            match State::element_under(self.current, &output, shell, seat) {
+                Some(KeyboardFocusTarget::Element(mapped)) => {
+                    ScreenshotTarget::Window(mapped.active_window())
+                }
+                Some(KeyboardFocusTarget::Fullscreen(surface)) => ScreenshotTarget::Window(surface),
+                _ => ScreenshotTarget::Output(output),
+            },
+        )
+    }
+}
+
+pub struct ScreenshotHintInternal {
+    text: String,
+}
+
+impl Program for ScreenshotHintInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        text::body(&self.text)
+            .apply(container)
+            .padding([12, 24])
+            .class(theme::Container::custom(|theme| container::Style {
+                icon_color: Some(Color::from(theme.cosmic().background.on)),
+                text_color: Some(Color::from(theme.cosmic().background.on)),
+                background: Some(Background::Color(theme.cosmic().background.base.into())),
+                border: Border {
+                    radius: 18.0.into(),
+                    width: 0.0,
+                    color: Color::TRANSPARENT,
+                },
+                shadow: Default::default(),
+            }))
+            .width(Length::Shrink)
+            .height(Length::Shrink)
+            .into()
+    }
+}
+
+/// Pointer grab driving the [`ScreenshotSelection`] of the shell
+pub struct ScreenshotSelectionGrab {
+    seat: Seat<State>,
+    start_data: PointerGrabStartData<State>,
+}
+
+impl ScreenshotSelectionGrab {
+    pub fn new(seat: &Seat<State>) -> ScreenshotSelectionGrab {
+        let pointer = seat.get_pointer().unwrap();
+        ScreenshotSelectionGrab {
+            seat: seat.clone(),
+            start_data: PointerGrabStartData {
+                focus: None,
+                button: BTN_LEFT,
+                location: pointer.current_location(),
+            },
+        }
+    }
+
+    fn finish(&self, state: &mut State, target: Option<ScreenshotTarget>) {
+        state
+            .common
+            .event_loop_handle
+            .insert_idle(move |state| state.finish_screenshot_selection(target));
+    }
+}
+
+impl PointerGrab<State> for ScreenshotSelectionGrab {
+    fn motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While selecting, no client has pointer focus
+        handle.motion(data, None, event);
+
+        let dragging = {
+            let mut shell = data.common.shell.write();
+            let Some(selection) = shell.screenshot_selection_mut() else {
+                return;
+            };
+            selection.current = event.location.as_global();
+            selection.start.is_some()
+        };
+        if dragging {
+            data.schedule_render_all();
+        }
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, None, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &ButtonEvent,
+    ) {
+        match (event.button, event.state) {
+            (BTN_LEFT, ButtonState::Pressed) => {
+                let mut shell = data.common.shell.write();
+                if let Some(selection) = shell.screenshot_selection_mut() {
+                    selection.start = Some(selection.current);
+                }
+            }
+            (BTN_LEFT, ButtonState::Released) => {
+                let shell = data.common.shell.read();
+                let target = shell
+                    .screenshot_selection()
+                    .and_then(|selection| selection.target(&shell, &self.seat));
+                std::mem::drop(shell);
+                if target.is_some() {
+                    self.finish(data, target);
+                    handle.unset_grab(self, data, event.serial, event.time, true);
+                }
+            }
+            (BTN_RIGHT, ButtonState::Pressed) => {
+                handle.unset_grab(self, data, event.serial, event.time, true);
+            }
+            _ => {}
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details)
+    }
+
+    fn frame(&mut self, data: &mut State, handle: &mut PointerInnerHandle<'_, State>) {
+        handle.frame(data)
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event)
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event)
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event)
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event)
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event)
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event)
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event)
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event)
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<State> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, data: &mut State) {
+        // cancels, unless a target was chosen before
+        self.finish(data, None);
+    }
+}
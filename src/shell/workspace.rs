@@ -8,10 +8,10 @@ use crate::{
     },
     shell::{
         ANIMATION_DURATION, OverviewMode, SeatMoveGrabState,
-        layout::{floating::FloatingLayout, tiling::TilingLayout},
+        layout::{self, floating::FloatingLayout, tiling::TilingLayout},
     },
     state::State,
-    utils::{prelude::*, tween::EaseRectangle},
+    utils::{animation, prelude::*, tween::EaseRectangle},
     wayland::{
         handlers::screencopy::ScreencopySessions,
         protocols::{
@@ -20,7 +20,7 @@ use crate::{
         },
     },
 };
-use cosmic_comp_config::workspace::{OutputMatch, PinnedWorkspace};
+use cosmic_comp_config::workspace::{OutputMatch, PinnedWorkspace, TilingAlgorithm};
 
 use cosmic::theme::CosmicTheme;
 use cosmic_protocols::workspace::v2::server::zcosmic_workspace_handle_v2::TilingState;
@@ -86,7 +86,11 @@ fn output_match_for_output(output: &Output) -> OutputMatch {
 
 // If `disambguate` is true, check that edid *and* connector name match.
 // Otherwise, match only edid (if it exists)
-fn output_matches(output_match: &OutputMatch, output: &Output, disambiguate: bool) -> bool {
+pub(crate) fn output_matches(
+    output_match: &OutputMatch,
+    output: &Output,
+    disambiguate: bool,
+) -> bool {
     if output_match.edid.as_ref() != output.edid() {
         false
     } else if disambiguate || output_match.edid.is_none() {
@@ -103,6 +107,8 @@ pub struct Workspace {
     pub floating_layer: FloatingLayout,
     pub minimized_windows: Vec<MinimizedWindow>,
     pub tiling_enabled: bool,
+    /// Algorithm chosen for this workspace, `None` follows the configured one
+    pub tiling_algorithm: Option<TilingAlgorithm>,
     pub fullscreen: Option<FullscreenSurface>,
     pub pinned: bool,
     pub id: Option<String>,
@@ -358,9 +364,10 @@ impl Workspace {
         handle: WorkspaceHandle,
         output: Output,
         tiling_enabled: bool,
+        tiling_algorithm: TilingAlgorithm,
         theme: cosmic::Theme,
     ) -> Workspace {
-        let tiling_layer = TilingLayout::new(theme.clone(), &output);
+        let tiling_layer = TilingLayout::new(theme.clone(), &output, tiling_algorithm);
         let floating_layer = FloatingLayout::new(theme, &output);
         let output_match = output_match_for_output(&output);
 
@@ -369,6 +376,7 @@ impl Workspace {
             tiling_layer,
             floating_layer,
             tiling_enabled,
+            tiling_algorithm: None,
             minimized_windows: Vec::new(),
             fullscreen: None,
             pinned: false,
@@ -390,9 +398,14 @@ impl Workspace {
         pinned: &PinnedWorkspace,
         handle: WorkspaceHandle,
         output: Output,
+        tiling_algorithm: TilingAlgorithm,
         theme: cosmic::Theme,
     ) -> Self {
-        let tiling_layer = TilingLayout::new(theme.clone(), &output);
+        let tiling_layer = TilingLayout::new(
+            theme.clone(),
+            &output,
+            pinned.tiling_algorithm.unwrap_or(tiling_algorithm),
+        );
         let floating_layer = FloatingLayout::new(theme, &output);
         let output_match = output_match_for_output(&output);
 
@@ -401,6 +414,7 @@ impl Workspace {
             tiling_layer,
             floating_layer,
             tiling_enabled: pinned.tiling_enabled,
+            tiling_algorithm: pinned.tiling_algorithm,
             minimized_windows: Vec::new(),
             fullscreen: None,
             pinned: true,
@@ -431,6 +445,7 @@ impl Workspace {
                     edid: output.edid,
                 },
                 tiling_enabled: self.tiling_enabled,
+                tiling_algorithm: self.tiling_algorithm,
                 id: self.id.clone(),
             })
         } else {
@@ -513,7 +528,7 @@ impl Workspace {
     pub fn update_animations(&mut self) -> HashMap<ClientId, Client> {
         if let Some(f) = self.fullscreen.as_mut() {
             if let Some(start) = f.start_at.as_ref() {
-                let duration_since = Instant::now().duration_since(*start);
+                let duration_since = animation::now().duration_since(*start);
                 if duration_since > FULLSCREEN_ANIMATION_DURATION {
                     f.start_at.take();
                     self.dirty.store(true, Ordering::SeqCst);
@@ -521,7 +536,7 @@ impl Workspace {
             }
 
             if let Some(end) = f.ended_at {
-                let duration_since = Instant::now().duration_since(end);
+                let duration_since = animation::now().duration_since(end);
                 if duration_since >= FULLSCREEN_ANIMATION_DURATION {
                     let _ = self.fullscreen.take();
                     self.dirty.store(true, Ordering::SeqCst);
@@ -1008,12 +1023,12 @@ impl Workspace {
                 let f = self.fullscreen.as_mut().unwrap();
                 f.previous_geometry = Some(to);
                 f.ended_at = Some(
-                    Instant::now()
+                    animation::now()
                         - (FULLSCREEN_ANIMATION_DURATION
                             - f.start_at
                                 .take()
                                 .map(|earlier| {
-                                    Instant::now()
+                                    animation::now()
                                         .duration_since(earlier)
                                         .min(FULLSCREEN_ANIMATION_DURATION)
                                 })
@@ -1217,7 +1232,7 @@ impl Workspace {
             surface: window.clone(),
             previous_state: restore,
             previous_geometry,
-            start_at: Some(Instant::now()),
+            start_at: Some(animation::now()),
             ended_at: None,
         });
 
@@ -1253,13 +1268,13 @@ impl Workspace {
             }
 
             surface.ended_at = Some(
-                Instant::now()
+                animation::now()
                     - (FULLSCREEN_ANIMATION_DURATION
                         - surface
                             .start_at
                             .take()
                             .map(|earlier| {
-                                Instant::now()
+                                animation::now()
                                     .duration_since(earlier)
                                     .min(FULLSCREEN_ANIMATION_DURATION)
                             })
@@ -1302,6 +1317,24 @@ impl Workspace {
         }
     }
 
+    /// Switches this workspace to the next tiling algorithm, regardless of the configured one.
+    ///
+    /// Cycles from the tree to master-stack to dwindle, `default` being the configured
+    /// algorithm, which also provides the master-stack parameters if it has any.
+    pub fn cycle_tiling_algorithm(&mut self, default: TilingAlgorithm) {
+        let master_stack = match default {
+            TilingAlgorithm::MasterStack { .. } => default,
+            _ => TilingAlgorithm::DEFAULT_MASTER_STACK,
+        };
+        let next = match self.tiling_algorithm.unwrap_or(default) {
+            TilingAlgorithm::Tree => master_stack,
+            TilingAlgorithm::MasterStack { .. } => TilingAlgorithm::Dwindle,
+            TilingAlgorithm::Dwindle => TilingAlgorithm::Tree,
+        };
+        self.tiling_algorithm = Some(next);
+        self.tiling_layer.set_algorithm(next);
+    }
+
     pub fn toggle_tiling(
         &mut self,
         seat: &Seat<State>,
@@ -1325,7 +1358,10 @@ impl Workspace {
             }
 
             let focus_stack = self.focus_stack.get(seat);
-            for window in floating_windows.into_iter() {
+            for window in floating_windows
+                .into_iter()
+                .filter(|w| !layout::is_x11_auxiliary(&w.active_window()))
+            {
                 self.floating_layer.unmap(&window, None);
                 self.tiling_layer
                     .map(window, Some(focus_stack.iter()), None)
@@ -1375,7 +1411,9 @@ impl Workspace {
             if self.tiling_layer.mapped().any(|(m, _)| m == window) {
                 let _ = self.tiling_layer.unmap(window, None);
                 self.floating_layer.map(window.clone(), None);
-            } else if self.floating_layer.mapped().any(|w| w == window) {
+            } else if self.floating_layer.mapped().any(|w| w == window)
+                && !layout::is_x11_auxiliary(&window.active_window())
+            {
                 let focus_stack = self.focus_stack.get(seat);
                 self.floating_layer.unmap(window, None);
                 self.tiling_layer
@@ -1530,7 +1568,7 @@ impl Workspace {
 
             let (target_geo, alpha) = match (fullscreen.start_at, fullscreen.ended_at) {
                 (Some(started), _) => {
-                    let duration = Instant::now().duration_since(started).as_secs_f64()
+                    let duration = animation::now().duration_since(started).as_secs_f64()
                         / FULLSCREEN_ANIMATION_DURATION.as_secs_f64();
                     (
                         ease(
@@ -1544,7 +1582,7 @@ impl Workspace {
                     )
                 }
                 (_, Some(ended)) => {
-                    let duration = Instant::now().duration_since(ended).as_secs_f64()
+                    let duration = animation::now().duration_since(ended).as_secs_f64()
                         / FULLSCREEN_ANIMATION_DURATION.as_secs_f64();
                     (
                         ease(
@@ -1610,14 +1648,14 @@ impl Workspace {
             // floating surfaces
             let alpha = match &overview.0 {
                 OverviewMode::Started(_, started) => {
-                    (1.0 - (Instant::now().duration_since(*started).as_millis()
+                    (1.0 - (animation::now().duration_since(*started).as_millis()
                         / ANIMATION_DURATION.as_millis()) as f32)
                         .max(0.0)
                         * 0.4
                         + 0.6
                 }
                 OverviewMode::Ended(_, ended) => {
-                    ((Instant::now().duration_since(*ended).as_millis()
+                    ((animation::now().duration_since(*ended).as_millis()
                         / ANIMATION_DURATION.as_millis()) as f32)
                         * 0.4
                         + 0.6
@@ -1648,12 +1686,12 @@ impl Workspace {
 
             let alpha = match &overview.0 {
                 OverviewMode::Started(_, start) => Some(
-                    (Instant::now().duration_since(*start).as_millis() as f64 / 100.0).min(1.0)
+                    (animation::now().duration_since(*start).as_millis() as f64 / 100.0).min(1.0)
                         as f32,
                 ),
                 OverviewMode::Active(_) => Some(1.0),
                 OverviewMode::Ended(_, ended) => Some(
-                    1.0 - (Instant::now().duration_since(*ended).as_millis() as f64 / 100.0)
+                    1.0 - (animation::now().duration_since(*ended).as_millis() as f64 / 100.0)
                         .min(1.0) as f32,
                 ),
                 OverviewMode::None => None,
@@ -1731,7 +1769,7 @@ impl Workspace {
 
             let (target_geo, alpha) = match (fullscreen.start_at, fullscreen.ended_at) {
                 (Some(started), _) => {
-                    let duration = Instant::now().duration_since(started).as_secs_f64()
+                    let duration = animation::now().duration_since(started).as_secs_f64()
                         / FULLSCREEN_ANIMATION_DURATION.as_secs_f64();
                     (
                         ease(
@@ -1745,7 +1783,7 @@ impl Workspace {
                     )
                 }
                 (_, Some(ended)) => {
-                    let duration = Instant::now().duration_since(ended).as_secs_f64()
+                    let duration = animation::now().duration_since(ended).as_secs_f64()
                         / FULLSCREEN_ANIMATION_DURATION.as_secs_f64();
                     (
                         ease(
@@ -1791,14 +1829,14 @@ impl Workspace {
             // floating surfaces
             let alpha = match &overview.0 {
                 OverviewMode::Started(_, started) => {
-                    (1.0 - (Instant::now().duration_since(*started).as_millis()
+                    (1.0 - (animation::now().duration_since(*started).as_millis()
                         / ANIMATION_DURATION.as_millis()) as f32)
                         .max(0.0)
                         * 0.4
                         + 0.6
                 }
                 OverviewMode::Ended(_, ended) => {
-                    ((Instant::now().duration_since(*ended).as_millis()
+                    ((animation::now().duration_since(*ended).as_millis()
                         / ANIMATION_DURATION.as_millis()) as f32)
                         * 0.4
                         + 0.6
@@ -34,6 +34,7 @@ use tracing::error;
 use crate::{
     state::State,
     utils::{
+        animation,
         float::NextDown,
         iced::{IcedElement, Program},
         prelude::*,
@@ -127,7 +128,7 @@ impl OutputZoomState {
 
     pub fn animating_focal_point(&mut self) -> Point<f64, Local> {
         if let Some((old_point, start)) = self.previous_point.as_ref() {
-            let duration_since = Instant::now().duration_since(*start);
+            let duration_since = animation::now().duration_since(*start);
             if duration_since > ANIMATION_DURATION {
                 self.previous_point.take();
                 return self.focal_point;
@@ -157,7 +158,7 @@ impl OutputZoomState {
 
     pub fn animating_level(&self) -> f64 {
         if let Some((old_level, start)) = self.previous_level.as_ref() {
-            let percentage = Instant::now().duration_since(*start).as_millis() as f32
+            let percentage = animation::now().duration_since(*start).as_millis() as f32
                 / ANIMATION_DURATION.as_millis() as f32;
 
             ease(EaseInOutCubic, *old_level, self.level, percentage)
@@ -174,7 +175,7 @@ impl OutputZoomState {
         if self
             .previous_level
             .as_ref()
-            .is_some_and(|(_, start)| Instant::now().duration_since(*start) > ANIMATION_DURATION)
+            .is_some_and(|(_, start)| animation::now().duration_since(*start) > ANIMATION_DURATION)
         {
             self.previous_level.take();
         }
@@ -183,7 +184,7 @@ impl OutputZoomState {
     }
 
     pub fn update(&mut self, level: f64, animate: bool, movement: ZoomMovement, increment: u32) {
-        self.previous_level = animate.then_some((self.animating_level(), Instant::now()));
+        self.previous_level = animate.then_some((self.animating_level(), animation::now()));
         self.level = level;
         self.element.set_additional_scale(level.min(4.));
         self.element.queue_message(ZoomMessage::Update {
@@ -270,7 +271,8 @@ impl ZoomState {
 
         // animate movement type changes
         if self.movement != movement {
-            output_state_ref.previous_point = Some((output_state_ref.focal_point, Instant::now()));
+            output_state_ref.previous_point =
+                Some((output_state_ref.focal_point, animation::now()));
             self.movement = movement;
         }
 
@@ -300,7 +302,7 @@ impl ZoomState {
                         output_geometry.loc.y + output_geometry.size.h - 1,
                     );
                     output_state_ref.previous_point =
-                        Some((output_state_ref.focal_point, Instant::now()));
+                        Some((output_state_ref.focal_point, animation::now()));
                     output_state_ref.focal_point = focal_point.to_local(output).to_f64();
                 } else if !zoomed_output_geometry.contains(cursor_position.to_global(output)) {
                     let mut diff = output_state_ref.focal_point.to_global(output)
@@ -2,6 +2,7 @@
 
 use crate::{
     backend::{
+        headless::HeadlessState,
         kms::{KmsGuard, KmsState},
         render::{GlMultiError, RendererRef},
         winit::WinitState,
@@ -9,22 +10,42 @@ use crate::{
     },
     config::{CompOutputConfig, Config, ScreenFilter},
     dbus::a11y_keyboard_monitor::A11yKeyboardMonitorState,
-    input::{PointerFocusState, gestures::GestureState},
+    idle::IdleState,
+    input::{
+        PointerFocusState, dwell_click::DwellClickState, gestures::GestureState,
+        mouse_keys::MouseKeysState,
+    },
     shell::{CosmicSurface, SeatExt, Shell, grabs::SeatMoveGrabState},
     utils::prelude::OutputExt,
     wayland::{
-        handlers::{data_device::get_dnd_icon, screencopy::SessionHolder},
+        audit,
+        consent::{self, ConsentState},
+        handlers::{
+            compositor::{client_compositor_state, signal_frame_barriers},
+            data_device::get_dnd_icon,
+            screencopy::SessionHolder,
+            xdg_shell::ping::PingState,
+        },
+        policy::PolicyState,
         protocols::{
             a11y::A11yState,
+            background_effect::BackgroundEffectState,
+            color_management::ColorManagementState,
+            color_representation::ColorRepresentationState,
             corner_radius::CornerRadiusState,
             drm::WlDrmState,
+            gamma_control::GammaControlState,
             image_capture_source::ImageCaptureSourceState,
             output_configuration::OutputConfigurationState,
             output_power::OutputPowerState,
             overlap_notify::OverlapNotifyState,
+            pointer_warp::PointerWarpState,
             screencopy::ScreencopyState,
+            session_management::SessionManagementState,
             toplevel_info::ToplevelInfoState,
             toplevel_management::{ManagementCapabilities, ToplevelManagementState},
+            toplevel_tag::ToplevelTagState,
+            virtual_pointer::VirtualPointerManagerState,
             workspace::{WorkspaceState, WorkspaceUpdateGuard},
         },
     },
@@ -32,7 +53,10 @@ use crate::{
 };
 use anyhow::Context;
 use calloop::RegistrationToken;
-use cosmic_comp_config::output::comp::{OutputConfig, OutputState};
+use cosmic_comp_config::{
+    output::comp::{OutputConfig, OutputState},
+    policy::PrivilegedProtocol,
+};
 use futures_executor::ThreadPool;
 use i18n_embed::{
     DesktopLanguageRequester,
@@ -74,9 +98,12 @@ use smithay::{
     utils::{Clock, Monotonic, Point},
     wayland::{
         alpha_modifier::AlphaModifierState,
+        commit_timing::CommitTimingManagerState,
         compositor::{CompositorClientState, CompositorState, SurfaceData},
+        content_type::ContentTypeState,
         cursor_shape::CursorShapeManagerState,
         dmabuf::{DmabufFeedback, DmabufGlobal, DmabufState},
+        fifo::FifoManagerState,
         fractional_scale::{FractionalScaleManagerState, with_fractional_scale},
         idle_inhibit::IdleInhibitManagerState,
         idle_notify::IdleNotifierState,
@@ -103,11 +130,13 @@ use smithay::{
         shm::ShmState,
         single_pixel_buffer::SinglePixelBufferState,
         tablet_manager::TabletManagerState,
+        tearing_control::TearingControlState,
         text_input::TextInputManagerState,
         viewporter::ViewporterState,
         virtual_keyboard::VirtualKeyboardManagerState,
         xdg_activation::XdgActivationState,
         xdg_foreign::XdgForeignState,
+        xdg_system_bell::XdgSystemBellState,
         xwayland_keyboard_grab::XWaylandKeyboardGrabState,
         xwayland_shell::XWaylandShellState,
     },
@@ -123,6 +152,8 @@ use std::{
     cmp::min,
     collections::HashSet,
     ffi::OsString,
+    os::{fd::AsRawFd, unix::net::UnixStream},
+    path::Path,
     process::Child,
     sync::{Arc, LazyLock, Once, atomic::AtomicBool},
     time::{Duration, Instant},
@@ -151,23 +182,32 @@ pub struct ClientState {
     pub advertised_drm_node: Option<DrmNode>,
     pub evls: LoopSignal,
     pub security_context: Option<SecurityContext>,
+    /// Pid of the connecting process, if known
+    pub pid: Option<u32>,
+    /// Sandbox engine of the connecting process, for sandboxed clients connecting to the
+    /// socket directly instead of through a security context
+    pub detected_sandbox: Option<&'static str>,
 }
 
 impl ClientState {
     /// We treat a client as "sandboxed" if it has a security context for any sandbox engine
-    /// other than `com.system76.CosmicPanel`
+    /// other than `com.system76.CosmicPanel`, or its process runs in a sandbox
     pub fn not_sandboxed(&self) -> bool {
-        self.security_context
-            .as_ref()
-            .is_none_or(|security_context| {
-                security_context.sandbox_engine.as_deref() == Some("com.system76.CosmicPanel")
-            })
+        self.detected_sandbox.is_none()
+            && self
+                .security_context
+                .as_ref()
+                .is_none_or(|security_context| {
+                    security_context.sandbox_engine.as_deref() == Some("com.system76.CosmicPanel")
+                })
     }
 }
 
 impl ClientData for ClientState {
     fn initialized(&self, _client_id: ClientId) {}
-    fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {
+    fn disconnected(&self, client_id: ClientId, _reason: DisconnectReason) {
+        consent::forget_client(&client_id);
+        audit::forget_client(&client_id);
         self.evls.wakeup();
     }
 }
@@ -222,6 +262,8 @@ pub struct Common {
     pub should_stop: bool,
     pub local_offset: time::UtcOffset,
     pub gesture_state: Option<GestureState>,
+    pub mouse_keys_state: MouseKeysState,
+    pub dwell_click_state: DwellClickState,
 
     pub kiosk_child: Option<Child>,
     pub theme: cosmic::Theme,
@@ -236,6 +278,7 @@ pub struct Common {
     pub output_state: OutputManagerState,
     pub output_configuration_state: OutputConfigurationState<State>,
     pub output_power_state: OutputPowerState,
+    pub gamma_control_state: GammaControlState,
     pub presentation_state: PresentationState,
     pub primary_selection_state: PrimarySelectionState,
     pub ext_data_control_state: ExtDataControlState,
@@ -244,9 +287,13 @@ pub struct Common {
     pub screencopy_state: ScreencopyState,
     pub seat_state: SeatState<State>,
     pub session_lock_manager_state: SessionLockManagerState,
+    pub session_management_state: SessionManagementState,
     pub idle_notifier_state: IdleNotifierState<State>,
     pub idle_inhibit_manager_state: IdleInhibitManagerState,
     pub idle_inhibiting_surfaces: HashSet<WlSurface>,
+    pub idle_state: IdleState,
+    pub policy_state: PolicyState,
+    pub consent_state: ConsentState,
     pub shm_state: ShmState,
     pub cursor_shape_manager_state: CursorShapeManagerState,
     pub wl_drm_state: WlDrmState<Option<DrmNode>>,
@@ -259,6 +306,7 @@ pub struct Common {
 
     // shell-related wayland state
     pub xdg_shell_state: XdgShellState,
+    pub ping_state: PingState,
     pub layer_shell_state: WlrLayerShellState,
     pub toplevel_info_state: ToplevelInfoState<State, CosmicSurface>,
     pub toplevel_management_state: ToplevelManagementState,
@@ -272,6 +320,15 @@ pub struct Common {
 
     #[cfg(feature = "systemd")]
     pub inhibit_lid_fd: Option<OwnedFd>,
+    /// Delay inhibitor, held to lock the session before suspending
+    #[cfg(feature = "systemd")]
+    pub inhibit_sleep_fd: Option<OwnedFd>,
+    /// Releases `inhibit_sleep_fd`, if the lock screen doesn't show up in time
+    #[cfg(feature = "systemd")]
+    pub sleep_lock_timeout: Option<RegistrationToken>,
+    /// Delay inhibitor, held to save the session state before shutting down
+    #[cfg(feature = "systemd")]
+    pub inhibit_shutdown_fd: Option<OwnedFd>,
 }
 
 #[derive(Debug)]
@@ -279,6 +336,7 @@ pub enum BackendData {
     X11(X11State),
     Winit(WinitState),
     Kms(KmsState),
+    Headless(HeadlessState),
     // TODO
     // Wayland(WaylandState),
     Unset,
@@ -288,6 +346,7 @@ pub enum LockedBackend<'a> {
     X11(&'a mut X11State),
     Winit(&'a mut WinitState),
     Kms(KmsGuard<'a>),
+    Headless(&'a mut HeadlessState),
 }
 
 #[derive(Debug, Clone)]
@@ -331,6 +390,13 @@ impl BackendData {
         }
     }
 
+    pub fn headless(&mut self) -> &mut HeadlessState {
+        match self {
+            BackendData::Headless(headless_state) => headless_state,
+            _ => unreachable!("Called headless in non headless backend"),
+        }
+    }
+
     pub fn schedule_render(&mut self, output: &Output) {
         match self {
             BackendData::Winit(_) => {} // We cannot do this on the winit backend.
@@ -338,6 +404,7 @@ impl BackendData {
             // Swapping with damage (which should be empty on these frames) is likely good enough anyway.
             BackendData::X11(state) => state.schedule_render(output),
             BackendData::Kms(state) => state.schedule_render(output),
+            BackendData::Headless(state) => state.schedule_render(output),
             _ => unreachable!("No backend was initialized"),
         }
     }
@@ -358,6 +425,9 @@ impl BackendData {
             BackendData::X11(state) => {
                 state.renderer.import_dmabuf(&dmabuf, None)?;
             }
+            BackendData::Headless(state) => {
+                state.renderer.import_dmabuf(&dmabuf, None)?;
+            }
             _ => unreachable!("No backend set when importing dmabuf"),
         };
         Ok(None)
@@ -391,6 +461,7 @@ impl BackendData {
             }
             BackendData::Winit(winit) => Ok(RendererRef::Glow(winit.backend.renderer())),
             BackendData::X11(x11) => Ok(RendererRef::Glow(&mut x11.renderer)),
+            BackendData::Headless(headless) => Ok(RendererRef::Glow(&mut headless.renderer)),
             _ => unreachable!("No backend set when getting offscreen renderer"),
         }
     }
@@ -400,6 +471,7 @@ impl BackendData {
             BackendData::Kms(state) => state.update_screen_filter(screen_filter),
             BackendData::Winit(state) => state.update_screen_filter(screen_filter),
             BackendData::X11(state) => state.update_screen_filter(screen_filter),
+            BackendData::Headless(state) => state.update_screen_filter(screen_filter),
             _ => unreachable!("No backend set when setting screen filters"),
         }
     }
@@ -409,6 +481,7 @@ impl BackendData {
             BackendData::Kms(state) => LockedBackend::Kms(state.lock_devices()),
             BackendData::X11(state) => LockedBackend::X11(state),
             BackendData::Winit(state) => LockedBackend::Winit(state),
+            BackendData::Headless(state) => LockedBackend::Headless(state),
             _ => unreachable!("Tried to lock unset backend"),
         }
     }
@@ -420,6 +493,7 @@ impl LockedBackend<'_> {
             LockedBackend::Kms(state) => state.all_outputs(),
             LockedBackend::X11(state) => state.all_outputs(),
             LockedBackend::Winit(state) => state.all_outputs(),
+            LockedBackend::Headless(state) => state.all_outputs(),
         }
     }
 
@@ -507,6 +581,7 @@ impl LockedBackend<'_> {
             ),
             LockedBackend::Winit(state) => state.apply_config_for_outputs(test_only),
             LockedBackend::X11(state) => state.apply_config_for_outputs(test_only),
+            LockedBackend::Headless(state) => state.apply_config_for_outputs(test_only),
         }?;
 
         let mut shell_ref = shell.write();
@@ -554,6 +629,7 @@ impl LockedBackend<'_> {
                 // Swapping with damage (which should be empty on these frames) is likely good enough anyway.
                 LockedBackend::X11(state) => state.schedule_render(&output),
                 LockedBackend::Kms(state) => state.schedule_render(&output),
+                LockedBackend::Headless(state) => state.schedule_render(&output),
             }
         }
 
@@ -590,6 +666,43 @@ pub fn client_has_no_security_context(client: &Client) -> bool {
         .is_none_or(|client_state| client_state.security_context.is_none())
 }
 
+/// Whether the client's process runs on the host, so its credentials can be trusted
+pub fn client_runs_on_host(client: &Client) -> bool {
+    client.get_data::<ClientState>().is_none_or(|client_state| {
+        client_state.security_context.is_none() && client_state.detected_sandbox.is_none()
+    })
+}
+
+/// Detects the sandbox engine `pid` is confined by, from what the engines expose in `/proc`
+fn detect_sandbox(pid: u32) -> Option<&'static str> {
+    // flatpak places its metadata at the root of the sandbox
+    if Path::new(&format!("/proc/{pid}/root/.flatpak-info")).exists() {
+        return Some("org.flatpak");
+    }
+    // snaps are confined by apparmor profiles named after the snap
+    let label = std::fs::read_to_string(format!("/proc/{pid}/attr/current")).ok()?;
+    label.starts_with("snap.").then_some("io.snapcraft")
+}
+
+fn peer_pid(stream: &UnixStream) -> Option<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (ret == 0 && cred.pid > 0).then_some(cred.pid as u32)
+}
+
 fn client_not_sandboxed(client: &Client) -> bool {
     client
         .get_data::<ClientState>()
@@ -611,6 +724,8 @@ impl State {
         let local_offset = UtcOffset::current_local_offset().expect("No yet multithreaded");
         let clock = Clock::new();
         let config = Config::load(&handle);
+        let policy_state = PolicyState::new(&config.cosmic_conf.privileged_protocols);
+        let consent_state = ConsentState::new(dh, &handle, &policy_state);
         let compositor_state = CompositorState::new::<Self>(dh);
         let corner_radius_state = CornerRadiusState::new::<Self>(dh);
         let data_device_state = DataDeviceState::new::<Self>(dh);
@@ -618,16 +733,39 @@ impl State {
         let fractional_scale_state = FractionalScaleManagerState::new::<State>(dh);
         let keyboard_shortcuts_inhibit_state = KeyboardShortcutsInhibitState::new::<Self>(dh);
         let output_state = OutputManagerState::new_with_xdg_output::<Self>(dh);
-        let output_configuration_state =
-            OutputConfigurationState::new(dh, handle.clone(), client_not_sandboxed);
-        let output_power_state = OutputPowerState::new::<Self, _>(dh, client_not_sandboxed);
+        let output_configuration_state = OutputConfigurationState::new(
+            dh,
+            handle.clone(),
+            policy_state.filter(PrivilegedProtocol::OutputManagement, client_not_sandboxed),
+        );
+        let output_power_state = OutputPowerState::new::<Self, _>(
+            dh,
+            policy_state.filter(PrivilegedProtocol::OutputManagement, client_not_sandboxed),
+        );
+        let gamma_control_state = GammaControlState::new::<Self, _>(
+            dh,
+            policy_state.filter(PrivilegedProtocol::OutputManagement, client_not_sandboxed),
+        );
         let overlap_notify_state =
             OverlapNotifyState::new::<Self, _>(dh, client_has_no_security_context);
         let presentation_state = PresentationState::new::<Self>(dh, clock.id() as u32);
         let primary_selection_state = PrimarySelectionState::new::<Self>(dh);
-        let image_capture_source_state =
-            ImageCaptureSourceState::new::<Self, _>(dh, client_not_sandboxed);
-        let screencopy_state = ScreencopyState::new::<Self, _>(dh, client_not_sandboxed);
+        let image_capture_source_state = ImageCaptureSourceState::new::<Self, _>(
+            dh,
+            consent_state.filter(
+                PrivilegedProtocol::Screencopy,
+                &policy_state,
+                client_not_sandboxed,
+            ),
+        );
+        let screencopy_state = ScreencopyState::new::<Self, _>(
+            dh,
+            consent_state.filter(
+                PrivilegedProtocol::Screencopy,
+                &policy_state,
+                client_not_sandboxed,
+            ),
+        );
         let shm_state =
             ShmState::new::<Self>(dh, vec![wl_shm::Format::Xbgr8888, wl_shm::Format::Abgr8888]);
         let cursor_shape_manager_state = CursorShapeManagerState::new::<State>(dh);
@@ -646,26 +784,62 @@ impl State {
         SecurityContextState::new::<Self, _>(dh, client_has_no_security_context);
         InputMethodManagerState::new::<Self, _>(dh, client_not_sandboxed);
         TextInputManagerState::new::<Self>(dh);
-        VirtualKeyboardManagerState::new::<State, _>(dh, client_not_sandboxed);
+        VirtualKeyboardManagerState::new::<State, _>(
+            dh,
+            consent_state.filter(
+                PrivilegedProtocol::VirtualInput,
+                &policy_state,
+                client_not_sandboxed,
+            ),
+        );
+        VirtualPointerManagerState::new::<State, _>(
+            dh,
+            consent_state.filter(
+                PrivilegedProtocol::VirtualInput,
+                &policy_state,
+                client_not_sandboxed,
+            ),
+        );
         AlphaModifierState::new::<Self>(dh);
         SinglePixelBufferState::new::<Self>(dh);
+        TearingControlState::new::<Self>(dh);
+        ContentTypeState::new::<Self>(dh);
+        FifoManagerState::new::<Self>(dh);
+        CommitTimingManagerState::new::<Self>(dh);
+        ColorManagementState::new::<Self>(dh);
+        ColorRepresentationState::new::<Self>(dh);
+        BackgroundEffectState::new::<Self, _>(dh, client_not_sandboxed);
+        ToplevelTagState::new::<Self>(dh);
+        XdgSystemBellState::new::<Self>(dh);
+        PointerWarpState::new::<Self>(dh);
+        let session_management_state = SessionManagementState::new::<Self>(dh);
 
         let idle_notifier_state = IdleNotifierState::<Self>::new(dh, handle.clone());
         let idle_inhibit_manager_state = IdleInhibitManagerState::new::<State>(dh);
         let idle_inhibiting_surfaces = HashSet::new();
+        let idle_state = IdleState::new();
 
         let ext_data_control_state = ExtDataControlState::new::<Self, _>(
             dh,
             Some(&primary_selection_state),
-            client_not_sandboxed,
+            consent_state.filter(
+                PrivilegedProtocol::DataControl,
+                &policy_state,
+                client_not_sandboxed,
+            ),
         );
         let wlr_data_control_state = WlrDataControlState::new::<Self, _>(
             dh,
             Some(&primary_selection_state),
-            client_not_sandboxed,
+            consent_state.filter(
+                PrivilegedProtocol::DataControl,
+                &policy_state,
+                client_not_sandboxed,
+            ),
         );
 
         let shell = Arc::new(parking_lot::RwLock::new(Shell::new(&config)));
+        let theme = crate::theme::compositor_theme(config.cosmic_conf.high_contrast);
 
         let layer_shell_state =
             WlrLayerShellState::new_with_filter::<State, _>(dh, client_not_sandboxed);
@@ -680,7 +854,10 @@ impl State {
         );
         let xdg_activation_state = XdgActivationState::new::<State>(dh);
         let xdg_foreign_state = XdgForeignState::new::<State>(dh);
-        let toplevel_info_state = ToplevelInfoState::new(dh, client_not_sandboxed);
+        let toplevel_info_state = ToplevelInfoState::new(
+            dh,
+            policy_state.filter(PrivilegedProtocol::ToplevelManagement, client_not_sandboxed),
+        );
         let toplevel_management_state = ToplevelManagementState::new::<State, _>(
             dh,
             vec![
@@ -690,20 +867,34 @@ impl State {
                 ManagementCapabilities::Minimize,
                 ManagementCapabilities::MoveToWorkspace,
             ],
-            client_not_sandboxed,
+            policy_state.filter(PrivilegedProtocol::ToplevelManagement, client_not_sandboxed),
+        );
+        let workspace_state = WorkspaceState::new(
+            dh,
+            policy_state.filter(
+                PrivilegedProtocol::WorkspaceManagement,
+                client_not_sandboxed,
+            ),
         );
-        let workspace_state = WorkspaceState::new(dh, client_not_sandboxed);
 
         let async_executor = ThreadPool::builder().pool_size(1).create().unwrap();
 
-        if let Err(err) = crate::dbus::init(&handle, &async_executor) {
+        if let Err(err) = crate::dbus::init(&handle, &async_executor, &policy_state) {
             tracing::warn!(?err, "Failed to initialize dbus handlers");
         }
+        crate::dbus::a11y_focus::set_enabled(
+            config.cosmic_conf.focus_highlight.enabled,
+            &async_executor,
+        );
+        crate::utils::animation::set_speed(config.cosmic_conf.animation_speed);
+        crate::backend::kms::set_allow_tearing(config.cosmic_conf.allow_tearing);
 
         let a11y_state = A11yState::new::<State, _>(dh, client_not_sandboxed);
 
         let a11y_keyboard_monitor_state = A11yKeyboardMonitorState::new(&async_executor);
 
+        let ping_state = PingState::new(&handle);
+
         State {
             common: Common {
                 config,
@@ -722,9 +913,11 @@ impl State {
                 startup_done: Arc::new(AtomicBool::new(false)),
                 should_stop: false,
                 gesture_state: None,
+                mouse_keys_state: MouseKeysState::default(),
+                dwell_click_state: DwellClickState::default(),
 
                 kiosk_child: None,
-                theme: cosmic::theme::system_preference(),
+                theme,
 
                 compositor_state,
                 corner_radius_state,
@@ -734,16 +927,21 @@ impl State {
                 idle_notifier_state,
                 idle_inhibit_manager_state,
                 idle_inhibiting_surfaces,
+                idle_state,
+                policy_state,
+                consent_state,
                 image_capture_source_state,
                 screencopy_state,
                 shm_state,
                 cursor_shape_manager_state,
                 seat_state,
                 session_lock_manager_state,
+                session_management_state,
                 keyboard_shortcuts_inhibit_state,
                 output_state,
                 output_configuration_state,
                 output_power_state,
+                gamma_control_state,
                 overlap_notify_state,
                 presentation_state,
                 primary_selection_state,
@@ -754,6 +952,7 @@ impl State {
                 kde_decoration_state,
                 xdg_decoration_state,
                 xdg_shell_state,
+                ping_state,
                 layer_shell_state,
                 toplevel_info_state,
                 toplevel_management_state,
@@ -769,6 +968,12 @@ impl State {
 
                 #[cfg(feature = "systemd")]
                 inhibit_lid_fd: None,
+                #[cfg(feature = "systemd")]
+                inhibit_sleep_fd: None,
+                #[cfg(feature = "systemd")]
+                sleep_lock_timeout: None,
+                #[cfg(feature = "systemd")]
+                inhibit_shutdown_fd: None,
             },
             backend: BackendData::Unset,
             ready: Once::new(),
@@ -776,7 +981,12 @@ impl State {
         }
     }
 
-    pub fn new_client_state(&self) -> ClientState {
+    pub fn new_client_state(&self, stream: &UnixStream) -> ClientState {
+        let pid = peer_pid(stream);
+        let detected_sandbox = pid.and_then(detect_sandbox);
+        if let Some(sandbox_engine) = detected_sandbox {
+            tracing::debug!(pid, sandbox_engine, "Client connected from a sandbox");
+        }
         ClientState {
             compositor_client_state: CompositorClientState::default(),
             advertised_drm_node: match &self.backend {
@@ -785,14 +995,88 @@ impl State {
             },
             evls: self.common.event_loop_signal.clone(),
             security_context: None,
+            pid,
+            detected_sandbox,
+        }
+    }
+
+    /// Disables the internal output, while the lid is closed, moving its workspaces to
+    /// the remaining outputs, and restores its configuration once opened.
+    #[cfg(feature = "systemd")]
+    pub fn set_lid_closed(&mut self, closed: bool) {
+        use tracing::{error, warn};
+
+        let backend = self.backend.lock();
+        let output = backend
+            .all_outputs()
+            .iter()
+            .find(|o| o.is_internal())
+            .cloned();
+
+        if closed {
+            backend.disable_internal_output(&mut self.common.output_configuration_state);
+        } else {
+            backend.enable_internal_output(&mut self.common.output_configuration_state);
+        }
+        std::mem::drop(backend);
+
+        if let Err(err) = self.refresh_output_config() {
+            if !closed {
+                warn!(?err, "Failed to re-enable internal connector");
+                if let Some(output) = output {
+                    output.config_mut().enabled = OutputState::Disabled;
+                    if let Err(err) = self.refresh_output_config() {
+                        error!("Unrecoverable output configuration error: {}", err);
+                    }
+                }
+            } else {
+                // Disabling an output should never fail.
+                error!("Unrecoverable output configuration error: {}", err);
+            }
+        }
+    }
+
+    /// Handles logind's `PrepareForShutdown`.
+    ///
+    /// Before rebooting or powering off, pinned workspaces and the state of toplevels managed
+    /// through session management are written out and pending config writes are flushed,
+    /// before the shutdown inhibitor is released.
+    #[cfg(feature = "systemd")]
+    pub fn prepare_for_shutdown(&mut self, start: bool) {
+        use tracing::{debug, warn};
+
+        if !start {
+            // taken again, if the shutdown was cancelled
+            if self.common.inhibit_shutdown_fd.is_none()
+                && matches!(self.backend, BackendData::Kms(_))
+            {
+                match crate::dbus::logind::inhibit_shutdown() {
+                    Ok(fd) => self.common.inhibit_shutdown_fd = Some(fd),
+                    Err(err) => warn!(?err, "Failed to inhibit shutdown"),
+                }
+            }
+            return;
         }
+
+        let Some(_fd) = self.common.inhibit_shutdown_fd.take() else {
+            return;
+        };
+
+        let shell = self.common.shell.read();
+        shell.workspaces.persist(&self.common.config);
+        std::mem::drop(shell);
+        self.save_toplevel_sessions();
+
+        crate::config::flush_writes();
+        debug!("Session state saved, continuing to shut down");
+        // drop _fd
     }
 
     fn update_inhibitor_locks(&mut self) {
         #[cfg(feature = "systemd")]
         {
             use smithay::backend::session::Session;
-            use tracing::{debug, error, warn};
+            use tracing::{debug, error};
 
             let outputs = self.backend.lock().all_outputs();
             let is_active = match &self.backend {
@@ -809,43 +1093,8 @@ impl State {
                         Ok(fd) => {
                             debug!("Inhibiting lid switch");
                             self.common.inhibit_lid_fd = Some(fd);
-
-                            let backend = self.backend.lock();
-                            let output = backend
-                                .all_outputs()
-                                .iter()
-                                .find(|o| o.is_internal())
-                                .cloned();
                             let closed = crate::dbus::logind::lid_closed().unwrap_or(false);
-
-                            if closed {
-                                backend.disable_internal_output(
-                                    &mut self.common.output_configuration_state,
-                                );
-                            } else {
-                                backend.enable_internal_output(
-                                    &mut self.common.output_configuration_state,
-                                );
-                            }
-                            std::mem::drop(backend);
-
-                            if let Err(err) = self.refresh_output_config() {
-                                if !closed {
-                                    warn!(?err, "Failed to re-enable internal connector");
-                                    if let Some(output) = output {
-                                        output.config_mut().enabled = OutputState::Disabled;
-                                        if let Err(err) = self.refresh_output_config() {
-                                            error!(
-                                                "Unrecoverable output configuration error: {}",
-                                                err
-                                            );
-                                        }
-                                    }
-                                } else {
-                                    // Disabling an output should never fail.
-                                    error!("Unrecoverable output configuration error: {}", err);
-                                }
-                            }
+                            self.set_lid_closed(closed);
                         }
                         Err(err) => {
                             error!("Failed to inhibit lid switch: {}", err);
@@ -854,29 +1103,24 @@ impl State {
                 }
             } else if let Some(_fd) = self.common.inhibit_lid_fd.take() {
                 debug!("Removing inhibitor-lock on lid switch");
-
-                let backend = self.backend.lock();
-                let output = backend
-                    .all_outputs()
-                    .iter()
-                    .find(|o| o.is_internal())
-                    .cloned();
-                backend.enable_internal_output(&mut self.common.output_configuration_state);
-                std::mem::drop(backend);
-
-                if let Err(err) = self.refresh_output_config() {
-                    warn!(?err, "Failed to re-enable internal connector");
-                    if let Some(output) = output {
-                        output.config_mut().enabled = OutputState::Disabled;
-                        if let Err(err) = self.refresh_output_config() {
-                            error!("Unrecoverable output configuration error: {}", err);
-                        }
-                    }
-                }
+                self.set_lid_closed(false);
                 // drop _fd
             }
         }
     }
+    /// Schedules a render of all outputs, e.g. after changing how all windows are drawn
+    pub fn schedule_render_all(&mut self) {
+        let outputs = self
+            .common
+            .shell
+            .read()
+            .outputs()
+            .cloned()
+            .collect::<Vec<_>>();
+        for output in &outputs {
+            self.backend.schedule_render(output);
+        }
+    }
 }
 
 fn primary_scanout_output_compare<'a>(
@@ -1204,6 +1448,7 @@ impl Common {
     #[profiling::function]
     pub fn send_frames(&self, output: &Output, sequence: Option<usize>) {
         let time = self.clock.now();
+        let unblocked_clients = RefCell::new(Vec::<Client>::new());
         let should_send = |surface: &WlSurface, states: &SurfaceData| {
             // Do the standard primary scanout output check. For pointer surfaces it deduplicates
             // the frame callbacks across potentially multiple outputs, and for regular windows and
@@ -1213,6 +1458,16 @@ impl Common {
                 return None;
             }
 
+            // fifo and commit-timing commits are paced by the primary output as well
+            if signal_frame_barriers(states, time) {
+                if let Some(client) = surface.client() {
+                    let mut clients = unblocked_clients.borrow_mut();
+                    if !clients.contains(&client) {
+                        clients.push(client);
+                    }
+                }
+            }
+
             let Some(sequence) = sequence else {
                 return Some(output.clone());
             };
@@ -1364,5 +1619,15 @@ impl Common {
         for layer_surface in map.layers() {
             layer_surface.send_frame(output, time, THROTTLE, should_send);
         }
+
+        let clients = unblocked_clients.into_inner();
+        if !clients.is_empty() {
+            self.event_loop_handle.insert_idle(move |state| {
+                let dh = state.common.display_handle.clone();
+                for client in clients {
+                    client_compositor_state(&client).blocker_cleared(state, &dh);
+                }
+            });
+        }
     }
 }
@@ -1,35 +1,50 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::Common;
 use libsystemd::daemon::{NotifyState, booted, notify};
-use std::process::Command;
 use tracing::{error, warn};
 
-pub fn ready(common: &Common) {
+/// Notify systemd, that we are ready.
+///
+/// The environment of the user manager is updated by [`crate::dbus::ready`].
+pub fn ready() {
     if booted() {
-        match Command::new("systemctl")
-            .args(["--user", "import-environment", "WAYLAND_DISPLAY", "DISPLAY"])
-            .env("WAYLAND_DISPLAY", &common.socket)
-            .env(
-                "DISPLAY",
-                common
-                    .xwayland_state
-                    .as_ref()
-                    .map(|s| format!(":{}", s.display))
-                    .unwrap_or_default(),
-            )
-            .status()
-        {
-            Ok(x) if x.success() => {}
-            Ok(x) => warn!(
-                exit_code = ?x.code(),
-                "Failed to import WAYLAND_DISPLAY/DISPLAY into systemd",
-            ),
-            Err(err) => error!(?err, "Failed to run systemctl although booted with systemd",),
-        };
-
         if let Err(err) = notify(false, &[NotifyState::Ready]) {
             error!(?err, "Failed to notify systemd");
         }
     }
 }
+
+/// Place a freshly spawned child into its own `app-cosmic-<name>-<pid>.scope`,
+/// so the OOM killer and resource accounting treat it separately from the compositor.
+///
+/// Must not be called from the main thread, as it blocks on the session bus.
+pub fn spawn_scope(pid: u32, command: &str) {
+    if !booted() {
+        return;
+    }
+
+    let app_name = command
+        .split_whitespace()
+        .find(|arg| !arg.contains('='))
+        .and_then(|exec| exec.rsplit('/').next())
+        .unwrap_or("unknown");
+    let unit_name = format!("app-cosmic-{}-{}.scope", escape_unit_name(app_name), pid);
+
+    if let Err(err) = crate::dbus::systemd::start_app_scope(&unit_name, command, pid) {
+        warn!(?err, "Failed to move \"{}\" into its own scope", command);
+    }
+}
+
+/// Escape a string to only contain characters valid in a unit name,
+/// similar to `systemd-escape`.
+fn escape_unit_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for (i, byte) in name.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' => escaped.push(byte as char),
+            b'.' if i != 0 => escaped.push('.'),
+            _ => escaped.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    escaped
+}
@@ -2,11 +2,16 @@
 
 // update a Arc<Mutex<Theme>> in the state on change of the theme and mark all interfaces for a redraw.
 
+use std::sync::Arc;
+
 use calloop::LoopHandle;
 use cosmic::cosmic_theme::{Theme, ThemeMode, palette};
 
 use crate::state::State;
 
+/// Minimum width of the active hint in high contrast mode
+const HIGH_CONTRAST_HINT: u32 = 4;
+
 pub(crate) fn _group_color(theme: &Theme) -> [f32; 3] {
     let neutral_8 = theme.palette.neutral_8;
     [neutral_8.red, neutral_8.green, neutral_8.blue]
@@ -20,26 +25,49 @@ pub(crate) fn active_window_hint(theme: &Theme) -> palette::Srgba {
     }
 }
 
+/// Theme of the ui drawn by the compositor.
+///
+/// In high contrast mode the system theme is replaced by its high contrast variant with a thicker
+/// active hint, without affecting the theme of applications.
+pub fn compositor_theme(high_contrast: bool) -> cosmic::Theme {
+    let theme = cosmic::theme::system_preference();
+    if !high_contrast {
+        return theme;
+    }
+
+    let base = if theme.cosmic().is_dark {
+        cosmic::Theme::dark_hc()
+    } else {
+        cosmic::Theme::light_hc()
+    };
+    let mut cosmic = base.cosmic().clone();
+    cosmic.active_hint = cosmic.active_hint.max(HIGH_CONTRAST_HINT);
+    cosmic::Theme::custom(Arc::new(cosmic))
+}
+
+/// Applies a changed compositor theme to all of its ui
+pub fn update_theme(state: &mut State) {
+    let new_theme = compositor_theme(state.common.config.cosmic_conf.high_contrast);
+    let theme = &mut state.common.theme;
+
+    if theme.theme_type != new_theme.theme_type {
+        *theme = new_theme;
+        let mut workspace_guard = state.common.workspace_state.update();
+        state.common.shell.write().set_theme(
+            theme.clone(),
+            &state.common.xdg_activation_state,
+            &mut workspace_guard,
+        );
+    }
+}
+
 pub fn watch_theme(handle: LoopHandle<'_, State>) -> Result<(), cosmic_config::Error> {
     let (ping_tx, ping_rx) = calloop::ping::make_ping().unwrap();
     let config_mode_helper = ThemeMode::config()?;
     let config_dark_helper = Theme::dark_config()?;
     let config_light_helper = Theme::light_config()?;
 
-    if let Err(e) = handle.insert_source(ping_rx, move |_, _, state| {
-        let new_theme = cosmic::theme::system_preference();
-        let theme = &mut state.common.theme;
-
-        if theme.theme_type != new_theme.theme_type {
-            *theme = new_theme;
-            let mut workspace_guard = state.common.workspace_state.update();
-            state.common.shell.write().set_theme(
-                theme.clone(),
-                &state.common.xdg_activation_state,
-                &mut workspace_guard,
-            );
-        }
-    }) {
+    if let Err(e) = handle.insert_source(ping_rx, move |_, _, state| update_theme(state)) {
         tracing::error!("{e}");
     };
 
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Clock driving all animations of the compositor.
+//!
+//! Animations take their timestamps from [`now`] instead of [`Instant::now`],
+//! so the speed of every animation can be changed at runtime without touching their durations.
+//! Timestamps of this clock must only be compared with each other, as it drifts from the
+//! system clock whenever the speed isn't `1.0`.
+
+use std::{
+    sync::{LazyLock, Mutex},
+    time::Instant,
+};
+
+/// Speed factor applied on top of the configured speed, while slow-motion is engaged
+const SLOW_MOTION_FACTOR: f64 = 0.1;
+/// Speed factor applied while saving power, halving the duration of animations
+const POWER_SAVING_FACTOR: f64 = 2.0;
+const MIN_SPEED: f64 = 0.01;
+const MAX_SPEED: f64 = 20.0;
+
+#[derive(Debug)]
+struct Clock {
+    speed: f64,
+    slow_motion: bool,
+    power_saving: bool,
+    /// Point on the system clock, the animation clock was last rebased at
+    real_base: Instant,
+    /// Time of the animation clock at `real_base`
+    base: Instant,
+}
+
+impl Clock {
+    fn now(&self) -> Instant {
+        let mut factor = if self.slow_motion {
+            self.speed * SLOW_MOTION_FACTOR
+        } else {
+            self.speed
+        };
+        if self.power_saving {
+            factor *= POWER_SAVING_FACTOR;
+        }
+        self.base + self.real_base.elapsed().mul_f64(factor)
+    }
+
+    /// Continue from the current time, before changing the speed
+    fn rebase(&mut self) {
+        let now = Instant::now();
+        self.base = self.now();
+        self.real_base = now;
+    }
+}
+
+static CLOCK: LazyLock<Mutex<Clock>> = LazyLock::new(|| {
+    let now = Instant::now();
+    Mutex::new(Clock {
+        speed: 1.0,
+        slow_motion: false,
+        power_saving: false,
+        real_base: now,
+        base: now,
+    })
+});
+
+/// Current time of the animation clock
+pub fn now() -> Instant {
+    CLOCK.lock().unwrap().now()
+}
+
+/// Sets the global animation speed, `2.0` plays animations twice as fast.
+///
+/// Invalid speeds, like an unset config value of `0.0`, reset the speed to `1.0`.
+pub fn set_speed(speed: f32) {
+    let speed = if speed.is_finite() && speed > 0.0 {
+        (speed as f64).clamp(MIN_SPEED, MAX_SPEED)
+    } else {
+        1.0
+    };
+    let mut clock = CLOCK.lock().unwrap();
+    if clock.speed != speed {
+        clock.rebase();
+        clock.speed = speed;
+    }
+}
+
+/// Slows down all animations to a tenth of their speed, while `enabled`
+pub fn set_slow_motion(enabled: bool) {
+    let mut clock = CLOCK.lock().unwrap();
+    if clock.slow_motion != enabled {
+        clock.rebase();
+        clock.slow_motion = enabled;
+    }
+}
+
+/// Speeds up all animations to save power, while `enabled`
+pub fn set_power_saving(enabled: bool) {
+    let mut clock = CLOCK.lock().unwrap();
+    if clock.power_saving != enabled {
+        clock.rebase();
+        clock.power_saving = enabled;
+    }
+}
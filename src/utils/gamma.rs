@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Gamma ramps applied to the CRTCs of outputs.
+
+use anyhow::{Context, Result, anyhow};
+
+/// Per channel lookup tables, mapping evenly spaced inputs to 16-bit outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GammaRamp {
+    pub red: Vec<u16>,
+    pub green: Vec<u16>,
+    pub blue: Vec<u16>,
+}
+
+/// Offset of the tag count in an ICC profile, following the header
+const ICC_TAG_COUNT_OFFSET: usize = 128;
+const VCGT_SIGNATURE: &[u8; 4] = b"vcgt";
+const VCGT_TYPE_TABLE: u32 = 0;
+const VCGT_TYPE_FORMULA: u32 = 1;
+/// Size of formula based curves
+const FORMULA_SIZE: usize = 256;
+
+/// Relative intensities of red, green and blue light of a black body at `temperature` Kelvin,
+/// normalized to 1 at the 6500K whitepoint of sRGB.
+///
+/// Uses Tanner Helland's approximation of the black body curve.
+pub fn whitepoint(temperature: u32) -> [f64; 3] {
+    fn rgb(temperature: u32) -> [f64; 3] {
+        let t = temperature as f64 / 100.;
+        let red = if t <= 66. {
+            255.
+        } else {
+            329.698727446 * (t - 60.).powf(-0.1332047592)
+        };
+        let green = if t <= 66. {
+            99.4708025861 * t.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (t - 60.).powf(-0.0755148492)
+        };
+        let blue = if t >= 66. {
+            255.
+        } else if t <= 19. {
+            0.
+        } else {
+            138.5177312231 * (t - 10.).ln() - 305.0447927307
+        };
+        [red, green, blue].map(|c| c.clamp(0., 255.) / 255.)
+    }
+
+    let white = rgb(6500);
+    let color = rgb(temperature);
+    [0, 1, 2].map(|i| (color[i] / white[i]).min(1.))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("Unexpected end of profile"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow!("Unexpected end of profile"))
+}
+
+/// Reads a `s15Fixed16Number`
+fn read_fixed(data: &[u8], offset: usize) -> Result<f64> {
+    Ok(read_u32(data, offset)? as i32 as f64 / 65536.)
+}
+
+/// Linearly interpolates `values` at the fractional index `pos`
+fn interpolate(values: &[u16], pos: f64) -> u16 {
+    let low = pos.floor() as usize;
+    let high = (low + 1).min(values.len() - 1);
+    let fract = pos - low as f64;
+    (values[low] as f64 * (1. - fract) + values[high] as f64 * fract).round() as u16
+}
+
+impl GammaRamp {
+    /// Ramp without any effect
+    pub fn linear(size: usize) -> GammaRamp {
+        let channel = (0..size)
+            .map(|i| (i * u16::MAX as usize / size.saturating_sub(1).max(1)) as u16)
+            .collect::<Vec<_>>();
+        GammaRamp {
+            red: channel.clone(),
+            green: channel.clone(),
+            blue: channel,
+        }
+    }
+
+    /// Scales the entries of the red, green and blue channels by `factors`,
+    /// darkening the output for factors below 1
+    pub fn scaled(&self, factors: [f64; 3]) -> GammaRamp {
+        let channel = |values: &[u16], factor: f64| {
+            values
+                .iter()
+                .map(|value| (*value as f64 * factor).round().clamp(0., u16::MAX as f64) as u16)
+                .collect()
+        };
+        GammaRamp {
+            red: channel(&self.red, factors[0]),
+            green: channel(&self.green, factors[1]),
+            blue: channel(&self.blue, factors[2]),
+        }
+    }
+
+    /// Maps the entries of the ramp through `outer`, applying both ramps one after another
+    pub fn compose(&self, outer: &GammaRamp) -> GammaRamp {
+        let channel = |values: &[u16], outer: &[u16]| {
+            values
+                .iter()
+                .map(|value| {
+                    if outer.is_empty() {
+                        return *value;
+                    }
+                    let pos = *value as f64 * (outer.len() - 1) as f64 / u16::MAX as f64;
+                    interpolate(outer, pos)
+                })
+                .collect()
+        };
+        GammaRamp {
+            red: channel(&self.red, &outer.red),
+            green: channel(&self.green, &outer.green),
+            blue: channel(&self.blue, &outer.blue),
+        }
+    }
+
+    /// Interpolates the ramp to `size` entries per channel, as required by a CRTC
+    pub fn resample(&self, size: usize) -> GammaRamp {
+        fn channel(values: &[u16], size: usize) -> Vec<u16> {
+            if values.len() == size || values.is_empty() {
+                return values.to_vec();
+            }
+            (0..size)
+                .map(|i| {
+                    let pos = i as f64 * (values.len() - 1) as f64 / (size - 1).max(1) as f64;
+                    interpolate(values, pos)
+                })
+                .collect()
+        }
+
+        GammaRamp {
+            red: channel(&self.red, size),
+            green: channel(&self.green, size),
+            blue: channel(&self.blue, size),
+        }
+    }
+
+    /// Reads the calibration curves (`vcgt` tag) of an ICC profile.
+    ///
+    /// Profiles without calibration curves return `None`.
+    pub fn from_icc(data: &[u8]) -> Result<Option<GammaRamp>> {
+        let tag_count = read_u32(data, ICC_TAG_COUNT_OFFSET).context("Invalid ICC profile")?;
+        let tag = (0..tag_count as usize)
+            .map(|i| ICC_TAG_COUNT_OFFSET + 4 + i * 12)
+            .find(|offset| data.get(*offset..*offset + 4) == Some(VCGT_SIGNATURE.as_slice()));
+        let Some(tag) = tag else {
+            return Ok(None);
+        };
+        let offset = read_u32(data, tag + 4)? as usize;
+
+        // type signature and reserved bytes precede the curves
+        match read_u32(data, offset + 8)? {
+            VCGT_TYPE_TABLE => {
+                let channels = read_u16(data, offset + 12)? as usize;
+                let entries = read_u16(data, offset + 14)? as usize;
+                let entry_size = read_u16(data, offset + 16)? as usize;
+                if channels != 3 || entries < 2 || !matches!(entry_size, 1 | 2) {
+                    return Err(anyhow!(
+                        "Unsupported calibration table of {} channels with {} entries of {} bytes",
+                        channels,
+                        entries,
+                        entry_size
+                    ));
+                }
+
+                let start = offset + 18;
+                let channel = |c: usize| {
+                    (0..entries)
+                        .map(|i| {
+                            let pos = start + (c * entries + i) * entry_size;
+                            if entry_size == 1 {
+                                data.get(pos)
+                                    .map(|value| *value as u16 * 257)
+                                    .ok_or_else(|| anyhow!("Unexpected end of profile"))
+                            } else {
+                                read_u16(data, pos)
+                            }
+                        })
+                        .collect::<Result<Vec<_>>>()
+                };
+                Ok(Some(GammaRamp {
+                    red: channel(0)?,
+                    green: channel(1)?,
+                    blue: channel(2)?,
+                }))
+            }
+            VCGT_TYPE_FORMULA => {
+                let channel = |c: usize| -> Result<Vec<u16>> {
+                    let start = offset + 12 + c * 12;
+                    let gamma = read_fixed(data, start)?;
+                    let min = read_fixed(data, start + 4)?;
+                    let max = read_fixed(data, start + 8)?;
+                    Ok((0..FORMULA_SIZE)
+                        .map(|i| {
+                            let input = i as f64 / (FORMULA_SIZE - 1) as f64;
+                            let value = min + (max - min) * input.powf(gamma);
+                            (value.clamp(0., 1.) * u16::MAX as f64).round() as u16
+                        })
+                        .collect())
+                };
+                Ok(Some(GammaRamp {
+                    red: channel(0)?,
+                    green: channel(1)?,
+                    blue: channel(2)?,
+                }))
+            }
+            kind => Err(anyhow!("Unknown calibration curve type {}", kind)),
+        }
+    }
+}
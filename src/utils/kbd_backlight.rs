@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keyboard backlight, changed through logind, so no privileges are needed.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tracing::warn;
+
+const LEDS_DIR: &str = "/sys/class/leds";
+/// Number of steps between off and full brightness
+const STEPS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacklightChange {
+    Up,
+    Down,
+    Toggle,
+}
+
+#[derive(Debug)]
+struct Device {
+    name: String,
+    path: PathBuf,
+    max: u32,
+}
+
+/// Brightness before toggling the backlight off
+static TOGGLED_OFF: Mutex<Option<u32>> = Mutex::new(None);
+
+fn read_value(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn find_device() -> Option<Device> {
+    std::fs::read_dir(LEDS_DIR)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.ends_with("::kbd_backlight") {
+                return None;
+            }
+            let path = entry.path();
+            let max = read_value(&path.join("max_brightness")).filter(|max| *max > 0)?;
+            Some(Device { name, path, max })
+        })
+        .next()
+}
+
+/// Applies `change` to the keyboard backlight, returning the new brightness in percent.
+///
+/// Returns `None` without a keyboard backlight.
+pub fn change(change: BacklightChange) -> Option<u32> {
+    let device = find_device()?;
+    let current = read_value(&device.path.join("brightness"))?;
+    let step = (device.max / STEPS).max(1);
+
+    let brightness = match change {
+        BacklightChange::Up => (current + step).min(device.max),
+        BacklightChange::Down => current.saturating_sub(step),
+        BacklightChange::Toggle => {
+            let mut toggled_off = TOGGLED_OFF.lock().unwrap();
+            if current > 0 {
+                *toggled_off = Some(current);
+                0
+            } else {
+                toggled_off.take().unwrap_or(device.max)
+            }
+        }
+    };
+
+    if brightness != current {
+        let name = device.name.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = crate::dbus::logind::set_brightness("leds", &name, brightness) {
+                warn!(?err, "Failed to set keyboard backlight brightness");
+            }
+        });
+    }
+
+    Some(brightness * 100 / device.max)
+}
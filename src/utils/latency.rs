@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Measurement of the latency from input events to the page flip presenting their effect.
+//!
+//! While enabled, the timestamp of every input event is attached to the active output,
+//! handed to the next frame queued on that output and turned into a sample, once the frame
+//! is presented. This includes frames, that didn't change because of the input,
+//! so the results are an upper bound for the reaction of the compositor and its clients.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+/// Samples kept per output, older ones are dropped
+const MAX_SAMPLES: usize = 10_000;
+/// Inputs kept per output, while no frame is queued
+const MAX_PENDING: usize = 1_000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static OUTPUTS: LazyLock<Mutex<HashMap<String, OutputLatency>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Default)]
+struct OutputLatency {
+    /// Input times not yet handed to a frame
+    pending: Vec<Duration>,
+    /// Input times of the queued frame
+    queued: Vec<Duration>,
+    samples: VecDeque<Duration>,
+}
+
+/// Percentiles of the latency of an output
+#[derive(Debug, Clone)]
+pub struct LatencyReport {
+    pub output: String,
+    pub samples: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Starts or stops measuring, previous samples are discarded when starting
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::Relaxed) != enabled && enabled {
+        reset();
+    }
+}
+
+pub fn reset() {
+    OUTPUTS.lock().unwrap().clear();
+}
+
+/// An input event happened at `time` on the monotonic clock, affecting `output`
+pub fn input(output: &str, time: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    let mut outputs = OUTPUTS.lock().unwrap();
+    let pending = &mut outputs.entry(output.to_string()).or_default().pending;
+    if pending.len() < MAX_PENDING {
+        pending.push(time);
+    }
+}
+
+/// A frame including all inputs since the last frame was queued on `output`
+pub fn frame_queued(output: &str) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(latency) = OUTPUTS.lock().unwrap().get_mut(output) {
+        let pending = std::mem::take(&mut latency.pending);
+        latency.queued.extend(pending);
+    }
+}
+
+/// The queued frame of `output` was presented at `time` on the monotonic clock
+pub fn presented(output: &str, time: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(latency) = OUTPUTS.lock().unwrap().get_mut(output) {
+        for input in std::mem::take(&mut latency.queued) {
+            if latency.samples.len() == MAX_SAMPLES {
+                latency.samples.pop_front();
+            }
+            latency.samples.push_back(time.saturating_sub(input));
+        }
+    }
+}
+
+pub fn reports() -> Vec<LatencyReport> {
+    let outputs = OUTPUTS.lock().unwrap();
+    let mut reports = outputs
+        .iter()
+        .filter(|(_, latency)| !latency.samples.is_empty())
+        .map(|(output, latency)| {
+            let mut samples = latency.samples.iter().copied().collect::<Vec<_>>();
+            samples.sort_unstable();
+            let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+            LatencyReport {
+                output: output.clone(),
+                samples: samples.len(),
+                p50: percentile(50),
+                p90: percentile(90),
+                p99: percentile(99),
+                max: samples[samples.len() - 1],
+            }
+        })
+        .collect::<Vec<_>>();
+    reports.sort_by(|a, b| a.output.cmp(&b.output));
+    reports
+}
@@ -3,10 +3,16 @@
 pub mod env;
 mod ids;
 pub(crate) use self::ids::id_gen;
+pub mod animation;
 pub mod float;
+pub mod gamma;
 pub mod geometry;
 pub mod iced;
+#[cfg(feature = "systemd")]
+pub mod kbd_backlight;
+pub mod latency;
 pub mod prelude;
+pub mod profiler;
 pub mod quirks;
 pub mod rlimit;
 pub mod screenshot;
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Runtime toggle for the coarse pipeline spans (input, layout, render, page-flip),
+//! so traces of stutter episodes can be captured without rebuilding or restarting.
+//!
+//! Spans are only recorded, if cosmic-comp was built with `profile-with-tracy`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Start the profiler client (if compiled in) and read the initial state
+/// from `COSMIC_COMP_PROFILE`.
+pub fn init() {
+    #[cfg(feature = "profile-with-tracy")]
+    tracy_client::Client::start();
+
+    if crate::utils::env::bool_var("COSMIC_COMP_PROFILE").unwrap_or(false) {
+        set_enabled(true);
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::Relaxed) != enabled {
+        if cfg!(feature = "profile-with-tracy") {
+            info!("Pipeline profiling {}", if enabled { "enabled" } else { "disabled" });
+        } else {
+            info!("Pipeline profiling toggled, but cosmic-comp was built without a profiler");
+        }
+    }
+}
+
+/// Guard for a profiling span, that ends when dropped.
+pub struct Span {
+    #[cfg(feature = "profile-with-tracy")]
+    _span: Option<tracy_client::Span>,
+}
+
+/// Open a named span, if profiling is currently enabled.
+#[track_caller]
+pub fn span(name: &'static str) -> Span {
+    #[cfg(feature = "profile-with-tracy")]
+    {
+        let location = std::panic::Location::caller();
+        Span {
+            _span: is_enabled()
+                .then(tracy_client::Client::running)
+                .flatten()
+                .map(|client| {
+                    client.span_alloc(Some(name), "", location.file(), location.line(), 0)
+                }),
+        }
+    }
+    #[cfg(not(feature = "profile-with-tracy"))]
+    {
+        let _ = name;
+        Span {}
+    }
+}
+
+/// Mark the end of a frame, if profiling is currently enabled.
+pub fn frame_mark() {
+    #[cfg(feature = "profile-with-tracy")]
+    if is_enabled() {
+        if let Some(client) = tracy_client::Client::running() {
+            client.frame_mark();
+        }
+    }
+}
@@ -1,32 +1,60 @@
+use std::{path::PathBuf, sync::Arc};
+
 use anyhow::Context;
+use futures_channel::oneshot;
 use smithay::{
     backend::{
         allocator::Fourcc,
         renderer::{
-            ExportMem, ImportAll, Offscreen, Renderer,
+            Bind, ExportMem, ImportAll, ImportMem, Offscreen, Renderer,
             damage::OutputDamageTracker,
-            element::{AsRenderElements, surface::WaylandSurfaceRenderElement},
-            gles::GlesRenderbuffer,
+            element::{AsRenderElements, RenderElement, surface::WaylandSurfaceRenderElement},
+            gles::{GlesRenderbuffer, GlesTexture},
         },
     },
     desktop::utils::bbox_from_surface_tree,
-    utils::{Scale, Transform},
+    input::pointer::Focus,
+    output::Output,
+    utils::{
+        Buffer as BufferCoords, Monotonic, Physical, Rectangle, SERIAL_COUNTER, Scale, Size, Time,
+        Transform,
+    },
     wayland::seat::WaylandFocus,
 };
 use tracing::warn;
 
 use crate::{
-    backend::render::RendererRef,
-    shell::element::CosmicSurface,
+    backend::render::{
+        CursorMode, RendererRef,
+        element::{AsGlowRenderer, CosmicElement, FromGlesError},
+        output_elements,
+    },
+    dbus::screenshot_portal::{ScreenshotRequest, ScreenshotResponse},
+    shell::{
+        CosmicMappedRenderElement, Shell, WorkspaceRenderElement,
+        element::CosmicSurface,
+        screenshot_selection::{ScreenshotSelection, ScreenshotSelectionGrab, ScreenshotTarget},
+    },
     state::{State, advertised_node_for_surface},
+    utils::prelude::*,
 };
 
 pub fn screenshot_window(state: &mut State, surface: &CosmicSurface) {
+    if let Err(err) = save_window_screenshot(state, surface) {
+        warn!(?err, "Failed to take screenshot")
+    }
+}
+
+/// Takes a screenshot of `surface` alone, returning the path of the saved png
+pub fn save_window_screenshot(
+    state: &mut State,
+    surface: &CosmicSurface,
+) -> anyhow::Result<PathBuf> {
     fn render_window<R>(
         renderer: &mut R,
         window: &CosmicSurface,
         offset: &time::UtcOffset,
-    ) -> anyhow::Result<()>
+    ) -> anyhow::Result<PathBuf>
     where
         R: Renderer + ImportAll + Offscreen<GlesRenderbuffer> + ExportMem,
         R::TextureId: Clone + 'static,
@@ -64,59 +92,260 @@ pub fn screenshot_window(state: &mut State, surface: &CosmicSurface) {
         )?;
         let gl_data = renderer.map_texture(&mapping)?;
 
-        if let Ok(Some(path)) = xdg_user::pictures() {
-            let local_timestamp = time::OffsetDateTime::now_utc().to_offset(*offset);
-            let mut title = window.title();
-            title.truncate(227); // 255 - time - png
-            let name = sanitize_filename::sanitize(format!(
-                "{}_{}.png",
-                title,
-                local_timestamp
-                    .format(time::macros::format_description!(
-                        "[year]-[month]-[day]_[hour]:[minute]:[second]_[subsecond digits:4]"
-                    ))
-                    .unwrap(),
-            ));
-            let file = std::fs::File::create(path.join(name))?;
-
-            let writer = &mut std::io::BufWriter::new(file);
-            let mut encoder = png::Encoder::new(writer, bbox.size.w as u32, bbox.size.h as u32);
-            encoder.set_color(png::ColorType::Rgba);
-            encoder.set_depth(png::BitDepth::Eight);
-            encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2)); // 1.0 / 2.2, unscaled, but rounded
-            let source_chromaticities = png::SourceChromaticities::new(
-                // Using unscaled instantiation here
-                (0.31270, 0.32900),
-                (0.64000, 0.33000),
-                (0.30000, 0.60000),
-                (0.15000, 0.06000),
+        let mut title = window.title();
+        title.truncate(227); // 255 - time - png
+        save_png(
+            gl_data,
+            (bbox.size.w as u32, bbox.size.h as u32),
+            &title,
+            offset,
+        )
+    }
+
+    let wl_surface = surface.wl_surface().context("Window has no surface")?;
+    let renderer = state
+        .backend
+        .offscreen_renderer(|kms| {
+            advertised_node_for_surface(&wl_surface, &state.common.display_handle)
+                .or(*kms.primary_node.read().unwrap())
+        })
+        .with_context(|| "Failed to get renderer for screenshot")?;
+    match renderer {
+        RendererRef::Glow(renderer) => render_window(renderer, surface, &state.common.local_offset),
+        RendererRef::GlMulti(mut renderer) => {
+            render_window(&mut renderer, surface, &state.common.local_offset)
+        }
+    }
+}
+
+/// Saves rgba `data` as a png named after `title` and the current time in the pictures directory
+fn save_png(
+    data: &[u8],
+    (width, height): (u32, u32),
+    title: &str,
+    offset: &time::UtcOffset,
+) -> anyhow::Result<PathBuf> {
+    let dir = xdg_user::pictures()
+        .ok()
+        .flatten()
+        .context("No pictures directory")?;
+    let local_timestamp = time::OffsetDateTime::now_utc().to_offset(*offset);
+    let name = sanitize_filename::sanitize(format!(
+        "{}_{}.png",
+        title,
+        local_timestamp
+            .format(time::macros::format_description!(
+                "[year]-[month]-[day]_[hour]:[minute]:[second]_[subsecond digits:4]"
+            ))
+            .unwrap(),
+    ));
+    let path = dir.join(name);
+    let file = std::fs::File::create(&path)?;
+
+    let writer = &mut std::io::BufWriter::new(file);
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2)); // 1.0 / 2.2, unscaled, but rounded
+    let source_chromaticities = png::SourceChromaticities::new(
+        // Using unscaled instantiation here
+        (0.31270, 0.32900),
+        (0.64000, 0.33000),
+        (0.30000, 0.60000),
+        (0.15000, 0.06000),
+    );
+    encoder.set_source_chromaticities(source_chromaticities);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+
+    Ok(path)
+}
+
+/// Takes a screenshot of `output` without cursors, returning the path of the saved png.
+///
+/// Only `region` is saved, if given, in output local coordinates.
+pub fn screenshot_output(
+    state: &mut State,
+    output: &Output,
+    region: Option<Rectangle<i32, Local>>,
+) -> anyhow::Result<PathBuf> {
+    fn render_output<R>(
+        renderer: &mut R,
+        shell: &Arc<parking_lot::RwLock<Shell>>,
+        now: Time<Monotonic>,
+        output: &Output,
+        region: Option<Rectangle<i32, Local>>,
+        offset: &time::UtcOffset,
+    ) -> anyhow::Result<PathBuf>
+    where
+        R: Renderer
+            + ImportAll
+            + ImportMem
+            + ExportMem
+            + Bind<GlesTexture>
+            + Offscreen<GlesRenderbuffer>
+            + AsGlowRenderer,
+        R::TextureId: Send + Clone + 'static,
+        R::Error: FromGlesError + Send + Sync + 'static,
+        CosmicElement<R>: RenderElement<R>,
+        CosmicMappedRenderElement<R>: RenderElement<R>,
+        WorkspaceRenderElement<R>: RenderElement<R>,
+    {
+        let mode = output.current_mode().context("Output has no mode")?;
+        let size = output.current_transform().transform_size(mode.size);
+        let scale = output.current_scale().fractional_scale();
+
+        let elements = output_elements(None, renderer, shell, now, output, CursorMode::None, None)
+            .map_err(|err| anyhow::anyhow!("Failed to accumulate elements: {:?}", err))?;
+        let clear_color = shell.read().clear_color(output);
+
+        // TODO: 10-bit
+        let format = Fourcc::Abgr8888;
+        let buffer_size = Size::<i32, BufferCoords>::from((size.w, size.h));
+        let mut render_buffer =
+            Offscreen::<GlesRenderbuffer>::create_buffer(renderer, format, buffer_size)?;
+        let mut fb = renderer.bind(&mut render_buffer)?;
+        let mut output_damage_tracker = OutputDamageTracker::new(size, scale, Transform::Normal);
+        output_damage_tracker
+            .render_output(renderer, &mut fb, 0, &elements, clear_color)
+            .map_err(|err| match err {
+                smithay::backend::renderer::damage::Error::Rendering(err) => err,
+                smithay::backend::renderer::damage::Error::OutputNoMode(_) => unreachable!(),
+            })?;
+        let mapping = renderer.copy_framebuffer(&fb, Rectangle::from_size(buffer_size), format)?;
+        let gl_data = renderer.map_texture(&mapping)?;
+
+        let Some(region) = region else {
+            return save_png(
+                gl_data,
+                (size.w as u32, size.h as u32),
+                "Screenshot",
+                offset,
             );
-            encoder.set_source_chromaticities(source_chromaticities);
-            let mut writer = encoder.write_header()?;
-            writer.write_image_data(gl_data)?;
+        };
+        let region = region
+            .as_logical()
+            .to_physical_precise_round(scale)
+            .intersection(Rectangle::from_size(size))
+            .context("Region is outside of the output")?;
+        save_png(
+            &crop(gl_data, size.w, region),
+            (region.size.w as u32, region.size.h as u32),
+            "Screenshot",
+            offset,
+        )
+    }
+
+    let shell = state.common.shell.clone();
+    let now = state.common.clock.now();
+    let offset = state.common.local_offset;
+    let renderer = state
+        .backend
+        .offscreen_renderer(|kms| *kms.primary_node.read().unwrap())
+        .with_context(|| "Failed to get renderer for screenshot")?;
+    match renderer {
+        RendererRef::Glow(renderer) => {
+            render_output(renderer, &shell, now, output, region, &offset)
+        }
+        RendererRef::GlMulti(mut renderer) => {
+            render_output(&mut renderer, &shell, now, output, region, &offset)
         }
+    }
+}
 
-        Ok(())
+/// Copies `region` out of rgba `data`, that is `width` pixels wide
+fn crop(data: &[u8], width: i32, region: Rectangle<i32, Physical>) -> Vec<u8> {
+    let stride = width as usize * 4;
+    let row_len = region.size.w as usize * 4;
+    (region.loc.y..region.loc.y + region.size.h)
+        .flat_map(|y| {
+            let start = y as usize * stride + region.loc.x as usize * 4;
+            &data[start..start + row_len]
+        })
+        .copied()
+        .collect()
+}
+
+/// Screenshot of the target, returning the path of the saved png
+pub fn screenshot_target(state: &mut State, target: ScreenshotTarget) -> anyhow::Result<PathBuf> {
+    match target {
+        ScreenshotTarget::Output(output) => screenshot_output(state, &output, None),
+        ScreenshotTarget::Region(output, region) => screenshot_output(state, &output, Some(region)),
+        ScreenshotTarget::Window(surface) => save_window_screenshot(state, &surface),
     }
+}
 
-    if let Some(wl_surface) = surface.wl_surface() {
-        let res = state
-            .backend
-            .offscreen_renderer(|kms| {
-                advertised_node_for_surface(&wl_surface, &state.common.display_handle)
-                    .or(*kms.primary_node.read().unwrap())
-            })
-            .with_context(|| "Failed to get renderer for screenshot")
-            .and_then(|renderer| match renderer {
-                RendererRef::Glow(renderer) => {
-                    render_window(renderer, surface, &state.common.local_offset)
-                }
-                RendererRef::GlMulti(mut renderer) => {
-                    render_window(&mut renderer, surface, &state.common.local_offset)
-                }
-            });
-        if let Err(err) = res {
-            warn!(?err, "Failed to take screenshot")
+impl State {
+    /// Takes a screenshot for the portal, letting the user choose what to capture if interactive
+    pub fn handle_screenshot_request(&mut self, request: ScreenshotRequest) {
+        if request.interactive {
+            self.start_screenshot_selection(request.reply);
+            return;
         }
+
+        let output = self.common.shell.read().seats.last_active().active_output();
+        let response = match screenshot_output(self, &output, None) {
+            Ok(path) => ScreenshotResponse::Saved(path),
+            Err(err) => {
+                warn!(?err, "Failed to take screenshot");
+                ScreenshotResponse::Failed
+            }
+        };
+        let _ = request.reply.send(response);
+    }
+
+    fn start_screenshot_selection(&mut self, reply: oneshot::Sender<ScreenshotResponse>) {
+        let mut shell = self.common.shell.write();
+        let seat = shell.seats.last_active().clone();
+        let pointer = seat.get_pointer().unwrap();
+        // don't take over a grab in progress
+        if pointer.is_grabbed()
+            || shell.session_lock.is_some()
+            || shell.screenshot_selection().is_some()
+        {
+            let _ = reply.send(ScreenshotResponse::Cancelled);
+            return;
+        }
+
+        let selection = ScreenshotSelection::new(
+            reply,
+            &seat,
+            self.common.event_loop_handle.clone(),
+            shell.theme().clone(),
+        );
+        shell.set_screenshot_selection(selection);
+        std::mem::drop(shell);
+
+        pointer.set_grab(
+            self,
+            ScreenshotSelectionGrab::new(&seat),
+            SERIAL_COUNTER.next_serial(),
+            Focus::Clear,
+        );
+        self.schedule_render_all();
+    }
+
+    /// Ends the screenshot selection, capturing `target` or cancelling if `None`
+    pub fn finish_screenshot_selection(&mut self, target: Option<ScreenshotTarget>) {
+        let Some(selection) = self.common.shell.write().take_screenshot_selection() else {
+            return;
+        };
+        self.schedule_render_all();
+
+        let seat = self.common.shell.read().seats.last_active().clone();
+        let pointer = seat.get_pointer().unwrap();
+        if pointer.with_grab(|_, grab| grab.is::<ScreenshotSelectionGrab>()) == Some(true) {
+            pointer.unset_grab(self, SERIAL_COUNTER.next_serial(), 0);
+        }
+
+        let response = match target.map(|target| screenshot_target(self, target)) {
+            Some(Ok(path)) => ScreenshotResponse::Saved(path),
+            Some(Err(err)) => {
+                warn!(?err, "Failed to take screenshot");
+                ScreenshotResponse::Failed
+            }
+            None => ScreenshotResponse::Cancelled,
+        };
+        selection.reply(response);
     }
 }
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Audit log of privileged protocol usage.
+//!
+//! Keeps the most recent binds of privileged globals and capture sessions in memory, so users
+//! can check which clients snooped on them through `com.system76.CosmicComp.Audit`.
+//!
+//! Binds are recorded by the globals themselves, or for globals implemented by smithay by the
+//! `GlobalDispatch` implementations of [`State`](crate::state::State) forwarding to them.
+//! Virtual input devices and data-control devices are recorded when created, and every
+//! selection offered to a data-control device is recorded for the client owning it.
+
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use smithay::reexports::wayland_server::{Client, backend::ClientId};
+use tracing::debug;
+
+use crate::state::ClientState;
+
+/// Number of entries kept before dropping the oldest
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEvent {
+    /// The client bound the global
+    Bound,
+    /// The client created a virtual input or data-control device
+    DeviceCreated,
+    /// A new selection was offered to the client's data-control device
+    SelectionOffered,
+    CaptureStarted,
+    CaptureStopped,
+}
+
+impl AuditEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEvent::Bound => "bound",
+            AuditEvent::DeviceCreated => "device-created",
+            AuditEvent::SelectionOffered => "selection-offered",
+            AuditEvent::CaptureStarted => "capture-started",
+            AuditEvent::CaptureStopped => "capture-stopped",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub time: SystemTime,
+    pub protocol: PrivilegedProtocol,
+    pub event: AuditEvent,
+    pub pid: Option<u32>,
+    pub executable: Option<PathBuf>,
+    pub sandbox_engine: Option<String>,
+    pub app_id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+}
+
+static AUDIT_LOG: LazyLock<Mutex<AuditLog>> = LazyLock::new(Default::default);
+
+/// Clients with a data-control device, which receive every new selection
+static SELECTION_READERS: Mutex<Vec<Client>> = Mutex::new(Vec::new());
+
+/// Records `event` of `client` for `protocol`
+pub fn record(client: &Client, protocol: PrivilegedProtocol, event: AuditEvent) {
+    let data = client.get_data::<ClientState>();
+    let pid = data.and_then(|data| data.pid);
+    let context = data.and_then(|data| data.security_context.as_ref());
+    let entry = AuditEntry {
+        time: SystemTime::now(),
+        protocol,
+        event,
+        pid,
+        executable: pid.and_then(|pid| std::fs::read_link(format!("/proc/{}/exe", pid)).ok()),
+        sandbox_engine: context.and_then(|context| context.sandbox_engine.clone()),
+        app_id: context.and_then(|context| context.app_id.clone()),
+    };
+    debug!(?entry, "Privileged protocol used");
+
+    let mut log = AUDIT_LOG.lock().unwrap();
+    if log.entries.len() >= MAX_ENTRIES {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(entry);
+}
+
+/// Recorded entries, oldest first
+pub fn entries() -> Vec<AuditEntry> {
+    AUDIT_LOG.lock().unwrap().entries.iter().cloned().collect()
+}
+
+/// Records the creation of a data-control device by `client`, so later selections offered
+/// to it are recorded as well
+pub fn track_selection_reader(client: &Client) {
+    record(
+        client,
+        PrivilegedProtocol::DataControl,
+        AuditEvent::DeviceCreated,
+    );
+    let mut readers = SELECTION_READERS.lock().unwrap();
+    if !readers.iter().any(|reader| reader.id() == client.id()) {
+        readers.push(client.clone());
+    }
+}
+
+/// Records a new selection for every client with a data-control device
+pub fn selection_offered() {
+    let readers = SELECTION_READERS.lock().unwrap().clone();
+    for reader in &readers {
+        record(
+            reader,
+            PrivilegedProtocol::DataControl,
+            AuditEvent::SelectionOffered,
+        );
+    }
+}
+
+/// Stops tracking a disconnected client
+pub fn forget_client(client_id: &ClientId) {
+    SELECTION_READERS
+        .lock()
+        .unwrap()
+        .retain(|reader| reader.id() != *client_id);
+}
@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Persistence of the clipboard.
+//!
+//! Whenever a client sets the clipboard, its plain text and one image format are copied into
+//! the compositor. Once the client drops the selection, usually because it exited, the copy is
+//! offered instead, so closing an application doesn't empty the clipboard. Other formats aren't
+//! copied, as reading every format of every selection is expensive and rarely needed.
+//!
+//! Disabled by default, see `clipboard_persistence`.
+
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Read, Write},
+    os::unix::{io::OwnedFd, net::UnixStream},
+    sync::{Arc, Mutex},
+};
+
+use calloop::{Interest, LoopHandle, Mode, PostAction, generic::Generic};
+use rustix::fs::OFlags;
+use smithay::{
+    input::Seat,
+    wayland::selection::{
+        SelectionTarget,
+        data_device::{request_data_device_client_selection, set_data_device_selection},
+    },
+    xwayland::xwm::XwmId,
+};
+use tracing::{debug, warn};
+
+use crate::state::State;
+
+/// Plain text formats to copy, most preferred first, only one of them is kept
+const TEXT_MIME_TYPES: &[&str] = &["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+/// Image format to copy, if offered, otherwise the first image format is kept
+const PREFERRED_IMAGE_MIME_TYPE: &str = "image/png";
+
+/// Owner of a selection set by the compositor instead of a wayland client
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionOwner {
+    Xwm(XwmId),
+    /// Contents kept after the offering client dropped the selection
+    Persisted(Arc<PersistedSelection>),
+}
+
+/// Copied contents of a selection by mime type
+#[derive(Debug, PartialEq)]
+pub struct PersistedSelection(Vec<(String, Arc<[u8]>)>);
+
+impl PersistedSelection {
+    pub fn mime_types(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|(mime_type, _)| mime_type.clone())
+            .collect()
+    }
+
+    /// Writes the contents for `mime_type` into `fd`, without blocking on the receiving client
+    pub fn send(&self, mime_type: &str, fd: OwnedFd, evlh: &LoopHandle<'static, State>) {
+        let Some((_, data)) = self.0.iter().find(|(m, _)| m == mime_type) else {
+            return;
+        };
+        let data = data.clone();
+        if let Err(err) = rustix::fs::fcntl_getfl(&fd)
+            .and_then(|flags| rustix::fs::fcntl_setfl(&fd, flags | OFlags::NONBLOCK))
+        {
+            debug!(?err, "Failed to send persisted clipboard.");
+            return;
+        }
+
+        let mut written = 0;
+        if let Err(err) = evlh.insert_source(
+            Generic::new(File::from(fd), Interest::WRITE, Mode::Level),
+            move |_, file, _| {
+                let mut file: &File = file.as_ref();
+                while written < data.len() {
+                    match file.write(&data[written..]) {
+                        Ok(n) => written += n,
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                            return Ok(PostAction::Continue);
+                        }
+                        Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                        Err(err) => {
+                            debug!(?err, "Failed to send persisted clipboard.");
+                            break;
+                        }
+                    }
+                }
+                Ok(PostAction::Remove)
+            },
+        ) {
+            warn!(?err, "Failed to send persisted clipboard.");
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    contents: Vec<(String, Arc<[u8]>)>,
+    /// Mime types still being read
+    pending: usize,
+    size: usize,
+    too_large: bool,
+    /// The client dropped the selection, offer the copy once all reads finished
+    dropped: bool,
+}
+
+/// Copy of the clipboard last set by a client of the seat
+#[derive(Debug, Default)]
+struct SeatClipboard(Mutex<Option<Arc<Mutex<Snapshot>>>>);
+
+/// Starts copying the clipboard a client just set on `seat`
+pub fn snapshot(state: &mut State, seat: &Seat<State>, mime_types: Vec<String>) {
+    let config = &state.common.config.cosmic_conf.clipboard_persistence;
+    let mut current = seat
+        .user_data()
+        .get_or_insert_threadsafe(SeatClipboard::default)
+        .0
+        .lock()
+        .unwrap();
+    *current = None;
+
+    if !config.enabled
+        || mime_types
+            .iter()
+            .any(|mime_type| config.excluded_mime_types.contains(mime_type))
+    {
+        return;
+    }
+
+    let mime_types = preferred_mime_types(&mime_types);
+    if mime_types.is_empty() {
+        return;
+    }
+
+    let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+    *current = Some(snapshot.clone());
+    let max_size = config.max_size;
+    let seat = seat.clone();
+    // the selection is only set, once the handler returned
+    state.common.event_loop_handle.insert_idle(move |state| {
+        if !is_current(&seat, &snapshot) {
+            return;
+        }
+        for mime_type in mime_types {
+            if let Err(err) = read(state, &seat, &snapshot, mime_type, max_size) {
+                warn!(?err, "Failed to copy clipboard.");
+                snapshot.lock().unwrap().too_large = true;
+                break;
+            }
+        }
+    });
+}
+
+/// Formats of `mime_types` worth copying, see the module documentation
+fn preferred_mime_types(mime_types: &[String]) -> Vec<String> {
+    let text = TEXT_MIME_TYPES
+        .iter()
+        .find_map(|text| mime_types.iter().find(|mime_type| mime_type == text));
+    let image = mime_types
+        .iter()
+        .find(|mime_type| *mime_type == PREFERRED_IMAGE_MIME_TYPE)
+        .or_else(|| {
+            mime_types
+                .iter()
+                .find(|mime_type| mime_type.starts_with("image/"))
+        });
+    text.into_iter().chain(image).cloned().collect()
+}
+
+/// The client offering the clipboard of `seat` dropped it, offers the copy instead
+pub fn selection_dropped(state: &mut State, seat: &Seat<State>) {
+    let Some(snapshot) = current(seat) else {
+        return;
+    };
+
+    let mut guard = snapshot.lock().unwrap();
+    guard.dropped = true;
+    if guard.pending == 0 {
+        std::mem::drop(guard);
+        let seat = seat.clone();
+        // the selection is only cleared, once the handler returned
+        state
+            .common
+            .event_loop_handle
+            .insert_idle(move |state| offer(state, &seat, &snapshot));
+    }
+}
+
+fn current(seat: &Seat<State>) -> Option<Arc<Mutex<Snapshot>>> {
+    seat.user_data()
+        .get::<SeatClipboard>()
+        .and_then(|clipboard| clipboard.0.lock().unwrap().clone())
+}
+
+fn is_current(seat: &Seat<State>, snapshot: &Arc<Mutex<Snapshot>>) -> bool {
+    current(seat).is_some_and(|current| Arc::ptr_eq(&current, snapshot))
+}
+
+fn read(
+    state: &mut State,
+    seat: &Seat<State>,
+    snapshot: &Arc<Mutex<Snapshot>>,
+    mime_type: String,
+    max_size: usize,
+) -> io::Result<()> {
+    let (reader, writer) = UnixStream::pair()?;
+    reader.set_nonblocking(true)?;
+    request_data_device_client_selection(seat, mime_type.clone(), OwnedFd::from(writer))
+        .map_err(io::Error::other)?;
+    snapshot.lock().unwrap().pending += 1;
+
+    let seat = seat.clone();
+    let snapshot = snapshot.clone();
+    let mut buffer = Vec::new();
+    state
+        .common
+        .event_loop_handle
+        .insert_source(
+            Generic::new(reader, Interest::READ, Mode::Level),
+            move |_, stream, state| {
+                let mut stream: &UnixStream = stream.as_ref();
+                let result = stream.read_to_end(&mut buffer);
+
+                let mut guard = snapshot.lock().unwrap();
+                match result {
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        if guard.size + buffer.len() <= max_size {
+                            return Ok(PostAction::Continue);
+                        }
+                        guard.too_large = true;
+                    }
+                    Err(err) => {
+                        debug!(?err, ?mime_type, "Failed to read clipboard.");
+                    }
+                    Ok(_) => {
+                        guard.size += buffer.len();
+                        if guard.size > max_size {
+                            guard.too_large = true;
+                        } else {
+                            let data = std::mem::take(&mut buffer);
+                            guard.contents.push((mime_type.clone(), data.into()));
+                        }
+                    }
+                }
+
+                guard.pending -= 1;
+                let finished = guard.pending == 0 && guard.dropped;
+                std::mem::drop(guard);
+                if finished {
+                    offer(state, &seat, &snapshot);
+                }
+                Ok(PostAction::Remove)
+            },
+        )
+        .map_err(|err| io::Error::other(err.error))?;
+
+    Ok(())
+}
+
+fn offer(state: &mut State, seat: &Seat<State>, snapshot: &Arc<Mutex<Snapshot>>) {
+    if !is_current(seat, snapshot) {
+        return;
+    }
+    let snapshot = seat
+        .user_data()
+        .get::<SeatClipboard>()
+        .unwrap()
+        .0
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap();
+
+    let snapshot = std::mem::take(&mut *snapshot.lock().unwrap());
+    if snapshot.too_large || snapshot.contents.is_empty() {
+        return;
+    }
+
+    let selection = Arc::new(PersistedSelection(snapshot.contents));
+    let mime_types = selection.mime_types();
+    debug!(?mime_types, "Keeping clipboard of dropped selection.");
+    set_data_device_selection(
+        &state.common.display_handle,
+        seat,
+        mime_types.clone(),
+        SelectionOwner::Persisted(selection),
+    );
+    state
+        .common
+        .sync_xwayland_selection(SelectionTarget::Clipboard, Some(mime_types));
+}
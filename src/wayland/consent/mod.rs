@@ -0,0 +1,513 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Consent prompts for privileged protocols.
+//!
+//! Clients connected through a security context with an app id may request screencopy,
+//! data-control or virtual input devices, unless an allow-list is configured for the protocol
+//! in `privileged_protocols`. The user is asked through a compositor-drawn dialog, see
+//! [`crate::shell::consent_dialog`], and "Always Allow" or "Deny" is remembered per app id in
+//! `cosmic-comp/capture_consent.ron`. Clients that aren't sandboxed are left to the policy.
+//!
+//! Capture sessions are held back until the prompt is answered, so "Allow Once" applies to the
+//! pending sessions only. Data-control and virtual input offer no such point to wait at,
+//! clients yet to be asked see an inert placeholder global instead, which prompts once bound.
+//! Instead of "Allow Once" they are offered "Allow Until Logout", which grants them until the
+//! compositor exits, and clients have to reconnect to pick up the real globals.
+//!
+//! While any connected client may create virtual input devices, an indicator is shown.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::OpenOptions,
+    path::PathBuf,
+    sync::{
+        Arc, LazyLock, Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use calloop::{
+    LoopHandle,
+    channel::{self, Channel, Sender},
+};
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use smithay::reexports::wayland_server::{Client, DisplayHandle, backend::ClientId};
+use tracing::{debug, error, warn};
+
+mod placeholder;
+
+use crate::{
+    state::{ClientState, State},
+    wayland::{
+        policy::PolicyState,
+        protocols::screencopy::{FailureReason, Frame, Session, SessionRef},
+    },
+};
+
+/// Decisions by app id, as persisted on disk
+type ConsentDecisions = HashMap<String, HashMap<PrivilegedProtocol, bool>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentAnswer {
+    AllowOnce,
+    AlwaysAllow,
+    Deny,
+    /// The prompt was closed without choosing
+    Dismissed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Consent {
+    /// Not subject to consent, access is left to the [`PolicyState`]
+    NotRequired,
+    Granted,
+    Denied,
+    /// The user needs to be asked on behalf of the given app id
+    Unknown(String),
+}
+
+#[derive(Debug)]
+enum ConsentEvent {
+    Request(String, PrivilegedProtocol),
+    Answer(String, PrivilegedProtocol, ConsentAnswer),
+    SyntheticInputChanged,
+}
+
+/// Connected clients allowed to create virtual input devices
+static SYNTHETIC_INPUT_CLIENTS: LazyLock<Mutex<HashSet<ClientId>>> =
+    LazyLock::new(Default::default);
+static EVENTS: OnceLock<Sender<ConsentEvent>> = OnceLock::new();
+
+/// Whether any connected client may currently create virtual input devices
+pub fn synthetic_input_possible() -> bool {
+    !SYNTHETIC_INPUT_CLIENTS.lock().unwrap().is_empty()
+}
+
+/// Marks `client` as able to create virtual input devices, called once it bound a manager
+pub(crate) fn allow_synthetic_input(client: &Client) {
+    let mut clients = SYNTHETIC_INPUT_CLIENTS.lock().unwrap();
+    if clients.insert(client.id()) && clients.len() == 1 {
+        if let Some(tx) = EVENTS.get() {
+            let _ = tx.send(ConsentEvent::SyntheticInputChanged);
+        }
+    }
+}
+
+pub(crate) fn forget_client(client_id: &ClientId) {
+    let mut clients = SYNTHETIC_INPUT_CLIENTS.lock().unwrap();
+    if clients.remove(client_id) && clients.is_empty() {
+        if let Some(tx) = EVENTS.get() {
+            let _ = tx.send(ConsentEvent::SyntheticInputChanged);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Decisions {
+    path: Option<PathBuf>,
+    persisted: ConsentDecisions,
+    /// Decisions lasting until the compositor exits
+    session: HashMap<(String, PrivilegedProtocol), bool>,
+}
+
+impl Decisions {
+    fn get(&self, app_id: &str, protocol: PrivilegedProtocol) -> Option<bool> {
+        self.persisted
+            .get(app_id)
+            .and_then(|decisions| decisions.get(&protocol))
+            .or_else(|| self.session.get(&(app_id.to_string(), protocol)))
+            .copied()
+    }
+
+    fn persist(&mut self, app_id: &str, protocol: PrivilegedProtocol, allowed: bool) {
+        self.persisted
+            .entry(app_id.to_string())
+            .or_default()
+            .insert(protocol, allowed);
+
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let writer = match OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)
+        {
+            Ok(writer) => writer,
+            Err(err) => {
+                error!(?err, "Failed to persist capture consent.");
+                return;
+            }
+        };
+        if let Err(err) = ron::ser::to_writer_pretty(writer, &self.persisted, Default::default()) {
+            error!(?err, "Failed to persist capture consent.");
+        }
+    }
+}
+
+/// Answers a prompt once, counting it as dismissed if dropped unanswered
+#[derive(Debug, Clone)]
+pub struct ConsentReply(Arc<ReplyInner>);
+
+#[derive(Debug)]
+struct ReplyInner {
+    app_id: String,
+    protocol: PrivilegedProtocol,
+    answered: AtomicBool,
+    tx: Sender<ConsentEvent>,
+}
+
+impl ConsentReply {
+    /// App id the prompt is for
+    pub fn app_name(&self) -> &str {
+        &self.0.app_id
+    }
+
+    pub fn protocol(&self) -> PrivilegedProtocol {
+        self.0.protocol
+    }
+
+    pub fn answer(&self, answer: ConsentAnswer) {
+        if !self.0.answered.swap(true, Ordering::SeqCst) {
+            let _ = self.0.tx.send(ConsentEvent::Answer(
+                self.0.app_id.clone(),
+                self.0.protocol,
+                answer,
+            ));
+        }
+    }
+
+    /// Drops the prompt without answering, so it can be asked again
+    pub(crate) fn withdraw(&self) {
+        self.0.answered.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for ReplyInner {
+    fn drop(&mut self) {
+        if !self.answered.load(Ordering::SeqCst) {
+            let _ = self.tx.send(ConsentEvent::Answer(
+                self.app_id.clone(),
+                self.protocol,
+                ConsentAnswer::Dismissed,
+            ));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PendingSession {
+    app_id: String,
+    session: Session,
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+pub struct ConsentState {
+    decisions: Arc<Mutex<Decisions>>,
+    tx: Sender<ConsentEvent>,
+    queue: VecDeque<(String, PrivilegedProtocol)>,
+    prompting: Option<(String, PrivilegedProtocol)>,
+    pending_sessions: Vec<PendingSession>,
+}
+
+impl ConsentState {
+    pub fn new(
+        dh: &DisplayHandle,
+        evlh: &LoopHandle<'static, State>,
+        policy: &PolicyState,
+    ) -> Self {
+        let path = xdg::BaseDirectories::new()
+            .place_state_file("cosmic-comp/capture_consent.ron")
+            .ok();
+        let persisted = Self::load(&path);
+
+        let (tx, rx): (Sender<ConsentEvent>, Channel<ConsentEvent>) = channel::channel();
+        if let Err(err) = evlh.insert_source(rx, |event, _, state| {
+            if let channel::Event::Msg(event) = event {
+                match event {
+                    ConsentEvent::Request(app_id, protocol) => {
+                        state.request_consent(app_id, protocol)
+                    }
+                    ConsentEvent::Answer(app_id, protocol, answer) => {
+                        state.answer_consent(app_id, protocol, answer)
+                    }
+                    ConsentEvent::SyntheticInputChanged => state.schedule_render_all(),
+                }
+            }
+        }) {
+            warn!(?err, "Failed to listen for consent prompts");
+        }
+        let _ = EVENTS.set(tx.clone());
+
+        let decisions = Arc::new(Mutex::new(Decisions {
+            path,
+            persisted,
+            session: HashMap::new(),
+        }));
+        let placeholder_decisions = decisions.clone();
+        let placeholder_policy = policy.clone();
+        placeholder::create_globals(dh, move |client, protocol| {
+            matches!(
+                consent(
+                    &placeholder_decisions,
+                    client,
+                    protocol,
+                    &placeholder_policy
+                ),
+                Consent::Unknown(_)
+            )
+        });
+
+        ConsentState {
+            decisions,
+            tx,
+            queue: VecDeque::new(),
+            prompting: None,
+            pending_sessions: Vec::new(),
+        }
+    }
+
+    fn load(path: &Option<PathBuf>) -> ConsentDecisions {
+        path.as_deref()
+            .filter(|path| path.exists())
+            .and_then(|path| {
+                ron::de::from_reader::<_, ConsentDecisions>(
+                    OpenOptions::new().read(true).open(path).ok()?,
+                )
+                .map_err(|err| {
+                    warn!(?err, "Failed to read capture_consent.ron, resetting..");
+                    if let Err(err) = std::fs::remove_file(path) {
+                        error!(?err, "Failed to remove capture_consent.ron.");
+                    }
+                })
+                .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Consent of `client` to use `protocol`
+    pub fn consent(
+        &self,
+        client: &Client,
+        protocol: PrivilegedProtocol,
+        policy: &PolicyState,
+    ) -> Consent {
+        consent(&self.decisions, client, protocol, policy)
+    }
+
+    /// Global filter for `protocol`, taking consent into account.
+    ///
+    /// Clients yet to be asked see a placeholder instead, except for capture sessions,
+    /// which wait for the answer.
+    pub fn filter(
+        &self,
+        protocol: PrivilegedProtocol,
+        policy: &PolicyState,
+        default: fn(&Client) -> bool,
+    ) -> impl Fn(&Client) -> bool + Clone + Send + Sync + 'static {
+        let decisions = self.decisions.clone();
+        let policy = policy.clone();
+        move |client| match consent(&decisions, client, protocol, &policy) {
+            Consent::NotRequired => policy.client_allowed(client, protocol, default),
+            Consent::Granted => true,
+            Consent::Denied => false,
+            Consent::Unknown(_) => protocol == PrivilegedProtocol::Screencopy,
+        }
+    }
+
+    /// Holds back `session` until the user answered for `app_id`
+    pub fn hold_session(&mut self, app_id: String, session: Session) {
+        let _ = self.tx.send(ConsentEvent::Request(
+            app_id.clone(),
+            PrivilegedProtocol::Screencopy,
+        ));
+        self.pending_sessions.push(PendingSession {
+            app_id,
+            session,
+            frames: Vec::new(),
+        });
+    }
+
+    /// Holds back `frame`, if its session is waiting for consent
+    pub fn hold_frame(&mut self, session: &SessionRef, frame: Frame) -> Option<Frame> {
+        match self
+            .pending_sessions
+            .iter_mut()
+            .find(|pending| pending.session == *session)
+        {
+            Some(pending) => {
+                pending.frames.push(frame);
+                None
+            }
+            None => Some(frame),
+        }
+    }
+
+    /// Forgets a destroyed session, returns if it was held back
+    pub fn remove_session(&mut self, session: &SessionRef) -> bool {
+        let len = self.pending_sessions.len();
+        self.pending_sessions
+            .retain(|pending| pending.session != *session);
+        len != self.pending_sessions.len()
+    }
+}
+
+fn consent(
+    decisions: &Mutex<Decisions>,
+    client: &Client,
+    protocol: PrivilegedProtocol,
+    policy: &PolicyState,
+) -> Consent {
+    if policy.is_configured(protocol) {
+        return Consent::NotRequired;
+    }
+    let Some(data) = client.get_data::<ClientState>() else {
+        return Consent::NotRequired;
+    };
+    if data.not_sandboxed() {
+        return Consent::NotRequired;
+    }
+    let app_id = data
+        .security_context
+        .as_ref()
+        .and_then(|context| context.app_id.clone());
+    let Some(app_id) = app_id else {
+        return Consent::Denied;
+    };
+
+    match decisions.lock().unwrap().get(&app_id, protocol) {
+        Some(true) => Consent::Granted,
+        Some(false) => Consent::Denied,
+        None => Consent::Unknown(app_id),
+    }
+}
+
+impl State {
+    fn placeholder_bound(&mut self, client: &Client, protocol: PrivilegedProtocol) {
+        let consent =
+            self.common
+                .consent_state
+                .consent(client, protocol, &self.common.policy_state);
+        if let Consent::Unknown(app_id) = consent {
+            self.request_consent(app_id, protocol);
+        }
+    }
+
+    fn request_consent(&mut self, app_id: String, protocol: PrivilegedProtocol) {
+        let consent_state = &mut self.common.consent_state;
+        let request = (app_id, protocol);
+        if consent_state.prompting.as_ref() == Some(&request)
+            || consent_state.queue.contains(&request)
+        {
+            return;
+        }
+        consent_state.queue.push_back(request);
+        self.show_consent_prompt();
+    }
+
+    /// Prompts for the next queued request, unless already prompting
+    pub fn show_consent_prompt(&mut self) {
+        if self.common.consent_state.prompting.is_some()
+            || self.common.shell.read().session_lock.is_some()
+        {
+            return;
+        }
+        let Some((app_id, protocol)) = self.common.consent_state.queue.pop_front() else {
+            return;
+        };
+
+        let reply = ConsentReply(Arc::new(ReplyInner {
+            app_id: app_id.clone(),
+            protocol,
+            answered: AtomicBool::new(false),
+            tx: self.common.consent_state.tx.clone(),
+        }));
+        let output = self
+            .common
+            .shell
+            .write()
+            .show_consent_dialog(reply, self.common.event_loop_handle.clone());
+        self.common.consent_state.prompting = Some((app_id, protocol));
+        self.backend.schedule_render(&output);
+    }
+
+    /// Takes down the prompt while the session is locked, it is shown again by
+    /// [`State::show_consent_prompt`]
+    pub fn withdraw_consent_prompt(&mut self) {
+        let Some(dialog) = self.common.shell.write().hide_consent_dialog() else {
+            return;
+        };
+        dialog.reply().withdraw();
+        self.backend.schedule_render(dialog.output());
+
+        let consent_state = &mut self.common.consent_state;
+        if let Some(request) = consent_state.prompting.take() {
+            consent_state.queue.push_front(request);
+        }
+    }
+
+    fn answer_consent(
+        &mut self,
+        app_id: String,
+        protocol: PrivilegedProtocol,
+        answer: ConsentAnswer,
+    ) {
+        debug!(app_id, ?protocol, ?answer, "Consent prompt answered");
+        if let Some(dialog) = self.common.shell.write().hide_consent_dialog() {
+            self.backend.schedule_render(dialog.output());
+        }
+        let consent_state = &mut self.common.consent_state;
+        if consent_state.prompting.as_ref() == Some(&(app_id.clone(), protocol)) {
+            consent_state.prompting = None;
+        }
+
+        {
+            let mut decisions = consent_state.decisions.lock().unwrap();
+            match (answer, protocol) {
+                (ConsentAnswer::AlwaysAllow, _) => decisions.persist(&app_id, protocol, true),
+                (ConsentAnswer::Deny, _) => decisions.persist(&app_id, protocol, false),
+                // capture sessions can simply ask again
+                (_, PrivilegedProtocol::Screencopy) => {}
+                (ConsentAnswer::AllowOnce, _) => {
+                    decisions.session.insert((app_id.clone(), protocol), true);
+                }
+                (ConsentAnswer::Dismissed, _) => {
+                    decisions.session.insert((app_id.clone(), protocol), false);
+                }
+            }
+        }
+
+        if protocol == PrivilegedProtocol::Screencopy {
+            let (pending, rest) = std::mem::take(&mut consent_state.pending_sessions)
+                .into_iter()
+                .partition::<Vec<_>, _>(|pending| pending.app_id == app_id);
+            consent_state.pending_sessions = rest;
+
+            let allowed = matches!(
+                answer,
+                ConsentAnswer::AllowOnce | ConsentAnswer::AlwaysAllow
+            );
+            for PendingSession {
+                session, frames, ..
+            } in pending
+            {
+                if allowed {
+                    let session_ref = SessionRef::clone(&session);
+                    self.start_screencopy_session(session);
+                    for frame in frames {
+                        self.screencopy_frame(session_ref.clone(), frame);
+                    }
+                } else {
+                    for frame in frames {
+                        frame.fail(FailureReason::Stopped);
+                    }
+                    session.stop();
+                }
+            }
+        }
+
+        self.show_consent_prompt();
+    }
+}
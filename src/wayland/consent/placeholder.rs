@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Inert stand-ins for privileged globals, that are advertised to clients, which still need
+//! the user's consent.
+//!
+//! Binding a placeholder asks the user, all of its objects ignore their requests. Clients have
+//! to rebind, usually by reconnecting, to use the real global once allowed.
+
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use smithay::reexports::{
+    wayland_protocols::ext::data_control::v1::server::{
+        ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+        ext_data_control_manager_v1::{self, ExtDataControlManagerV1},
+        ext_data_control_source_v1::{self, ExtDataControlSourceV1},
+    },
+    wayland_protocols_misc::zwp_virtual_keyboard_v1::server::{
+        zwp_virtual_keyboard_manager_v1::{self, ZwpVirtualKeyboardManagerV1},
+        zwp_virtual_keyboard_v1::{self, ZwpVirtualKeyboardV1},
+    },
+    wayland_protocols_wlr::data_control::v1::server::{
+        zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+        zwlr_data_control_manager_v1::{self, ZwlrDataControlManagerV1},
+        zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+    },
+    wayland_protocols_wlr::virtual_pointer::v1::server::{
+        zwlr_virtual_pointer_manager_v1::{self, ZwlrVirtualPointerManagerV1},
+        zwlr_virtual_pointer_v1::{self, ZwlrVirtualPointerV1},
+    },
+    wayland_server::{
+        Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, backend::GlobalId,
+    },
+};
+
+use crate::state::State;
+
+pub struct PlaceholderGlobalData {
+    protocol: PrivilegedProtocol,
+    filter: Box<dyn for<'a> Fn(&'a Client) -> bool + Send + Sync>,
+}
+
+/// User data of placeholder objects
+#[derive(Debug)]
+pub struct Placeholder;
+
+/// Creates the placeholder globals, shown to clients matching `filter`
+pub fn create_globals<F>(dh: &DisplayHandle, filter: F) -> Vec<GlobalId>
+where
+    F: Fn(&Client, PrivilegedProtocol) -> bool + Clone + Send + Sync + 'static,
+{
+    let data = |protocol| {
+        let filter = filter.clone();
+        PlaceholderGlobalData {
+            protocol,
+            filter: Box::new(move |client| filter(client, protocol)),
+        }
+    };
+
+    vec![
+        dh.create_global::<State, ExtDataControlManagerV1, _>(
+            1,
+            data(PrivilegedProtocol::DataControl),
+        ),
+        dh.create_global::<State, ZwlrDataControlManagerV1, _>(
+            2,
+            data(PrivilegedProtocol::DataControl),
+        ),
+        dh.create_global::<State, ZwpVirtualKeyboardManagerV1, _>(
+            1,
+            data(PrivilegedProtocol::VirtualInput),
+        ),
+        dh.create_global::<State, ZwlrVirtualPointerManagerV1, _>(
+            2,
+            data(PrivilegedProtocol::VirtualInput),
+        ),
+    ]
+}
+
+impl GlobalDispatch<ExtDataControlManagerV1, PlaceholderGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        _dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ExtDataControlManagerV1>,
+        global_data: &PlaceholderGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        data_init.init(resource, Placeholder);
+        state.placeholder_bound(client, global_data.protocol);
+    }
+
+    fn can_view(client: Client, global_data: &PlaceholderGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl Dispatch<ExtDataControlManagerV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ExtDataControlManagerV1,
+        request: ext_data_control_manager_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        match request {
+            ext_data_control_manager_v1::Request::CreateDataSource { id } => {
+                data_init.init(id, Placeholder);
+            }
+            ext_data_control_manager_v1::Request::GetDataDevice { id, .. } => {
+                // tell the client right away, that the device is unusable
+                data_init.init(id, Placeholder).finished();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtDataControlDeviceV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ExtDataControlDeviceV1,
+        _request: ext_data_control_device_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, State>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtDataControlSourceV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ExtDataControlSourceV1,
+        _request: ext_data_control_source_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, State>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<ZwlrDataControlManagerV1, PlaceholderGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        _dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwlrDataControlManagerV1>,
+        global_data: &PlaceholderGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        data_init.init(resource, Placeholder);
+        state.placeholder_bound(client, global_data.protocol);
+    }
+
+    fn can_view(client: Client, global_data: &PlaceholderGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwlrDataControlManagerV1,
+        request: zwlr_data_control_manager_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        match request {
+            zwlr_data_control_manager_v1::Request::CreateDataSource { id } => {
+                data_init.init(id, Placeholder);
+            }
+            zwlr_data_control_manager_v1::Request::GetDataDevice { id, .. } => {
+                data_init.init(id, Placeholder).finished();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwlrDataControlDeviceV1,
+        _request: zwlr_data_control_device_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, State>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwlrDataControlSourceV1,
+        _request: zwlr_data_control_source_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, State>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<ZwpVirtualKeyboardManagerV1, PlaceholderGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        _dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwpVirtualKeyboardManagerV1>,
+        global_data: &PlaceholderGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        data_init.init(resource, Placeholder);
+        state.placeholder_bound(client, global_data.protocol);
+    }
+
+    fn can_view(client: Client, global_data: &PlaceholderGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwpVirtualKeyboardManagerV1,
+        request: zwp_virtual_keyboard_manager_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        if let zwp_virtual_keyboard_manager_v1::Request::CreateVirtualKeyboard { id, .. } = request
+        {
+            data_init.init(id, Placeholder);
+        }
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwpVirtualKeyboardV1,
+        _request: zwp_virtual_keyboard_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, State>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<ZwlrVirtualPointerManagerV1, PlaceholderGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        _dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwlrVirtualPointerManagerV1>,
+        global_data: &PlaceholderGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        data_init.init(resource, Placeholder);
+        state.placeholder_bound(client, global_data.protocol);
+    }
+
+    fn can_view(client: Client, global_data: &PlaceholderGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl Dispatch<ZwlrVirtualPointerManagerV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwlrVirtualPointerManagerV1,
+        request: zwlr_virtual_pointer_manager_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        match request {
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointer { id, .. }
+            | zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointerWithOutput {
+                id, ..
+            } => {
+                data_init.init(id, Placeholder);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrVirtualPointerV1, Placeholder> for State {
+    fn request(
+        _state: &mut State,
+        _client: &Client,
+        _resource: &ZwlrVirtualPointerV1,
+        _request: zwlr_virtual_pointer_v1::Request,
+        _data: &Placeholder,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, State>,
+    ) {
+    }
+}
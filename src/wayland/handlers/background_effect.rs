@@ -0,0 +1,5 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{state::State, wayland::protocols::background_effect::delegate_background_effect};
+
+delegate_background_effect!(State);
@@ -0,0 +1,5 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{state::State, wayland::protocols::color_management::delegate_color_management};
+
+delegate_color_management!(State);
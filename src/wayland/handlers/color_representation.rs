@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    state::State, wayland::protocols::color_representation::delegate_color_representation,
+};
+
+delegate_color_representation!(State);
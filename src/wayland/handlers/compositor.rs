@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{shell::grabs::SeatMoveGrabState, state::ClientState, utils::prelude::*};
+use crate::{
+    shell::grabs::SeatMoveGrabState,
+    state::ClientState,
+    utils::prelude::*,
+    wayland::handlers::{decoration::xdg_mode, session_management::RestoredToplevel},
+};
 use calloop::Interest;
 use smithay::{
     backend::renderer::{
@@ -19,6 +24,7 @@ use smithay::{
         },
         dmabuf::get_dmabuf,
         drm_syncobj::DrmSyncobjCachedState,
+        fifo::FifoBarrierCachedState,
         seat::WaylandFocus,
         shell::{
             wlr_layer::LayerSurfaceAttributes,
@@ -151,6 +157,27 @@ pub fn recursive_frame_time_estimation(
     overall_estimate
 }
 
+/// Signals the fifo barrier and due commit timers of a surface after a vblank of its primary
+/// output, returning if the client may have commits to unblock.
+pub fn signal_frame_barriers(states: &SurfaceData, frame_target: Time<Monotonic>) -> bool {
+    let mut signaled = false;
+    if let Some(barrier) = states
+        .cached_state
+        .get::<FifoBarrierCachedState>()
+        .current()
+        .barrier
+        .take()
+    {
+        barrier.signal();
+        signaled = true;
+    }
+    if let Some(commit_timer) = states.data_map.get::<CommitTimerBarrierStateUserData>() {
+        commit_timer.lock().unwrap().signal_until(frame_target);
+        signaled = true;
+    }
+    signaled
+}
+
 pub const FRAME_TIME_FILTER: KindEvaluation = KindEvaluation::Dynamic({
     fn frame_time_filter_fn(states: &SurfaceData) -> Kind {
         let clock = Clock::<Monotonic>::new();
@@ -177,6 +204,28 @@ impl CompositorHandler for State {
 
     fn new_surface(&mut self, surface: &WlSurface) {
         add_pre_commit_hook::<Self, _>(surface, move |state, _dh, surface| {
+            // frame paced commits wait for the vblank or presentation time they target,
+            // see `signal_frame_barriers`
+            let (fifo_barrier, commit_timer) = with_states(surface, |surface_data| {
+                let fifo_barrier = surface_data
+                    .cached_state
+                    .get::<FifoBarrierCachedState>()
+                    .pending()
+                    .barrier
+                    .take();
+                let commit_timer = surface_data
+                    .data_map
+                    .get::<CommitTimerBarrierStateUserData>()
+                    .and_then(|commit_timer| commit_timer.lock().unwrap().next_deadline());
+                (fifo_barrier, commit_timer)
+            });
+            if let Some(barrier) = fifo_barrier {
+                add_blocker(surface, barrier);
+            }
+            if let Some(deadline) = commit_timer {
+                add_blocker(surface, deadline);
+            }
+
             let mut acquire_point = None;
             let maybe_dmabuf = with_states(surface, |surface_data| {
                 acquire_point = surface_data
@@ -383,6 +432,19 @@ impl State {
             .find(|pending| pending.surface.wl_surface().as_deref() == Some(surface))
         {
             if let Some(toplevel) = pending.surface.0.toplevel() {
+                let rule = shell.window_rule_actions(&pending.surface);
+                if let Some(mode) = rule.decorations.or_else(|| {
+                    self.common
+                        .config
+                        .cosmic_conf
+                        .decoration_overrides
+                        .get(&pending.surface.app_id())
+                        .copied()
+                }) {
+                    pending
+                        .surface
+                        .set_decoration_override(Some(xdg_mode(mode)));
+                }
                 let initial_size = if let Some(output) = pending.fullscreen.as_ref() {
                     Some(output.geometry().size.as_logical())
                 } else if pending.maximized {
@@ -390,7 +452,13 @@ impl State {
                     let zone = layer_map_for_output(&active_output).non_exclusive_zone();
                     Some(zone.size)
                 } else {
-                    None
+                    pending
+                        .surface
+                        .user_data()
+                        .get::<RestoredToplevel>()
+                        .and_then(RestoredToplevel::floating_geometry)
+                        .map(|geometry| geometry.size)
+                        .or(rule.size.map(Size::from))
                 };
                 if toplevel_ensure_initial_configure(toplevel, initial_size)
                     && with_renderer_surface_state(surface, |state| state.buffer().is_some())
@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::state::State;
+pub use smithay::reexports::wayland_protocols::wp::content_type::v1::server::wp_content_type_v1::Type as ContentType;
+use smithay::{
+    delegate_content_type,
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    wayland::{compositor::with_states, content_type::ContentTypeSurfaceCachedState},
+};
+
+/// Kind of content `surface` displays, `ContentType::None` without a hint
+pub fn content_type(surface: &WlSurface) -> ContentType {
+    with_states(surface, |states| {
+        *states
+            .cached_state
+            .get::<ContentTypeSurfaceCachedState>()
+            .current()
+            .content_type()
+    })
+}
+
+delegate_content_type!(State);
@@ -1,7 +1,23 @@
-use crate::state::State;
+use crate::{
+    state::State,
+    wayland::audit::{self, AuditEvent},
+};
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use smithay::{
-    delegate_ext_data_control,
-    wayland::selection::ext_data_control::{DataControlHandler, DataControlState},
+    reexports::{
+        wayland_protocols::ext::data_control::v1::server::{
+            ext_data_control_device_v1::ExtDataControlDeviceV1,
+            ext_data_control_manager_v1::{self, ExtDataControlManagerV1},
+            ext_data_control_source_v1::ExtDataControlSourceV1,
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, delegate_dispatch,
+        },
+    },
+    wayland::selection::ext_data_control::{
+        DataControlDeviceUserData, DataControlHandler, DataControlManagerGlobalData,
+        DataControlSourceUserData, DataControlState,
+    },
 };
 
 impl DataControlHandler for State {
@@ -10,4 +26,53 @@ impl DataControlHandler for State {
     }
 }
 
-delegate_ext_data_control!(State);
+// `delegate_ext_data_control!`, but recording binds and created devices
+impl GlobalDispatch<ExtDataControlManagerV1, DataControlManagerGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ExtDataControlManagerV1>,
+        global_data: &DataControlManagerGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        audit::record(client, PrivilegedProtocol::DataControl, AuditEvent::Bound);
+        <DataControlState as GlobalDispatch<_, _, State>>::bind(
+            state,
+            dh,
+            client,
+            resource,
+            global_data,
+            data_init,
+        )
+    }
+
+    fn can_view(client: Client, global_data: &DataControlManagerGlobalData) -> bool {
+        <DataControlState as GlobalDispatch<ExtDataControlManagerV1, _, State>>::can_view(
+            client,
+            global_data,
+        )
+    }
+}
+
+impl Dispatch<ExtDataControlManagerV1, ()> for State {
+    fn request(
+        state: &mut State,
+        client: &Client,
+        resource: &ExtDataControlManagerV1,
+        request: ext_data_control_manager_v1::Request,
+        data: &(),
+        dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        if let ext_data_control_manager_v1::Request::GetDataDevice { .. } = &request {
+            audit::track_selection_reader(client);
+        }
+        <DataControlState as Dispatch<_, _, State>>::request(
+            state, client, resource, request, data, dh, data_init,
+        )
+    }
+}
+
+delegate_dispatch!(State: [ExtDataControlDeviceV1: DataControlDeviceUserData] => DataControlState);
+delegate_dispatch!(State: [ExtDataControlSourceV1: DataControlSourceUserData] => DataControlState);
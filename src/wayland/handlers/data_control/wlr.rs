@@ -1,9 +1,25 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
+use crate::{
+    state::State,
+    wayland::audit::{self, AuditEvent},
+};
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use smithay::{
-    delegate_data_control,
-    wayland::selection::wlr_data_control::{DataControlHandler, DataControlState},
+    reexports::{
+        wayland_protocols_wlr::data_control::v1::server::{
+            zwlr_data_control_device_v1::ZwlrDataControlDeviceV1,
+            zwlr_data_control_manager_v1::{self, ZwlrDataControlManagerV1},
+            zwlr_data_control_source_v1::ZwlrDataControlSourceV1,
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, delegate_dispatch,
+        },
+    },
+    wayland::selection::wlr_data_control::{
+        DataControlDeviceUserData, DataControlHandler, DataControlManagerGlobalData,
+        DataControlSourceUserData, DataControlState,
+    },
 };
 
 impl DataControlHandler for State {
@@ -12,4 +28,53 @@ impl DataControlHandler for State {
     }
 }
 
-delegate_data_control!(State);
+// `delegate_data_control!`, but recording binds and created devices
+impl GlobalDispatch<ZwlrDataControlManagerV1, DataControlManagerGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwlrDataControlManagerV1>,
+        global_data: &DataControlManagerGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        audit::record(client, PrivilegedProtocol::DataControl, AuditEvent::Bound);
+        <DataControlState as GlobalDispatch<_, _, State>>::bind(
+            state,
+            dh,
+            client,
+            resource,
+            global_data,
+            data_init,
+        )
+    }
+
+    fn can_view(client: Client, global_data: &DataControlManagerGlobalData) -> bool {
+        <DataControlState as GlobalDispatch<ZwlrDataControlManagerV1, _, State>>::can_view(
+            client,
+            global_data,
+        )
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn request(
+        state: &mut State,
+        client: &Client,
+        resource: &ZwlrDataControlManagerV1,
+        request: zwlr_data_control_manager_v1::Request,
+        data: &(),
+        dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        if let zwlr_data_control_manager_v1::Request::GetDataDevice { .. } = &request {
+            audit::track_selection_reader(client);
+        }
+        <DataControlState as Dispatch<_, _, State>>::request(
+            state, client, resource, request, data, dh, data_init,
+        )
+    }
+}
+
+delegate_dispatch!(State: [ZwlrDataControlDeviceV1: DataControlDeviceUserData] => DataControlState);
+delegate_dispatch!(State: [ZwlrDataControlSourceV1: DataControlSourceUserData] => DataControlState);
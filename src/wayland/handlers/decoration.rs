@@ -1,5 +1,6 @@
 use std::{cell::RefCell, sync::Mutex};
 
+use cosmic_comp_config::{CosmicCompConfig, DecorationMode};
 use smithay::{
     delegate_kde_decoration, delegate_xdg_decoration,
     desktop::Window,
@@ -21,7 +22,10 @@ use smithay::{
 };
 use wayland_backend::protocol::WEnum;
 
-use crate::state::State;
+use crate::{
+    shell::focus::target::KeyboardFocusTarget,
+    state::{Common, State},
+};
 
 pub struct PreferredDecorationMode(RefCell<Option<XdgMode>>);
 
@@ -54,6 +58,36 @@ impl PreferredDecorationMode {
     }
 }
 
+/// Decoration mode forced on a window by the user, taking precedence over the client's preference
+pub struct DecorationOverride(RefCell<Option<XdgMode>>);
+
+impl DecorationOverride {
+    pub fn mode(window: &Window) -> Option<XdgMode> {
+        window
+            .user_data()
+            .get::<DecorationOverride>()
+            .and_then(|mode| *mode.0.borrow())
+    }
+
+    pub fn update(window: &Window, update: Option<XdgMode>) {
+        let user_data = window.user_data();
+        user_data.insert_if_missing(|| DecorationOverride(RefCell::new(None)));
+        *user_data
+            .get::<DecorationOverride>()
+            .unwrap()
+            .0
+            .borrow_mut() = update;
+    }
+}
+
+/// Mode of xdg-decoration matching a configured decoration mode
+pub fn xdg_mode(mode: DecorationMode) -> XdgMode {
+    match mode {
+        DecorationMode::ServerSide => XdgMode::ServerSide,
+        DecorationMode::ClientSide => XdgMode::ClientSide,
+    }
+}
+
 pub type KdeDecorationData = Mutex<KdeDecorationSurfaceState>;
 #[derive(Debug, Default)]
 pub struct KdeDecorationSurfaceState {
@@ -65,16 +99,15 @@ impl XdgDecorationHandler for State {
     fn new_decoration(&mut self, toplevel: ToplevelSurface) {
         let shell = self.common.shell.read();
         if let Some(mapped) = shell.element_for_surface(toplevel.wl_surface()) {
-            let mode = if mapped.is_stack() {
-                XdgMode::ServerSide
-            } else {
-                XdgMode::ClientSide
-            };
-
             if let Some((window, _)) = mapped
                 .windows()
                 .find(|(window, _)| window.wl_surface().as_deref() == Some(toplevel.wl_surface()))
             {
+                let mode = if mapped.is_stack() {
+                    XdgMode::ServerSide
+                } else {
+                    DecorationOverride::mode(&window.0).unwrap_or(XdgMode::ClientSide)
+                };
                 if let Some(toplevel) = window.0.toplevel() {
                     toplevel.with_pending_state(|state| {
                         state.decoration_mode = Some(mode);
@@ -94,6 +127,7 @@ impl XdgDecorationHandler for State {
             {
                 if let Some(toplevel) = window.0.toplevel() {
                     PreferredDecorationMode::update(&window.0, Some(mode));
+                    let mode = DecorationOverride::mode(&window.0).unwrap_or(mode);
                     toplevel.with_pending_state(|state| {
                         state.decoration_mode = Some(mode);
                     });
@@ -115,7 +149,7 @@ impl XdgDecorationHandler for State {
                 if let Some(toplevel) = window.0.toplevel() {
                     PreferredDecorationMode::update(&window.0, None);
                     toplevel.with_pending_state(|state| {
-                        state.decoration_mode = None;
+                        state.decoration_mode = DecorationOverride::mode(&window.0);
                     });
                     toplevel.send_configure();
                 }
@@ -124,6 +158,50 @@ impl XdgDecorationHandler for State {
     }
 }
 
+impl State {
+    /// Switches the focused window between server- and client-side decorations
+    pub fn toggle_focused_decorations(&mut self) {
+        let shell = self.common.shell.read();
+        let Some(KeyboardFocusTarget::Element(mapped)) = shell
+            .seats
+            .last_active()
+            .get_keyboard()
+            .and_then(|keyboard| keyboard.current_focus())
+        else {
+            return;
+        };
+        // windows in stacks always use the stack's decorations
+        if mapped.is_stack() {
+            return;
+        }
+
+        let window = mapped.active_window();
+        let mode = if window.is_decorated(true) {
+            XdgMode::ServerSide
+        } else {
+            XdgMode::ClientSide
+        };
+        window.set_decoration_override(Some(mode));
+        window.send_configure();
+    }
+}
+
+impl Common {
+    /// Applies changed decoration overrides of the config to mapped windows
+    pub fn update_decoration_overrides(&mut self, previous: &CosmicCompConfig) {
+        for mapped in self.shell.read().mapped().filter(|m| !m.is_stack()) {
+            let window = mapped.active_window();
+            let app_id = window.app_id();
+            let mode = self.config.cosmic_conf.decoration_overrides.get(&app_id);
+            if mode == previous.decoration_overrides.get(&app_id) {
+                continue;
+            }
+            window.set_decoration_override(mode.copied().map(xdg_mode));
+            window.send_configure();
+        }
+    }
+}
+
 impl KdeDecorationHandler for State {
     fn kde_decoration_state(&self) -> &KdeDecorationState {
         &self.common.kde_decoration_state
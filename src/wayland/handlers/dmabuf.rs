@@ -1,13 +1,23 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::{BackendData, State};
+use crate::state::{BackendData, ClientState, State};
+use calloop::timer::{TimeoutAction, Timer};
 use smithay::{
-    backend::allocator::dmabuf::Dmabuf,
+    backend::allocator::{Buffer, dmabuf::Dmabuf},
     delegate_dmabuf,
-    reexports::wayland_server::Resource,
+    reexports::wayland_server::{Client, Resource},
     wayland::dmabuf::{DmabufGlobal, DmabufHandler, DmabufState, ImportNotifier},
 };
-use tracing::warn;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+/// Delay before retrying a failed import, giving the driver a chance to recover
+const RETRY_DELAY: Duration = Duration::from_millis(16);
+/// Minimum time between warnings about failed imports
+const WARN_INTERVAL: Duration = Duration::from_secs(10);
 
 impl DmabufHandler for State {
     fn dmabuf_state(&mut self) -> &mut DmabufState {
@@ -19,13 +29,42 @@ impl DmabufHandler for State {
         global: &DmabufGlobal,
         dmabuf: Dmabuf,
         import_notifier: ImportNotifier,
+    ) {
+        self.import_dmabuf(global, dmabuf, import_notifier, true);
+    }
+}
+
+impl State {
+    fn import_dmabuf(
+        &mut self,
+        global: &DmabufGlobal,
+        dmabuf: Dmabuf,
+        import_notifier: ImportNotifier,
+        retry: bool,
     ) {
         match self
             .backend
-            .dmabuf_imported(import_notifier.client(), global, dmabuf)
+            .dmabuf_imported(import_notifier.client(), global, dmabuf.clone())
         {
+            Err(err) if retry => {
+                debug!(?err, "dmabuf import failed, retrying");
+                let global = global.clone();
+                let mut pending = Some((dmabuf, import_notifier));
+                if let Err(err) = self.common.event_loop_handle.insert_source(
+                    Timer::from_duration(RETRY_DELAY),
+                    move |_, _, state| {
+                        if let Some((dmabuf, import_notifier)) = pending.take() {
+                            state.import_dmabuf(&global, dmabuf, import_notifier, false);
+                        }
+                        TimeoutAction::Drop
+                    },
+                ) {
+                    warn!(?err, "Failed to schedule dmabuf import retry");
+                }
+            }
             Err(err) => {
-                tracing::debug!(?err, "dmabuf import failed");
+                warn_import_failed(import_notifier.client().as_ref(), &dmabuf, err);
+                // lets the client fall back to another allocation or wl_shm
                 import_notifier.failed()
             }
             Ok(Some(node)) => {
@@ -54,4 +93,21 @@ impl DmabufHandler for State {
     }
 }
 
+fn warn_import_failed(client: Option<&Client>, dmabuf: &Dmabuf, err: anyhow::Error) {
+    static LAST_WARNING: Mutex<Option<Instant>> = Mutex::new(None);
+
+    let format = dmabuf.format();
+    let mut last_warning = LAST_WARNING.lock().unwrap();
+    if last_warning.is_some_and(|last| last.elapsed() < WARN_INTERVAL) {
+        debug!(?err, ?format, "dmabuf import failed");
+        return;
+    }
+    *last_warning = Some(Instant::now());
+
+    let pid = client
+        .and_then(|client| client.get_data::<ClientState>())
+        .and_then(|data| data.pid);
+    warn!(?err, ?format, ?pid, "Failed to import client dmabuf");
+}
+
 delegate_dmabuf!(State);
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::output::Output;
+use tracing::warn;
+
+use crate::{
+    state::{BackendData, State},
+    utils::gamma::GammaRamp,
+    wayland::protocols::gamma_control::{
+        GammaControlHandler, GammaControlState, delegate_gamma_control,
+    },
+};
+
+impl GammaControlHandler for State {
+    fn gamma_control_state(&mut self) -> &mut GammaControlState {
+        &mut self.common.gamma_control_state
+    }
+
+    fn gamma_size(&mut self, output: &Output) -> Option<u32> {
+        match &self.backend {
+            BackendData::Kms(kms) => kms.gamma_size(output),
+            _ => None,
+        }
+    }
+
+    fn set_gamma(&mut self, output: &Output, ramp: Option<GammaRamp>) -> bool {
+        let BackendData::Kms(kms) = &mut self.backend else {
+            return false;
+        };
+        match kms.set_client_gamma(output, ramp) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!(?err, "Failed to set gamma ramp of {}", output.name());
+                false
+            }
+        }
+    }
+}
+
+delegate_gamma_control!(State);
@@ -1,12 +1,42 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
+use crate::{shell::SeatExt, state::State};
 use smithay::{
     delegate_keyboard_shortcuts_inhibit,
     wayland::keyboard_shortcuts_inhibit::{
         KeyboardShortcutsInhibitHandler, KeyboardShortcutsInhibitState, KeyboardShortcutsInhibitor,
+        KeyboardShortcutsInhibitorSeat,
     },
 };
+use tracing::debug;
+
+impl State {
+    /// Shows the shortcuts inhibit indicator, while the keyboard focus of the last active seat
+    /// inhibits shortcuts, and hides it otherwise
+    pub fn update_shortcuts_inhibit_indicator(&mut self) {
+        let mut shell = self.common.shell.write();
+        let seat = shell.seats.last_active().clone();
+        let inhibited = seat
+            .get_keyboard()
+            .unwrap()
+            .current_focus()
+            .and_then(|focus| focus.wl_surface().map(|surface| surface.into_owned()))
+            .is_some_and(|surface| {
+                seat.keyboard_shortcuts_inhibitor_for_surface(&surface)
+                    .is_some_and(|inhibitor| inhibitor.is_active())
+            });
+        let output = inhibited.then(|| seat.focused_or_active_output());
+        let outputs = shell.set_shortcuts_inhibit_indicator(
+            output.as_ref(),
+            self.common.event_loop_handle.clone(),
+        );
+        std::mem::drop(shell);
+
+        for output in outputs {
+            self.backend.schedule_render(&output);
+        }
+    }
+}
 
 impl KeyboardShortcutsInhibitHandler for State {
     fn keyboard_shortcuts_inhibit_state(&mut self) -> &mut KeyboardShortcutsInhibitState {
@@ -14,8 +44,34 @@ impl KeyboardShortcutsInhibitHandler for State {
     }
 
     fn new_inhibitor(&mut self, inhibitor: KeyboardShortcutsInhibitor) {
-        // TODO: Restrict what apps can inhibit shortcuts
+        let app_id = self
+            .common
+            .shell
+            .read()
+            .element_for_surface(inhibitor.wl_surface())
+            .and_then(|mapped| {
+                mapped
+                    .windows()
+                    .find(|(window, _)| window == inhibitor.wl_surface())
+                    .map(|(window, _)| window.app_id())
+            });
+        if !self
+            .common
+            .config
+            .cosmic_conf
+            .keyboard_shortcuts_inhibit
+            .allows(app_id.as_deref())
+        {
+            debug!(?app_id, "Denied keyboard shortcuts inhibitor.");
+            return;
+        }
+
         inhibitor.activate();
+        self.update_shortcuts_inhibit_indicator();
+    }
+
+    fn inhibitor_destroyed(&mut self, _inhibitor: KeyboardShortcutsInhibitor) {
+        self.update_shortcuts_inhibit_indicator();
     }
 }
 
@@ -6,11 +6,15 @@ use smithay::{
     desktop::{LayerSurface, PopupKind, WindowSurfaceType, layer_map_for_output},
     output::Output,
     reexports::wayland_server::protocol::wl_output::WlOutput,
-    wayland::shell::{
-        wlr_layer::{
-            Layer, LayerSurface as WlrLayerSurface, WlrLayerShellHandler, WlrLayerShellState,
+    wayland::{
+        compositor::with_states,
+        fractional_scale::with_fractional_scale,
+        shell::{
+            wlr_layer::{
+                Layer, LayerSurface as WlrLayerSurface, WlrLayerShellHandler, WlrLayerShellState,
+            },
+            xdg::PopupSurface,
         },
-        xdg::PopupSurface,
     },
 };
 
@@ -32,6 +36,13 @@ impl WlrLayerShellHandler for State {
             .as_ref()
             .and_then(Output::from_resource)
             .unwrap_or_else(|| seat.active_output());
+        // the fractional scale object may have been created before the role was assigned,
+        // so the output the surface is placed on was unknown back then
+        with_states(surface.wl_surface(), |states| {
+            with_fractional_scale(states, |fractional_scale| {
+                fractional_scale.set_preferred_scale(output.current_scale().fractional_scale());
+            });
+        });
         shell.pending_layers.push(PendingLayer {
             surface: LayerSurface::new(surface, namespace),
             output,
@@ -2,8 +2,13 @@
 
 pub mod a11y;
 pub mod alpha_modifier;
+pub mod background_effect;
 pub mod buffer;
+pub mod color_management;
+pub mod color_representation;
+pub mod commit_timing;
 pub mod compositor;
+pub mod content_type;
 pub mod corner_radius;
 pub mod data_control;
 pub mod data_device;
@@ -12,8 +17,10 @@ pub mod dmabuf;
 pub mod drm;
 pub mod drm_lease;
 pub mod drm_syncobj;
+pub mod fifo;
 pub mod foreign_toplevel_list;
 pub mod fractional_scale;
+pub mod gamma_control;
 pub mod idle_inhibit;
 pub mod idle_notify;
 pub mod image_capture_source;
@@ -26,6 +33,7 @@ pub mod output_power;
 pub mod overlap_notify;
 pub mod pointer_constraints;
 pub mod pointer_gestures;
+pub mod pointer_warp;
 pub mod presentation;
 pub mod primary_selection;
 pub mod relative_pointer;
@@ -34,17 +42,22 @@ pub mod seat;
 pub mod security_context;
 pub mod selection;
 pub mod session_lock;
+pub mod session_management;
 pub mod shm;
 pub mod single_pixel_buffer;
 pub mod tablet_manager;
+pub mod tearing_control;
 pub mod text_input;
 pub mod toplevel_info;
 pub mod toplevel_management;
+pub mod toplevel_tag;
 pub mod viewporter;
 pub mod virtual_keyboard;
+pub mod virtual_pointer;
 pub mod workspace;
 pub mod xdg_activation;
 pub mod xdg_foreign;
 pub mod xdg_shell;
+pub mod xdg_system_bell;
 pub mod xwayland_keyboard_grab;
 pub mod xwayland_shell;
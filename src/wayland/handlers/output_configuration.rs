@@ -40,9 +40,19 @@ impl OutputConfigurationHandler for State {
 }
 
 impl State {
-    fn output_configuration(
+    pub(crate) fn output_configuration(
         &mut self,
         test_only: bool,
+        conf: Vec<(Output, OutputConfiguration)>,
+    ) -> bool {
+        self.apply_output_configuration(test_only, true, conf)
+    }
+
+    /// Applies `conf`, only writing it to the config, if `persist` is set
+    pub(crate) fn apply_output_configuration(
+        &mut self,
+        test_only: bool,
+        persist: bool,
         mut conf: Vec<(Output, OutputConfiguration)>,
     ) -> bool {
         if conf
@@ -223,9 +233,11 @@ impl State {
         {
             self.common.output_configuration_state.disable_head(output);
         }
-        self.common
-            .config
-            .write_outputs(self.common.output_configuration_state.outputs());
+        if persist {
+            self.common
+                .config
+                .write_outputs(self.common.output_configuration_state.outputs());
+        }
         self.common.event_loop_handle.insert_idle(move |state| {
             state.common.output_configuration_state.update();
         });
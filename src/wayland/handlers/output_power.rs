@@ -25,6 +25,20 @@ pub fn set_all_surfaces_dpms_on(state: &mut State) {
     }
 }
 
+pub fn set_all_surfaces_dpms_off(state: &mut State) {
+    let mut changed = false;
+    for surface in kms_surfaces(state) {
+        if surface.get_dpms() {
+            surface.set_dpms(false);
+            changed = true;
+        }
+    }
+
+    if changed {
+        OutputPowerState::refresh(state);
+    }
+}
+
 fn kms_surfaces(state: &mut State) -> impl Iterator<Item = &mut Surface> {
     if let BackendData::Kms(kms_state) = &mut state.backend {
         Some(
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::{
+    input::pointer::MotionEvent,
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    utils::{Logical, Point, SERIAL_COUNTER, Serial},
+    wayland::{
+        pointer_constraints::{PointerConstraint, with_pointer_constraint},
+        seat::WaylandFocus,
+    },
+};
+
+use crate::{
+    state::State,
+    utils::prelude::*,
+    wayland::protocols::pointer_warp::{PointerWarpHandler, delegate_pointer_warp},
+};
+
+impl PointerWarpHandler for State {
+    fn warp_pointer(
+        &mut self,
+        surface: &WlSurface,
+        location: Point<f64, Logical>,
+        _serial: Serial,
+    ) {
+        // Enter serials of pointers aren't exposed, so instead of checking the serial,
+        // the surface has to have pointer focus right now.
+        let shell = self.common.shell.read();
+        let Some(seat) = shell
+            .seats
+            .iter()
+            .find(|seat| {
+                seat.get_pointer()
+                    .and_then(|pointer| pointer.current_focus())
+                    .is_some_and(|focus| focus.wl_surface().as_deref() == Some(surface))
+            })
+            .cloned()
+        else {
+            return;
+        };
+        let ptr = seat.get_pointer().unwrap();
+        let current_output = seat.active_output();
+        let Some((_, surface_loc)) =
+            State::surface_under(ptr.current_location().as_global(), &current_output, &shell)
+                .filter(|(target, _)| target.wl_surface().as_deref() == Some(surface))
+        else {
+            return;
+        };
+
+        // Locked pointers must stay in place, confined ones within their region
+        let allowed = with_pointer_constraint(surface, &ptr, |constraint| match constraint {
+            Some(constraint) if constraint.is_active() => match &*constraint {
+                PointerConstraint::Locked(_) => false,
+                PointerConstraint::Confined(confine) => confine
+                    .region()
+                    .is_none_or(|region| region.contains(location.to_i32_round())),
+            },
+            _ => true,
+        });
+        if !allowed {
+            return;
+        }
+
+        // Only warp to where the surface accepts input, without moving the pointer
+        // onto other windows or outputs of other clients
+        let position = surface_loc + location.as_global();
+        let Some(output) = shell
+            .outputs()
+            .find(|output| output.geometry().to_f64().contains(position))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(under) = State::surface_under(position, &output, &shell)
+            .filter(|(target, loc)| {
+                target.wl_surface().as_deref() == Some(surface) && *loc == surface_loc
+            })
+            .map(|(target, loc)| (target, loc.as_logical()))
+        else {
+            return;
+        };
+        std::mem::drop(shell);
+
+        seat.set_active_output(&output);
+        ptr.motion(
+            self,
+            Some(under),
+            &MotionEvent {
+                location: position.as_logical(),
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.common.clock.now().as_millis(),
+            },
+        );
+        ptr.frame(self);
+    }
+}
+
+delegate_pointer_warp!(State);
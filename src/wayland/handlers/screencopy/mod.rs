@@ -1,5 +1,6 @@
 use std::{borrow::Borrow, collections::HashMap, sync::Mutex};
 
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use smithay::{
     backend::{
         allocator::{Fourcc, Modifier},
@@ -24,11 +25,16 @@ use crate::{
     utils::prelude::{
         OutputExt, PointExt, PointGlobalExt, PointLocalExt, RectExt, RectLocalExt, SeatExt,
     },
-    wayland::protocols::{
-        image_capture_source::ImageCaptureSourceData,
-        screencopy::{
-            BufferConstraints, CursorSession, CursorSessionRef, DmabufConstraints, Frame, FrameRef,
-            ScreencopyHandler, ScreencopyState, Session, SessionRef, delegate_screencopy,
+    wayland::{
+        audit::{self, AuditEvent},
+        consent::Consent,
+        protocols::{
+            image_capture_source::ImageCaptureSourceData,
+            screencopy::{
+                BufferConstraints, CursorSession, CursorSessionRef, DmabufConstraints, Frame,
+                FrameRef, ScreencopyHandler, ScreencopyState, Session, SessionRef,
+                delegate_screencopy,
+            },
         },
     },
 };
@@ -85,50 +91,34 @@ impl ScreencopyHandler for State {
     }
 
     fn new_session(&mut self, session: Session) {
-        match session.source() {
-            ImageCaptureSourceData::Output(weak) => {
-                let Some(mut output) = weak.upgrade() else {
-                    session.stop();
-                    return;
-                };
-
-                session.user_data().insert_if_missing_threadsafe(|| {
-                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
-                        &output,
-                    )))
-                });
-
-                output.add_session(session);
-            }
-            ImageCaptureSourceData::Workspace(handle) => {
-                let mut shell = self.common.shell.write();
-                let Some(workspace) = shell.workspaces.space_for_handle_mut(&handle) else {
-                    session.stop();
-                    return;
-                };
-
-                session.user_data().insert_if_missing_threadsafe(|| {
-                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
-                        workspace.output(),
-                    )))
-                });
-                workspace.add_session(session);
-            }
-            ImageCaptureSourceData::Toplevel(mut toplevel) => {
-                let size = toplevel.geometry().size.to_physical(1);
-                session.user_data().insert_if_missing_threadsafe(|| {
-                    Mutex::new(SessionUserData::new(OutputDamageTracker::new(
-                        size,
-                        1.0,
-                        Transform::Normal,
-                    )))
-                });
-                toplevel.add_session(session);
+        let consent = session.client().map(|client| {
+            self.common.consent_state.consent(
+                &client,
+                PrivilegedProtocol::Screencopy,
+                &self.common.policy_state,
+            )
+        });
+        match consent {
+            Some(Consent::Denied) => session.stop(),
+            Some(Consent::Unknown(app_id)) => {
+                self.common.consent_state.hold_session(app_id, session)
             }
-            ImageCaptureSourceData::Destroyed => unreachable!(),
+            _ => self.start_screencopy_session(session),
         }
     }
     fn new_cursor_session(&mut self, session: CursorSession) {
+        let denied = session.client().is_some_and(|client| {
+            self.common.consent_state.consent(
+                &client,
+                PrivilegedProtocol::Screencopy,
+                &self.common.policy_state,
+            ) == Consent::Denied
+        });
+        if denied {
+            session.stop();
+            return;
+        }
+
         let (pointer_loc, pointer_size, hotspot) = {
             let seat = self.common.shell.read().seats.last_active().clone();
 
@@ -243,22 +233,8 @@ impl ScreencopyHandler for State {
     }
 
     fn frame(&mut self, session: SessionRef, frame: Frame) {
-        match session.source() {
-            ImageCaptureSourceData::Output(weak) => {
-                let Some(mut output) = weak.upgrade() else {
-                    return;
-                };
-
-                output.add_frame(session, frame);
-                self.backend.schedule_render(&output);
-            }
-            ImageCaptureSourceData::Workspace(handle) => {
-                render_workspace_to_buffer(self, session, frame, handle)
-            }
-            ImageCaptureSourceData::Toplevel(toplevel) => {
-                render_window_to_buffer(self, session, frame, &toplevel)
-            }
-            ImageCaptureSourceData::Destroyed => unreachable!(),
+        if let Some(frame) = self.common.consent_state.hold_frame(&session, frame) {
+            self.screencopy_frame(session, frame);
         }
     }
 
@@ -280,6 +256,17 @@ impl ScreencopyHandler for State {
     }
 
     fn session_destroyed(&mut self, session: SessionRef) {
+        if self.common.consent_state.remove_session(&session) {
+            return;
+        }
+        if let Some(client) = session.client() {
+            audit::record(
+                &client,
+                PrivilegedProtocol::Screencopy,
+                AuditEvent::CaptureStopped,
+            );
+        }
+
         match session.source() {
             ImageCaptureSourceData::Output(weak) => {
                 if let Some(mut output) = weak.upgrade() {
@@ -328,6 +315,81 @@ impl ScreencopyHandler for State {
     }
 }
 
+impl State {
+    pub fn start_screencopy_session(&mut self, session: Session) {
+        if let Some(client) = session.client() {
+            audit::record(
+                &client,
+                PrivilegedProtocol::Screencopy,
+                AuditEvent::CaptureStarted,
+            );
+        }
+
+        match session.source() {
+            ImageCaptureSourceData::Output(weak) => {
+                let Some(mut output) = weak.upgrade() else {
+                    session.stop();
+                    return;
+                };
+
+                session.user_data().insert_if_missing_threadsafe(|| {
+                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
+                        &output,
+                    )))
+                });
+
+                output.add_session(session);
+            }
+            ImageCaptureSourceData::Workspace(handle) => {
+                let mut shell = self.common.shell.write();
+                let Some(workspace) = shell.workspaces.space_for_handle_mut(&handle) else {
+                    session.stop();
+                    return;
+                };
+
+                session.user_data().insert_if_missing_threadsafe(|| {
+                    Mutex::new(SessionUserData::new(OutputDamageTracker::from_output(
+                        workspace.output(),
+                    )))
+                });
+                workspace.add_session(session);
+            }
+            ImageCaptureSourceData::Toplevel(mut toplevel) => {
+                let size = toplevel.geometry().size.to_physical(1);
+                session.user_data().insert_if_missing_threadsafe(|| {
+                    Mutex::new(SessionUserData::new(OutputDamageTracker::new(
+                        size,
+                        1.0,
+                        Transform::Normal,
+                    )))
+                });
+                toplevel.add_session(session);
+            }
+            ImageCaptureSourceData::Destroyed => unreachable!(),
+        }
+    }
+
+    pub fn screencopy_frame(&mut self, session: SessionRef, frame: Frame) {
+        match session.source() {
+            ImageCaptureSourceData::Output(weak) => {
+                let Some(mut output) = weak.upgrade() else {
+                    return;
+                };
+
+                output.add_frame(session, frame);
+                self.backend.schedule_render(&output);
+            }
+            ImageCaptureSourceData::Workspace(handle) => {
+                render_workspace_to_buffer(self, session, frame, handle)
+            }
+            ImageCaptureSourceData::Toplevel(toplevel) => {
+                render_window_to_buffer(self, session, frame, &toplevel)
+            }
+            ImageCaptureSourceData::Destroyed => unreachable!(),
+        }
+    }
+}
+
 fn constraints_for_output(output: &Output, backend: &mut BackendData) -> Option<BufferConstraints> {
     let mode = match output.current_mode() {
         Some(mode) => mode.size.to_logical(1).to_buffer(1, Transform::Normal),
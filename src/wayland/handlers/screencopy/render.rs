@@ -11,7 +11,7 @@ use smithay::{
                 surface::WaylandSurfaceRenderElement,
                 utils::{Relocate, RelocateRenderElement},
             },
-            gles::{GlesError, GlesRenderbuffer},
+            gles::{GlesError, GlesRenderbuffer, GlesTexture},
             sync::SyncPoint,
             utils::with_renderer_surface_state,
         },
@@ -302,7 +302,14 @@ pub fn render_workspace_to_buffer(
         handle: (WorkspaceHandle, usize),
     ) -> Result<RenderOutputResult<'d>, DTError<R::Error>>
     where
-        R: Renderer + ImportAll + ImportMem + ExportMem + Bind<Dmabuf> + Blit + AsGlowRenderer,
+        R: Renderer
+            + ImportAll
+            + ImportMem
+            + ExportMem
+            + Bind<Dmabuf>
+            + Bind<GlesTexture>
+            + Blit
+            + AsGlowRenderer,
         R::TextureId: Send + Clone + 'static,
         R::Error: FromGlesError,
         CosmicElement<R>: RenderElement<R>,
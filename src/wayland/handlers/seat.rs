@@ -29,6 +29,11 @@ impl SeatHandler for State {
         _seat: &smithay::input::Seat<Self>,
         _focused: Option<&Self::KeyboardFocus>,
     ) {
+        crate::dbus::control::focus_changed();
+        // the shell may still be locked, while the focus changes
+        self.common
+            .event_loop_handle
+            .insert_idle(|state| state.update_shortcuts_inhibit_indicator());
     }
 
     fn led_state_changed(&mut self, seat: &smithay::input::Seat<Self>, led_state: LedState) {
@@ -1,25 +1,78 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
+use crate::{
+    state::{Common, State},
+    wayland::{
+        audit,
+        clipboard::{self, SelectionOwner},
+    },
+};
 use smithay::{
     input::Seat,
     wayland::selection::{SelectionHandler, SelectionSource, SelectionTarget},
-    xwayland::xwm::XwmId,
 };
 use std::os::unix::io::OwnedFd;
 use tracing::warn;
 
 impl SelectionHandler for State {
-    type SelectionUserData = XwmId;
+    type SelectionUserData = SelectionOwner;
 
     fn new_selection(
         &mut self,
         target: SelectionTarget,
         source: Option<SelectionSource>,
+        seat: Seat<State>,
+    ) {
+        let mime_types = source.map(|source| source.mime_types());
+        if mime_types.is_some() {
+            audit::selection_offered();
+        }
+        if matches!(target, SelectionTarget::Clipboard) {
+            match &mime_types {
+                Some(mime_types) => clipboard::snapshot(self, &seat, mime_types.clone()),
+                None => clipboard::selection_dropped(self, &seat),
+            }
+        }
+
+        self.common.sync_xwayland_selection(target, mime_types);
+    }
+
+    fn send_selection(
+        &mut self,
+        target: SelectionTarget,
+        mime_type: String,
+        fd: OwnedFd,
         _seat: Seat<State>,
+        user_data: &Self::SelectionUserData,
+    ) {
+        match user_data {
+            SelectionOwner::Xwm(_) => {
+                if let Some(xwm) = self
+                    .common
+                    .xwayland_state
+                    .as_mut()
+                    .and_then(|xstate| xstate.xwm.as_mut())
+                {
+                    if let Err(err) = xwm.send_selection(target, mime_type, fd) {
+                        warn!(?err, "Failed to send selection (X11 -> Wayland).");
+                    }
+                }
+            }
+            SelectionOwner::Persisted(selection) => {
+                selection.send(&mime_type, fd, &self.common.event_loop_handle)
+            }
+        }
+    }
+}
+
+impl Common {
+    /// Forwards a selection set by wayland clients to Xwayland, once it has keyboard focus
+    pub fn sync_xwayland_selection(
+        &mut self,
+        target: SelectionTarget,
+        mime_types: Option<Vec<String>>,
     ) {
         let Some(xwm_id) = self
-            .common
             .xwayland_state
             .as_ref()
             .and_then(|xstate| xstate.xwm.as_ref())
@@ -28,52 +81,34 @@ impl SelectionHandler for State {
             return;
         };
 
-        let x_has_focus = self.common.has_x_keyboard_focus(xwm_id);
+        let x_has_focus = self.has_x_keyboard_focus(xwm_id);
 
-        let xstate = self.common.xwayland_state.as_mut().unwrap();
+        let xstate = self.xwayland_state.as_mut().unwrap();
         let xwm = xstate.xwm.as_mut().unwrap();
 
-        if let Some(source) = &source {
+        if let Some(mime_types) = mime_types {
             if x_has_focus {
-                if let Err(err) = xwm.new_selection(target, Some(source.mime_types())) {
-                    warn!(?err, "Failed to set Xwayland clipboard selection.");
+                if let Err(err) = xwm.new_selection(target, Some(mime_types)) {
+                    warn!(?err, ?target, "Failed to set Xwayland selection.");
                 }
             } else {
                 match target {
                     SelectionTarget::Clipboard => {
-                        xstate.clipboard_selection_dirty = Some(source.mime_types())
-                    }
-                    SelectionTarget::Primary => {
-                        xstate.primary_selection_dirty = Some(source.mime_types())
+                        xstate.clipboard_selection_dirty = Some(mime_types)
                     }
+                    SelectionTarget::Primary => xstate.primary_selection_dirty = Some(mime_types),
                 };
             }
         } else {
             if let Err(err) = xwm.new_selection(target, None) {
-                warn!(?err, "Failed to clear Xwayland selection.");
-            }
-            xstate.clipboard_selection_dirty = None;
-            xstate.primary_selection_dirty = None;
-        }
-    }
-
-    fn send_selection(
-        &mut self,
-        target: SelectionTarget,
-        mime_type: String,
-        fd: OwnedFd,
-        _seat: Seat<State>,
-        _user_data: &Self::SelectionUserData,
-    ) {
-        if let Some(xwm) = self
-            .common
-            .xwayland_state
-            .as_mut()
-            .and_then(|xstate| xstate.xwm.as_mut())
-        {
-            if let Err(err) = xwm.send_selection(target, mime_type, fd) {
-                warn!(?err, "Failed to send selection (X11 -> Wayland).");
+                warn!(?err, ?target, "Failed to clear Xwayland selection.");
             }
+            // only drop the pending update of the cleared selection,
+            // clearing PRIMARY mustn't lose a clipboard set while X11 wasn't focused
+            match target {
+                SelectionTarget::Clipboard => xstate.clipboard_selection_dirty = None,
+                SelectionTarget::Primary => xstate.primary_selection_dirty = None,
+            };
         }
     }
 }
@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{shell::SessionLock, state::State, utils::prelude::*};
+use crate::{
+    shell::{SessionLock, lock_fallback::LockFallback},
+    state::State,
+    utils::prelude::*,
+};
+use calloop::timer::{TimeoutAction, Timer};
 use smithay::{
     delegate_session_lock,
     output::Output,
@@ -10,7 +15,24 @@ use smithay::{
         LockSurface, SessionLockHandler, SessionLockManagerState, SessionLocker,
     },
 };
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+#[cfg(feature = "systemd")]
+use {
+    crate::{dbus::logind::SleepEvent, state::BackendData},
+    smithay::{desktop::utils::surface_primary_scanout_output, wayland::compositor::with_states},
+    tracing::debug,
+};
+
+/// Time given to the screen locker to cover all outputs, before the built-in lock screen is shown
+const LOCK_SURFACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time given to the screen locker to show up, before suspending anyway
+#[cfg(feature = "systemd")]
+const SLEEP_LOCK_TIMEOUT: Duration = Duration::from_secs(3);
 
 impl SessionLockHandler for State {
     fn lock_state(&mut self) -> &mut SessionLockManagerState {
@@ -37,11 +59,28 @@ impl SessionLockHandler for State {
         shell.session_lock = Some(SessionLock {
             ext_session_lock,
             surfaces: HashMap::new(),
+            locked_at: Instant::now(),
+            fallbacks: HashMap::new(),
         });
 
         for output in shell.outputs() {
             self.backend.schedule_render(output);
         }
+        std::mem::drop(shell);
+
+        // both would take the keyboard from the lock screen
+        self.withdraw_consent_prompt();
+        self.finish_screenshot_selection(None);
+
+        if let Err(err) = self.common.event_loop_handle.insert_source(
+            Timer::from_duration(LOCK_SURFACE_TIMEOUT),
+            |_, _, state| {
+                state.refresh_session_lock_fallback();
+                TimeoutAction::Drop
+            },
+        ) {
+            warn!(?err, "Failed to schedule the session lock fallback");
+        }
     }
 
     fn unlock(&mut self) {
@@ -51,6 +90,10 @@ impl SessionLockHandler for State {
         for output in shell.outputs() {
             self.backend.schedule_render(output);
         }
+        std::mem::drop(shell);
+
+        // prompts are held back while locked
+        self.show_consent_prompt();
     }
 
     fn new_surface(&mut self, lock_surface: LockSurface, wl_output: WlOutput) {
@@ -70,4 +113,141 @@ impl SessionLockHandler for State {
     }
 }
 
+impl State {
+    /// Shows the built-in lock screen on outputs, that aren't covered by the screen locker.
+    ///
+    /// That is every output once the locker died, or outputs it didn't create a lock surface
+    /// for within [`LOCK_SURFACE_TIMEOUT`], so they are neither blank nor exposed.
+    pub fn refresh_session_lock_fallback(&mut self) {
+        let mut shell = self.common.shell.write();
+        let outputs = shell.outputs().cloned().collect::<Vec<_>>();
+        let theme = shell.theme().clone();
+        let Some(session_lock) = shell.session_lock.as_mut() else {
+            return;
+        };
+
+        let locker_alive = session_lock.ext_session_lock.is_alive();
+        let timed_out = session_lock.locked_at.elapsed() >= LOCK_SURFACE_TIMEOUT;
+        let mut changed = Vec::new();
+        for output in &outputs {
+            let covered = locker_alive
+                && (!timed_out
+                    || session_lock
+                        .surfaces
+                        .get(output)
+                        .is_some_and(|surface| surface.wl_surface().is_alive()));
+            if covered {
+                if session_lock.fallbacks.remove(output).is_some() {
+                    changed.push(output.clone());
+                }
+            } else if session_lock
+                .fallbacks
+                .get(output)
+                .is_none_or(|fallback| fallback.is_outdated(output))
+            {
+                if !locker_alive && session_lock.fallbacks.is_empty() {
+                    warn!("Screen locker died, showing the built-in lock screen");
+                }
+                session_lock.fallbacks.insert(
+                    output.clone(),
+                    LockFallback::new(output, self.common.event_loop_handle.clone(), theme.clone()),
+                );
+                changed.push(output.clone());
+            }
+        }
+        session_lock
+            .fallbacks
+            .retain(|output, _| outputs.contains(output));
+        std::mem::drop(shell);
+
+        for output in changed {
+            self.backend.schedule_render(&output);
+        }
+    }
+}
+
+#[cfg(feature = "systemd")]
+impl State {
+    /// Handles logind's `PrepareForSleep`.
+    ///
+    /// Before suspending the session is locked and suspend delayed, until the lock screen
+    /// was presented on every output, so no desktop contents are shown after resuming.
+    pub fn prepare_for_sleep(&mut self, event: SleepEvent) {
+        if let SleepEvent::Resumed(fd) = event {
+            if let Some(token) = self.common.sleep_lock_timeout.take() {
+                self.common.event_loop_handle.remove(token);
+            }
+            // replacing an inhibitor, that is still held, releases it
+            if matches!(self.backend, BackendData::Kms(_)) {
+                self.common.inhibit_sleep_fd = fd;
+            }
+            return;
+        }
+
+        if self.common.inhibit_sleep_fd.is_none() {
+            return;
+        }
+        if self.common.shell.read().session_lock.is_none() {
+            std::thread::spawn(|| {
+                if let Err(err) = crate::dbus::logind::lock_session() {
+                    warn!(?err, "Failed to lock the session");
+                }
+            });
+        }
+
+        match self.common.event_loop_handle.insert_source(
+            Timer::from_duration(SLEEP_LOCK_TIMEOUT),
+            |_, _, state| {
+                warn!("Screen locker didn't show up in time, suspending anyway");
+                state.common.sleep_lock_timeout = None;
+                state.common.inhibit_sleep_fd = None;
+                TimeoutAction::Drop
+            },
+        ) {
+            Ok(token) => self.common.sleep_lock_timeout = Some(token),
+            Err(_) => {
+                self.common.inhibit_sleep_fd = None;
+                return;
+            }
+        }
+
+        for output in self.common.shell.read().outputs() {
+            self.backend.schedule_render(output);
+        }
+        self.check_sleep_lock();
+    }
+
+    /// Allows suspending to continue, once every output presented its lock surface
+    pub fn check_sleep_lock(&mut self) {
+        if self.common.sleep_lock_timeout.is_none() {
+            return;
+        }
+
+        let shell = self.common.shell.read();
+        let Some(session_lock) = shell.session_lock.as_ref() else {
+            return;
+        };
+        let presented = shell.outputs().all(|output| {
+            session_lock
+                .surfaces
+                .get(output)
+                .is_some_and(|lock_surface| {
+                    let surface = lock_surface.wl_surface();
+                    with_states(surface, |states| {
+                        surface_primary_scanout_output(surface, states).as_ref() == Some(output)
+                    })
+                })
+        });
+        std::mem::drop(shell);
+
+        if presented {
+            debug!("Session locked, continuing to suspend");
+            if let Some(token) = self.common.sleep_lock_timeout.take() {
+                self.common.event_loop_handle.remove(token);
+            }
+            self.common.inhibit_sleep_fd = None;
+        }
+    }
+}
+
 delegate_session_lock!(State);
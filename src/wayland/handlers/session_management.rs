@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::Mutex;
+
+use smithay::{
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::XdgToplevel,
+    utils::{Logical, Rectangle},
+    wayland::{
+        compositor::with_states,
+        shell::xdg::{ToplevelSurface, XdgToplevelSurfaceRoleAttributes},
+    },
+};
+
+use crate::{
+    config::SavedToplevel,
+    state::State,
+    wayland::protocols::session_management::{
+        RestoreResult, SessionManagementHandler, SessionManagementState,
+        delegate_session_management,
+    },
+};
+
+/// Saved state of a pending window, applied once it gets mapped
+#[derive(Debug, Default)]
+pub struct RestoredToplevel(Mutex<Option<SavedToplevel>>);
+
+impl RestoredToplevel {
+    pub fn take(&self) -> Option<SavedToplevel> {
+        self.0.lock().unwrap().take()
+    }
+
+    /// Geometry relative to the output, if the window is restored floating
+    pub fn floating_geometry(&self) -> Option<Rectangle<i32, Logical>> {
+        self.0.lock().unwrap().as_ref().and_then(floating_geometry)
+    }
+}
+
+/// Geometry of `saved` relative to its output, if it was floating
+pub fn floating_geometry(saved: &SavedToplevel) -> Option<Rectangle<i32, Logical>> {
+    saved
+        .geometry
+        .filter(|_| !saved.tiled)
+        .map(|(x, y, w, h)| Rectangle::new((x, y).into(), (w, h).into()))
+}
+
+impl State {
+    /// Xdg toplevel surface of the `toplevel` resource
+    pub fn toplevel_surface(&self, toplevel: &XdgToplevel) -> Option<ToplevelSurface> {
+        self.common
+            .xdg_shell_state
+            .toplevel_surfaces()
+            .iter()
+            .find(|surface| surface.xdg_toplevel() == toplevel)
+            .cloned()
+    }
+
+    fn saved_toplevel(&self, toplevel: &XdgToplevel) -> Option<SavedToplevel> {
+        let surface = self.toplevel_surface(toplevel)?;
+        self.common
+            .shell
+            .read()
+            .workspaces
+            .saved_toplevel(surface.wl_surface())
+    }
+
+    /// Saves the state of `toplevel`, if it is managed through a session
+    pub fn save_toplevel_session(&mut self, toplevel: &ToplevelSurface) {
+        let Some((session, name)) = self
+            .common
+            .session_management_state
+            .toplevel_session(toplevel.xdg_toplevel())
+        else {
+            return;
+        };
+        let Some(saved) = self
+            .common
+            .shell
+            .read()
+            .workspaces
+            .saved_toplevel(toplevel.wl_surface())
+        else {
+            return;
+        };
+        self.store_toplevel(&session, &name, saved);
+    }
+
+    fn store_toplevel(&mut self, session: &str, name: &str, saved: SavedToplevel) {
+        self.common
+            .config
+            .dynamic_conf
+            .toplevel_sessions_mut()
+            .sessions
+            .entry(session.to_string())
+            .or_default()
+            .insert(name.to_string(), saved);
+    }
+
+    /// Saves the state of all toplevels managed through sessions
+    pub fn save_toplevel_sessions(&mut self) {
+        let saved = self
+            .common
+            .session_management_state
+            .toplevels()
+            .filter_map(|(session, name, toplevel)| {
+                Some((
+                    session.to_string(),
+                    name.to_string(),
+                    self.saved_toplevel(toplevel)?,
+                ))
+            })
+            .collect::<Vec<_>>();
+        if saved.is_empty() {
+            return;
+        }
+
+        let mut sessions = self.common.config.dynamic_conf.toplevel_sessions_mut();
+        for (session, name, saved) in saved {
+            sessions
+                .sessions
+                .entry(session)
+                .or_default()
+                .insert(name, saved);
+        }
+    }
+}
+
+impl SessionManagementHandler for State {
+    fn session_management_state(&mut self) -> &mut SessionManagementState {
+        &mut self.common.session_management_state
+    }
+
+    fn session_exists(&mut self, id: &str) -> bool {
+        self.common
+            .config
+            .dynamic_conf
+            .toplevel_sessions()
+            .sessions
+            .contains_key(id)
+    }
+
+    fn create_session(&mut self, id: &str) {
+        self.common
+            .config
+            .dynamic_conf
+            .toplevel_sessions_mut()
+            .sessions
+            .insert(id.to_string(), Default::default());
+    }
+
+    fn remove_session(&mut self, id: &str) {
+        self.common
+            .config
+            .dynamic_conf
+            .toplevel_sessions_mut()
+            .sessions
+            .remove(id);
+    }
+
+    fn add_toplevel(&mut self, session: &str, name: &str, toplevel: &XdgToplevel) {
+        // toplevels may be added to a session after being mapped
+        if let Some(saved) = self.saved_toplevel(toplevel) {
+            self.store_toplevel(session, name, saved);
+        }
+    }
+
+    fn restore_toplevel(
+        &mut self,
+        session: &str,
+        name: &str,
+        toplevel: &XdgToplevel,
+    ) -> RestoreResult {
+        let Some(surface) = self.toplevel_surface(toplevel) else {
+            return RestoreResult::NotFound;
+        };
+        let initial_configure_sent = with_states(surface.wl_surface(), |states| {
+            states
+                .data_map
+                .get::<Mutex<XdgToplevelSurfaceRoleAttributes>>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .initial_configure_sent
+        });
+        if initial_configure_sent {
+            return RestoreResult::AlreadyMapped;
+        }
+
+        let Some(saved) = self
+            .common
+            .config
+            .dynamic_conf
+            .toplevel_sessions()
+            .sessions
+            .get(session)
+            .and_then(|toplevels| toplevels.get(name))
+            .cloned()
+        else {
+            return RestoreResult::NotFound;
+        };
+
+        let mut shell = self.common.shell.write();
+        let fullscreen_output = saved
+            .fullscreen
+            .then(|| shell.outputs().find(|o| o.name() == saved.output).cloned())
+            .flatten();
+        let Some(pending) = shell
+            .pending_windows
+            .iter_mut()
+            .find(|pending| pending.surface == surface)
+        else {
+            return RestoreResult::NotFound;
+        };
+
+        pending.maximized = saved.maximized;
+        if fullscreen_output.is_some() {
+            pending.fullscreen = fullscreen_output;
+        }
+        let user_data = pending.surface.user_data();
+        user_data.insert_if_missing_threadsafe(RestoredToplevel::default);
+        *user_data
+            .get::<RestoredToplevel>()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap() = Some(saved);
+
+        RestoreResult::Restored
+    }
+
+    fn toplevel_session_destroyed(&mut self, session: &str, name: &str, toplevel: &XdgToplevel) {
+        if let Some(saved) = self.saved_toplevel(toplevel) {
+            self.store_toplevel(session, name, saved);
+        }
+    }
+
+    fn remove_toplevel(&mut self, session: &str, name: &str) {
+        if let Some(toplevels) = self
+            .common
+            .config
+            .dynamic_conf
+            .toplevel_sessions_mut()
+            .sessions
+            .get_mut(session)
+        {
+            toplevels.remove(name);
+        }
+    }
+}
+
+delegate_session_management!(State);
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::state::State;
+use smithay::{
+    delegate_tearing_control,
+    reexports::{
+        wayland_protocols::wp::tearing_control::v1::server::wp_tearing_control_v1::PresentationHint,
+        wayland_server::protocol::wl_surface::WlSurface,
+    },
+    wayland::{compositor::with_states, tearing_control::TearingControlSurfaceCachedState},
+};
+
+/// Whether `surface` prefers presenting with tearing over waiting for vblank
+pub fn wants_tearing(surface: &WlSurface) -> bool {
+    with_states(surface, |states| {
+        matches!(
+            states
+                .cached_state
+                .get::<TearingControlSurfaceCachedState>()
+                .current()
+                .presentation_hint(),
+            PresentationHint::Async
+        )
+    })
+}
+
+delegate_tearing_control!(State);
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use smithay::utils::{Rectangle, user_data::UserDataMap};
+use smithay::{
+    utils::{Rectangle, user_data::UserDataMap},
+    wayland::security_context::SecurityContext,
+};
 
 use crate::{
     shell::CosmicSurface,
@@ -55,6 +58,10 @@ impl Window for CosmicSurface {
         CosmicSurface::is_resizing(self, true).unwrap_or(false)
     }
 
+    fn is_urgent(&self) -> bool {
+        CosmicSurface::is_urgent(self)
+    }
+
     fn global_geometry(&self) -> Option<Rectangle<i32, Global>> {
         CosmicSurface::global_geometry(self)
     }
@@ -62,6 +69,23 @@ impl Window for CosmicSurface {
     fn user_data(&self) -> &UserDataMap {
         CosmicSurface::user_data(self)
     }
+
+    fn pid(&self) -> Option<u32> {
+        CosmicSurface::pid(self)
+    }
+
+    fn security_context(&self) -> Option<SecurityContext> {
+        CosmicSurface::security_context(self)
+    }
+
+    fn sandbox_engine(&self) -> Option<String> {
+        CosmicSurface::sandbox_engine(self)
+    }
+
+    fn tag(&self) -> (Option<String>, Option<String>) {
+        let tag = CosmicSurface::tag(self);
+        (tag.tag, tag.description)
+    }
 }
 
 delegate_toplevel_info!(State, CosmicSurface);
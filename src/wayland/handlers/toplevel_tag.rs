@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::Mutex;
+
+use smithay::{
+    reexports::{
+        wayland_protocols::xdg::shell::server::xdg_toplevel::XdgToplevel,
+        wayland_server::protocol::wl_surface::WlSurface,
+    },
+    wayland::compositor::with_states,
+};
+
+use crate::{
+    state::State,
+    wayland::protocols::toplevel_tag::{ToplevelTagHandler, delegate_toplevel_tag},
+};
+
+/// Tag of a toplevel and its description, as set through xdg-toplevel-tag
+#[derive(Debug, Default, Clone)]
+pub struct ToplevelTag {
+    pub tag: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Tag of the toplevel `surface`
+pub fn toplevel_tag(surface: &WlSurface) -> ToplevelTag {
+    with_states(surface, |states| {
+        states
+            .data_map
+            .get::<Mutex<ToplevelTag>>()
+            .map(|tag| tag.lock().unwrap().clone())
+            .unwrap_or_default()
+    })
+}
+
+impl State {
+    fn update_toplevel_tag(
+        &mut self,
+        toplevel: &XdgToplevel,
+        update: impl FnOnce(&mut ToplevelTag),
+    ) {
+        let Some(surface) = self.toplevel_surface(toplevel) else {
+            return;
+        };
+        with_states(surface.wl_surface(), |states| {
+            let tag = states
+                .data_map
+                .get_or_insert_threadsafe(|| Mutex::new(ToplevelTag::default()));
+            update(&mut tag.lock().unwrap());
+        });
+    }
+}
+
+impl ToplevelTagHandler for State {
+    fn set_tag(&mut self, toplevel: &XdgToplevel, tag: String) {
+        self.update_toplevel_tag(toplevel, |toplevel_tag| toplevel_tag.tag = Some(tag));
+    }
+
+    fn set_description(&mut self, toplevel: &XdgToplevel, description: String) {
+        self.update_toplevel_tag(toplevel, |toplevel_tag| {
+            toplevel_tag.description = Some(description)
+        });
+    }
+}
+
+delegate_toplevel_tag!(State);
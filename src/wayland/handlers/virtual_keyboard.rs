@@ -1,6 +1,79 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
-use smithay::delegate_virtual_keyboard_manager;
+use crate::{
+    state::State,
+    wayland::{
+        audit::{self, AuditEvent},
+        consent,
+    },
+};
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use smithay::{
+    reexports::{
+        wayland_protocols_misc::zwp_virtual_keyboard_v1::server::{
+            zwp_virtual_keyboard_manager_v1::{self, ZwpVirtualKeyboardManagerV1},
+            zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, delegate_dispatch,
+        },
+    },
+    wayland::virtual_keyboard::{
+        VirtualKeyboardManagerGlobalData, VirtualKeyboardManagerState, VirtualKeyboardUserData,
+    },
+};
 
-delegate_virtual_keyboard_manager!(State);
+// `delegate_virtual_keyboard_manager!`, but recording binds and created devices
+impl GlobalDispatch<ZwpVirtualKeyboardManagerV1, VirtualKeyboardManagerGlobalData> for State {
+    fn bind(
+        state: &mut State,
+        dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwpVirtualKeyboardManagerV1>,
+        global_data: &VirtualKeyboardManagerGlobalData,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        audit::record(client, PrivilegedProtocol::VirtualInput, AuditEvent::Bound);
+        consent::allow_synthetic_input(client);
+        <VirtualKeyboardManagerState as GlobalDispatch<_, _, State>>::bind(
+            state,
+            dh,
+            client,
+            resource,
+            global_data,
+            data_init,
+        )
+    }
+
+    fn can_view(client: Client, global_data: &VirtualKeyboardManagerGlobalData) -> bool {
+        <VirtualKeyboardManagerState as GlobalDispatch<ZwpVirtualKeyboardManagerV1, _, State>>::can_view(
+            client,
+            global_data,
+        )
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for State {
+    fn request(
+        state: &mut State,
+        client: &Client,
+        resource: &ZwpVirtualKeyboardManagerV1,
+        request: zwp_virtual_keyboard_manager_v1::Request,
+        data: &(),
+        dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        if let zwp_virtual_keyboard_manager_v1::Request::CreateVirtualKeyboard { .. } = &request {
+            audit::record(
+                client,
+                PrivilegedProtocol::VirtualInput,
+                AuditEvent::DeviceCreated,
+            );
+        }
+        <VirtualKeyboardManagerState as Dispatch<_, _, State>>::request(
+            state, client, resource, request, data, dh, data_init,
+        )
+    }
+}
+
+delegate_dispatch!(State: [ZwpVirtualKeyboardV1: VirtualKeyboardUserData<State>] => VirtualKeyboardManagerState);
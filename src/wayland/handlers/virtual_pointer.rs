@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::{
+    backend::input::InputEvent, input::Seat, output::Output,
+    reexports::wayland_server::protocol::wl_seat::WlSeat, utils::Point,
+};
+
+use crate::{
+    input::virtual_pointer::{VirtualPointerDevice, VirtualPointerEvent, VirtualPointerInput},
+    utils::prelude::*,
+    wayland::protocols::virtual_pointer::{VirtualPointerHandler, delegate_virtual_pointer},
+};
+
+impl VirtualPointerHandler for State {
+    fn add_virtual_pointer(&mut self, device: &VirtualPointerDevice, seat: Option<&WlSeat>) {
+        let shell = self.common.shell.read();
+        let seat = seat
+            .and_then(Seat::<State>::from_resource)
+            .unwrap_or_else(|| shell.seats.last_active().clone());
+        let led_state = seat.get_keyboard().unwrap().led_state();
+        seat.devices().add_device(device, led_state);
+    }
+
+    fn remove_virtual_pointer(&mut self, device: &VirtualPointerDevice) {
+        for seat in self.common.shell.read().seats.iter() {
+            seat.devices().remove_device(device);
+        }
+    }
+
+    fn virtual_pointer_input(
+        &mut self,
+        event: InputEvent<VirtualPointerInput>,
+        output: Option<&Output>,
+    ) {
+        let InputEvent::PointerMotionAbsolute { event } = event else {
+            self.process_input_event(event);
+            return;
+        };
+
+        // Absolute events are processed relative to the active output of the seat,
+        // so move the seat to the output under the position first.
+        let shell = self.common.shell.read();
+        let Some(seat) = shell.seats.for_device(&event.device).cloned() else {
+            return;
+        };
+        let area = match output {
+            Some(output) => output.geometry(),
+            None => match shell
+                .outputs()
+                .map(|output| output.geometry())
+                .reduce(|acc, geometry| acc.merge(geometry))
+            {
+                Some(area) => area,
+                None => return,
+            },
+        };
+        let position = Point::<f64, Global>::from((
+            area.loc.x as f64 + event.position.0 * area.size.w as f64 / event.extent.0,
+            area.loc.y as f64 + event.position.1 * area.size.h as f64 / event.extent.1,
+        ));
+        let target = output
+            .or_else(|| {
+                shell
+                    .outputs()
+                    .find(|output| output.geometry().to_f64().contains(position))
+            })
+            .cloned()
+            .unwrap_or_else(|| seat.active_output());
+        std::mem::drop(shell);
+
+        let geometry = target.geometry();
+        seat.set_active_output(&target);
+        let event = VirtualPointerEvent {
+            position: (
+                position.x - geometry.loc.x as f64,
+                position.y - geometry.loc.y as f64,
+            ),
+            extent: (geometry.size.w as f64, geometry.size.h as f64),
+            ..event
+        };
+        self.process_input_event(InputEvent::PointerMotionAbsolute { event });
+    }
+}
+
+delegate_virtual_pointer!(State);
@@ -109,11 +109,18 @@ impl XdgActivationHandler for State {
 
         match context {
             ActivationContext::UrgentOnly => {
-                let shell = self.common.shell.write();
+                let mut shell = self.common.shell.write();
                 if let Some((workspace, _output)) = shell.workspace_for_surface(&surface) {
                     let mut workspace_guard = self.common.workspace_state.update();
                     workspace_guard.add_workspace_state(&workspace, WState::Urgent);
                 }
+                if let Some((window, _)) = shell
+                    .element_for_surface(&surface)
+                    .and_then(|mapped| mapped.windows().find(|(window, _)| window == &surface))
+                {
+                    window.set_urgent(true);
+                }
+                shell.ring_bell(Some(&surface));
             }
             ActivationContext::Workspace(_) => {
                 self.activate_surface(
@@ -236,10 +243,12 @@ impl State {
                     .and_then(|w| w.get_fullscreen())
                     .cloned()
                 {
+                    surface.set_urgent(true);
                     shell.append_focus_stack(surface, &seat)
                 }
                 let mut workspace_guard = self.common.workspace_state.update();
                 workspace_guard.add_workspace_state(&workspace, WState::Urgent);
+                shell.ring_bell(Some(surface));
             }
         } else if let Some((activation_key, context)) = pending_activation {
             shell.pending_activations.insert(activation_key, context);
@@ -22,8 +22,8 @@ use smithay::{
         compositor::with_states,
         seat::WaylandFocus,
         shell::xdg::{
-            PopupSurface, PositionerState, SurfaceCachedState, ToplevelSurface, XdgShellHandler,
-            XdgShellState,
+            PopupSurface, PositionerState, ShellClient, SurfaceCachedState, ToplevelSurface,
+            XdgShellHandler, XdgShellState,
         },
     },
 };
@@ -32,6 +32,7 @@ use tracing::warn;
 
 use super::compositor::client_compositor_state;
 
+pub mod ping;
 pub mod popup;
 
 pub type PopupGrabData = Cell<Option<PopupGrab<State>>>;
@@ -41,6 +42,14 @@ impl XdgShellHandler for State {
         &mut self.common.xdg_shell_state
     }
 
+    fn new_client(&mut self, client: ShellClient) {
+        self.common.ping_state.add_client(client);
+    }
+
+    fn client_pong(&mut self, client: ShellClient) {
+        self.client_ponged(&client);
+    }
+
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
         let mut shell = self.common.shell.write();
         let seat = shell.seats.last_active().clone();
@@ -314,6 +323,7 @@ impl XdgShellHandler for State {
     }
 
     fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
+        self.save_toplevel_session(&surface);
         let (output, clients) = {
             let mut shell = self.common.shell.write();
             let seat = shell.seats.last_active().clone();
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Detection of unresponsive clients via `xdg_wm_base.ping`.
+//!
+//! Every client is pinged periodically. A client, that doesn't answer within [`PING_TIMEOUT`],
+//! is considered unresponsive until the next pong: its windows are tinted and clicking them
+//! opens a dialog offering to force-quit the application.
+
+use crate::utils::prelude::*;
+use smithay::{
+    reexports::calloop::{
+        LoopHandle,
+        timer::{TimeoutAction, Timer},
+    },
+    utils::SERIAL_COUNTER,
+    wayland::shell::xdg::{ShellClient, ToplevelSurface},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+struct PingData {
+    sent: Option<Instant>,
+    unresponsive: bool,
+}
+
+type PingDataRef = Arc<Mutex<PingData>>;
+
+fn ping_data(client: &ShellClient) -> Option<PingDataRef> {
+    client
+        .with_data(|data| {
+            data.insert_if_missing_threadsafe(PingDataRef::default);
+            data.get::<PingDataRef>().unwrap().clone()
+        })
+        .ok()
+}
+
+#[derive(Debug)]
+pub struct PingState {
+    clients: Vec<ShellClient>,
+}
+
+impl PingState {
+    pub fn new(evlh: &LoopHandle<'static, State>) -> PingState {
+        if let Err(err) = evlh.insert_source(Timer::from_duration(PING_INTERVAL), |_, _, state| {
+            state.ping_clients();
+            TimeoutAction::ToDuration(PING_INTERVAL)
+        }) {
+            warn!(?err, "Failed to schedule client pings");
+        }
+
+        PingState {
+            clients: Vec::new(),
+        }
+    }
+
+    pub fn add_client(&mut self, client: ShellClient) {
+        self.clients.push(client);
+    }
+}
+
+/// Whether the client owning `toplevel` failed to answer its last ping in time
+pub fn is_unresponsive(toplevel: &ToplevelSurface) -> bool {
+    ping_data(&toplevel.client()).is_some_and(|data| data.lock().unwrap().unresponsive)
+}
+
+/// Give the client owning `toplevel` another [`PING_TIMEOUT`] to respond
+pub fn wait_for(toplevel: &ToplevelSurface) {
+    if let Some(data) = ping_data(&toplevel.client()) {
+        let mut data = data.lock().unwrap();
+        // the outstanding ping can't be resent, so restart its timeout instead
+        if data.sent.is_some() {
+            data.sent = Some(Instant::now());
+        }
+        data.unresponsive = false;
+    }
+}
+
+impl State {
+    fn ping_clients(&mut self) {
+        let mut changed = false;
+
+        self.common.ping_state.clients.retain(|c| c.alive());
+        for client in &self.common.ping_state.clients {
+            let Some(data) = ping_data(client) else {
+                continue;
+            };
+            let mut data = data.lock().unwrap();
+            match data.sent {
+                Some(sent) => {
+                    if !data.unresponsive && sent.elapsed() >= PING_TIMEOUT {
+                        debug!(?client, "Client is not responding");
+                        data.unresponsive = true;
+                        changed = true;
+                    }
+                }
+                None => {
+                    if client.send_ping(SERIAL_COUNTER.next_serial()).is_ok() {
+                        data.sent = Some(Instant::now());
+                    }
+                }
+            }
+        }
+
+        if changed {
+            self.schedule_render_all();
+        }
+    }
+
+    pub(super) fn client_ponged(&mut self, client: &ShellClient) {
+        let Some(data) = ping_data(client) else {
+            return;
+        };
+        let was_unresponsive = std::mem::take(&mut *data.lock().unwrap()).unresponsive;
+        if was_unresponsive {
+            debug!(?client, "Client is responding again");
+            self.schedule_render_all();
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::{
+    delegate_xdg_system_bell, reexports::wayland_server::protocol::wl_surface::WlSurface,
+    wayland::xdg_system_bell::XdgSystemBellHandler,
+};
+
+use crate::{dbus::sound, state::State, wayland::protocols::workspace::State as WState};
+
+impl XdgSystemBellHandler for State {
+    fn ring(&mut self, surface: Option<WlSurface>) {
+        let system_bell = self.common.config.cosmic_conf.system_bell;
+        let mut shell = self.common.shell.write();
+
+        if let Some(surface) = surface.as_ref().filter(|_| system_bell.mark_urgent) {
+            let window = shell
+                .element_for_surface(surface)
+                .and_then(|mapped| mapped.windows().find(|(window, _)| window == surface))
+                .map(|(window, _)| window);
+            if let Some(window) = window.filter(|window| !window.is_activated(false)) {
+                window.set_urgent(true);
+                if let Some((workspace, _output)) = shell.workspace_for_surface(surface) {
+                    let mut workspace_guard = self.common.workspace_state.update();
+                    workspace_guard.add_workspace_state(&workspace, WState::Urgent);
+                }
+            }
+        }
+        shell.ring_bell(surface.as_ref());
+        std::mem::drop(shell);
+
+        if system_bell.sound {
+            sound::play_sound(&self.common.async_executor, "bell");
+        }
+    }
+}
+
+delegate_xdg_system_bell!(State);
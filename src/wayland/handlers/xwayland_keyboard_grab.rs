@@ -8,6 +8,7 @@ use smithay::{
     wayland::xwayland_keyboard_grab::{XWaylandKeyboardGrab, XWaylandKeyboardGrabHandler},
 };
 use std::sync::Mutex;
+use tracing::debug;
 
 #[derive(Default)]
 pub struct XWaylandGrabSeatData {
@@ -16,6 +17,33 @@ pub struct XWaylandGrabSeatData {
 
 impl XWaylandKeyboardGrabHandler for State {
     fn grab(&mut self, surface: WlSurface, seat: Seat<Self>, grab: XWaylandKeyboardGrab<Self>) {
+        if let Some(allowed) = self
+            .common
+            .config
+            .cosmic_conf
+            .xwayland_keyboard_grab
+            .as_ref()
+        {
+            let app_id = self
+                .common
+                .shell
+                .read()
+                .element_for_surface(&surface)
+                .and_then(|mapped| {
+                    mapped
+                        .windows()
+                        .find(|(window, _)| window == &surface)
+                        .map(|(window, _)| window.app_id())
+                });
+            if !app_id
+                .as_ref()
+                .is_some_and(|app_id| allowed.contains(app_id))
+            {
+                debug!(?app_id, "Denied Xwayland keyboard grab.");
+                return;
+            }
+        }
+
         let data = seat
             .user_data()
             .get_or_insert(XWaylandGrabSeatData::default);
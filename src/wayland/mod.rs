@@ -1,4 +1,9 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+pub mod audit;
+pub mod clipboard;
+pub mod consent;
 pub mod handlers;
+pub mod policy;
 pub mod protocols;
+pub mod toplevel_metadata;
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Access policy for privileged globals.
+//!
+//! By default privileged globals are advertised to every client, that isn't sandboxed.
+//! The `privileged_protocols` config key replaces that default with an allow-list per protocol,
+//! checked whenever a global is advertised to or bound by a client.
+
+use crate::state::{ClientState, client_runs_on_host};
+use cosmic_comp_config::policy::{ClientMatcher, PrivilegedProtocol, ProtocolPolicy};
+use smithay::reexports::wayland_server::Client;
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+#[derive(Debug, Clone)]
+pub struct PolicyState {
+    policy: Arc<RwLock<ProtocolPolicy>>,
+}
+
+impl PolicyState {
+    pub fn new(policy: &ProtocolPolicy) -> PolicyState {
+        track_dbus_names(policy);
+        PolicyState {
+            policy: Arc::new(RwLock::new(policy.clone())),
+        }
+    }
+
+    /// Replace the policy, affecting globals advertised from now on
+    pub fn update(&self, policy: &ProtocolPolicy) {
+        track_dbus_names(policy);
+        *self.policy.write().unwrap() = policy.clone();
+    }
+
+    /// Global filter for `protocol`, falling back to `default` without a configured allow-list
+    pub fn filter(
+        &self,
+        protocol: PrivilegedProtocol,
+        default: fn(&Client) -> bool,
+    ) -> impl Fn(&Client) -> bool + Clone + Send + Sync + 'static {
+        let state = self.clone();
+        move |client| state.client_allowed(client, protocol, default)
+    }
+
+    /// Whether an allow-list is configured for `protocol`
+    pub fn is_configured(&self, protocol: PrivilegedProtocol) -> bool {
+        self.policy.read().unwrap().contains_key(&protocol)
+    }
+
+    /// Well-known names of the `DBusName` matchers configured for `protocol`, if any.
+    ///
+    /// Used to restrict D-Bus methods, that give the same access as `protocol`.
+    pub fn dbus_names(&self, protocol: PrivilegedProtocol) -> Option<Vec<String>> {
+        let policy = self.policy.read().unwrap();
+        let matchers = policy.get(&protocol)?;
+        Some(
+            matchers
+                .iter()
+                .filter_map(|matcher| match matcher {
+                    ClientMatcher::DBusName(name) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn client_allowed(
+        &self,
+        client: &Client,
+        protocol: PrivilegedProtocol,
+        default: fn(&Client) -> bool,
+    ) -> bool {
+        let policy = self.policy.read().unwrap();
+        let Some(matchers) = policy.get(&protocol) else {
+            return default(client);
+        };
+
+        matchers
+            .iter()
+            .any(|matcher| client_matches(client, matcher))
+    }
+}
+
+/// Filters can't wait for D-Bus, so owners of names of `DBusName` matchers are looked up eagerly
+fn track_dbus_names(policy: &ProtocolPolicy) {
+    crate::dbus::track_name_owner_pids(policy.values().flatten().filter_map(
+        |matcher| match matcher {
+            ClientMatcher::DBusName(name) => Some(name.as_str()),
+            _ => None,
+        },
+    ));
+}
+
+fn client_matches(client: &Client, matcher: &ClientMatcher) -> bool {
+    match matcher {
+        ClientMatcher::SecurityContext {
+            sandbox_engine,
+            app_id,
+        } => client
+            .get_data::<ClientState>()
+            .and_then(|data| data.security_context.as_ref())
+            .is_some_and(|context| {
+                context.sandbox_engine.as_ref() == Some(sandbox_engine)
+                    && app_id
+                        .as_ref()
+                        .is_none_or(|app_id| context.app_id.as_ref() == Some(app_id))
+            }),
+        // sandboxed clients share the process of their security context listener,
+        // or see their own executables at host paths
+        ClientMatcher::Executable(path) => {
+            client_runs_on_host(client) && client_executable(client).is_some_and(|exe| &exe == path)
+        }
+        ClientMatcher::DBusName(name) => {
+            client_runs_on_host(client)
+                && client_pid(client)
+                    .is_some_and(|pid| crate::dbus::name_owner_pid(name) == Some(pid))
+        }
+    }
+}
+
+/// Filters run while the display is locked, so the pid is captured when the client connects
+fn client_pid(client: &Client) -> Option<u32> {
+    client.get_data::<ClientState>()?.pid
+}
+
+fn client_executable(client: &Client) -> Option<PathBuf> {
+    let pid = client_pid(client)?;
+    std::fs::read_link(format!("/proc/{}/exe", pid)).ok()
+}
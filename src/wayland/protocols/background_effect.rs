@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! ext-background-effect-v1, letting surfaces request a blurred backdrop.
+//!
+//! The blur region is kept in the surfaces' cached state, so the renderer can pick it up.
+//! Only layer surfaces get a backdrop, so the global is only shown to clients that may
+//! create those.
+
+pub use generated::{ext_background_effect_manager_v1, ext_background_effect_surface_v1};
+
+#[allow(non_snake_case, non_upper_case_globals, non_camel_case_types)]
+mod generated {
+    use smithay::reexports::wayland_server::{self, protocol::*};
+
+    pub mod __interfaces {
+        use smithay::reexports::wayland_server::protocol::__interfaces::*;
+        use wayland_backend;
+        wayland_scanner::generate_interfaces!("resources/protocols/ext-background-effect-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_server_code!("resources/protocols/ext-background-effect-v1.xml");
+}
+
+use std::sync::Mutex;
+
+use ext_background_effect_manager_v1::{Capability, ExtBackgroundEffectManagerV1};
+use ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1;
+use smithay::{
+    reexports::wayland_server::{
+        Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+        backend::GlobalId, protocol::wl_surface::WlSurface,
+    },
+    wayland::compositor::{Cacheable, RegionAttributes, get_region_attributes, with_states},
+};
+
+/// Background effects of a surface, applied on commit
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundEffectCachedState {
+    /// Surface-local region to blur the background behind
+    pub blur_region: Option<RegionAttributes>,
+}
+
+impl Cacheable for BackgroundEffectCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        self.clone()
+    }
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// The background effect surface of a wl_surface, at most one may exist
+#[derive(Default)]
+struct BackgroundEffectSurfaceMarker(Mutex<Option<Weak<ExtBackgroundEffectSurfaceV1>>>);
+
+/// Region `surface` wants its background blurred in, if any
+pub fn surface_blur_region(surface: &WlSurface) -> Option<RegionAttributes> {
+    with_states(surface, |states| {
+        states
+            .cached_state
+            .get::<BackgroundEffectCachedState>()
+            .current()
+            .blur_region
+            .clone()
+    })
+}
+
+#[derive(Debug)]
+pub struct BackgroundEffectState {
+    global: GlobalId,
+}
+
+pub struct BackgroundEffectGlobalData {
+    filter: Box<dyn for<'a> Fn(&'a Client) -> bool + Send + Sync>,
+}
+
+impl BackgroundEffectState {
+    pub fn new<D, F>(dh: &DisplayHandle, client_filter: F) -> BackgroundEffectState
+    where
+        D: GlobalDispatch<ExtBackgroundEffectManagerV1, BackgroundEffectGlobalData> + 'static,
+        F: for<'a> Fn(&'a Client) -> bool + Send + Sync + 'static,
+    {
+        let global = dh.create_global::<D, ExtBackgroundEffectManagerV1, _>(
+            1,
+            BackgroundEffectGlobalData {
+                filter: Box::new(client_filter),
+            },
+        );
+        BackgroundEffectState { global }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<ExtBackgroundEffectManagerV1, BackgroundEffectGlobalData, D>
+    for BackgroundEffectState
+where
+    D: GlobalDispatch<ExtBackgroundEffectManagerV1, BackgroundEffectGlobalData>
+        + Dispatch<ExtBackgroundEffectManagerV1, ()>
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtBackgroundEffectManagerV1>,
+        _global_data: &BackgroundEffectGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+        manager.capabilities(Capability::Blur);
+    }
+
+    fn can_view(client: Client, global_data: &BackgroundEffectGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtBackgroundEffectManagerV1, (), D> for BackgroundEffectState
+where
+    D: Dispatch<ExtBackgroundEffectManagerV1, ()>
+        + Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &ExtBackgroundEffectManagerV1,
+        request: ext_background_effect_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_background_effect_manager_v1::Request::GetBackgroundEffect { id, surface } => {
+                let exists = with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(BackgroundEffectSurfaceMarker::default);
+                    let guard = marker.0.lock().unwrap();
+                    guard.as_ref().is_some_and(|s| s.upgrade().is_ok())
+                });
+                if exists {
+                    resource.post_error(
+                        ext_background_effect_manager_v1::Error::BackgroundEffectExists as u32,
+                        format!("{surface:?} already has a background effect surface"),
+                    );
+                    return;
+                }
+                let obj = data_init.init(id, surface.downgrade());
+                with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(BackgroundEffectSurfaceMarker::default);
+                    *marker.0.lock().unwrap() = Some(obj.downgrade());
+                });
+            }
+            ext_background_effect_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>, D> for BackgroundEffectState
+where
+    D: Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>> + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &ExtBackgroundEffectSurfaceV1,
+        request: ext_background_effect_surface_v1::Request,
+        data: &Weak<WlSurface>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let Ok(surface) = data.upgrade() else {
+            if !matches!(request, ext_background_effect_surface_v1::Request::Destroy) {
+                resource.post_error(
+                    ext_background_effect_surface_v1::Error::SurfaceDestroyed as u32,
+                    "The surface was destroyed",
+                );
+            }
+            return;
+        };
+        with_states(&surface, |states| {
+            let mut cached = states.cached_state.get::<BackgroundEffectCachedState>();
+            let pending = cached.pending();
+            match request {
+                ext_background_effect_surface_v1::Request::SetBlurRegion { region } => {
+                    pending.blur_region = region.as_ref().map(get_region_attributes);
+                }
+                ext_background_effect_surface_v1::Request::Destroy => {
+                    *pending = BackgroundEffectCachedState::default();
+                }
+                _ => unreachable!(),
+            }
+        });
+    }
+}
+
+macro_rules! delegate_background_effect {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::background_effect::ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1: $crate::wayland::protocols::background_effect::BackgroundEffectGlobalData
+        ] => $crate::wayland::protocols::background_effect::BackgroundEffectState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::background_effect::ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1: ()
+        ] => $crate::wayland::protocols::background_effect::BackgroundEffectState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::background_effect::ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::wayland::protocols::background_effect::BackgroundEffectState);
+    };
+}
+pub(crate) use delegate_background_effect;
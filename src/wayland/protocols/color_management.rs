@@ -0,0 +1,549 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! wp-color-management, limited to the SDR color spaces the renderer composites in.
+//!
+//! Outputs and preferred descriptions are always sRGB, clients can describe their surfaces
+//! with the named sRGB primaries and transfer function. Other transfer functions, like
+//! gamma 2.2, are only to be offered once the renderer converts between them. Descriptions
+//! are kept in the surfaces' cached state, so a color managed renderer can pick them up later.
+
+use std::sync::Mutex;
+
+use smithay::{
+    output::{Output, WeakOutput},
+    reexports::{
+        wayland_protocols::wp::color_management::v1::server::{
+            wp_color_management_output_v1::{self, WpColorManagementOutputV1},
+            wp_color_management_surface_feedback_v1::{self, WpColorManagementSurfaceFeedbackV1},
+            wp_color_management_surface_v1::{self, WpColorManagementSurfaceV1},
+            wp_color_manager_v1::{
+                self, Primaries, RenderIntent, TransferFunction, WpColorManagerV1,
+            },
+            wp_image_description_creator_params_v1::{self, WpImageDescriptionCreatorParamsV1},
+            wp_image_description_info_v1::WpImageDescriptionInfoV1,
+            wp_image_description_v1::{self, WpImageDescriptionV1},
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+            backend::GlobalId, protocol::wl_surface::WlSurface,
+        },
+    },
+    wayland::compositor::{Cacheable, with_states},
+};
+use wayland_backend::{protocol::WEnum, server::ClientId};
+
+/// Transfer functions surfaces may be described with, all with sRGB primaries.
+///
+/// Content is composited as is, so only descriptions needing no conversion are offered.
+const SUPPORTED_TRANSFER_FUNCTIONS: [TransferFunction; 1] = [TransferFunction::Srgb];
+
+/// Color space of a surface or output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDescription {
+    pub transfer_function: TransferFunction,
+    pub primaries: Primaries,
+}
+
+impl ImageDescription {
+    pub const SRGB: ImageDescription = ImageDescription {
+        transfer_function: TransferFunction::Srgb,
+        primaries: Primaries::Srgb,
+    };
+
+    /// Identity announced to clients, equal for equal descriptions
+    fn identity(&self) -> u32 {
+        match self.transfer_function {
+            TransferFunction::Gamma22 => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Image description of a surface, applied on commit
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ColorManagementCachedState {
+    pub description: Option<ImageDescription>,
+}
+
+impl Cacheable for ColorManagementCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        *self
+    }
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// The color management surface of a wl_surface, at most one may exist
+#[derive(Default)]
+struct ColorManagementSurfaceMarker(Mutex<Option<Weak<WpColorManagementSurfaceV1>>>);
+
+/// Image description of `surface`, `None` if the client didn't describe it
+pub fn surface_image_description(surface: &WlSurface) -> Option<ImageDescription> {
+    with_states(surface, |states| {
+        states
+            .cached_state
+            .get::<ColorManagementCachedState>()
+            .current()
+            .description
+    })
+}
+
+#[derive(Debug)]
+pub struct ColorManagementState {
+    global: GlobalId,
+}
+
+impl ColorManagementState {
+    pub fn new<D>(dh: &DisplayHandle) -> ColorManagementState
+    where
+        D: GlobalDispatch<WpColorManagerV1, ()> + 'static,
+    {
+        let global = dh.create_global::<D, WpColorManagerV1, _>(1, ());
+        ColorManagementState { global }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+pub struct ImageDescriptionData {
+    /// `None` for descriptions, that failed
+    description: Option<ImageDescription>,
+    /// Whether the client may query the description, only true for descriptions it didn't create
+    info_allowed: bool,
+}
+
+pub type CreatorParamsData = Mutex<Option<(Option<TransferFunction>, Option<Primaries>)>>;
+
+impl<D> GlobalDispatch<WpColorManagerV1, (), D> for ColorManagementState
+where
+    D: GlobalDispatch<WpColorManagerV1, ()> + Dispatch<WpColorManagerV1, ()> + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpColorManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+        manager.supported_intent(RenderIntent::Perceptual);
+        manager.supported_feature(wp_color_manager_v1::Feature::Parametric);
+        for transfer_function in SUPPORTED_TRANSFER_FUNCTIONS {
+            manager.supported_tf_named(transfer_function);
+        }
+        manager.supported_primaries_named(Primaries::Srgb);
+        manager.done();
+    }
+}
+
+impl<D> Dispatch<WpColorManagerV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagerV1, ()>
+        + Dispatch<WpColorManagementOutputV1, WeakOutput>
+        + Dispatch<WpColorManagementSurfaceV1, Weak<WlSurface>>
+        + Dispatch<WpColorManagementSurfaceFeedbackV1, ()>
+        + Dispatch<WpImageDescriptionCreatorParamsV1, CreatorParamsData>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpColorManagerV1,
+        request: wp_color_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_manager_v1::Request::GetOutput { id, output } => {
+                let output = Output::from_resource(&output)
+                    .map(|o| o.downgrade())
+                    .unwrap_or_default();
+                data_init.init(id, output);
+            }
+            wp_color_manager_v1::Request::GetSurface { id, surface } => {
+                let exists = with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(ColorManagementSurfaceMarker::default);
+                    let guard = marker.0.lock().unwrap();
+                    guard.as_ref().is_some_and(|s| s.upgrade().is_ok())
+                });
+                if exists {
+                    resource.post_error(
+                        wp_color_manager_v1::Error::SurfaceExists as u32,
+                        format!("{surface:?} already has a color management surface"),
+                    );
+                    return;
+                }
+                let obj = data_init.init(id, surface.downgrade());
+                with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(ColorManagementSurfaceMarker::default);
+                    *marker.0.lock().unwrap() = Some(obj.downgrade());
+                });
+            }
+            wp_color_manager_v1::Request::GetSurfaceFeedback { id, .. } => {
+                data_init.init(id, ());
+            }
+            wp_color_manager_v1::Request::CreateParametricCreator { obj } => {
+                data_init.init(obj, Mutex::new(Some((None, None))));
+            }
+            wp_color_manager_v1::Request::CreateIccCreator { .. } => {
+                resource.post_error(
+                    wp_color_manager_v1::Error::UnsupportedFeature as u32,
+                    "ICC image descriptions are not supported",
+                );
+            }
+            wp_color_manager_v1::Request::CreateWindowsScrgb { .. } => {
+                resource.post_error(
+                    wp_color_manager_v1::Error::UnsupportedFeature as u32,
+                    "scRGB image descriptions are not supported",
+                );
+            }
+            wp_color_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Initializes an image description of the compositor, which clients may query
+fn init_known_description<D>(
+    data_init: &mut DataInit<'_, D>,
+    id: New<WpImageDescriptionV1>,
+    description: Option<ImageDescription>,
+) where
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData> + 'static,
+{
+    let obj = data_init.init(
+        id,
+        ImageDescriptionData {
+            description,
+            info_allowed: true,
+        },
+    );
+    match description {
+        Some(description) => obj.ready(description.identity()),
+        None => obj.failed(
+            wp_image_description_v1::Cause::NoOutput,
+            "The output was removed".into(),
+        ),
+    }
+}
+
+impl<D> Dispatch<WpColorManagementOutputV1, WeakOutput, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementOutputV1, WeakOutput>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionData>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &WpColorManagementOutputV1,
+        request: wp_color_management_output_v1::Request,
+        data: &WeakOutput,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_output_v1::Request::GetImageDescription { image_description } => {
+                let description = data.upgrade().map(|_| ImageDescription::SRGB);
+                init_known_description(data_init, image_description, description);
+            }
+            wp_color_management_output_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpColorManagementSurfaceV1, Weak<WlSurface>, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementSurfaceV1, Weak<WlSurface>> + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpColorManagementSurfaceV1,
+        request: wp_color_management_surface_v1::Request,
+        data: &Weak<WlSurface>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let Ok(surface) = data.upgrade() else {
+            if !matches!(request, wp_color_management_surface_v1::Request::Destroy) {
+                resource.post_error(
+                    wp_color_management_surface_v1::Error::Inert as u32,
+                    "The surface was destroyed",
+                );
+            }
+            return;
+        };
+        let description = match request {
+            wp_color_management_surface_v1::Request::SetImageDescription {
+                image_description,
+                render_intent,
+            } => {
+                if render_intent != WEnum::Value(RenderIntent::Perceptual) {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::RenderIntent as u32,
+                        "Unsupported render intent",
+                    );
+                    return;
+                }
+                let Some(description) = image_description
+                    .data::<ImageDescriptionData>()
+                    .and_then(|data| data.description)
+                else {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::ImageDescription as u32,
+                        "The image description failed",
+                    );
+                    return;
+                };
+                Some(description)
+            }
+            wp_color_management_surface_v1::Request::UnsetImageDescription
+            | wp_color_management_surface_v1::Request::Destroy => None,
+            _ => unreachable!(),
+        };
+
+        with_states(&surface, |states| {
+            states
+                .cached_state
+                .get::<ColorManagementCachedState>()
+                .pending()
+                .description = description;
+        });
+    }
+}
+
+impl<D> Dispatch<WpColorManagementSurfaceFeedbackV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementSurfaceFeedbackV1, ()>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionData>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &WpColorManagementSurfaceFeedbackV1,
+        request: wp_color_management_surface_feedback_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_surface_feedback_v1::Request::GetPreferred {
+                image_description,
+            }
+            | wp_color_management_surface_feedback_v1::Request::GetPreferredParametric {
+                image_description,
+            } => {
+                init_known_description(data_init, image_description, Some(ImageDescription::SRGB));
+            }
+            wp_color_management_surface_feedback_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionCreatorParamsV1, CreatorParamsData, D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionCreatorParamsV1, CreatorParamsData>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionData>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpImageDescriptionCreatorParamsV1,
+        request: wp_image_description_creator_params_v1::Request,
+        data: &CreatorParamsData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        use wp_image_description_creator_params_v1::Error;
+
+        let mut guard = data.lock().unwrap();
+        let Some((transfer_function, primaries)) = guard.as_mut() else {
+            return;
+        };
+        match request {
+            wp_image_description_creator_params_v1::Request::SetTfNamed { tf } => {
+                if transfer_function.is_some() {
+                    resource.post_error(Error::AlreadySet as u32, "Transfer function already set");
+                    return;
+                }
+                match tf {
+                    WEnum::Value(tf) if SUPPORTED_TRANSFER_FUNCTIONS.contains(&tf) => {
+                        *transfer_function = Some(tf);
+                    }
+                    _ => resource
+                        .post_error(Error::InvalidTf as u32, "Unsupported transfer function"),
+                }
+            }
+            wp_image_description_creator_params_v1::Request::SetPrimariesNamed {
+                primaries: named,
+            } => {
+                if primaries.is_some() {
+                    resource.post_error(Error::AlreadySet as u32, "Primaries already set");
+                    return;
+                }
+                match named {
+                    WEnum::Value(Primaries::Srgb) => *primaries = Some(Primaries::Srgb),
+                    _ => resource
+                        .post_error(Error::InvalidPrimariesNamed as u32, "Unsupported primaries"),
+                }
+            }
+            wp_image_description_creator_params_v1::Request::SetMaxCll { .. }
+            | wp_image_description_creator_params_v1::Request::SetMaxFall { .. } => {
+                // only meaningful for HDR content, which isn't supported
+            }
+            wp_image_description_creator_params_v1::Request::Create { image_description } => {
+                let (Some(transfer_function), Some(primaries)) = (*transfer_function, *primaries)
+                else {
+                    resource.post_error(
+                        Error::IncompleteSet as u32,
+                        "Transfer function and primaries are required",
+                    );
+                    return;
+                };
+                *guard = None;
+                let description = ImageDescription {
+                    transfer_function,
+                    primaries,
+                };
+                let obj = data_init.init(
+                    image_description,
+                    ImageDescriptionData {
+                        description: Some(description),
+                        info_allowed: false,
+                    },
+                );
+                obj.ready(description.identity());
+            }
+            _ => resource.post_error(
+                Error::UnsupportedFeature as u32,
+                "Only named sRGB primaries and transfer functions are supported",
+            ),
+        }
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionV1, ImageDescriptionData, D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>
+        + Dispatch<WpImageDescriptionInfoV1, ()>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpImageDescriptionV1,
+        request: wp_image_description_v1::Request,
+        data: &ImageDescriptionData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_image_description_v1::Request::GetInformation { information } => {
+                if !data.info_allowed {
+                    resource.post_error(
+                        wp_image_description_v1::Error::NoInformation as u32,
+                        "Information is only available for descriptions of the compositor",
+                    );
+                    return;
+                }
+                let Some(description) = data.description else {
+                    resource.post_error(
+                        wp_image_description_v1::Error::NotReady as u32,
+                        "The image description failed",
+                    );
+                    return;
+                };
+                let info = data_init.init(information, ());
+                send_information(&info, &description);
+            }
+            wp_image_description_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionInfoV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionInfoV1, ()> + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &WpImageDescriptionInfoV1,
+        _request: <WpImageDescriptionInfoV1 as Resource>::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _resource: &WpImageDescriptionInfoV1,
+        _data: &(),
+    ) {
+    }
+}
+
+/// Sends the sRGB primaries and the reference display luminances of sRGB
+fn send_information(info: &WpImageDescriptionInfoV1, description: &ImageDescription) {
+    // CIE 1931 xy chromaticities of red, green, blue and the D65 whitepoint, times 1'000'000
+    const SRGB_PRIMARIES: [i32; 8] = [
+        640_000, 330_000, 300_000, 600_000, 150_000, 60_000, 312_700, 329_000,
+    ];
+    let [r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y] = SRGB_PRIMARIES;
+
+    info.primaries_named(description.primaries);
+    info.primaries(r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y);
+    info.tf_named(description.transfer_function);
+    // minimum in 0.0001 cd/m², maximum and reference white in cd/m²
+    info.luminances(2000, 80, 80);
+    info.target_primaries(r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y);
+    info.target_luminance(2000, 80);
+    info.done();
+}
+
+macro_rules! delegate_color_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_manager_v1::WpColorManagerV1: ()
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_manager_v1::WpColorManagerV1: ()
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_output_v1::WpColorManagementOutputV1: smithay::output::WeakOutput
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_surface_v1::WpColorManagementSurfaceV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_surface_feedback_v1::WpColorManagementSurfaceFeedbackV1: ()
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1: $crate::wayland::protocols::color_management::CreatorParamsData
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_v1::WpImageDescriptionV1: $crate::wayland::protocols::color_management::ImageDescriptionData
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_info_v1::WpImageDescriptionInfoV1: ()
+        ] => $crate::wayland::protocols::color_management::ColorManagementState);
+    };
+}
+pub(crate) use delegate_color_management;
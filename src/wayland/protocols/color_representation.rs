@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! wp-color-representation, limited to the encodings buffers are imported with.
+//!
+//! YCbCr dmabufs are imported through EGL, which converts them as BT.601 limited range,
+//! so only that and plain RGB are advertised. The representation is kept in the surfaces'
+//! cached state, so the renderer and plane assignment can pick it up later.
+
+use std::sync::Mutex;
+
+use smithay::{
+    reexports::{
+        wayland_protocols::wp::color_representation::v1::server::{
+            wp_color_representation_manager_v1::{self, WpColorRepresentationManagerV1},
+            wp_color_representation_surface_v1::{
+                self, AlphaMode, ChromaLocation, Coefficients, Range,
+                WpColorRepresentationSurfaceV1,
+            },
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+            backend::GlobalId, protocol::wl_surface::WlSurface,
+        },
+    },
+    wayland::compositor::{Cacheable, with_states},
+};
+use wayland_backend::protocol::WEnum;
+
+/// Coefficients and ranges buffers are converted with
+const SUPPORTED_COEFFICIENTS_AND_RANGES: [(Coefficients, Range); 2] = [
+    (Coefficients::Identity, Range::Full),
+    (Coefficients::Bt601, Range::Limited),
+];
+
+/// Color representation of a surface, applied on commit
+#[derive(Debug, Clone, Copy)]
+pub struct ColorRepresentationCachedState {
+    pub alpha_mode: AlphaMode,
+    /// `None` for the default of the buffer's format
+    pub coefficients_and_range: Option<(Coefficients, Range)>,
+    pub chroma_location: Option<ChromaLocation>,
+}
+
+impl Default for ColorRepresentationCachedState {
+    fn default() -> Self {
+        ColorRepresentationCachedState {
+            alpha_mode: AlphaMode::PremultipliedElectrical,
+            coefficients_and_range: None,
+            chroma_location: None,
+        }
+    }
+}
+
+impl Cacheable for ColorRepresentationCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        *self
+    }
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// The color representation surface of a wl_surface, at most one may exist
+#[derive(Default)]
+struct ColorRepresentationSurfaceMarker(Mutex<Option<Weak<WpColorRepresentationSurfaceV1>>>);
+
+/// Color representation of `surface`
+pub fn surface_color_representation(surface: &WlSurface) -> ColorRepresentationCachedState {
+    with_states(surface, |states| {
+        *states
+            .cached_state
+            .get::<ColorRepresentationCachedState>()
+            .current()
+    })
+}
+
+#[derive(Debug)]
+pub struct ColorRepresentationState {
+    global: GlobalId,
+}
+
+impl ColorRepresentationState {
+    pub fn new<D>(dh: &DisplayHandle) -> ColorRepresentationState
+    where
+        D: GlobalDispatch<WpColorRepresentationManagerV1, ()> + 'static,
+    {
+        let global = dh.create_global::<D, WpColorRepresentationManagerV1, _>(1, ());
+        ColorRepresentationState { global }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<WpColorRepresentationManagerV1, (), D> for ColorRepresentationState
+where
+    D: GlobalDispatch<WpColorRepresentationManagerV1, ()>
+        + Dispatch<WpColorRepresentationManagerV1, ()>
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpColorRepresentationManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+        manager.supported_alpha_mode(AlphaMode::PremultipliedElectrical);
+        for (coefficients, range) in SUPPORTED_COEFFICIENTS_AND_RANGES {
+            manager.supported_coefficients_and_ranges(coefficients, range);
+        }
+        manager.done();
+    }
+}
+
+impl<D> Dispatch<WpColorRepresentationManagerV1, (), D> for ColorRepresentationState
+where
+    D: Dispatch<WpColorRepresentationManagerV1, ()>
+        + Dispatch<WpColorRepresentationSurfaceV1, Weak<WlSurface>>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpColorRepresentationManagerV1,
+        request: wp_color_representation_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_representation_manager_v1::Request::GetSurface { id, surface } => {
+                let exists = with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(ColorRepresentationSurfaceMarker::default);
+                    let guard = marker.0.lock().unwrap();
+                    guard.as_ref().is_some_and(|s| s.upgrade().is_ok())
+                });
+                if exists {
+                    resource.post_error(
+                        wp_color_representation_manager_v1::Error::SurfaceExists as u32,
+                        format!("{surface:?} already has a color representation surface"),
+                    );
+                    return;
+                }
+                let obj = data_init.init(id, surface.downgrade());
+                with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(ColorRepresentationSurfaceMarker::default);
+                    *marker.0.lock().unwrap() = Some(obj.downgrade());
+                });
+            }
+            wp_color_representation_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpColorRepresentationSurfaceV1, Weak<WlSurface>, D> for ColorRepresentationState
+where
+    D: Dispatch<WpColorRepresentationSurfaceV1, Weak<WlSurface>> + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpColorRepresentationSurfaceV1,
+        request: wp_color_representation_surface_v1::Request,
+        data: &Weak<WlSurface>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        use wp_color_representation_surface_v1::Error;
+
+        let Ok(surface) = data.upgrade() else {
+            if !matches!(
+                request,
+                wp_color_representation_surface_v1::Request::Destroy
+            ) {
+                resource.post_error(Error::Inert as u32, "The surface was destroyed");
+            }
+            return;
+        };
+        with_states(&surface, |states| {
+            let mut cached = states.cached_state.get::<ColorRepresentationCachedState>();
+            let pending = cached.pending();
+            match request {
+                wp_color_representation_surface_v1::Request::SetAlphaMode { alpha_mode } => {
+                    match alpha_mode {
+                        WEnum::Value(AlphaMode::PremultipliedElectrical) => {
+                            pending.alpha_mode = AlphaMode::PremultipliedElectrical;
+                        }
+                        _ => resource.post_error(
+                            Error::AlphaMode as u32,
+                            "Only premultiplied electrical alpha is supported",
+                        ),
+                    }
+                }
+                wp_color_representation_surface_v1::Request::SetCoefficientsAndRange {
+                    coefficients,
+                    range,
+                } => match (coefficients, range) {
+                    (WEnum::Value(coefficients), WEnum::Value(range))
+                        if SUPPORTED_COEFFICIENTS_AND_RANGES.contains(&(coefficients, range)) =>
+                    {
+                        pending.coefficients_and_range = Some((coefficients, range));
+                    }
+                    _ => resource.post_error(
+                        Error::Coefficients as u32,
+                        "Unsupported coefficients and range",
+                    ),
+                },
+                wp_color_representation_surface_v1::Request::SetChromaLocation {
+                    chroma_location,
+                } => match chroma_location {
+                    WEnum::Value(chroma_location) => {
+                        pending.chroma_location = Some(chroma_location);
+                    }
+                    WEnum::Unknown(_) => {
+                        resource.post_error(Error::ChromaLocation as u32, "Invalid chroma location")
+                    }
+                },
+                wp_color_representation_surface_v1::Request::Destroy => {
+                    *pending = ColorRepresentationCachedState::default();
+                }
+                _ => unreachable!(),
+            }
+        });
+    }
+}
+
+macro_rules! delegate_color_representation {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_representation::v1::server::wp_color_representation_manager_v1::WpColorRepresentationManagerV1: ()
+        ] => $crate::wayland::protocols::color_representation::ColorRepresentationState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_representation::v1::server::wp_color_representation_manager_v1::WpColorRepresentationManagerV1: ()
+        ] => $crate::wayland::protocols::color_representation::ColorRepresentationState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_representation::v1::server::wp_color_representation_surface_v1::WpColorRepresentationSurfaceV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::wayland::protocols::color_representation::ColorRepresentationState);
+    };
+}
+pub(crate) use delegate_color_representation;
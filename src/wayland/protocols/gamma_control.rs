@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! wlr-gamma-control, for night light tools like gammastep and wlsunset.
+//!
+//! Ramps set by clients are composed with the compositor's own adjustments by the backend,
+//! only one client can control the gamma of an output at a time.
+
+use std::{io, os::fd::OwnedFd};
+
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use rustix::fs::OFlags;
+use smithay::{
+    output::{Output, WeakOutput},
+    reexports::{
+        wayland_protocols_wlr::gamma_control::v1::server::{
+            zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+            zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+            backend::GlobalId,
+        },
+    },
+};
+use tracing::debug;
+use wayland_backend::server::ClientId;
+
+use crate::{
+    utils::gamma::GammaRamp,
+    wayland::audit::{self, AuditEvent},
+};
+
+pub trait GammaControlHandler {
+    fn gamma_control_state(&mut self) -> &mut GammaControlState;
+    /// Entries per channel of the ramps of `output`, `None` if its gamma can't be set
+    fn gamma_size(&mut self, output: &Output) -> Option<u32>;
+    /// Applies the ramp of a client to `output`, removing it if `None`.
+    ///
+    /// Returns whether the ramp was applied.
+    fn set_gamma(&mut self, output: &Output, ramp: Option<GammaRamp>) -> bool;
+}
+
+#[derive(Debug)]
+pub struct GammaControlState {
+    global: GlobalId,
+    /// Gamma controls currently applied, at most one per output
+    controls: Vec<ZwlrGammaControlV1>,
+}
+
+impl GammaControlState {
+    pub fn new<D, F>(dh: &DisplayHandle, client_filter: F) -> GammaControlState
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData> + 'static,
+        F: for<'a> Fn(&'a Client) -> bool + Send + Sync + 'static,
+    {
+        let global = dh.create_global::<D, ZwlrGammaControlManagerV1, _>(
+            1,
+            GammaControlManagerGlobalData {
+                filter: Box::new(client_filter),
+            },
+        );
+
+        GammaControlState {
+            global,
+            controls: Vec::new(),
+        }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Fails the gamma control of a removed output
+    pub fn output_removed(&mut self, output: &Output) {
+        self.controls.retain(|control| {
+            let removed = control_output(control) == *output;
+            if removed {
+                control.failed();
+            }
+            !removed
+        });
+    }
+}
+
+fn control_output(control: &ZwlrGammaControlV1) -> WeakOutput {
+    control
+        .data::<GammaControlData>()
+        .map(|data| data.output.clone())
+        .unwrap_or_default()
+}
+
+pub struct GammaControlManagerGlobalData {
+    filter: Box<dyn for<'a> Fn(&'a Client) -> bool + Send + Sync>,
+}
+
+pub struct GammaControlData {
+    output: WeakOutput,
+    size: u32,
+}
+
+impl<D> GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData, D>
+    for GammaControlState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+        + Dispatch<ZwlrGammaControlManagerV1, ()>
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwlrGammaControlManagerV1>,
+        _global_data: &GammaControlManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        audit::record(
+            client,
+            PrivilegedProtocol::OutputManagement,
+            AuditEvent::Bound,
+        );
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &GammaControlManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlManagerV1, (), D> for GammaControlState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+        + Dispatch<ZwlrGammaControlManagerV1, ()>
+        + Dispatch<ZwlrGammaControlV1, GammaControlData>
+        + GammaControlHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _obj: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } => {
+                let output = Output::from_resource(&output);
+                let size = output.as_ref().and_then(|o| state.gamma_size(o));
+                let control = data_init.init(
+                    id,
+                    GammaControlData {
+                        output: output.as_ref().map(|o| o.downgrade()).unwrap_or_default(),
+                        size: size.unwrap_or(0),
+                    },
+                );
+
+                let (Some(output), Some(size)) = (output, size) else {
+                    control.failed();
+                    return;
+                };
+                let controls = &mut state.gamma_control_state().controls;
+                if controls
+                    .iter()
+                    .any(|existing| control_output(existing) == output)
+                {
+                    control.failed();
+                    return;
+                }
+                controls.push(control.clone());
+                control.gamma_size(size);
+            }
+            zwlr_gamma_control_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlV1, GammaControlData, D> for GammaControlState
+where
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData> + GammaControlHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        obj: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        data: &GammaControlData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => {
+                if !state.gamma_control_state().controls.contains(obj) {
+                    // already failed
+                    return;
+                }
+                let Some(output) = data.output.upgrade() else {
+                    return;
+                };
+
+                let applied = match read_ramp(fd, data.size as usize) {
+                    Ok(ramp) => state.set_gamma(&output, Some(ramp)),
+                    Err(err) => {
+                        debug!(?err, "Invalid gamma ramp for {}", output.name());
+                        false
+                    }
+                };
+                if !applied {
+                    state.gamma_control_state().controls.retain(|c| c != obj);
+                    state.set_gamma(&output, None);
+                    obj.failed();
+                }
+            }
+            zwlr_gamma_control_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        obj: &ZwlrGammaControlV1,
+        data: &GammaControlData,
+    ) {
+        let controls = &mut state.gamma_control_state().controls;
+        if controls.contains(obj) {
+            controls.retain(|c| c != obj);
+            if let Some(output) = data.output.upgrade() {
+                state.set_gamma(&output, None);
+            }
+        }
+    }
+}
+
+/// Reads the red, green and blue channels of `size` native-endian entries each.
+///
+/// The ramp has to be complete when it is sent, so the fd is read without blocking and a short
+/// read fails, instead of stalling the compositor on a client that never writes it.
+fn read_ramp(fd: OwnedFd, size: usize) -> io::Result<GammaRamp> {
+    let flags = rustix::fs::fcntl_getfl(&fd)?;
+    rustix::fs::fcntl_setfl(&fd, flags | OFlags::NONBLOCK)?;
+
+    let mut bytes = vec![0; size * 3 * 2];
+    let read = rustix::io::pread(&fd, &mut bytes, 0)?;
+    if read != bytes.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("short gamma ramp, {read} of {} bytes", bytes.len()),
+        ));
+    }
+
+    let values = bytes
+        .chunks_exact(2)
+        .map(|chunk| u16::from_ne_bytes([chunk[0], chunk[1]]))
+        .collect::<Vec<_>>();
+    Ok(GammaRamp {
+        red: values[..size].to_vec(),
+        green: values[size..size * 2].to_vec(),
+        blue: values[size * 2..].to_vec(),
+    })
+}
+
+macro_rules! delegate_gamma_control {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: $crate::wayland::protocols::gamma_control::GammaControlManagerGlobalData
+        ] => $crate::wayland::protocols::gamma_control::GammaControlState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: ()
+        ] => $crate::wayland::protocols::gamma_control::GammaControlState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_v1::ZwlrGammaControlV1: $crate::wayland::protocols::gamma_control::GammaControlData
+        ] => $crate::wayland::protocols::gamma_control::GammaControlState);
+    };
+}
+pub(crate) use delegate_gamma_control;
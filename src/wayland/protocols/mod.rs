@@ -1,13 +1,21 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 pub mod a11y;
+pub mod background_effect;
+pub mod color_management;
+pub mod color_representation;
 pub mod corner_radius;
 pub mod drm;
+pub mod gamma_control;
 pub mod image_capture_source;
 pub mod output_configuration;
 pub mod output_power;
 pub mod overlap_notify;
+pub mod pointer_warp;
 pub mod screencopy;
+pub mod session_management;
 pub mod toplevel_info;
 pub mod toplevel_management;
+pub mod toplevel_tag;
+pub mod virtual_pointer;
 pub mod workspace;
@@ -15,6 +15,7 @@ use smithay::{
 };
 
 use cosmic_comp_config::output::comp::OutputState as EnabledState;
+use cosmic_comp_config::policy::PrivilegedProtocol;
 
 use cosmic_protocols::output_management::v1::server::{
     zcosmic_output_configuration_head_v1::{self, ZcosmicOutputConfigurationHeadV1},
@@ -23,6 +24,7 @@ use cosmic_protocols::output_management::v1::server::{
     zcosmic_output_manager_v1::{self, ZcosmicOutputManagerV1},
 };
 
+use crate::wayland::audit::{self, AuditEvent};
 use crate::wayland::protocols::output_configuration::*;
 
 impl<D> GlobalDispatch<ZcosmicOutputManagerV1, OutputMngrGlobalData, D>
@@ -39,11 +41,16 @@ where
     fn bind(
         _state: &mut D,
         _dh: &DisplayHandle,
-        _client: &Client,
+        client: &Client,
         resource: New<ZcosmicOutputManagerV1>,
         _global_data: &OutputMngrGlobalData,
         data_init: &mut DataInit<'_, D>,
     ) {
+        audit::record(
+            client,
+            PrivilegedProtocol::OutputManagement,
+            AuditEvent::Bound,
+        );
         data_init.init(resource, ());
     }
 
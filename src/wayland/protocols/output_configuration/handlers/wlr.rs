@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use cosmic_protocols::output_management::v1::server::zcosmic_output_configuration_v1;
 use smithay::{
     output::{Mode, Output, WeakOutput},
@@ -20,6 +21,7 @@ use smithay::{
 };
 use std::convert::TryInto;
 
+use crate::wayland::audit::{self, AuditEvent};
 use crate::wayland::protocols::output_configuration::*;
 
 impl<D> GlobalDispatch<ZwlrOutputManagerV1, OutputMngrGlobalData, D> for OutputConfigurationState<D>
@@ -36,11 +38,16 @@ where
     fn bind(
         state: &mut D,
         dh: &DisplayHandle,
-        _client: &Client,
+        client: &Client,
         resource: New<ZwlrOutputManagerV1>,
         _global_data: &OutputMngrGlobalData,
         data_init: &mut DataInit<'_, D>,
     ) {
+        audit::record(
+            client,
+            PrivilegedProtocol::OutputManagement,
+            AuditEvent::Bound,
+        );
         let mut instance = OutputMngrInstance {
             obj: data_init.init(resource, ()),
             heads: Vec::new(),
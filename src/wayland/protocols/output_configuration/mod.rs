@@ -326,6 +326,11 @@ where
     pub fn outputs(&self) -> impl Iterator<Item = Output> {
         self.outputs.clone().into_iter()
     }
+
+    /// Serial of the last configuration sent to clients, changes with every update
+    pub fn serial(&self) -> u32 {
+        self.serial_counter
+    }
 }
 
 fn send_head_to_client<D>(dh: &DisplayHandle, mngr: &mut OutputMngrInstance, output: &Output)
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use smithay::{
     output::{Output, WeakOutput},
     reexports::{
@@ -16,6 +17,8 @@ use smithay::{
 use std::{collections::HashMap, mem};
 use wayland_backend::{protocol::WEnum, server::ClientId};
 
+use crate::wayland::audit::{self, AuditEvent};
+
 pub trait OutputPowerHandler {
     fn output_power_state(&mut self) -> &mut OutputPowerState;
     fn get_dpms(&mut self, output: &Output) -> Option<bool>;
@@ -90,11 +93,16 @@ where
     fn bind(
         _state: &mut D,
         _dh: &DisplayHandle,
-        _client: &Client,
+        client: &Client,
         resource: New<ZwlrOutputPowerManagerV1>,
         _global_data: &OutputPowerManagerGlobalData,
         data_init: &mut DataInit<'_, D>,
     ) {
+        audit::record(
+            client,
+            PrivilegedProtocol::OutputManagement,
+            AuditEvent::Bound,
+        );
         data_init.init(resource, ());
     }
 
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! wp-pointer-warp, letting clients move the pointer within their own surfaces.
+//!
+//! Requests are passed on to the handler, which decides whether to honor them.
+
+use smithay::{
+    reexports::{
+        wayland_protocols::wp::pointer_warp::v1::server::wp_pointer_warp_v1::{
+            self, WpPointerWarpV1,
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+            backend::GlobalId, protocol::wl_surface::WlSurface,
+        },
+    },
+    utils::{Logical, Point, Serial},
+};
+
+pub trait PointerWarpHandler {
+    /// A client asks to move the pointer to `location` in the coordinates of `surface`.
+    ///
+    /// `serial` is the serial of the pointer enter event of `surface`.
+    fn warp_pointer(&mut self, surface: &WlSurface, location: Point<f64, Logical>, serial: Serial);
+}
+
+#[derive(Debug)]
+pub struct PointerWarpState {
+    global: GlobalId,
+}
+
+impl PointerWarpState {
+    pub fn new<D>(dh: &DisplayHandle) -> PointerWarpState
+    where
+        D: GlobalDispatch<WpPointerWarpV1, ()> + 'static,
+    {
+        let global = dh.create_global::<D, WpPointerWarpV1, _>(1, ());
+        PointerWarpState { global }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<WpPointerWarpV1, (), D> for PointerWarpState
+where
+    D: GlobalDispatch<WpPointerWarpV1, ()> + Dispatch<WpPointerWarpV1, ()> + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpPointerWarpV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<WpPointerWarpV1, (), D> for PointerWarpState
+where
+    D: Dispatch<WpPointerWarpV1, ()> + PointerWarpHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _obj: &WpPointerWarpV1,
+        request: wp_pointer_warp_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_pointer_warp_v1::Request::WarpPointer {
+                surface,
+                pointer,
+                x,
+                y,
+                serial,
+            } => {
+                // the pointer has to belong to the client of the surface
+                if !pointer.id().same_client_as(&surface.id()) {
+                    return;
+                }
+                state.warp_pointer(&surface, (x, y).into(), Serial::from(serial));
+            }
+            wp_pointer_warp_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+macro_rules! delegate_pointer_warp {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::pointer_warp::v1::server::wp_pointer_warp_v1::WpPointerWarpV1: ()
+        ] => $crate::wayland::protocols::pointer_warp::PointerWarpState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::pointer_warp::v1::server::wp_pointer_warp_v1::WpPointerWarpV1: ()
+        ] => $crate::wayland::protocols::pointer_warp::PointerWarpState);
+    };
+}
+pub(crate) use delegate_pointer_warp;
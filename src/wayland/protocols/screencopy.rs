@@ -7,6 +7,7 @@ use std::{
 pub use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::{
     ext_image_copy_capture_frame_v1::FailureReason,
 };
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::{
     ext_image_copy_capture_cursor_session_v1::{self, ExtImageCopyCaptureCursorSessionV1},
     ext_image_copy_capture_frame_v1::{self, ExtImageCopyCaptureFrameV1},
@@ -34,6 +35,7 @@ use tracing::debug;
 use wayland_backend::server::GlobalId;
 
 use super::image_capture_source::ImageCaptureSourceData;
+use crate::wayland::audit::{self, AuditEvent};
 
 #[derive(Debug)]
 pub struct ScreencopyState {
@@ -166,6 +168,10 @@ impl SessionRef {
         self.inner.lock().unwrap().draw_cursors
     }
 
+    pub fn client(&self) -> Option<Client> {
+        self.obj.client()
+    }
+
     pub fn user_data(&self) -> &UserDataMap {
         &self.user_data
     }
@@ -297,6 +303,10 @@ impl CursorSessionRef {
         self.inner.lock().unwrap().source.clone()
     }
 
+    pub fn client(&self) -> Option<Client> {
+        self.obj.client()
+    }
+
     pub fn has_cursor(&self) -> bool {
         self.inner.lock().unwrap().position.is_some()
     }
@@ -581,11 +591,12 @@ where
     fn bind(
         _state: &mut D,
         _handle: &DisplayHandle,
-        _client: &Client,
+        client: &Client,
         resource: New<ExtImageCopyCaptureManagerV1>,
         _global_data: &ScreencopyGlobalData,
         data_init: &mut DataInit<'_, D>,
     ) {
+        audit::record(client, PrivilegedProtocol::Screencopy, AuditEvent::Bound);
         data_init.init(resource, ScreencopyData);
     }
 
@@ -0,0 +1,382 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! xx-session-management-v1, letting clients restore the window state of their toplevels.
+//!
+//! Sessions are identified by a string handed out by the compositor. Toplevels are added to a
+//! session under a name chosen by the client, the state stored for that name may be restored
+//! on a new toplevel before its initial commit. Storing and restoring the state itself is left
+//! to the [`SessionManagementHandler`].
+
+pub use generated::{xx_session_manager_v1, xx_session_v1, xx_toplevel_session_v1};
+
+#[allow(non_snake_case, non_upper_case_globals, non_camel_case_types)]
+mod generated {
+    use smithay::reexports::{
+        wayland_protocols::xdg::shell::server::*,
+        wayland_server::{self, protocol::*},
+    };
+
+    pub mod __interfaces {
+        use smithay::reexports::{
+            wayland_protocols::xdg::shell::server::__interfaces::*,
+            wayland_server::protocol::__interfaces::*,
+        };
+        use wayland_backend;
+        wayland_scanner::generate_interfaces!("resources/protocols/xx-session-management-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_server_code!("resources/protocols/xx-session-management-v1.xml");
+}
+
+use std::collections::HashMap;
+
+use smithay::reexports::{
+    wayland_protocols::xdg::shell::server::xdg_toplevel::XdgToplevel,
+    wayland_server::{
+        Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+        backend::{ClientId, GlobalId},
+    },
+};
+use xx_session_manager_v1::XxSessionManagerV1;
+use xx_session_v1::XxSessionV1;
+use xx_toplevel_session_v1::XxToplevelSessionV1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreResult {
+    Restored,
+    /// Nothing was stored for the toplevel
+    NotFound,
+    /// The toplevel had its initial commit already
+    AlreadyMapped,
+}
+
+pub trait SessionManagementHandler {
+    fn session_management_state(&mut self) -> &mut SessionManagementState;
+    /// Whether the session `id` was created before and wasn't removed since
+    fn session_exists(&mut self, id: &str) -> bool;
+    fn create_session(&mut self, id: &str);
+    fn remove_session(&mut self, id: &str);
+    /// `toplevel` was added to `session` as `name`, its state is to be stored from now on
+    fn add_toplevel(&mut self, session: &str, name: &str, toplevel: &XdgToplevel);
+    /// Applies the state stored for `name` in `session` to the not yet mapped `toplevel`
+    fn restore_toplevel(
+        &mut self,
+        session: &str,
+        name: &str,
+        toplevel: &XdgToplevel,
+    ) -> RestoreResult;
+    /// `toplevel` stops being tracked, its last state is to be kept
+    fn toplevel_session_destroyed(&mut self, session: &str, name: &str, toplevel: &XdgToplevel);
+    fn remove_toplevel(&mut self, session: &str, name: &str);
+}
+
+#[derive(Debug)]
+pub struct SessionData {
+    id: String,
+}
+
+#[derive(Debug)]
+pub struct ToplevelSessionData {
+    session: String,
+    name: String,
+    toplevel: XdgToplevel,
+}
+
+#[derive(Debug)]
+pub struct SessionManagementState {
+    global: GlobalId,
+    /// Session objects by the id of the session they are in use for
+    sessions: HashMap<String, XxSessionV1>,
+    /// Toplevel session objects of sessions in use
+    toplevels: Vec<XxToplevelSessionV1>,
+}
+
+impl SessionManagementState {
+    pub fn new<D>(dh: &DisplayHandle) -> SessionManagementState
+    where
+        D: GlobalDispatch<XxSessionManagerV1, ()> + 'static,
+    {
+        let global = dh.create_global::<D, XxSessionManagerV1, _>(1, ());
+        SessionManagementState {
+            global,
+            sessions: HashMap::new(),
+            toplevels: Vec::new(),
+        }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Session and name of the tracked toplevels
+    pub fn toplevels(&self) -> impl Iterator<Item = (&str, &str, &XdgToplevel)> {
+        self.toplevels.iter().filter_map(|obj| {
+            let data = obj.data::<ToplevelSessionData>()?;
+            Some((data.session.as_str(), data.name.as_str(), &data.toplevel))
+        })
+    }
+
+    /// Session and name `toplevel` is tracked as
+    pub fn toplevel_session(&self, toplevel: &XdgToplevel) -> Option<(String, String)> {
+        self.toplevels()
+            .find(|(_, _, t)| *t == toplevel)
+            .map(|(session, name, _)| (session.to_string(), name.to_string()))
+    }
+
+    fn is_active(&self, session: &XxSessionV1, id: &str) -> bool {
+        self.sessions.get(id) == Some(session)
+    }
+
+    fn is_tracked(&self, toplevel_session: &XxToplevelSessionV1) -> bool {
+        self.toplevels.contains(toplevel_session)
+    }
+
+    fn untrack(&mut self, toplevel_session: &XxToplevelSessionV1) {
+        self.toplevels.retain(|obj| obj != toplevel_session);
+    }
+
+    /// Stops using the session `id`, making its toplevel session objects inert.
+    ///
+    /// Returns the names and toplevels that were tracked.
+    fn release(&mut self, id: &str) -> Vec<(String, XdgToplevel)> {
+        self.sessions.remove(id);
+        let mut released = Vec::new();
+        self.toplevels
+            .retain(|obj| match obj.data::<ToplevelSessionData>() {
+                Some(data) if data.session == id => {
+                    released.push((data.name.clone(), data.toplevel.clone()));
+                    false
+                }
+                _ => true,
+            });
+        released
+    }
+}
+
+impl<D> GlobalDispatch<XxSessionManagerV1, (), D> for SessionManagementState
+where
+    D: GlobalDispatch<XxSessionManagerV1, ()> + Dispatch<XxSessionManagerV1, ()> + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<XxSessionManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<XxSessionManagerV1, (), D> for SessionManagementState
+where
+    D: Dispatch<XxSessionManagerV1, ()>
+        + Dispatch<XxSessionV1, SessionData>
+        + SessionManagementHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        resource: &XxSessionManagerV1,
+        request: xx_session_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xx_session_manager_v1::Request::GetSession { id, session, .. } => {
+                let existing = session.filter(|session| state.session_exists(session));
+                if let Some(session) = existing.as_ref() {
+                    let previous = state.session_management_state().sessions.get(session);
+                    if let Some(previous) = previous.cloned() {
+                        if previous.client().is_some_and(|c| c.id() == client.id()) {
+                            resource.post_error(
+                                xx_session_manager_v1::Error::InUse as u32,
+                                format!("Session {session} is already in use"),
+                            );
+                            return;
+                        }
+                        previous.replaced();
+                        for (name, toplevel) in state.session_management_state().release(session) {
+                            state.toplevel_session_destroyed(session, &name, &toplevel);
+                        }
+                    }
+                }
+
+                let restored = existing.is_some();
+                let session_id = existing.unwrap_or_else(|| {
+                    let id = format!("{:032x}", rand::random::<u128>());
+                    state.create_session(&id);
+                    id
+                });
+                let obj = data_init.init(
+                    id,
+                    SessionData {
+                        id: session_id.clone(),
+                    },
+                );
+                if restored {
+                    obj.restored();
+                } else {
+                    obj.created(session_id.clone());
+                }
+                state
+                    .session_management_state()
+                    .sessions
+                    .insert(session_id, obj);
+            }
+            xx_session_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<XxSessionV1, SessionData, D> for SessionManagementState
+where
+    D: Dispatch<XxSessionV1, SessionData>
+        + Dispatch<XxToplevelSessionV1, ToplevelSessionData>
+        + SessionManagementHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &XxSessionV1,
+        request: xx_session_v1::Request,
+        data: &SessionData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let active = state
+            .session_management_state()
+            .is_active(resource, &data.id);
+        let (id, toplevel, name, restore) = match request {
+            xx_session_v1::Request::AddToplevel { id, toplevel, name } => {
+                (id, toplevel, name, false)
+            }
+            xx_session_v1::Request::RestoreToplevel { id, toplevel, name } => {
+                (id, toplevel, name, true)
+            }
+            xx_session_v1::Request::Remove => {
+                if active {
+                    state.session_management_state().release(&data.id);
+                    state.remove_session(&data.id);
+                }
+                return;
+            }
+            xx_session_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let obj = data_init.init(
+            id,
+            ToplevelSessionData {
+                session: data.id.clone(),
+                name: name.clone(),
+                toplevel: toplevel.clone(),
+            },
+        );
+        if !active {
+            // replaced sessions are inert
+            return;
+        }
+        if state
+            .session_management_state()
+            .toplevels()
+            .any(|(session, n, t)| session == data.id && (n == name || *t == toplevel))
+        {
+            resource.post_error(
+                xx_session_v1::Error::NameInUse as u32,
+                format!("{name} is already in use in this session"),
+            );
+            return;
+        }
+
+        if restore {
+            match state.restore_toplevel(&data.id, &name, &toplevel) {
+                RestoreResult::Restored => obj.restored(&toplevel),
+                RestoreResult::NotFound => {}
+                RestoreResult::AlreadyMapped => {
+                    resource.post_error(
+                        xx_session_v1::Error::AlreadyMapped as u32,
+                        "The toplevel was committed before being restored",
+                    );
+                    return;
+                }
+            }
+        } else {
+            state.add_toplevel(&data.id, &name, &toplevel);
+        }
+        state.session_management_state().toplevels.push(obj);
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &XxSessionV1, data: &SessionData) {
+        if state
+            .session_management_state()
+            .is_active(resource, &data.id)
+        {
+            for (name, toplevel) in state.session_management_state().release(&data.id) {
+                state.toplevel_session_destroyed(&data.id, &name, &toplevel);
+            }
+        }
+    }
+}
+
+impl<D> Dispatch<XxToplevelSessionV1, ToplevelSessionData, D> for SessionManagementState
+where
+    D: Dispatch<XxToplevelSessionV1, ToplevelSessionData> + SessionManagementHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &XxToplevelSessionV1,
+        request: xx_toplevel_session_v1::Request,
+        data: &ToplevelSessionData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xx_toplevel_session_v1::Request::Remove => {
+                if state.session_management_state().is_tracked(resource) {
+                    state.session_management_state().untrack(resource);
+                    state.remove_toplevel(&data.session, &data.name);
+                }
+            }
+            xx_toplevel_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &XxToplevelSessionV1,
+        data: &ToplevelSessionData,
+    ) {
+        if state.session_management_state().is_tracked(resource) {
+            state.session_management_state().untrack(resource);
+            state.toplevel_session_destroyed(&data.session, &data.name, &data.toplevel);
+        }
+    }
+}
+
+macro_rules! delegate_session_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::session_management::xx_session_manager_v1::XxSessionManagerV1: ()
+        ] => $crate::wayland::protocols::session_management::SessionManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::session_management::xx_session_manager_v1::XxSessionManagerV1: ()
+        ] => $crate::wayland::protocols::session_management::SessionManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::session_management::xx_session_v1::XxSessionV1: $crate::wayland::protocols::session_management::SessionData
+        ] => $crate::wayland::protocols::session_management::SessionManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::protocols::session_management::xx_toplevel_session_v1::XxToplevelSessionV1: $crate::wayland::protocols::session_management::ToplevelSessionData
+        ] => $crate::wayland::protocols::session_management::SessionManagementState);
+    };
+}
+pub(crate) use delegate_session_management;
@@ -13,15 +13,23 @@ use smithay::{
         },
     },
     utils::{IsAlive, Logical, Rectangle, user_data::UserDataMap},
-    wayland::foreign_toplevel_list::{
-        ForeignToplevelHandle, ForeignToplevelListHandler, ForeignToplevelListState,
+    wayland::{
+        foreign_toplevel_list::{
+            ForeignToplevelHandle, ForeignToplevelListHandler, ForeignToplevelListState,
+        },
+        security_context::SecurityContext,
     },
 };
 
 use crate::utils::prelude::{Global, OutputExt, RectGlobalExt};
+use crate::wayland::{
+    audit::{self, AuditEvent},
+    toplevel_metadata::{self, ToplevelMetadata},
+};
 
 use super::workspace::{WorkspaceHandle, WorkspaceHandler, WorkspaceState};
 
+use cosmic_comp_config::policy::PrivilegedProtocol;
 use cosmic_protocols::toplevel_info::v1::server::{
     zcosmic_toplevel_handle_v1::{self, State as States, ZcosmicToplevelHandleV1},
     zcosmic_toplevel_info_v1::{self, ZcosmicToplevelInfoV1},
@@ -37,8 +45,16 @@ pub trait Window: IsAlive + Clone + PartialEq + Send {
     fn is_minimized(&self) -> bool;
     fn is_sticky(&self) -> bool;
     fn is_resizing(&self) -> bool;
+    fn is_urgent(&self) -> bool;
     fn global_geometry(&self) -> Option<Rectangle<i32, Global>>;
     fn user_data(&self) -> &UserDataMap;
+    /// Pid of the process owning the toplevel, if known
+    fn pid(&self) -> Option<u32>;
+    fn security_context(&self) -> Option<SecurityContext>;
+    /// Sandbox engine of the toplevel's client, also for sandboxes without a security context
+    fn sandbox_engine(&self) -> Option<String>;
+    /// Tag of the toplevel and its description, as set through xdg-toplevel-tag
+    fn tag(&self) -> (Option<String>, Option<String>);
 }
 
 #[derive(Debug)]
@@ -143,11 +159,16 @@ where
     fn bind(
         state: &mut D,
         dh: &DisplayHandle,
-        _client: &Client,
+        client: &Client,
         resource: New<ZcosmicToplevelInfoV1>,
         _global_data: &ToplevelInfoGlobalData,
         data_init: &mut DataInit<'_, D>,
     ) {
+        audit::record(
+            client,
+            PrivilegedProtocol::ToplevelManagement,
+            AuditEvent::Bound,
+        );
         let instance = data_init.init(resource, ());
         for window in &state.toplevel_info_state().toplevels {
             send_toplevel_to_client::<D, W>(dh, state.workspace_state(), &instance, window);
@@ -335,6 +356,17 @@ where
         let toplevel_handle = self
             .foreign_toplevel_list
             .new_toplevel::<D>(toplevel.title(), toplevel.app_id());
+        toplevel_metadata::insert(
+            &toplevel_handle.identifier(),
+            ToplevelMetadata {
+                sandbox_engine: toplevel.sandbox_engine(),
+                ..ToplevelMetadata::new(
+                    toplevel.pid(),
+                    toplevel.security_context(),
+                    toplevel.is_urgent(),
+                )
+            },
+        );
         toplevel
             .user_data()
             .insert_if_missing(move || ToplevelStateInner::from_foreign(toplevel_handle));
@@ -360,6 +392,7 @@ where
                 }
             }
             if let Some(handle) = state_inner.foreign_handle.take() {
+                toplevel_metadata::remove(&handle.identifier());
                 self.foreign_toplevel_list.remove_toplevel(&handle);
             }
             *state_inner = Default::default();
@@ -382,6 +415,16 @@ where
                 .rectangles
                 .retain(|(surface, _)| surface.upgrade().is_ok());
             if window.alive() {
+                if let Some(handle) = state.foreign_handle.as_ref() {
+                    toplevel_metadata::set_urgent(&handle.identifier(), window.is_urgent());
+                    let (tag, description) = window.tag();
+                    toplevel_metadata::set_tag(
+                        &handle.identifier(),
+                        &window.app_id(),
+                        tag,
+                        description,
+                    );
+                }
                 std::mem::drop(state);
                 for instance in &self.instances {
                     let changed = send_toplevel_to_client::<D, W>(
@@ -394,6 +437,9 @@ where
                 }
                 true
             } else {
+                if let Some(handle) = state.foreign_handle.as_ref() {
+                    toplevel_metadata::remove(&handle.identifier());
+                }
                 for (_info, handle) in &state.instances {
                     // don't send events to stopped instances
                     if handle.version() < zcosmic_toplevel_info_v1::REQ_GET_COSMIC_TOPLEVEL_SINCE
@@ -428,6 +474,13 @@ where
     pub fn registered_toplevels(&self) -> impl Iterator<Item = &W> {
         self.toplevels.iter()
     }
+
+    /// Toplevel of the ext-foreign-toplevel handle with `identifier`
+    pub fn toplevel_for_identifier(&self, identifier: &str) -> Option<&W> {
+        self.toplevels
+            .iter()
+            .find(|w| toplevel_identifier(*w).is_some_and(|id| id == identifier))
+    }
 }
 
 fn send_toplevel_to_client<D, W>(
@@ -622,6 +675,18 @@ where
     changed
 }
 
+/// Identifier of the ext-foreign-toplevel handle of `toplevel`
+pub fn toplevel_identifier(toplevel: &impl Window) -> Option<String> {
+    toplevel
+        .user_data()
+        .get::<ToplevelState>()?
+        .lock()
+        .unwrap()
+        .foreign_handle
+        .as_ref()
+        .map(|handle| handle.identifier())
+}
+
 pub fn window_from_handle<W: Window + 'static>(handle: ZcosmicToplevelHandleV1) -> Option<W> {
     handle
         .data::<ToplevelHandleState<W>>()
@@ -11,11 +11,14 @@ use smithay::{
     utils::{Logical, Rectangle},
 };
 
+use cosmic_comp_config::policy::PrivilegedProtocol;
 pub use cosmic_protocols::toplevel_management::v1::server::zcosmic_toplevel_manager_v1::ZcosmicToplelevelManagementCapabilitiesV1 as ManagementCapabilities;
 use cosmic_protocols::toplevel_management::v1::server::zcosmic_toplevel_manager_v1::{
     self, ZcosmicToplevelManagerV1,
 };
 
+use crate::wayland::audit::{self, AuditEvent};
+
 use super::{
     toplevel_info::{ToplevelInfoHandler, ToplevelState, Window, window_from_handle},
     workspace::WorkspaceHandle,
@@ -145,11 +148,16 @@ where
     fn bind(
         state: &mut D,
         _dh: &DisplayHandle,
-        _client: &Client,
+        client: &Client,
         resource: New<ZcosmicToplevelManagerV1>,
         _global_data: &ToplevelManagerGlobalData,
         data_init: &mut DataInit<'_, D>,
     ) {
+        audit::record(
+            client,
+            PrivilegedProtocol::ToplevelManagement,
+            AuditEvent::Bound,
+        );
         let instance = data_init.init(resource, ());
         let capabilities = state
             .toplevel_management_state()
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! xdg-toplevel-tag-v1, letting clients tag their toplevels.
+//!
+//! Tags stay the same across restarts of the application, so together with the app id they
+//! identify a window for rules and scripts. Storing them is left to the [`ToplevelTagHandler`].
+
+use smithay::reexports::{
+    wayland_protocols::xdg::{
+        shell::server::xdg_toplevel::XdgToplevel,
+        toplevel_tag::v1::server::xdg_toplevel_tag_manager_v1::{self, XdgToplevelTagManagerV1},
+    },
+    wayland_server::{
+        Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, backend::GlobalId,
+    },
+};
+
+pub trait ToplevelTagHandler {
+    /// `toplevel` set its untranslated `tag`
+    fn set_tag(&mut self, toplevel: &XdgToplevel, tag: String);
+    /// `toplevel` set a translated, human-readable `description` of its tag
+    fn set_description(&mut self, toplevel: &XdgToplevel, description: String);
+}
+
+#[derive(Debug)]
+pub struct ToplevelTagState {
+    global: GlobalId,
+}
+
+impl ToplevelTagState {
+    pub fn new<D>(dh: &DisplayHandle) -> ToplevelTagState
+    where
+        D: GlobalDispatch<XdgToplevelTagManagerV1, ()> + 'static,
+    {
+        let global = dh.create_global::<D, XdgToplevelTagManagerV1, _>(1, ());
+        ToplevelTagState { global }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<XdgToplevelTagManagerV1, (), D> for ToplevelTagState
+where
+    D: GlobalDispatch<XdgToplevelTagManagerV1, ()>
+        + Dispatch<XdgToplevelTagManagerV1, ()>
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<XdgToplevelTagManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<XdgToplevelTagManagerV1, (), D> for ToplevelTagState
+where
+    D: Dispatch<XdgToplevelTagManagerV1, ()> + ToplevelTagHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &XdgToplevelTagManagerV1,
+        request: xdg_toplevel_tag_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xdg_toplevel_tag_manager_v1::Request::SetToplevelTag { toplevel, tag } => {
+                state.set_tag(&toplevel, tag);
+            }
+            xdg_toplevel_tag_manager_v1::Request::SetToplevelDescription {
+                toplevel,
+                description,
+            } => {
+                state.set_description(&toplevel, description);
+            }
+            xdg_toplevel_tag_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+macro_rules! delegate_toplevel_tag {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::toplevel_tag::v1::server::xdg_toplevel_tag_manager_v1::XdgToplevelTagManagerV1: ()
+        ] => $crate::wayland::protocols::toplevel_tag::ToplevelTagState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::toplevel_tag::v1::server::xdg_toplevel_tag_manager_v1::XdgToplevelTagManagerV1: ()
+        ] => $crate::wayland::protocols::toplevel_tag::ToplevelTagState);
+    };
+}
+pub(crate) use delegate_toplevel_tag;
@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::Mutex;
+
+use cosmic_comp_config::policy::PrivilegedProtocol;
+use smithay::{
+    backend::input::{Axis, AxisSource, InputEvent},
+    output::{Output, WeakOutput},
+    reexports::{
+        wayland_protocols_wlr::virtual_pointer::v1::server::{
+            zwlr_virtual_pointer_manager_v1::{self, ZwlrVirtualPointerManagerV1},
+            zwlr_virtual_pointer_v1::{self, ZwlrVirtualPointerV1},
+        },
+        wayland_server::{
+            Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+            backend::GlobalId,
+            protocol::{wl_pointer, wl_seat::WlSeat},
+        },
+    },
+};
+use wayland_backend::{protocol::WEnum, server::ClientId};
+
+use crate::{
+    input::virtual_pointer::{
+        VirtualPointerDevice, VirtualPointerEvent, VirtualPointerInput, axis_index,
+    },
+    wayland::{
+        audit::{self, AuditEvent},
+        consent,
+    },
+};
+
+pub trait VirtualPointerHandler {
+    /// Adds the device of a new virtual pointer to `seat`, the last active seat if `None`
+    fn add_virtual_pointer(&mut self, device: &VirtualPointerDevice, seat: Option<&WlSeat>);
+    fn remove_virtual_pointer(&mut self, device: &VirtualPointerDevice);
+    /// Processes an event of a virtual pointer.
+    ///
+    /// Absolute motion covers `output`, if the pointer is bound to one, or all outputs otherwise.
+    fn virtual_pointer_input(
+        &mut self,
+        event: InputEvent<VirtualPointerInput>,
+        output: Option<&Output>,
+    );
+}
+
+#[derive(Debug)]
+pub struct VirtualPointerManagerState {
+    global: GlobalId,
+}
+
+impl VirtualPointerManagerState {
+    pub fn new<D, F>(dh: &DisplayHandle, client_filter: F) -> VirtualPointerManagerState
+    where
+        D: GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData> + 'static,
+        F: for<'a> Fn(&'a Client) -> bool + Send + Sync + 'static,
+    {
+        let global = dh.create_global::<D, ZwlrVirtualPointerManagerV1, _>(
+            2,
+            VirtualPointerManagerGlobalData {
+                filter: Box::new(client_filter),
+            },
+        );
+        VirtualPointerManagerState { global }
+    }
+
+    pub fn global_id(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+pub struct VirtualPointerManagerGlobalData {
+    filter: Box<dyn for<'a> Fn(&'a Client) -> bool + Send + Sync>,
+}
+
+pub struct VirtualPointerData {
+    device: VirtualPointerDevice,
+    output: Option<WeakOutput>,
+    /// Axis event collected until the next frame
+    pending_axis: Mutex<Option<VirtualPointerEvent>>,
+}
+
+impl<D> GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData, D>
+    for VirtualPointerManagerState
+where
+    D: GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData>
+        + Dispatch<ZwlrVirtualPointerManagerV1, ()>
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        client: &Client,
+        resource: New<ZwlrVirtualPointerManagerV1>,
+        _global_data: &VirtualPointerManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        audit::record(client, PrivilegedProtocol::VirtualInput, AuditEvent::Bound);
+        consent::allow_synthetic_input(client);
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &VirtualPointerManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrVirtualPointerManagerV1, (), D> for VirtualPointerManagerState
+where
+    D: Dispatch<ZwlrVirtualPointerManagerV1, ()>
+        + Dispatch<ZwlrVirtualPointerV1, VirtualPointerData>
+        + VirtualPointerHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        _obj: &ZwlrVirtualPointerManagerV1,
+        request: zwlr_virtual_pointer_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let (seat, output, id) = match request {
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointer { seat, id } => {
+                (seat, None, id)
+            }
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointerWithOutput {
+                seat,
+                output,
+                id,
+            } => (
+                seat,
+                output
+                    .as_ref()
+                    .and_then(Output::from_resource)
+                    .map(|o| o.downgrade()),
+                id,
+            ),
+            zwlr_virtual_pointer_manager_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        audit::record(
+            client,
+            PrivilegedProtocol::VirtualInput,
+            AuditEvent::DeviceCreated,
+        );
+        let device = VirtualPointerDevice::unique();
+        state.add_virtual_pointer(&device, seat.as_ref());
+        data_init.init(
+            id,
+            VirtualPointerData {
+                device,
+                output,
+                pending_axis: Mutex::new(None),
+            },
+        );
+    }
+}
+
+impl<D> Dispatch<ZwlrVirtualPointerV1, VirtualPointerData, D> for VirtualPointerManagerState
+where
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerData> + VirtualPointerHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _obj: &ZwlrVirtualPointerV1,
+        request: zwlr_virtual_pointer_v1::Request,
+        data: &VirtualPointerData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let event = |time: u32| VirtualPointerEvent::new(data.device.clone(), time as u64 * 1000);
+        let update_axis = |time: u32, f: &mut dyn FnMut(&mut VirtualPointerEvent)| {
+            let mut pending_axis = data.pending_axis.lock().unwrap();
+            f(pending_axis.get_or_insert_with(|| event(time)));
+        };
+
+        match request {
+            zwlr_virtual_pointer_v1::Request::Motion { time, dx, dy } => {
+                let event = VirtualPointerEvent {
+                    position: (dx, dy),
+                    ..event(time)
+                };
+                state.virtual_pointer_input(InputEvent::PointerMotion { event }, None);
+            }
+            zwlr_virtual_pointer_v1::Request::MotionAbsolute {
+                time,
+                x,
+                y,
+                x_extent,
+                y_extent,
+            } => {
+                if x_extent == 0 || y_extent == 0 {
+                    return;
+                }
+                let output = match &data.output {
+                    Some(output) => match output.upgrade() {
+                        Some(output) => Some(output),
+                        // the output the pointer is bound to is gone
+                        None => return,
+                    },
+                    None => None,
+                };
+                let event = VirtualPointerEvent {
+                    position: (x as f64, y as f64),
+                    extent: (x_extent as f64, y_extent as f64),
+                    ..event(time)
+                };
+                state.virtual_pointer_input(
+                    InputEvent::PointerMotionAbsolute { event },
+                    output.as_ref(),
+                );
+            }
+            zwlr_virtual_pointer_v1::Request::Button {
+                time,
+                button,
+                state: button_state,
+            } => {
+                let event = VirtualPointerEvent {
+                    button,
+                    pressed: button_state == WEnum::Value(wl_pointer::ButtonState::Pressed),
+                    ..event(time)
+                };
+                state.virtual_pointer_input(InputEvent::PointerButton { event }, None);
+            }
+            zwlr_virtual_pointer_v1::Request::Axis { time, axis, value } => {
+                if let Some(axis) = axis_from_wl(axis) {
+                    update_axis(time, &mut |event| {
+                        event.axis[axis_index(axis)] = Some(value)
+                    });
+                }
+            }
+            zwlr_virtual_pointer_v1::Request::AxisDiscrete {
+                time,
+                axis,
+                value,
+                discrete,
+            } => {
+                if let Some(axis) = axis_from_wl(axis) {
+                    update_axis(time, &mut |event| {
+                        event.axis[axis_index(axis)] = Some(value);
+                        event.axis_v120[axis_index(axis)] = Some(discrete as f64 * 120.);
+                    });
+                }
+            }
+            zwlr_virtual_pointer_v1::Request::AxisStop { time, axis } => {
+                if let Some(axis) = axis_from_wl(axis) {
+                    update_axis(time, &mut |event| event.axis[axis_index(axis)] = Some(0.));
+                }
+            }
+            zwlr_virtual_pointer_v1::Request::AxisSource { axis_source } => {
+                let source = match axis_source {
+                    WEnum::Value(wl_pointer::AxisSource::Wheel) => AxisSource::Wheel,
+                    WEnum::Value(wl_pointer::AxisSource::Finger) => AxisSource::Finger,
+                    WEnum::Value(wl_pointer::AxisSource::Continuous) => AxisSource::Continuous,
+                    WEnum::Value(wl_pointer::AxisSource::WheelTilt) => AxisSource::WheelTilt,
+                    _ => return,
+                };
+                update_axis(0, &mut |event| event.axis_source = source);
+            }
+            zwlr_virtual_pointer_v1::Request::Frame => {
+                let pending_axis = data.pending_axis.lock().unwrap().take();
+                if let Some(event) = pending_axis {
+                    state.virtual_pointer_input(InputEvent::PointerAxis { event }, None);
+                }
+            }
+            zwlr_virtual_pointer_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        _obj: &ZwlrVirtualPointerV1,
+        data: &VirtualPointerData,
+    ) {
+        state.remove_virtual_pointer(&data.device);
+    }
+}
+
+fn axis_from_wl(axis: WEnum<wl_pointer::Axis>) -> Option<Axis> {
+    match axis {
+        WEnum::Value(wl_pointer::Axis::HorizontalScroll) => Some(Axis::Horizontal),
+        WEnum::Value(wl_pointer::Axis::VerticalScroll) => Some(Axis::Vertical),
+        _ => None,
+    }
+}
+
+macro_rules! delegate_virtual_pointer {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1: $crate::wayland::protocols::virtual_pointer::VirtualPointerManagerGlobalData
+        ] => $crate::wayland::protocols::virtual_pointer::VirtualPointerManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1: ()
+        ] => $crate::wayland::protocols::virtual_pointer::VirtualPointerManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1: $crate::wayland::protocols::virtual_pointer::VirtualPointerData
+        ] => $crate::wayland::protocols::virtual_pointer::VirtualPointerManagerState);
+    };
+}
+pub(crate) use delegate_virtual_pointer;
@@ -64,6 +64,7 @@ where
     cosmic_v2_global: GlobalId,
     ext_instances: Vec<ExtWorkspaceManagerV1>,
     groups: Vec<WorkspaceGroup>,
+    serial: u32,
     _marker: std::marker::PhantomData<D>,
 }
 pub struct WorkspaceUpdateGuard<'a, D>(&'a mut WorkspaceState<D>)
@@ -205,10 +206,16 @@ where
             cosmic_v2_global,
             ext_instances: Vec::new(),
             groups: Vec::new(),
+            serial: 0,
             _marker: std::marker::PhantomData,
         }
     }
 
+    /// Changes whenever workspaces are added, removed, moved or change their states
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
     pub fn workspace_belongs_to_group(
         &self,
         group: &WorkspaceGroupHandle,
@@ -371,6 +378,7 @@ where
                 ext_id,
             };
             group.workspaces.push(workspace);
+            self.0.serial = self.0.serial.wrapping_add(1);
             Some(WorkspaceHandle { id })
         } else {
             None
@@ -414,6 +422,7 @@ where
             group.workspaces.retain(|w| w.id != workspace.id);
         }
         WORKSPACE_IDS.lock().unwrap().remove(&workspace.id);
+        self.0.serial = self.0.serial.wrapping_add(1);
     }
 
     pub fn move_workspace_to_group(
@@ -451,6 +460,7 @@ where
         }
 
         self.0.groups[group_idx].workspaces.push(workspace);
+        self.0.serial = self.0.serial.wrapping_add(1);
     }
 
     pub fn workspace_belongs_to_group(
@@ -560,7 +570,10 @@ where
             .iter_mut()
             .find_map(|g| g.workspaces.iter_mut().find(|w| w.id == workspace.id))
         {
-            workspace.states.insert(state);
+            if !workspace.states.contains(state) {
+                workspace.states.insert(state);
+                self.0.serial = self.0.serial.wrapping_add(1);
+            }
         }
     }
 
@@ -571,7 +584,10 @@ where
             .iter_mut()
             .find_map(|g| g.workspaces.iter_mut().find(|w| w.id == workspace.id))
         {
-            workspace.states.remove(state);
+            if workspace.states.intersects(state) {
+                workspace.states.remove(state);
+                self.0.serial = self.0.serial.wrapping_add(1);
+            }
         }
     }
 
@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Metadata of toplevels, that the cosmic toplevel-info protocol has no events for.
+//!
+//! Entries are keyed by the identifier of the toplevel's ext-foreign-toplevel handle,
+//! so shell components can look up the process, sandbox, urgency and tag of a toplevel
+//! through `com.system76.CosmicComp.Toplevels`.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use smithay::wayland::security_context::SecurityContext;
+
+#[derive(Debug, Clone, Default)]
+pub struct ToplevelMetadata {
+    pub pid: Option<u32>,
+    pub sandbox_engine: Option<String>,
+    pub app_id: Option<String>,
+    pub instance_id: Option<String>,
+    /// The toplevel requested attention since it was last activated
+    pub urgent: bool,
+    /// Tag set through xdg-toplevel-tag
+    pub tag: Option<String>,
+    /// Human-readable description of the tag
+    pub tag_description: Option<String>,
+    /// `app_id/tag` of tagged toplevels, identifying them across restarts of the application
+    pub handle: Option<String>,
+}
+
+impl ToplevelMetadata {
+    pub fn new(pid: Option<u32>, security_context: Option<SecurityContext>, urgent: bool) -> Self {
+        ToplevelMetadata {
+            pid,
+            sandbox_engine: security_context
+                .as_ref()
+                .and_then(|context| context.sandbox_engine.clone()),
+            app_id: security_context
+                .as_ref()
+                .and_then(|context| context.app_id.clone()),
+            instance_id: security_context.and_then(|context| context.instance_id),
+            urgent,
+            tag: None,
+            tag_description: None,
+            handle: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Store {
+    toplevels: HashMap<String, ToplevelMetadata>,
+    /// Urgency changes not yet announced
+    urgency_changes: Vec<(String, bool)>,
+}
+
+static STORE: LazyLock<Mutex<Store>> = LazyLock::new(Default::default);
+
+pub fn insert(identifier: &str, metadata: ToplevelMetadata) {
+    STORE
+        .lock()
+        .unwrap()
+        .toplevels
+        .insert(identifier.to_string(), metadata);
+}
+
+pub fn remove(identifier: &str) {
+    STORE.lock().unwrap().toplevels.remove(identifier);
+}
+
+/// Updates the urgency of the toplevel, queueing an announcement if it changed
+pub fn set_urgent(identifier: &str, urgent: bool) {
+    let mut store = STORE.lock().unwrap();
+    let Some(metadata) = store.toplevels.get_mut(identifier) else {
+        return;
+    };
+    if metadata.urgent != urgent {
+        metadata.urgent = urgent;
+        store.urgency_changes.push((identifier.to_string(), urgent));
+    }
+}
+
+/// Updates the tag of the toplevel and the handle derived from it
+pub fn set_tag(
+    identifier: &str,
+    app_id: &str,
+    tag: Option<String>,
+    tag_description: Option<String>,
+) {
+    let mut store = STORE.lock().unwrap();
+    let Some(metadata) = store.toplevels.get_mut(identifier) else {
+        return;
+    };
+    metadata.handle = tag.as_ref().map(|tag| format!("{app_id}/{tag}"));
+    metadata.tag = tag;
+    metadata.tag_description = tag_description;
+}
+
+/// Urgency changes since the last call
+pub fn take_urgency_changes() -> Vec<(String, bool)> {
+    std::mem::take(&mut STORE.lock().unwrap().urgency_changes)
+}
+
+pub fn get(identifier: &str) -> Option<ToplevelMetadata> {
+    STORE.lock().unwrap().toplevels.get(identifier).cloned()
+}
+
+/// Identifier of the toplevel with the given `app_id/tag` handle
+pub fn find_handle(handle: &str) -> Option<String> {
+    STORE
+        .lock()
+        .unwrap()
+        .toplevels
+        .iter()
+        .find(|(_, metadata)| metadata.handle.as_deref() == Some(handle))
+        .map(|(identifier, _)| identifier.clone())
+}
+
+pub fn all() -> Vec<(String, ToplevelMetadata)> {
+    STORE
+        .lock()
+        .unwrap()
+        .toplevels
+        .iter()
+        .map(|(identifier, metadata)| (identifier.clone(), metadata.clone()))
+        .collect()
+}
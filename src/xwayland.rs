@@ -7,9 +7,9 @@ use crate::{
     },
     state::State,
     utils::prelude::*,
-    wayland::handlers::xdg_activation::ActivationContext,
+    wayland::{clipboard::SelectionOwner, handlers::xdg_activation::ActivationContext},
 };
-use cosmic_comp_config::{EavesdroppingKeyboardMode, XwaylandDescaling};
+use cosmic_comp_config::{CosmicCompConfig, EavesdroppingKeyboardMode, XwaylandDescaling};
 use smithay::{
     backend::{
         allocator::Fourcc,
@@ -320,7 +320,7 @@ impl Common {
                         .unwrap()
                         .new_selection(SelectionTarget::Primary, Some(mime_types))
                     {
-                        warn!(?err, "Failed to set Xwayland clipboard selection.");
+                        warn!(?err, "Failed to set Xwayland primary selection.");
                     }
                 }
             }
@@ -623,6 +623,18 @@ impl Common {
         // compare with current scale
         if Some(new_scale) != self.xwayland_scale {
             if let Some(xwayland) = self.xwayland_state.as_mut() {
+                // keep upscaled windows rendering at their scale
+                let old_scale = self.xwayland_scale.unwrap_or(1.);
+                for window in self
+                    .shell
+                    .read()
+                    .mapped()
+                    .flat_map(|m| m.windows().map(|(s, _)| s))
+                    .filter(|s| s.is_upscaled())
+                {
+                    window.set_upscale(Some(window.upscale() * new_scale / old_scale));
+                }
+
                 // backup geometries
                 let geometries = self
                     .shell
@@ -696,6 +708,32 @@ impl Common {
         }
     }
 
+    /// Applies the `xwayland_upscale` and `xwayland_dpi` rules to mapped windows, after they changed from `previous`
+    pub fn update_xwayland_upscale(&mut self, previous: &CosmicCompConfig) {
+        let scale = self.xwayland_scale.unwrap_or(1.);
+        for window in self
+            .shell
+            .read()
+            .mapped()
+            .flat_map(|m| m.windows().map(|(s, _)| s))
+            .filter(|s| s.x11_surface().is_some())
+        {
+            let app_id = window.app_id();
+            match upscale_rule(&self.config.cosmic_conf, &app_id, scale) {
+                Some(factor) => {
+                    if !window.is_upscaled() || window.upscale() != factor {
+                        window.set_upscale(Some(factor));
+                    }
+                }
+                None => {
+                    if upscale_rule(previous, &app_id, scale).is_some() && window.is_upscaled() {
+                        window.set_upscale(None);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn update_xwayland_primary_output(&mut self) {
         let mut xwayland_primary_output = None;
         for output in self.output_configuration_state.outputs() {
@@ -759,6 +797,13 @@ impl XwmHandler for State {
         }
 
         let surface = CosmicSurface::from(window);
+        if let Some(factor) = upscale_rule(
+            &self.common.config.cosmic_conf,
+            &surface.app_id(),
+            self.common.xwayland_scale.unwrap_or(1.),
+        ) {
+            surface.set_upscale(Some(factor));
+        }
         shell.pending_windows.push(PendingWindow {
             surface,
             seat,
@@ -884,11 +929,16 @@ impl XwmHandler for State {
 
             if let Some(current_geo) = current_geo {
                 let ssd_height = mapped.ssd_height(false).unwrap_or(0);
+                let upscale = mapped
+                    .windows()
+                    .find(|(s, _)| s == &window)
+                    .map_or(1., |(s, _)| s.upscale());
                 mapped.set_geometry(Rectangle::new(
                     current_geo.loc,
                     (
-                        w.map(|w| w as i32).unwrap_or(current_geo.size.w),
-                        h.map(|h| h as i32 + ssd_height)
+                        w.map(|w| (w as f64 * upscale).round() as i32)
+                            .unwrap_or(current_geo.size.w),
+                        h.map(|h| (h as f64 * upscale).round() as i32 + ssd_height)
                             .unwrap_or(current_geo.size.h),
                     )
                         .into(),
@@ -1132,7 +1182,12 @@ impl XwmHandler for State {
         let seat = self.common.shell.read().seats.last_active().clone();
         match selection {
             SelectionTarget::Clipboard => {
-                if let Err(err) = request_data_device_client_selection(&seat, mime_type, fd) {
+                if let Some(SelectionOwner::Persisted(selection)) =
+                    current_data_device_selection_userdata(&seat).as_deref()
+                {
+                    selection.send(&mime_type, fd, &self.common.event_loop_handle);
+                } else if let Err(err) = request_data_device_client_selection(&seat, mime_type, fd)
+                {
                     error!(
                         ?err,
                         "Failed to request current wayland clipboard for Xwayland.",
@@ -1159,12 +1214,18 @@ impl XwmHandler for State {
 
         let seat = self.common.shell.read().seats.last_active().clone();
         match selection {
-            SelectionTarget::Clipboard => {
-                set_data_device_selection(&self.common.display_handle, &seat, mime_types, xwm)
-            }
-            SelectionTarget::Primary => {
-                set_primary_selection(&self.common.display_handle, &seat, mime_types, xwm)
-            }
+            SelectionTarget::Clipboard => set_data_device_selection(
+                &self.common.display_handle,
+                &seat,
+                mime_types,
+                SelectionOwner::Xwm(xwm),
+            ),
+            SelectionTarget::Primary => set_primary_selection(
+                &self.common.display_handle,
+                &seat,
+                mime_types,
+                SelectionOwner::Xwm(xwm),
+            ),
         }
     }
 
@@ -1173,12 +1234,16 @@ impl XwmHandler for State {
         for seat in shell.seats.iter() {
             match selection {
                 SelectionTarget::Clipboard => {
-                    if current_data_device_selection_userdata(seat).as_deref() == Some(&xwm) {
+                    if current_data_device_selection_userdata(seat).as_deref()
+                        == Some(&SelectionOwner::Xwm(xwm))
+                    {
                         clear_data_device_selection(&self.common.display_handle, seat)
                     }
                 }
                 SelectionTarget::Primary => {
-                    if current_primary_selection_userdata(seat).as_deref() == Some(&xwm) {
+                    if current_primary_selection_userdata(seat).as_deref()
+                        == Some(&SelectionOwner::Xwm(xwm))
+                    {
                         clear_primary_selection(&self.common.display_handle, seat)
                     }
                 }
@@ -1203,3 +1268,14 @@ impl XwmHandler for State {
         xwayland_state.xwm = None;
     }
 }
+
+/// Upscale factor for windows of `app_id` by the `xwayland_dpi` or `xwayland_upscale` rules
+fn upscale_rule(config: &CosmicCompConfig, app_id: &str, scale: f64) -> Option<f64> {
+    if let Some(dpi) = config.xwayland_dpi.get(app_id).filter(|dpi| **dpi > 0) {
+        Some(scale * 96. / *dpi as f64)
+    } else if config.xwayland_upscale.iter().any(|id| id == app_id) {
+        Some(scale)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Minimal client showing a single toplevel filled with a solid color.
+//!
+//! Used by the headless screenshot mode of cosmic-comp (`cosmic-comp --screenshot`).
+//!
+//! Usage: `cosmic-comp-test-client --app-id <id> --color <RRGGBB[AA]> --size <W>x<H>`
+//!
+//! Not part of the cosmic-comp workspace, build with
+//! `cargo build --manifest-path test-client/Cargo.toml`.
+
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState},
+    delegate_compositor, delegate_output, delegate_registry, delegate_shm, delegate_xdg_shell,
+    delegate_xdg_window,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    shell::{
+        WaylandSurface,
+        xdg::{
+            XdgShell,
+            window::{Window, WindowConfigure, WindowDecorations, WindowHandler},
+        },
+    },
+    shm::{Shm, ShmHandler, slot::SlotPool},
+};
+use wayland_client::{
+    Connection, QueueHandle,
+    globals::registry_queue_init,
+    protocol::{wl_output, wl_shm, wl_surface},
+};
+
+struct Args {
+    app_id: String,
+    /// premultiplied ARGB
+    color: [u8; 4],
+    size: (u32, u32),
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut app_id = String::from("cosmic-comp-test-client");
+    let mut color = [0xff, 0xff, 0xff, 0xff];
+    let mut size = (640, 480);
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--app-id" => app_id = value,
+            "--color" => color = parse_color(&value)?,
+            "--size" => {
+                size = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or_else(|| format!("Invalid size: {}", value))?
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(Args {
+        app_id,
+        color,
+        size,
+    })
+}
+
+fn parse_color(value: &str) -> Result<[u8; 4], String> {
+    let invalid = || format!("Invalid color: {}", value);
+    let channel = |i: usize| {
+        value
+            .get(i * 2..i * 2 + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    let (r, g, b) = (
+        channel(0).ok_or_else(invalid)?,
+        channel(1).ok_or_else(invalid)?,
+        channel(2).ok_or_else(invalid)?,
+    );
+    let a = match value.len() {
+        6 => 0xff,
+        8 => channel(3).ok_or_else(invalid)?,
+        _ => return Err(invalid()),
+    };
+    let premultiply = |c: u8| (c as u16 * a as u16 / 0xff) as u8;
+    Ok([a, premultiply(r), premultiply(g), premultiply(b)])
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    let conn = Connection::connect_to_env().expect("Failed to connect to the compositor");
+    let (globals, mut event_queue) = registry_queue_init(&conn).expect("Failed to init registry");
+    let qh = event_queue.handle();
+
+    let compositor = CompositorState::bind(&globals, &qh).expect("wl_compositor not available");
+    let xdg_shell = XdgShell::bind(&globals, &qh).expect("xdg_wm_base not available");
+    let shm = Shm::bind(&globals, &qh).expect("wl_shm not available");
+
+    let surface = compositor.create_surface(&qh);
+    let window = xdg_shell.create_window(surface, WindowDecorations::RequestServer, &qh);
+    window.set_title(args.app_id.clone());
+    window.set_app_id(args.app_id.clone());
+    window.commit();
+
+    let pool = SlotPool::new((args.size.0 * args.size.1 * 4) as usize, &shm)
+        .expect("Failed to create shm pool");
+
+    let mut client = TestClient {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+        shm,
+        pool,
+        window,
+        color: args.color,
+        size: args.size,
+        exit: false,
+    };
+
+    while !client.exit {
+        event_queue
+            .blocking_dispatch(&mut client)
+            .expect("Connection to the compositor failed");
+    }
+}
+
+struct TestClient {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    shm: Shm,
+    pool: SlotPool,
+    window: Window,
+    color: [u8; 4],
+    size: (u32, u32),
+    exit: bool,
+}
+
+impl TestClient {
+    fn draw(&mut self) {
+        let (width, height) = self.size;
+        let stride = width as i32 * 4;
+        let (buffer, canvas) = self
+            .pool
+            .create_buffer(
+                width as i32,
+                height as i32,
+                stride,
+                wl_shm::Format::Argb8888,
+            )
+            .expect("Failed to create buffer");
+
+        // wl_shm formats are little-endian
+        let [a, r, g, b] = self.color;
+        for pixel in canvas.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[b, g, r, a]);
+        }
+
+        let surface = self.window.wl_surface();
+        surface.damage_buffer(0, 0, width as i32, height as i32);
+        buffer.attach_to(surface).expect("Failed to attach buffer");
+        self.window.commit();
+    }
+}
+
+impl CompositorHandler for TestClient {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_factor: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for TestClient {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl WindowHandler for TestClient {
+    fn request_close(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _window: &Window) {
+        self.exit = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _window: &Window,
+        configure: WindowConfigure,
+        _serial: u32,
+    ) {
+        // honor sizes chosen by the compositor (e.g. when tiled)
+        if let (Some(width), Some(height)) = configure.new_size {
+            self.size = (width.get(), height.get());
+        }
+        self.draw();
+    }
+}
+
+impl ShmHandler for TestClient {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl ProvidesRegistryState for TestClient {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState];
+}
+
+delegate_compositor!(TestClient);
+delegate_output!(TestClient);
+delegate_shm!(TestClient);
+delegate_xdg_shell!(TestClient);
+delegate_xdg_window!(TestClient);
+delegate_registry!(TestClient);
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Integration of cosmic-comp into the Wayland Conformance Test Suite (wlcs).
+//!
+//! Build with `cargo build --manifest-path wlcs_cosmic/Cargo.toml` and run
+//! `wlcs wlcs_cosmic/target/debug/libwlcs_cosmic.so` to execute the test suite.
+
+use std::{
+    io,
+    os::{
+        fd::{AsRawFd, OwnedFd},
+        unix::net::UnixStream,
+    },
+    thread::JoinHandle,
+};
+
+use calloop::channel::{Sender, channel};
+use cosmic_comp::{backend::headless::HeadlessCommand, utils::prelude::Global};
+use smithay::utils::Point;
+use wayland_sys::{
+    client::{wayland_client_handle, wl_display, wl_proxy},
+    common::wl_fixed_t,
+    ffi_dispatch,
+};
+use wlcs::{
+    Pointer, Touch, Wlcs, extension_list,
+    ffi_display_server_api::{WlcsExtensionDescriptor, WlcsIntegrationDescriptor},
+    wlcs_server_integration,
+};
+
+wlcs_server_integration!(CosmicWlcs);
+
+static SUPPORTED_EXTENSIONS: &[WlcsExtensionDescriptor] = &extension_list!(
+    ("wl_compositor", 6),
+    ("wl_subcompositor", 1),
+    ("wl_data_device_manager", 3),
+    ("wl_seat", 9),
+    ("wl_output", 4),
+    ("xdg_wm_base", 6),
+    ("zwlr_layer_shell_v1", 4),
+    ("zxdg_output_manager_v1", 3),
+    ("wp_viewporter", 1),
+    ("zwp_relative_pointer_manager_v1", 1),
+    ("zwp_pointer_constraints_v1", 1),
+    ("zwp_primary_selection_device_manager_v1", 1),
+    ("zwp_text_input_manager_v3", 1),
+);
+
+static DESCRIPTOR: WlcsIntegrationDescriptor = WlcsIntegrationDescriptor {
+    version: 1,
+    num_extensions: SUPPORTED_EXTENSIONS.len(),
+    supported_extensions: SUPPORTED_EXTENSIONS.as_ptr(),
+};
+
+fn from_fixed(value: wl_fixed_t) -> f64 {
+    value as f64 / 256.
+}
+
+struct CosmicWlcs {
+    server: Option<(Sender<HeadlessCommand>, JoinHandle<()>)>,
+    touch_ids: u32,
+}
+
+impl CosmicWlcs {
+    fn sender(&self) -> Sender<HeadlessCommand> {
+        self.server
+            .as_ref()
+            .map(|(sender, _)| sender.clone())
+            .expect("Compositor not started")
+    }
+}
+
+impl Wlcs for CosmicWlcs {
+    type Pointer = CosmicPointer;
+    type Touch = CosmicTouch;
+
+    fn new() -> Self {
+        CosmicWlcs {
+            server: None,
+            touch_ids: 0,
+        }
+    }
+
+    fn start(&mut self) {
+        let (sender, channel) = channel();
+        let handle = std::thread::spawn(move || {
+            if let Err(err) = cosmic_comp::run_headless(channel) {
+                tracing::error!(?err, "Compositor exited with an error");
+            }
+        });
+        self.server = Some((sender, handle));
+    }
+
+    fn stop(&mut self) {
+        if let Some((sender, handle)) = self.server.take() {
+            let _ = sender.send(HeadlessCommand::Stop);
+            let _ = handle.join();
+        }
+    }
+
+    fn create_client_socket(&self) -> io::Result<OwnedFd> {
+        let (client, server) = UnixStream::pair()?;
+        self.sender()
+            .send(HeadlessCommand::NewClient {
+                stream: server,
+                id: client.as_raw_fd(),
+            })
+            .map_err(|_| io::Error::other("Compositor not running"))?;
+        Ok(client.into())
+    }
+
+    fn position_window_absolute(
+        &self,
+        display: *mut wl_display,
+        surface: *mut wl_proxy,
+        x: i32,
+        y: i32,
+    ) {
+        // clients are identified by the fd of their end of the socket (see `create_client_socket`)
+        let client = unsafe { ffi_dispatch!(wayland_client_handle(), wl_display_get_fd, display) };
+        let surface = unsafe { ffi_dispatch!(wayland_client_handle(), wl_proxy_get_id, surface) };
+        let _ = self.sender().send(HeadlessCommand::PositionWindow {
+            client,
+            surface,
+            position: Point::from((x, y)),
+        });
+    }
+
+    fn create_pointer(&mut self) -> Option<Self::Pointer> {
+        Some(CosmicPointer {
+            sender: self.sender(),
+        })
+    }
+
+    fn create_touch(&mut self) -> Option<Self::Touch> {
+        self.touch_ids += 1;
+        Some(CosmicTouch {
+            sender: self.sender(),
+            id: self.touch_ids,
+        })
+    }
+
+    fn get_descriptor(&self) -> &WlcsIntegrationDescriptor {
+        &DESCRIPTOR
+    }
+}
+
+struct CosmicPointer {
+    sender: Sender<HeadlessCommand>,
+}
+
+impl Pointer for CosmicPointer {
+    fn move_absolute(&mut self, x: wl_fixed_t, y: wl_fixed_t) {
+        let position = Point::<f64, Global>::from((from_fixed(x), from_fixed(y)));
+        let _ = self
+            .sender
+            .send(HeadlessCommand::PointerMotionAbsolute(position));
+    }
+
+    fn move_relative(&mut self, dx: wl_fixed_t, dy: wl_fixed_t) {
+        let delta = Point::<f64, Global>::from((from_fixed(dx), from_fixed(dy)));
+        let _ = self
+            .sender
+            .send(HeadlessCommand::PointerMotionRelative(delta));
+    }
+
+    fn button_up(&mut self, button: i32) {
+        let _ = self.sender.send(HeadlessCommand::PointerButton {
+            button: button as u32,
+            pressed: false,
+        });
+    }
+
+    fn button_down(&mut self, button: i32) {
+        let _ = self.sender.send(HeadlessCommand::PointerButton {
+            button: button as u32,
+            pressed: true,
+        });
+    }
+}
+
+struct CosmicTouch {
+    sender: Sender<HeadlessCommand>,
+    id: u32,
+}
+
+impl Touch for CosmicTouch {
+    fn touch_down(&mut self, x: wl_fixed_t, y: wl_fixed_t) {
+        let position = Point::<f64, Global>::from((from_fixed(x), from_fixed(y)));
+        let _ = self.sender.send(HeadlessCommand::TouchDown {
+            id: self.id,
+            position,
+        });
+    }
+
+    fn touch_move(&mut self, x: wl_fixed_t, y: wl_fixed_t) {
+        let position = Point::<f64, Global>::from((from_fixed(x), from_fixed(y)));
+        let _ = self.sender.send(HeadlessCommand::TouchMotion {
+            id: self.id,
+            position,
+        });
+    }
+
+    fn touch_up(&mut self) {
+        let _ = self.sender.send(HeadlessCommand::TouchUp { id: self.id });
+    }
+}